@@ -5,6 +5,14 @@ pub type __s32 = c_int;
 pub type __u32 = c_uint;
 pub type __s64 = c_longlong;
 pub type __u64 = c_ulonglong;
+/// `struct open_how` of `openat2(2)`
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct open_how {
+    pub flags: __u64,
+    pub mode: __u64,
+    pub resolve: __u64,
+}
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct statx_timestamp {
@@ -1,6 +1,7 @@
 use crate::ctype_my::statx;
 use crate::ctypes;
 use crate::imp::stdio::{stdin, stdout};
+use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use axerrno::{LinuxError, LinuxResult, ax_err};
@@ -9,33 +10,37 @@ use axio::PollState;
 use axns::{ResArc, def_resource};
 use axtask::yield_now;
 use core::ffi::{c_char, c_int, c_short, c_void};
+use core::sync::atomic::{AtomicU32, Ordering};
 use core::time::Duration;
-use flatten_objects::FlattenObjects;
-use spin::Mutex;
 use spin::{Once, RwLock};
 
+/// Default soft limit of `RLIMIT_NOFILE`.
 pub const AX_FILE_LIMIT: usize = 1024;
+/// Upper bound of the `RLIMIT_NOFILE` limits, which is also the default hard
+/// limit (`/proc/sys/fs/nr_open` on Linux).
+pub const AX_NR_OPEN: usize = 1 << 20;
 
-static FILE_LIMIT: Mutex<(usize, usize)> = Mutex::new((AX_FILE_LIMIT, AX_FILE_LIMIT));
-
+/// Get the soft limit of `RLIMIT_NOFILE`.
 pub fn get_file_limit() -> usize {
-    FILE_LIMIT.lock().0
+    FILE_LIMIT.read().0
 }
+
+/// Get the hard limit of `RLIMIT_NOFILE`.
 pub fn get_file_limit_max() -> usize {
-    FILE_LIMIT.lock().1
+    FILE_LIMIT.read().1
 }
 
-pub fn set_file_limit(new_cur: usize, new_max: usize) -> Result<(), LinuxError> {
-    if new_cur > new_max || new_cur < 1 || new_max < 1 {
+/// Set the `RLIMIT_NOFILE` limits, the hard limit can not exceed
+/// [`AX_NR_OPEN`]. Descriptors already open above the new soft limit stay
+/// open.
+pub fn set_file_limit(new_cur: usize, new_max: usize) -> LinuxResult {
+    if new_cur > new_max {
         return Err(LinuxError::EINVAL);
     }
-    let current_count = current_fd_count();
-    if current_count > new_cur {
-        return Err(LinuxError::EBUSY);
+    if new_max > AX_NR_OPEN {
+        return Err(LinuxError::EPERM);
     }
-    let mut limit = FILE_LIMIT.lock();
-    limit.0 = new_cur;
-    limit.1 = new_max;
+    *FILE_LIMIT.write() = (new_cur, new_max);
     Ok(())
 }
 
@@ -59,6 +64,14 @@ pub trait FileLike: Send + Sync {
     fn into_any(self: Arc<Self>) -> Arc<dyn core::any::Any + Send + Sync>;
     fn poll(&self) -> LinuxResult<PollState>;
     fn set_nonblocking(&self, nonblocking: bool) -> LinuxResult;
+    /// Switch `O_APPEND` on or off, only meaningful to regular files.
+    fn set_append(&self, _append: bool) -> LinuxResult {
+        Ok(())
+    }
+    /// Switch `O_DIRECT` on or off, pipes take it as packet mode.
+    fn set_direct(&self, _direct: bool) -> LinuxResult {
+        Ok(())
+    }
 
     fn fgetxattr(
         &self,
@@ -109,55 +122,336 @@ pub trait FileLike: Send + Sync {
     }
 }
 
+/// Unshare the file descriptor table before closing the descriptors in the
+/// range, see `close_range(2)`.
+pub const CLOSE_RANGE_UNSHARE: u32 = 1 << 1;
+/// Mark the file descriptors in the range close-on-exec instead of closing
+/// them, see `close_range(2)`.
+pub const CLOSE_RANGE_CLOEXEC: u32 = 1 << 2;
+
+/// Flags of `open` that are not kept in the open file description.
+const OPEN_ONLY_FLAGS: u32 =
+    ctypes::O_CREAT | ctypes::O_EXCL | ctypes::O_NOCTTY | ctypes::O_TRUNC | ctypes::O_CLOEXEC;
+/// Status flags that `F_SETFL` can change.
+const SETFL_MASK: u32 =
+    ctypes::O_APPEND | ctypes::O_NONBLOCK | ctypes::O_ASYNC | ctypes::O_DIRECT | ctypes::O_NOATIME;
+
+/// An open file description.
+///
+/// Each `open` (or `pipe`, `socket`, ...) creates one, and it is shared by
+/// all descriptors duplicated from it by `dup` and `F_DUPFD` or inherited by
+/// `fork`. The file position lives in the [`FileLike`] object, the access
+/// mode and status flags (`F_GETFL`/`F_SETFL`) are kept here.
+pub struct OpenFile {
+    file: Arc<dyn FileLike>,
+    flags: AtomicU32,
+}
+
+impl OpenFile {
+    /// Create an open file description with the `flags` given to `open`.
+    pub fn new(file: Arc<dyn FileLike>, flags: u32) -> Self {
+        Self {
+            file,
+            flags: AtomicU32::new(flags & !OPEN_ONLY_FLAGS),
+        }
+    }
+
+    /// The opened file.
+    pub fn file(&self) -> &Arc<dyn FileLike> {
+        &self.file
+    }
+
+    /// The access mode and status flags.
+    pub fn flags(&self) -> u32 {
+        self.flags.load(Ordering::Acquire)
+    }
+
+    /// Set the status flags like `F_SETFL`, flags that can not be changed
+    /// are ignored.
+    pub fn set_flags(&self, flags: u32) -> LinuxResult {
+        let old = self.flags();
+        let new = (old & !SETFL_MASK) | (flags & SETFL_MASK);
+        if (old ^ new) & ctypes::O_NONBLOCK != 0 {
+            self.file.set_nonblocking(new & ctypes::O_NONBLOCK != 0)?;
+        }
+        if (old ^ new) & ctypes::O_APPEND != 0 {
+            self.file.set_append(new & ctypes::O_APPEND != 0)?;
+        }
+        if (old ^ new) & ctypes::O_DIRECT != 0 {
+            self.file.set_direct(new & ctypes::O_DIRECT != 0)?;
+        }
+        self.flags.store(new, Ordering::Release);
+        Ok(())
+    }
+}
+
+/// An entry of the file descriptor table.
+#[derive(Clone)]
+pub struct FdEntry {
+    /// The open file description.
+    pub file: Arc<OpenFile>,
+    /// Whether the descriptor is closed on `execve` (`FD_CLOEXEC`).
+    pub cloexec: bool,
+}
+
+impl FdEntry {
+    pub fn new(file: Arc<OpenFile>, cloexec: bool) -> Self {
+        Self { file, cloexec }
+    }
+}
+
+/// A growable file descriptor table.
+///
+/// Descriptors are kept in a sorted map, so a sparse high descriptor (e.g.
+/// from `dup2(fd, 100000)`) only costs one entry. New descriptors must be
+/// below the soft `RLIMIT_NOFILE` limit of the calling process, which is
+/// passed in by the caller since a table shared by `CLONE_FILES` may be used
+/// by processes with different limits.
+#[derive(Clone)]
+pub struct FdTable {
+    entries: BTreeMap<usize, FdEntry>,
+    /// All descriptors below `next_fd` are in use.
+    next_fd: usize,
+}
+
+impl FdTable {
+    pub const fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            next_fd: 0,
+        }
+    }
+
+    /// Number of open descriptors.
+    pub fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Open descriptors in ascending order.
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.entries.keys().copied()
+    }
+
+    pub fn get(&self, fd: usize) -> Option<&FdEntry> {
+        self.entries.get(&fd)
+    }
+
+    pub fn get_mut(&mut self, fd: usize) -> Option<&mut FdEntry> {
+        self.entries.get_mut(&fd)
+    }
+
+    pub fn is_assigned(&self, fd: usize) -> bool {
+        self.entries.contains_key(&fd)
+    }
+
+    /// Add an entry at the lowest free descriptor below `limit`.
+    pub fn add(&mut self, entry: FdEntry, limit: usize) -> LinuxResult<usize> {
+        self.add_from(0, entry, limit)
+    }
+
+    /// Add an entry at the lowest free descriptor not less than `min_fd` and
+    /// below `limit`.
+    ///
+    /// Returns `EINVAL` if `min_fd` is not below the limit, and `EMFILE` if
+    /// all descriptors from `min_fd` up to the limit are in use.
+    pub fn add_from(
+        &mut self,
+        min_fd: usize,
+        entry: FdEntry,
+        limit: usize,
+    ) -> LinuxResult<usize> {
+        if min_fd >= limit {
+            return Err(LinuxError::EINVAL);
+        }
+        let mut fd = min_fd.max(self.next_fd);
+        for &used in self.entries.range(fd..).map(|(fd, _)| fd) {
+            if used != fd {
+                break;
+            }
+            fd += 1;
+        }
+        if fd >= limit {
+            return Err(LinuxError::EMFILE);
+        }
+        self.entries.insert(fd, entry);
+        if min_fd <= self.next_fd {
+            self.next_fd = fd + 1;
+        }
+        Ok(fd)
+    }
+
+    /// Put an entry at `fd`, returning the entry it replaces.
+    ///
+    /// Returns `EBADF` if `fd` is not below `limit`.
+    pub fn add_at(
+        &mut self,
+        fd: usize,
+        entry: FdEntry,
+        limit: usize,
+    ) -> LinuxResult<Option<FdEntry>> {
+        if fd >= limit {
+            return Err(LinuxError::EBADF);
+        }
+        Ok(self.entries.insert(fd, entry))
+    }
+
+    pub fn remove(&mut self, fd: usize) -> Option<FdEntry> {
+        let entry = self.entries.remove(&fd)?;
+        self.next_fd = self.next_fd.min(fd);
+        Some(entry)
+    }
+}
+
+impl Default for FdTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The descriptor table of a process.
+///
+/// Processes created by `clone` with `CLONE_FILES` refer to the same
+/// [`FdTable`] until one of them unshares it.
+pub struct SharedFdTable(RwLock<Arc<RwLock<FdTable>>>);
+
+impl SharedFdTable {
+    pub fn new(table: FdTable) -> Self {
+        Self(RwLock::new(Arc::new(RwLock::new(table))))
+    }
+
+    /// The table in use.
+    pub fn table(&self) -> Arc<RwLock<FdTable>> {
+        self.0.read().clone()
+    }
+
+    /// Switch to a private copy of the table if it is shared with other
+    /// processes.
+    pub fn unshare(&self) {
+        let mut shared = self.0.write();
+        if Arc::strong_count(&shared) > 1 {
+            let table = shared.read().clone();
+            *shared = Arc::new(RwLock::new(table));
+        }
+    }
+
+    /// Close all descriptors when the process exits. A table still used by
+    /// other processes is only left.
+    pub fn clear(&self) {
+        let empty = Arc::new(RwLock::new(FdTable::new()));
+        let table = core::mem::replace(&mut *self.0.write(), empty);
+        // closing the files may sleep, not under the lock
+        drop(table);
+    }
+}
+
 def_resource! {
-    pub static FD_TABLE: ResArc<RwLock<FlattenObjects<Arc<dyn FileLike>, AX_FILE_LIMIT>>> = ResArc::new();
+    pub static FD_TABLE: ResArc<SharedFdTable> = ResArc::new();
+    /// The soft and hard `RLIMIT_NOFILE` limits. They belong to the process
+    /// and are inherited by `fork`, but unlike [`FD_TABLE`] never shared by
+    /// `CLONE_FILES`.
+    pub static FILE_LIMIT: ResArc<RwLock<(usize, usize)>> = ResArc::new();
+}
+
+impl FILE_LIMIT {
+    /// Return a copy of the inner limits.
+    pub fn copy_inner(&self) -> RwLock<(usize, usize)> {
+        RwLock::new(*self.read())
+    }
 }
 
 impl FD_TABLE {
     /// Return a copy of the inner table.
-    pub fn copy_inner(&self) -> RwLock<FlattenObjects<Arc<dyn FileLike>, AX_FILE_LIMIT>> {
-        let table = self.read();
-        let mut new_table = FlattenObjects::new();
-        for id in table.ids() {
-            let _ = new_table.add_at(id, table.get(id).unwrap().clone());
-        }
-        RwLock::new(new_table)
+    pub fn copy_inner(&self) -> SharedFdTable {
+        SharedFdTable::new(self.table().read().clone())
     }
 
-    pub fn clear(&self) {
-        let mut table = self.write();
-        let ids: Vec<_> = table.ids().collect();
-        for i in ids {
-            table.remove(i).unwrap();
+    /// Return a reference to the same table, for `CLONE_FILES`.
+    pub fn share_inner(&self) -> SharedFdTable {
+        SharedFdTable(RwLock::new(self.table()))
+    }
+
+    /// Close the file descriptors marked close-on-exec, called by `execve`.
+    ///
+    /// Like Linux, the new program gets a private table.
+    pub fn close_on_exec(&self) {
+        self.unshare();
+        let table = self.table();
+        let mut table = table.write();
+        let ids: Vec<_> = table
+            .ids()
+            .filter(|&fd| table.get(fd).unwrap().cloexec)
+            .collect();
+        for fd in ids {
+            table.remove(fd);
         }
     }
 }
 
 /// Get the current number of open file descriptors
 pub fn current_fd_count() -> usize {
-    FD_TABLE.read().count()
+    FD_TABLE.table().read().count()
 }
 
 /// Get a file by `fd`.
 pub fn get_file_like(fd: c_int) -> LinuxResult<Arc<dyn FileLike>> {
+    get_open_file(fd).map(|file| file.file().clone())
+}
+
+/// Get the open file description of `fd`.
+pub fn get_open_file(fd: c_int) -> LinuxResult<Arc<OpenFile>> {
     FD_TABLE
+        .table()
         .read()
         .get(fd as usize)
-        .cloned()
+        .map(|entry| entry.file.clone())
         .ok_or(LinuxError::EBADF)
 }
 
 /// Add a file to the file descriptor table.
 pub fn add_file_like(f: Arc<dyn FileLike>) -> LinuxResult<c_int> {
-    if current_fd_count() >= get_file_limit() {
-        return Err(LinuxError::EMFILE);
-    }
-    Ok(FD_TABLE.write().add(f).map_err(|_| LinuxError::EMFILE)? as c_int)
+    add_file_like_cloexec(f, false)
+}
+
+/// Add a file to the file descriptor table, with `FD_CLOEXEC` set if
+/// `cloexec` is true.
+pub fn add_file_like_cloexec(f: Arc<dyn FileLike>, cloexec: bool) -> LinuxResult<c_int> {
+    add_open_file(Arc::new(OpenFile::new(f, ctypes::O_RDWR)), cloexec)
+}
+
+/// Add an open file description to the file descriptor table.
+pub fn add_open_file(file: Arc<OpenFile>, cloexec: bool) -> LinuxResult<c_int> {
+    let limit = get_file_limit();
+    let fd = FD_TABLE
+        .table()
+        .write()
+        .add(FdEntry::new(file, cloexec), limit)?;
+    Ok(fd as c_int)
+}
+
+/// Get the `FD_CLOEXEC` flag of `fd`.
+pub fn get_cloexec(fd: c_int) -> LinuxResult<bool> {
+    FD_TABLE
+        .table()
+        .read()
+        .get(fd as usize)
+        .map(|entry| entry.cloexec)
+        .ok_or(LinuxError::EBADF)
+}
+
+/// Set the `FD_CLOEXEC` flag of `fd`.
+pub fn set_cloexec(fd: c_int, cloexec: bool) -> LinuxResult {
+    FD_TABLE
+        .table()
+        .write()
+        .get_mut(fd as usize)
+        .map(|entry| entry.cloexec = cloexec)
+        .ok_or(LinuxError::EBADF)
 }
 
 /// Close a file by `fd`.
 pub fn close_file_like(fd: c_int) -> LinuxResult {
     let f = FD_TABLE
+        .table()
         .write()
         .remove(fd as usize)
         .ok_or(LinuxError::EBADF)?;
@@ -178,8 +472,37 @@ pub fn sys_close(fd: c_int) -> c_int {
 }
 
 fn dup_fd(old_fd: c_int) -> LinuxResult<c_int> {
-    let f = get_file_like(old_fd)?;
-    let new_fd = add_file_like(f)?;
+    let f = get_open_file(old_fd)?;
+    let new_fd = add_open_file(f, false)?;
+    Ok(new_fd)
+}
+
+/// Duplicate `old_fd` to the lowest free descriptor not less than `min_fd`
+/// (`F_DUPFD`, `F_DUPFD_CLOEXEC`).
+fn dup_fd_from(old_fd: c_int, min_fd: usize, cloexec: bool) -> LinuxResult<c_int> {
+    let f = get_open_file(old_fd)?;
+    let limit = get_file_limit();
+    let new_fd = FD_TABLE
+        .table()
+        .write()
+        .add_from(min_fd, FdEntry::new(f, cloexec), limit)?;
+    Ok(new_fd as c_int)
+}
+
+/// Make `new_fd` refer to the file of `old_fd`, closing `new_fd` first if it
+/// is open.
+fn dup_fd_to(old_fd: c_int, new_fd: c_int, cloexec: bool) -> LinuxResult<c_int> {
+    if new_fd < 0 {
+        return Err(LinuxError::EBADF);
+    }
+    let f = get_open_file(old_fd)?;
+    let limit = get_file_limit();
+    // 原来的 new_fd 在释放表锁之后关闭
+    let old = FD_TABLE
+        .table()
+        .write()
+        .add_at(new_fd as usize, FdEntry::new(f, cloexec), limit)?;
+    drop(old);
     Ok(new_fd)
 }
 
@@ -194,52 +517,89 @@ pub fn sys_dup2(old_fd: c_int, new_fd: c_int) -> c_int {
     debug!("sys_dup2 <= old_fd: {}, new_fd: {}", old_fd, new_fd);
     syscall_body!(sys_dup2, {
         if old_fd == new_fd {
-            let r = sys_fcntl(old_fd, ctypes::F_GETFD as _, 0);
-            if r >= 0 {
-                return Ok(old_fd);
-            } else {
-                return Ok(r);
-            }
-        }
-        if new_fd as usize >= AX_FILE_LIMIT {
-            return Err(LinuxError::EBADF);
+            // 只检查 old_fd 是否有效
+            get_cloexec(old_fd)?;
+            return Ok(old_fd);
         }
+        dup_fd_to(old_fd, new_fd, false)
+    })
+}
 
-        let f = get_file_like(old_fd)?;
-        let mut fd_table = FD_TABLE.write();
-        // 先关闭 new_fd（如果存在）
-        if fd_table.is_assigned(new_fd as usize) {
-            debug!("Removing existing resource at new_fd={}", new_fd);
-            fd_table.remove(new_fd as usize); // 移除旧资源
+/// Duplicate a file descriptor like [`sys_dup2`], with `O_CLOEXEC` as the
+/// only allowed flag, and fail if `old_fd` equals `new_fd`.
+pub fn sys_dup3(old_fd: c_int, new_fd: c_int, flags: c_int) -> c_int {
+    debug!(
+        "sys_dup3 <= old_fd: {}, new_fd: {}, flags: {:#o}",
+        old_fd, new_fd, flags
+    );
+    syscall_body!(sys_dup3, {
+        if flags & !(ctypes::O_CLOEXEC as c_int) != 0 || old_fd == new_fd {
+            return Err(LinuxError::EINVAL);
         }
-        // 再绑定新资源
-        fd_table.add_at(new_fd as usize, f).map_err(|e| {
-            debug!("FD_TABLE.add_at failed for new_fd={}", new_fd);
-            LinuxError::EMFILE
-        })?;
+        dup_fd_to(old_fd, new_fd, flags & ctypes::O_CLOEXEC as c_int != 0)
+    })
+}
 
-        Ok(new_fd)
+/// Close the file descriptors from `first` to `last` (inclusive), or mark
+/// them close-on-exec with [`CLOSE_RANGE_CLOEXEC`].
+///
+/// With [`CLOSE_RANGE_UNSHARE`], a descriptor table shared with other
+/// processes by `CLONE_FILES` is first replaced with a private copy, so the
+/// descriptors are only closed in the calling process.
+pub fn sys_close_range(first: u32, last: u32, flags: u32) -> c_int {
+    debug!(
+        "sys_close_range <= first: {}, last: {}, flags: {:#x}",
+        first, last, flags
+    );
+    syscall_body!(sys_close_range, {
+        if flags & !(CLOSE_RANGE_UNSHARE | CLOSE_RANGE_CLOEXEC) != 0 || first > last {
+            return Err(LinuxError::EINVAL);
+        }
+        if flags & CLOSE_RANGE_UNSHARE != 0 {
+            FD_TABLE.unshare();
+        }
+        let fd_table = FD_TABLE.table();
+        let mut fd_table = fd_table.write();
+        let ids: Vec<_> = fd_table
+            .ids()
+            .filter(|&fd| (first as usize..=last as usize).contains(&fd))
+            .collect();
+        for fd in ids {
+            if flags & CLOSE_RANGE_CLOEXEC != 0 {
+                fd_table.get_mut(fd).unwrap().cloexec = true;
+            } else {
+                fd_table.remove(fd);
+            }
+        }
+        Ok(0)
     })
 }
+
 /// Manipulate file descriptor.
-///
-/// TODO: `SET/GET` command is ignored, hard-code stdin/stdout
 pub fn sys_fcntl(fd: c_int, cmd: c_int, arg: usize) -> c_int {
     debug!("sys_fcntl <= fd: {} cmd: {} arg: {}", fd, cmd, arg);
     syscall_body!(sys_fcntl, {
         match cmd as u32 {
-            ctypes::F_DUPFD => dup_fd(fd),
-            ctypes::F_DUPFD_CLOEXEC => {
-                // TODO: Change fd flags
-                dup_fd(fd)
+            ctypes::F_DUPFD => dup_fd_from(fd, arg, false),
+            ctypes::F_DUPFD_CLOEXEC => dup_fd_from(fd, arg, true),
+            ctypes::F_GETFD => Ok(if get_cloexec(fd)? {
+                ctypes::FD_CLOEXEC as c_int
+            } else {
+                0
+            }),
+            ctypes::F_SETFD => {
+                set_cloexec(fd, arg & ctypes::FD_CLOEXEC as usize != 0)?;
+                Ok(0)
             }
+            ctypes::F_GETFL => Ok(get_open_file(fd)?.flags() as c_int),
             ctypes::F_SETFL => {
-                if fd == 0 || fd == 1 || fd == 2 {
-                    return Ok(0);
-                }
-                get_file_like(fd)?.set_nonblocking(arg & (ctypes::O_NONBLOCK as usize) > 0)?;
+                get_open_file(fd)?.set_flags(arg as u32)?;
                 Ok(0)
             }
+            #[cfg(feature = "pipe")]
+            ctypes::F_GETPIPE_SZ => Ok(super::pipe::Pipe::from_fd(fd)?.capacity() as c_int),
+            #[cfg(feature = "pipe")]
+            ctypes::F_SETPIPE_SZ => Ok(super::pipe::Pipe::from_fd(fd)?.set_capacity(arg)? as c_int),
             _ => {
                 warn!("unsupported fcntl parameters: cmd {}", cmd);
                 Ok(0)
@@ -353,7 +713,16 @@ pub fn sys_ppoll(
                 }
             }
 
-            // 5. 让出CPU，避免忙等待
+            // 5. 被信号打断时返回 EINTR，并写回剩余的超时时间
+            if super::task::interrupted() {
+                if let Some(duration) = timeout_duration {
+                    let remain = duration.saturating_sub(axhal::time::monotonic_time() - start_time);
+                    unsafe { *(timeout_ts as *mut ctypes::timespec) = remain.into() };
+                }
+                return Err(super::task::erestartnohand());
+            }
+
+            // 6. 让出CPU，避免忙等待
             yield_now();
         }
     })
@@ -405,17 +774,22 @@ fn poll_once(fds: *mut ctypes::pollfd, nfds: ctypes::nfds_t) -> LinuxResult<usiz
     Ok(ready_count)
 }
 
+fn stdio_file(f: Arc<dyn FileLike>) -> Arc<OpenFile> {
+    Arc::new(OpenFile::new(f, ctypes::O_RDWR))
+}
+
 #[ctor_bare::register_ctor]
 fn init_stdio() {
-    let mut fd_table = flatten_objects::FlattenObjects::new();
+    let mut fd_table = FdTable::new();
     fd_table
-        .add_at(0, Arc::new(stdin()) as _)
+        .add_at(0, FdEntry::new(stdio_file(Arc::new(stdin())), false), AX_FILE_LIMIT)
         .unwrap_or_else(|_| panic!()); // stdin
     fd_table
-        .add_at(1, Arc::new(stdout()) as _)
+        .add_at(1, FdEntry::new(stdio_file(Arc::new(stdout())), false), AX_FILE_LIMIT)
         .unwrap_or_else(|_| panic!()); // stdout
     fd_table
-        .add_at(2, Arc::new(stdout()) as _)
+        .add_at(2, FdEntry::new(stdio_file(Arc::new(stdout())), false), AX_FILE_LIMIT)
         .unwrap_or_else(|_| panic!()); // stderr
-    FD_TABLE.init_new(spin::RwLock::new(fd_table));
+    FD_TABLE.init_new(SharedFdTable::new(fd_table));
+    FILE_LIMIT.init_new(spin::RwLock::new((AX_FILE_LIMIT, AX_NR_OPEN)));
 }
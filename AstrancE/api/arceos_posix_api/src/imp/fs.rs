@@ -11,11 +11,13 @@ use core::str::from_utf8;
 use core::{panic, ptr, slice};
 use static_assertions::assert_eq_size;
 
-use super::fd_ops::{FileLike, get_file_like};
+use super::fd_ops::{FileLike, OpenFile, add_open_file, get_file_like};
+use super::path_link::{resolve_error, resolve_file_path};
 use crate::AT_FDCWD;
-use crate::ctype_my::{__u32, statx, statx_timestamp};
+use crate::ctype_my::{__u32, open_how, statx, statx_timestamp};
 use crate::ctypes::{__IncompleteArrayField, stat, time_t, timespec, timeval};
 use axerrno::{LinuxError, LinuxResult};
+use axfs::api::{MountFlags, RenameFlags, ResolveFlags, Resolved, ResolvedPath};
 use axfs::fops::OpenOptions;
 use axfs_vfs::structs::VfsNodeAttrX;
 use axio::{PollState, SeekFrom};
@@ -26,6 +28,8 @@ use crate::{ctypes, utils::char_ptr_to_str};
 pub const UTIME_NOW: c_long = (1 << 30) - 1;
 pub const UTIME_OMIT: c_long = (1 << 30) - 2;
 const AT_EMPTY_PATH: c_int = 0x1000;
+const AT_SYMLINK_NOFOLLOW: c_int = 0x100;
+const AT_SYMLINK_FOLLOW: c_int = 0x400;
 // use crate::time;
 /// File wrapper for `axfs::fops::File`.
 pub struct File {
@@ -41,8 +45,8 @@ impl File {
         }
     }
 
-    fn add_to_fd_table(self) -> LinuxResult<c_int> {
-        super::fd_ops::add_file_like(Arc::new(self))
+    fn add_to_fd_table(self, flags: c_int) -> LinuxResult<c_int> {
+        add_open_fd(Arc::new(self), flags)
     }
 
     pub fn from_fd(fd: c_int) -> LinuxResult<Arc<Self>> {
@@ -79,9 +83,19 @@ fn get_c_string_length(name: *const c_char) -> usize {
     len
 }
 
+/// Convert the error of a read. Devices blocked in a read, such as the
+/// terminal, return [`BadState`](axerrno::AxError::BadState) when the task is
+/// interrupted, for lack of `EINTR` in `AxError`.
+fn read_error(err: axerrno::AxError) -> LinuxError {
+    match err {
+        axerrno::AxError::BadState if super::task::interrupted() => super::task::erestartsys(),
+        err => err.into(),
+    }
+}
+
 impl FileLike for File {
     fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
-        Ok(self.inner.lock().read(buf)?)
+        self.inner.lock().read(buf).map_err(read_error)
     }
 
     fn write(&self, buf: &[u8]) -> LinuxResult<usize> {
@@ -102,7 +116,7 @@ impl FileLike for File {
         self.inner
             .lock()
             .read_at(_offset, _buf)
-            .map_err(LinuxError::from)
+            .map_err(read_error)
     }
 
     fn write_at(&self, _buf: &[u8], _offset: u64) -> LinuxResult<usize> {
@@ -113,6 +127,7 @@ impl FileLike for File {
     }
 
     fn set_atime(&self, atime: u32, atime_n: u32) -> LinuxResult<usize> {
+        check_writable(self.inner.lock().mount_flags())?;
         let r = self
             .inner
             .lock()
@@ -121,6 +136,7 @@ impl FileLike for File {
         Ok(r)
     }
     fn set_mtime(&self, mtime: u32, mtime_n: u32) -> LinuxResult<usize> {
+        check_writable(self.inner.lock().mount_flags())?;
         let r = self
             .inner
             .lock()
@@ -139,7 +155,13 @@ impl FileLike for File {
         })
     }
 
-    fn set_nonblocking(&self, _nonblocking: bool) -> LinuxResult {
+    fn set_nonblocking(&self, nonblocking: bool) -> LinuxResult {
+        self.inner.lock().set_nonblocking(nonblocking);
+        Ok(())
+    }
+
+    fn set_append(&self, append: bool) -> LinuxResult {
+        self.inner.lock().set_append(append);
         Ok(())
     }
 
@@ -169,17 +191,19 @@ impl FileLike for File {
         size: usize,
         flags: usize,
     ) -> LinuxResult<usize> {
+        check_writable(self.inner.lock().mount_flags())?;
         let name_len = get_c_string_length(name.clone());
         Ok(self.inner.lock().set_xattr(name, name_len, value, size)?)
     }
 
     fn fremovexattr(&self, name: *const c_char) -> LinuxResult<usize> {
+        check_writable(self.inner.lock().mount_flags())?;
         let name_len = get_c_string_length(name.clone());
         Ok(self.inner.lock().remove_xattr(name, name_len)?)
     }
 }
 
-fn attr2stat(metadata: VfsNodeAttr) -> ctypes::stat {
+pub(crate) fn attr2stat(metadata: VfsNodeAttr) -> ctypes::stat {
     let ty = metadata.file_type() as u8;
     let perm = metadata.perm().bits() as u32;
     let st_mode = ((ty as u32) << 12) | perm;
@@ -190,6 +214,7 @@ fn attr2stat(metadata: VfsNodeAttr) -> ctypes::stat {
         st_nlink: metadata.nlink() as _,
         st_uid: metadata.uid() as _,
         st_gid: metadata.gid() as _,
+        st_rdev: metadata.rdev() as _,
         st_size: metadata.size() as _,
         st_blksize: 512,
         st_blocks: metadata.blocks() as _,
@@ -257,9 +282,10 @@ fn attr2statx(metadata: VfsNodeAttrX) -> statx {
 }
 
 /// Convert open flags to [`OpenOptions`].
-fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
+fn flags_to_options(flags: c_int, mode: ctypes::mode_t) -> OpenOptions {
     let flags = flags as u32;
     let mut options = OpenOptions::new();
+    options.mode(mode as u32 & 0o7777);
     match flags & 0b11 {
         ctypes::O_RDONLY => options.read(true),
         ctypes::O_WRONLY => options.write(true),
@@ -287,6 +313,56 @@ fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
     options
 }
 
+/// Fail with `EROFS` if `flags` are those of a read-only mount.
+fn check_writable(flags: MountFlags) -> LinuxResult {
+    if flags.contains(MountFlags::RDONLY) {
+        return Err(LinuxError::EROFS);
+    }
+    Ok(())
+}
+
+/// Fail with `EROFS` if `path` lies on a read-only mount, following a
+/// symbolic link in the last component if `follow` is set.
+fn check_mount_writable(path: &str, follow: bool) -> LinuxResult {
+    check_writable(axfs::api::mount_flags(path, follow))
+}
+
+/// Fail with `EROFS` if opening `resolved` with the open `flags` would modify
+/// a read-only mount: by writing to or truncating a file that is not a FIFO,
+/// socket or device, or by creating a missing file.
+fn check_open_writable(resolved: &ResolvedPath, flags: c_int) -> LinuxResult {
+    let flags = flags as u32;
+    let modify = match &resolved.target {
+        Resolved::Node(node) => {
+            let special = node.get_attr().is_ok_and(|attr| {
+                matches!(
+                    attr.file_type(),
+                    VfsNodeType::Fifo
+                        | VfsNodeType::CharDevice
+                        | VfsNodeType::BlockDevice
+                        | VfsNodeType::Socket
+                )
+            });
+            flags & (0b11 | ctypes::O_TRUNC) != 0 && !special
+        }
+        Resolved::Missing { .. } => flags & ctypes::O_CREAT != 0,
+    };
+    if modify {
+        check_writable(resolved.mount.flags())?;
+    }
+    Ok(())
+}
+
+/// [`check_open_writable`] for `path` relative to the directory `start`.
+fn check_open_path(start: &str, path: &str, flags: c_int) -> LinuxResult {
+    let follow = flags as u32 & ctypes::O_NOFOLLOW == 0;
+    match axfs::api::resolve_path(start, path, ResolveFlags::empty(), follow) {
+        Ok(resolved) => check_open_writable(&resolved, flags),
+        // the open reports the error itself
+        Err(_) => Ok(()),
+    }
+}
+
 /// Open a file by `filename` and insert it into the file descriptor table.
 ///
 /// Return its index in the file table (`fd`). Return `EMFILE` if it already
@@ -295,11 +371,14 @@ pub fn sys_open(filename: *const c_char, flags: c_int, mode: ctypes::mode_t) ->
     let filename = char_ptr_to_str(filename);
     debug!("sys_open <= {:?} {:#o} {:#o}", filename, flags, mode);
     syscall_body!(sys_open, {
+        let filename = filename?;
+        check_open_path(".", filename, flags)?;
         add_file_or_directory_fd(
             axfs::fops::File::open,
             axfs::fops::Directory::open_dir,
-            filename?,
+            filename,
             &flags_to_options(flags, mode),
+            flags,
         )
     })
 }
@@ -332,11 +411,13 @@ pub fn sys_openat(
 
     Directory::from_fd(dirfd)
         .and_then(|dir| {
+            check_open_path(&dir.path(), filename, flags)?;
             add_file_or_directory_fd(
                 |filename, options| dir.inner.lock().open_file_at(filename, options),
                 |filename, options| dir.inner.lock().open_dir_at(filename, options),
                 filename,
                 &flags_to_options(flags, mode),
+                flags,
             )
         })
         .unwrap_or_else(|e| {
@@ -345,8 +426,128 @@ pub fn sys_openat(
         })
 }
 
+/// Flags accepted by `openat2`.
+const VALID_OPEN_FLAGS: u32 = 0b11
+    | ctypes::O_CREAT
+    | ctypes::O_EXCL
+    | ctypes::O_NOCTTY
+    | ctypes::O_TRUNC
+    | ctypes::O_APPEND
+    | ctypes::O_NONBLOCK
+    | ctypes::O_DSYNC
+    | ctypes::O_SYNC
+    | ctypes::O_ASYNC
+    | ctypes::O_DIRECT
+    | ctypes::O_LARGEFILE
+    | ctypes::O_DIRECTORY
+    | ctypes::O_NOFOLLOW
+    | ctypes::O_NOATIME
+    | ctypes::O_CLOEXEC
+    | ctypes::O_PATH
+    | ctypes::O_TMPFILE;
+
+/// Open a file like [`sys_openat`], with the path walk restricted by the
+/// `RESOLVE_*` flags in `how`, see `openat2(2)`.
+///
+/// `size` is the size of `how` known to the caller, later extensions of
+/// `open_how` must be zero.
+pub fn sys_openat2(
+    dirfd: c_int,
+    filename: *const c_char,
+    how: *const open_how,
+    size: usize,
+) -> LinuxResult<isize> {
+    const OPEN_HOW_SIZE_VER0: usize = 24;
+    // 与 Linux 相同，结构体最大不超过一页
+    const OPEN_HOW_SIZE_MAX: usize = 4096;
+    if how.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    if size < OPEN_HOW_SIZE_VER0 {
+        return Err(LinuxError::EINVAL);
+    }
+    if size > OPEN_HOW_SIZE_MAX {
+        return Err(LinuxError::E2BIG);
+    }
+    if size > size_of::<open_how>() {
+        let ext = unsafe {
+            slice::from_raw_parts(
+                (how as *const u8).add(size_of::<open_how>()),
+                size - size_of::<open_how>(),
+            )
+        };
+        if ext.iter().any(|&b| b != 0) {
+            return Err(LinuxError::E2BIG);
+        }
+    }
+    let how = unsafe { how.read() };
+    let filename = char_ptr_to_str(filename)?;
+    debug!(
+        "sys_openat2 <= {} {:?} {:#o} {:#o} {:#x}",
+        dirfd, filename, how.flags, how.mode, how.resolve
+    );
+
+    if how.flags & !(VALID_OPEN_FLAGS as u64) != 0 || how.mode & !0o7777 != 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let flags = how.flags as c_int;
+    let creates = how.flags as u32 & ctypes::O_CREAT != 0
+        || how.flags as u32 & ctypes::O_TMPFILE == ctypes::O_TMPFILE;
+    if how.mode != 0 && !creates {
+        return Err(LinuxError::EINVAL);
+    }
+    let resolve = ResolveFlags::from_bits(how.resolve).ok_or(LinuxError::EINVAL)?;
+    if resolve.contains(ResolveFlags::BENEATH | ResolveFlags::IN_ROOT) {
+        return Err(LinuxError::EINVAL);
+    }
+    if filename.is_empty() {
+        return Err(LinuxError::ENOENT);
+    }
+
+    let nofollow = how.flags as u32 & ctypes::O_NOFOLLOW != 0;
+    // 直接打开解析时检查过的节点，不再按路径重新查找
+    let resolved = resolve_file_path(dirfd as isize, filename, resolve, !nofollow)?;
+    match &resolved.target {
+        Resolved::Node(node) => {
+            let attr = node.get_attr()?;
+            if nofollow && attr.file_type().is_symlink() {
+                return Err(LinuxError::ELOOP);
+            }
+            if filename.ends_with('/') && !attr.is_dir() {
+                return Err(LinuxError::ENOTDIR);
+            }
+        }
+        Resolved::Missing { .. } if filename.ends_with('/') => {
+            return Err(if creates {
+                LinuxError::EISDIR
+            } else {
+                LinuxError::ENOENT
+            });
+        }
+        Resolved::Missing { .. } => {}
+    }
+    check_open_writable(&resolved, flags)?;
+    add_file_or_directory_fd(
+        |_, opts| axfs::fops::File::open_resolved(&resolved, opts),
+        |_, opts| axfs::fops::Directory::open_resolved(&resolved, opts),
+        &resolved.path,
+        &flags_to_options(flags, how.mode as ctypes::mode_t),
+        flags,
+    )
+    .map(|fd| fd as isize)
+}
+
+/// Apply the `mode` of `mkdirat` to a new directory, on filesystems that
+/// keep modes.
+fn set_dir_mode(path: &str, mode: ctypes::mode_t) -> axio::Result<()> {
+    let perm = axfs::api::Permissions::from_bits_truncate((mode & 0o7777) as u16);
+    match axfs::api::set_permissions(path, perm, true) {
+        Err(axerrno::AxError::Unsupported) => Ok(()),
+        res => res,
+    }
+}
+
 /// Create a directory by `dirname` relatively to `dirfd`.
-/// TODO: handle `mode`
 pub fn sys_mkdirat(dirfd: c_int, dirname: *const c_char, mode: ctypes::mode_t) -> c_int {
     let dirname = match char_ptr_to_str(dirname) {
         Ok(s) => s,
@@ -356,15 +557,23 @@ pub fn sys_mkdirat(dirfd: c_int, dirname: *const c_char, mode: ctypes::mode_t) -
     debug!("sys_mkdirat <= {} {:?} {:#o}", dirfd, dirname, mode);
 
     if dirname.starts_with('/') || dirfd == AT_FDCWD as _ {
-        return create_dir(dirname).and(Ok(0)).unwrap_or_else(|e| {
-            debug!("sys_mkdirat => {}", e);
-            -1
-        });
+        if let Err(e) = check_mount_writable(dirname, false) {
+            return -(e.code() as c_int);
+        }
+        return create_dir(dirname)
+            .and_then(|_| set_dir_mode(dirname, mode))
+            .and(Ok(0))
+            .unwrap_or_else(|e| {
+                debug!("sys_mkdirat => {}", e);
+                -1
+            });
     }
 
     Directory::from_fd(dirfd)
         .and_then(|dir| {
+            check_mount_writable(&join(&dir.path(), &[dirname]), false)?;
             dir.inner.lock().create_dir(dirname);
+            set_dir_mode(&join(&dir.path(), &[dirname]), mode)?;
             Ok(0)
         })
         .unwrap_or_else(|e| {
@@ -503,6 +712,50 @@ pub unsafe fn sys_fstatat(
     Ok(0)
 }
 
+/// Add an opened file or directory to the file descriptor table, as a new
+/// open file description with the `flags` given to `open`.
+fn add_open_fd(file: Arc<dyn FileLike>, flags: c_int) -> LinuxResult<c_int> {
+    let file = Arc::new(OpenFile::new(file, flags as u32));
+    add_open_file(file, flags & ctypes::O_CLOEXEC as c_int != 0)
+}
+
+/// Attach an opened `file` to what it stands for: a FIFO to its pipe buffer,
+/// a device node to the device registered under its device number.
+fn open_special(
+    mut file: axfs::fops::File,
+    path: &str,
+    flags: c_int,
+) -> LinuxResult<Arc<dyn FileLike>> {
+    let attr = file.get_attr()?;
+    match attr.file_type() {
+        VfsNodeType::Fifo => {
+            #[cfg(feature = "pipe")]
+            {
+                return Ok(Arc::new(super::pipe::open_fifo(file, flags as u32)?));
+            }
+            #[cfg(not(feature = "pipe"))]
+            return Err(LinuxError::ENXIO);
+        }
+        // nodev 挂载上的设备文件不能打开
+        VfsNodeType::CharDevice | VfsNodeType::BlockDevice
+            if file.mount_flags().contains(MountFlags::NODEV) =>
+        {
+            return Err(LinuxError::EACCES);
+        }
+        // 设备号为 0 的节点没有登记的设备，只能按节点本身打开；
+        // devfs 中的节点本身就是登记的设备，open_device 不会重复打开
+        VfsNodeType::CharDevice | VfsNodeType::BlockDevice if attr.rdev() != 0 => {
+            file.open_device().map_err(|e| match e {
+                axerrno::AxError::NotFound => LinuxError::ENXIO,
+                e => e.into(),
+            })?;
+        }
+        _ => {}
+    }
+    file.set_nonblocking(flags as u32 & ctypes::O_NONBLOCK != 0);
+    Ok(Arc::new(File::new(file, path.into())))
+}
+
 /// Use the function to open file or directory, then add into file descriptor table.
 /// First try opening files, if fails, try directory.
 pub fn add_file_or_directory_fd<F, D, E>(
@@ -510,6 +763,7 @@ pub fn add_file_or_directory_fd<F, D, E>(
     open_dir: D,
     filename: &str,
     options: &OpenOptions,
+    flags: c_int,
 ) -> LinuxResult<c_int>
 where
     E: Into<LinuxError>,
@@ -519,18 +773,15 @@ where
     if !options.has_directory() {
         match open_file(filename, options)
             .map_err(Into::into)
-            .and_then(|f| File::new(f, filename.into()).add_to_fd_table())
+            .and_then(|f| open_special(f, filename, flags))
+            .and_then(|f| add_open_fd(f, flags))
         {
             Err(LinuxError::EISDIR) => {}
             r => return r,
         }
     }
 
-    Directory::new(
-        open_dir(filename, options).map_err(Into::into)?,
-        filename.to_string(),
-    )
-    .add_to_fd_table()
+    Directory::new(open_dir(filename, options).map_err(Into::into)?).add_to_fd_table(flags)
 }
 
 /// Set the position of the file indicated by `fd`.
@@ -814,6 +1065,8 @@ pub fn sys_rename(old: *const c_char, new: *const c_char) -> c_int {
         let old_path = char_ptr_to_str(old)?;
         let new_path = char_ptr_to_str(new)?;
         debug!("sys_rename <= old: {:?}, new: {:?}", old_path, new_path);
+        check_mount_writable(old_path, false)?;
+        check_mount_writable(new_path, false)?;
         axfs::api::rename(old_path, new_path)?;
         Ok(0)
     })
@@ -837,7 +1090,7 @@ pub fn sys_renameat(
             old_path.to_string()
         } else {
             match Directory::from_fd(old_dirfd) {
-                Ok(old_dir) => join(old_dir.path(), &[old_path]),
+                Ok(old_dir) => join(&old_dir.path(), &[old_path]),
                 Err(_) => return Err(LinuxError::EBADF), // 无效的文件描述符
             }
         };
@@ -846,31 +1099,71 @@ pub fn sys_renameat(
             new_path.to_string()
         } else {
             match Directory::from_fd(new_dirfd) {
-                Ok(new_dir) => join(new_dir.path(), &[new_path]),
+                Ok(new_dir) => join(&new_dir.path(), &[new_path]),
                 Err(_) => return Err(LinuxError::EBADF), // 无效的文件描述符
             }
         };
         debug!("sys_rename <= old: {:?}, new: {:?}", old_path, new_path);
+        check_mount_writable(&old_path, false)?;
+        check_mount_writable(&new_path, false)?;
         axfs::api::rename(&old_path, &new_path)?;
         Ok(0)
     })
 }
+/// Rename `old` to `new` like [`sys_renameat`], with the `RENAME_*` `flags`,
+/// see `renameat2(2)`.
+///
+/// Without flags an existing `new` is replaced, atomically except on FAT.
+/// Return `EXDEV` if the two paths are on different mounts.
+pub fn sys_renameat2(
+    old_dirfd: c_int,
+    old: *const c_char,
+    new_dirfd: c_int,
+    new: *const c_char,
+    flags: c_uint,
+) -> LinuxResult<isize> {
+    let old = char_ptr_to_str(old)?;
+    let new = char_ptr_to_str(new)?;
+    debug!(
+        "sys_renameat2 <= {} {:?} {} {:?} {:#x}",
+        old_dirfd, old, new_dirfd, new, flags
+    );
+    let flags = RenameFlags::from_bits(flags).ok_or(LinuxError::EINVAL)?;
+    if flags.contains(RenameFlags::EXCHANGE)
+        && flags.intersects(RenameFlags::NOREPLACE | RenameFlags::WHITEOUT)
+    {
+        return Err(LinuxError::EINVAL);
+    }
+    if old.is_empty() || new.is_empty() {
+        return Err(LinuxError::ENOENT);
+    }
+
+    let old_path = resolve_file_path(old_dirfd as isize, old, ResolveFlags::empty(), false)?.path;
+    let new_path = resolve_file_path(new_dirfd as isize, new, ResolveFlags::empty(), false)?.path;
+    check_mount_writable(&old_path, false)?;
+    check_mount_writable(&new_path, false)?;
+    axfs::api::rename2(&old_path, &new_path, flags).map_err(|err| match resolve_error(err) {
+        // 文件系统不支持所给的标志
+        LinuxError::ENOSYS if !flags.is_empty() => LinuxError::EINVAL,
+        err => err,
+    })?;
+    Ok(0)
+}
+
 /// Directory wrapper for `axfs::fops::Directory`.
 pub struct Directory {
     inner: Mutex<axfs::fops::Directory>,
-    path: String,
 }
 
 impl Directory {
-    fn new(inner: axfs::fops::Directory, path: String) -> Self {
+    fn new(inner: axfs::fops::Directory) -> Self {
         Self {
             inner: Mutex::new(inner),
-            path,
         }
     }
 
-    fn add_to_fd_table(self) -> LinuxResult<c_int> {
-        super::fd_ops::add_file_like(Arc::new(self))
+    fn add_to_fd_table(self, flags: c_int) -> LinuxResult<c_int> {
+        add_open_fd(Arc::new(self), flags)
     }
 
     /// Open a directory by `fd`.
@@ -881,9 +1174,9 @@ impl Directory {
             .map_err(|_| LinuxError::EINVAL)
     }
 
-    /// Get the path of the directory.
-    pub fn path(&self) -> &str {
-        &self.path
+    /// Get the current absolute path of the directory.
+    pub fn path(&self) -> String {
+        self.inner.lock().path()
     }
 }
 
@@ -942,6 +1235,7 @@ impl FileLike for Directory {
     }
 
     fn set_atime(&self, atime: u32, atime_n: u32) -> LinuxResult<usize> {
+        check_writable(self.inner.lock().mount_flags())?;
         let r = self
             .inner
             .lock()
@@ -950,6 +1244,7 @@ impl FileLike for Directory {
         Ok(r)
     }
     fn set_mtime(&self, mtime: u32, mtime_n: u32) -> LinuxResult<usize> {
+        check_writable(self.inner.lock().mount_flags())?;
         let r = self
             .inner
             .lock()
@@ -983,10 +1278,12 @@ impl FileLike for Directory {
         size: usize,
         flags: usize,
     ) -> LinuxResult<usize> {
+        check_writable(self.inner.lock().mount_flags())?;
         let name_len = get_c_string_length(name.clone());
         Ok(self.inner.lock().set_xattr(name, name_len, value, size)?)
     }
     fn fremovexattr(&self, name: *const c_char) -> LinuxResult<usize> {
+        check_writable(self.inner.lock().mount_flags())?;
         let name_len = get_c_string_length(name.clone());
         Ok(self.inner.lock().remove_xattr(name, name_len)?)
     }
@@ -1205,6 +1502,7 @@ pub unsafe fn sys_getdents(
 pub fn sys_unlink(path: *const c_char) -> LinuxResult<isize> {
     let path = char_ptr_to_str(path).map_err(|_| LinuxError::EFAULT)?;
     warn!("sys_unlink <= {:?}", path);
+    check_mount_writable(path, false)?;
     remove_file(path)?;
     Ok(0)
 }
@@ -1215,22 +1513,272 @@ pub fn sys_unlinkat(dir_fd: i32, path: *const c_char) -> LinuxResult<isize> {
     let dir: Arc<Directory> = Directory::from_fd(dir_fd)?;
     let path = char_ptr_to_str(path).map_err(|_| LinuxError::EFAULT)?;
     warn!("sys_unlinkat <= {dir_fd} {:?}", path);
+    check_mount_writable(&join(&dir.path(), &[path]), false)?;
     dir.inner.lock().remove_file(path)?;
     Ok(0)
 }
 
+/// Resolve `path` relative to the directory `dirfd`.
+fn path_at(dirfd: c_int, path: &str) -> LinuxResult<String> {
+    if path.starts_with('/') || dirfd == AT_FDCWD as c_int {
+        Ok(path.into())
+    } else {
+        Ok(join(&Directory::from_fd(dirfd)?.path(), &[path]))
+    }
+}
+
+/// Path of the file or directory opened as `fd`.
+fn fd_path(fd: c_int) -> LinuxResult<String> {
+    match File::from_fd(fd) {
+        Ok(file) => Ok(file.path().into()),
+        Err(_) => Ok(Directory::from_fd(fd).map_err(|_| LinuxError::EBADF)?.path()),
+    }
+}
+
+/// Target of the `/proc/self/fd/<fd>` link: the path of an opened file or
+/// directory, the console for the standard streams, and names like
+/// `pipe:[ino]` that lead nowhere, as on Linux, for the other files.
+pub fn fd_link_target(fd: c_int) -> LinuxResult<String> {
+    if let Ok(path) = fd_path(fd) {
+        return Ok(path);
+    }
+    let st = get_file_like(fd)?.stat()?;
+    Ok(match st.st_mode & 0o170000 {
+        0o020000 => "/dev/console".into(),
+        0o010000 => alloc::format!("pipe:[{}]", st.st_ino),
+        0o140000 => alloc::format!("socket:[{}]", st.st_ino),
+        _ => "anon_inode:[eventpoll]".into(),
+    })
+}
+
+/// Path for the `*at` syscalls, where an empty path with `AT_EMPTY_PATH`
+/// refers to `dirfd` itself.
+fn path_at_or_fd(dirfd: c_int, path: &str, flags: c_int) -> LinuxResult<String> {
+    if path.is_empty() {
+        if flags & AT_EMPTY_PATH == 0 {
+            return Err(LinuxError::ENOENT);
+        }
+        return fd_path(dirfd);
+    }
+    path_at(dirfd, path)
+}
+
+/// Create a hard link `newpath` to `oldpath`.
+pub fn sys_linkat(
+    olddirfd: c_int,
+    oldpath: *const c_char,
+    newdirfd: c_int,
+    newpath: *const c_char,
+    flags: c_int,
+) -> LinuxResult<isize> {
+    let old = char_ptr_to_str(oldpath)?;
+    let new = char_ptr_to_str(newpath)?;
+    debug!(
+        "sys_linkat <= {} {:?} {} {:?} {:#x}",
+        olddirfd, old, newdirfd, new, flags
+    );
+    if flags & !(AT_SYMLINK_FOLLOW | AT_EMPTY_PATH) != 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let old = path_at_or_fd(olddirfd, old, flags)?;
+    let new = path_at(newdirfd, new)?;
+    check_mount_writable(&new, false)?;
+    axfs::api::hard_link(&old, &new, flags & AT_SYMLINK_FOLLOW != 0).map_err(resolve_error)?;
+    Ok(0)
+}
+
+/// Create a symbolic link `linkpath` containing `target`.
+pub fn sys_symlinkat(
+    target: *const c_char,
+    newdirfd: c_int,
+    linkpath: *const c_char,
+) -> LinuxResult<isize> {
+    let target = char_ptr_to_str(target)?;
+    let link = char_ptr_to_str(linkpath)?;
+    debug!("sys_symlinkat <= {:?} {} {:?}", target, newdirfd, link);
+    let link = path_at(newdirfd, link)?;
+    check_mount_writable(&link, false)?;
+    axfs::api::symlink(target, &link)?;
+    Ok(0)
+}
+
+/// Create a filesystem node `path`: a regular file, a FIFO, a socket or a
+/// device node with the device number `dev`, as given by the type in `mode`.
+pub fn sys_mknodat(
+    dirfd: c_int,
+    path: *const c_char,
+    mode: ctypes::mode_t,
+    dev: u64,
+) -> LinuxResult<isize> {
+    const S_IFMT: u32 = 0o170000;
+    let path = char_ptr_to_str(path)?;
+    debug!("sys_mknodat <= {} {:?} {:#o} {:#x}", dirfd, path, mode, dev);
+    let ty = match mode & S_IFMT {
+        0 | 0o100000 => None,
+        0o010000 => Some(VfsNodeType::Fifo),
+        0o020000 => Some(VfsNodeType::CharDevice),
+        0o060000 => Some(VfsNodeType::BlockDevice),
+        0o140000 => Some(VfsNodeType::Socket),
+        0o040000 => return Err(LinuxError::EPERM),
+        _ => return Err(LinuxError::EINVAL),
+    };
+    let path = path_at(dirfd, path)?;
+    check_mount_writable(&path, false)?;
+    match ty {
+        Some(ty) => {
+            axfs::api::mknod(&path, ty, dev)?;
+            let perm = axfs::api::Permissions::from_bits_truncate((mode & 0o7777) as u16);
+            match axfs::api::set_permissions(&path, perm, false) {
+                Err(axerrno::AxError::Unsupported) => {}
+                res => res?,
+            }
+        }
+        None => {
+            let mut options = OpenOptions::new();
+            options.write(true);
+            options.create_new(true);
+            options.mode(mode & 0o7777);
+            axfs::fops::File::open(&path, &options)?;
+        }
+    }
+    Ok(0)
+}
+
+/// Read the target of the symbolic link `path`, without a trailing NUL.
+pub fn sys_readlinkat(
+    dirfd: c_int,
+    path: *const c_char,
+    buf: *mut c_char,
+    bufsiz: usize,
+) -> LinuxResult<isize> {
+    let path = char_ptr_to_str(path)?;
+    debug!("sys_readlinkat <= {} {:?} {}", dirfd, path, bufsiz);
+    if buf.is_null() || bufsiz == 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let target = axfs::api::read_link(&path_at_or_fd(dirfd, path, AT_EMPTY_PATH)?)?;
+    let len = target.len().min(bufsiz);
+    unsafe { ptr::copy_nonoverlapping(target.as_ptr(), buf as *mut u8, len) };
+    Ok(len as isize)
+}
+
+/// Change the permission mode of `path`.
+pub fn sys_fchmodat(
+    dirfd: c_int,
+    path: *const c_char,
+    mode: ctypes::mode_t,
+    flags: c_int,
+) -> LinuxResult<isize> {
+    let path = char_ptr_to_str(path)?;
+    debug!("sys_fchmodat <= {} {:?} {:#o} {:#x}", dirfd, path, mode, flags);
+    if flags & !(AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH) != 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let path = path_at_or_fd(dirfd, path, flags)?;
+    check_mount_writable(&path, flags & AT_SYMLINK_NOFOLLOW == 0)?;
+    let perm = axfs::api::Permissions::from_bits_truncate((mode & 0o7777) as u16);
+    axfs::api::set_permissions(&path, perm, flags & AT_SYMLINK_NOFOLLOW == 0)?;
+    Ok(0)
+}
+
+/// Change the permission mode of the file opened as `fd`.
+pub fn sys_fchmod(fd: c_int, mode: ctypes::mode_t) -> LinuxResult<isize> {
+    sys_fchmodat(fd, c"".as_ptr(), mode, AT_EMPTY_PATH)
+}
+
+/// Change the owner and group of `path`, `-1` keeps the current one.
+pub fn sys_fchownat(
+    dirfd: c_int,
+    path: *const c_char,
+    owner: u32,
+    group: u32,
+    flags: c_int,
+) -> LinuxResult<isize> {
+    let path = char_ptr_to_str(path)?;
+    debug!(
+        "sys_fchownat <= {} {:?} {} {} {:#x}",
+        dirfd, path, owner as i32, group as i32, flags
+    );
+    if flags & !(AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH) != 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let path = path_at_or_fd(dirfd, path, flags)?;
+    check_mount_writable(&path, flags & AT_SYMLINK_NOFOLLOW == 0)?;
+    let id = |id: u32| (id != u32::MAX).then_some(id);
+    match axfs::api::chown(&path, id(owner), id(group), flags & AT_SYMLINK_NOFOLLOW == 0) {
+        // filesystems without owners only have root's files
+        Err(axerrno::AxError::Unsupported) => Ok(0),
+        res => res.map(|_| 0).map_err(LinuxError::from),
+    }
+}
+
+/// Change the owner and group of the file opened as `fd`.
+pub fn sys_fchown(fd: c_int, owner: u32, group: u32) -> LinuxResult<isize> {
+    sys_fchownat(fd, c"".as_ptr(), owner, group, AT_EMPTY_PATH)
+}
+
+/// Abort pending requests of the filesystem before unmounting it. None of
+/// the filesystems here have any, so a busy mount still fails with `EBUSY`.
+const MNT_FORCE: c_int = 1;
+/// Lazily unmount: detach the filesystem and its submounts now.
+const MNT_DETACH: c_int = 2;
+/// Only mark the mount as expired (not supported).
+const MNT_EXPIRE: c_int = 4;
+/// Do not dereference the target if it is a symbolic link.
+const UMOUNT_NOFOLLOW: c_int = 8;
+
+/// Mount the filesystem `fstype` from `src` at `mnt`.
+///
+/// `src` is a block device such as `/dev/vdb` or `/dev/vda2` for disk
+/// filesystems, or the source directory for `MS_BIND`.
 pub fn sys_mount(
     src: *const c_char,
     mnt: *const c_char,
     fstype: *const c_char,
     mntflag: usize,
+    data: *const c_void,
 ) -> LinuxResult<isize> {
-    debug!("mount simple return");
+    let target = char_ptr_to_str(mnt)?;
+    let data = if data.is_null() { "" } else { char_ptr_to_str(data as *const c_char)? };
+    let source = if src.is_null() { "" } else { char_ptr_to_str(src)? };
+    let fstype = if fstype.is_null() { "" } else { char_ptr_to_str(fstype)? };
+    let flags = MountFlags::from_bits_truncate(mntflag as u32);
+    debug!(
+        "sys_mount <= {:?} {:?} {:?} {:?}",
+        source, target, fstype, flags
+    );
+    if !flags.intersects(MountFlags::REMOUNT | MountFlags::BIND)
+        && !axfs::api::filesystems().contains(&fstype)
+    {
+        return Err(LinuxError::ENODEV);
+    }
+    axfs::api::mount(source, target, fstype, flags, data)?;
     Ok(0)
 }
 
-pub fn sys_umount2(mnt: *const c_char) -> LinuxResult<isize> {
-    debug!("umount2 simple return");
+/// Unmount the filesystem mounted at `mnt`.
+///
+/// A filesystem with open files, directories or working directories on it
+/// is busy unless `MNT_DETACH` is given, in which case it is detached at
+/// once and unmounted when the last of them is closed. `MNT_FORCE` does not
+/// make a busy filesystem unmountable.
+pub fn sys_umount2(mnt: *const c_char, flags: c_int) -> LinuxResult<isize> {
+    let target = char_ptr_to_str(mnt)?;
+    debug!("sys_umount2 <= {:?} {:#x}", target, flags);
+    if flags & !(MNT_FORCE | MNT_DETACH | MNT_EXPIRE | UMOUNT_NOFOLLOW) != 0
+        || (flags & MNT_EXPIRE != 0 && flags & (MNT_FORCE | MNT_DETACH) != 0)
+    {
+        return Err(LinuxError::EINVAL);
+    }
+    if flags & MNT_EXPIRE != 0 {
+        return Err(LinuxError::EAGAIN);
+    }
+    if flags & MNT_FORCE != 0 {
+        // 没有可中止的请求，按普通卸载处理
+        debug!("sys_umount2: MNT_FORCE has no requests to abort on {:?}", target);
+    }
+    let detach = flags & MNT_DETACH != 0;
+    axfs::api::umount(target, detach)?;
     Ok(0)
 }
 
@@ -1293,6 +1841,7 @@ pub fn sys_utimensat(
     if !path.is_null() {
         let pathname = char_ptr_to_str(path).map_err(|_| LinuxError::EBADF)?;
         if dirfd == AT_FDCWD as i32 {
+            check_mount_writable(pathname, flags & AT_SYMLINK_NOFOLLOW == 0)?;
             let dir = CURRENT_DIR.lock().clone();
             let file = dir.lookup(pathname).map_err(|e| {
                 debug!("lookup failed: {:?}", e);
@@ -1358,6 +1907,19 @@ pub fn sys_pwrite64(fd: c_int, buf: *const u8, count: usize, offset: isize) -> L
     file.write_at(slice, offset as u64).map(|n| n as isize)
 }
 
+/// Write the data of the file opened as `fd` to its device, including the
+/// pages modified through shared memory mappings.
+pub fn sys_fsync(fd: c_int) -> LinuxResult<isize> {
+    File::from_fd(fd)?.inner().lock().flush()?;
+    Ok(0)
+}
+
+/// Write back all cached file data.
+pub fn sys_sync() -> LinuxResult<isize> {
+    axfs::api::sync();
+    Ok(0)
+}
+
 pub unsafe fn c_char_to_str(c_str: *const c_char) -> Result<&'static str, LinuxError> {
     if c_str.is_null() {
         return Err(LinuxError::EINVAL); // 或其它适当错误
@@ -8,7 +8,7 @@ use axio::PollState;
 use axnet::{TcpSocket, UdpSocket};
 use axsync::Mutex;
 
-use super::fd_ops::FileLike;
+use super::fd_ops::{FileLike, OpenFile};
 use crate::ctypes;
 use crate::utils::char_ptr_to_str;
 
@@ -18,8 +18,16 @@ pub enum Socket {
 }
 
 impl Socket {
-    fn add_to_fd_table(self) -> LinuxResult<c_int> {
-        super::fd_ops::add_file_like(Arc::new(self))
+    /// Adds the socket to the fd table, applying `SOCK_NONBLOCK` and
+    /// `SOCK_CLOEXEC` from `flags`.
+    fn add_to_fd_table(self, flags: u32) -> LinuxResult<c_int> {
+        let nonblock = flags & ctypes::SOCK_NONBLOCK;
+        if nonblock != 0 {
+            self.set_nonblocking(true)?;
+        }
+        // SOCK_NONBLOCK 与 O_NONBLOCK 取值相同
+        let file = OpenFile::new(Arc::new(self), ctypes::O_RDWR | nonblock);
+        super::fd_ops::add_open_file(Arc::new(file), flags & ctypes::SOCK_CLOEXEC != 0)
     }
 
     fn from_fd(fd: c_int) -> LinuxResult<Arc<Self>> {
@@ -36,7 +44,40 @@ impl Socket {
         }
     }
 
+    /// Waits until a blocking socket becomes readable, returns `EINTR` if
+    /// interrupted by a signal.
+    ///
+    /// Blocking receives in `axnet` cannot be interrupted, so we wait here and
+    /// the following receive will not block. Unconnected sockets are left to
+    /// the receive functions to report the error.
+    fn wait_readable(&self) -> LinuxResult {
+        let skip = match self {
+            Socket::Udp(udpsocket) => {
+                let udpsocket = udpsocket.lock();
+                udpsocket.is_nonblocking() || udpsocket.local_addr().is_err()
+            }
+            Socket::Tcp(tcpsocket) => {
+                let tcpsocket = tcpsocket.lock();
+                tcpsocket.is_nonblocking() || tcpsocket.peer_addr().is_err()
+            }
+        };
+        if skip {
+            return Ok(());
+        }
+        loop {
+            axnet::poll_interfaces();
+            if self.poll()?.readable {
+                return Ok(());
+            }
+            if super::task::interrupted() {
+                return Err(super::task::erestartsys());
+            }
+            crate::sys_sched_yield();
+        }
+    }
+
     fn recv(&self, buf: &mut [u8]) -> LinuxResult<usize> {
+        self.wait_readable()?;
         match self {
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().recv_from(buf).map(|e| e.0)?),
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().recv(buf)?),
@@ -87,6 +128,7 @@ impl Socket {
     }
 
     fn recvfrom(&self, buf: &mut [u8]) -> LinuxResult<(usize, Option<SocketAddr>)> {
+        self.wait_readable()?;
         match self {
             // diff: must bind before recvfrom
             Socket::Udp(udpsocket) => Ok(udpsocket
@@ -233,14 +275,16 @@ pub fn sys_socket(domain: c_int, socktype: c_int, protocol: c_int) -> c_int {
     debug!("sys_socket <= {} {} {}", domain, socktype, protocol);
     let (domain, socktype, protocol) = (domain as u32, socktype as u32, protocol as u32);
     syscall_body!(sys_socket, {
+        let flags = socktype & (ctypes::SOCK_CLOEXEC | ctypes::SOCK_NONBLOCK);
+        let socktype = socktype & !flags;
         match (domain, socktype, protocol) {
             (ctypes::AF_INET, ctypes::SOCK_STREAM, ctypes::IPPROTO_TCP)
             | (ctypes::AF_INET, ctypes::SOCK_STREAM, 0) => {
-                Socket::Tcp(Mutex::new(TcpSocket::new())).add_to_fd_table()
+                Socket::Tcp(Mutex::new(TcpSocket::new())).add_to_fd_table(flags)
             }
             (ctypes::AF_INET, ctypes::SOCK_DGRAM, ctypes::IPPROTO_UDP)
             | (ctypes::AF_INET, ctypes::SOCK_DGRAM, 0) => {
-                Socket::Udp(Mutex::new(UdpSocket::new())).add_to_fd_table()
+                Socket::Udp(Mutex::new(UdpSocket::new())).add_to_fd_table(flags)
             }
             _ => Err(LinuxError::EINVAL),
         }
@@ -407,19 +451,36 @@ pub unsafe fn sys_accept(
     socket_fd: c_int,
     socket_addr: *mut ctypes::sockaddr,
     socket_len: *mut ctypes::socklen_t,
+) -> c_int {
+    unsafe { sys_accept4(socket_fd, socket_addr, socket_len, 0) }
+}
+
+/// Accept for connections on a socket, with `SOCK_NONBLOCK` and
+/// `SOCK_CLOEXEC` applied to the new socket.
+///
+/// Return file descriptor for the accepted socket if success.
+pub unsafe fn sys_accept4(
+    socket_fd: c_int,
+    socket_addr: *mut ctypes::sockaddr,
+    socket_len: *mut ctypes::socklen_t,
+    flags: c_int,
 ) -> c_int {
     debug!(
-        "sys_accept <= {} {:#x} {:#x}",
-        socket_fd, socket_addr as usize, socket_len as usize
+        "sys_accept4 <= {} {:#x} {:#x} {:#x}",
+        socket_fd, socket_addr as usize, socket_len as usize, flags
     );
-    syscall_body!(sys_accept, {
+    syscall_body!(sys_accept4, {
+        let flags = flags as u32;
+        if flags & !(ctypes::SOCK_CLOEXEC | ctypes::SOCK_NONBLOCK) != 0 {
+            return Err(LinuxError::EINVAL);
+        }
         if socket_addr.is_null() || socket_len.is_null() {
             return Err(LinuxError::EFAULT);
         }
         let socket = Socket::from_fd(socket_fd)?;
         let new_socket = socket.accept()?;
         let addr = new_socket.peer_addr()?;
-        let new_fd = Socket::add_to_fd_table(Socket::Tcp(Mutex::new(new_socket)))?;
+        let new_fd = Socket::add_to_fd_table(Socket::Tcp(Mutex::new(new_socket)), flags)?;
         unsafe {
             (*socket_addr, *socket_len) = into_sockaddr(addr);
         }
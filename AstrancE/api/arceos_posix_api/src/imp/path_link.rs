@@ -5,8 +5,8 @@ use core::ops::Deref;
 use spin::RwLock;
 
 use alloc::string::{String, ToString};
-use axerrno::{AxError, AxResult};
-use axfs::api::{canonicalize, current_dir};
+use axerrno::{AxError, AxResult, LinuxError, LinuxResult};
+use axfs::api::{ResolveError, ResolveFlags, ResolvedPath, canonicalize, current_dir};
 
 /// 一个规范化的文件路径表示
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
@@ -335,6 +335,34 @@ pub fn handle_file_path(
     FilePath::new(&path)
 }
 
+/// 从 `dir_fd` 出发按 `resolve` 的限制逐个分量解析 `path`（见 openat2），
+/// 返回不含符号链接的绝对路径及其节点；`follow_last` 为假时不跟随最后一个分量的符号链接
+pub fn resolve_file_path(
+    dir_fd: isize,
+    path: &str,
+    resolve: ResolveFlags,
+    follow_last: bool,
+) -> LinuxResult<ResolvedPath> {
+    let start = if dir_fd == AT_FDCWD {
+        current_dir()?
+    } else {
+        super::fd_ops::get_file_like(dir_fd as i32)?;
+        super::fs::Directory::from_fd(dir_fd as i32)
+            .map_err(|_| LinuxError::ENOTDIR)?
+            .path()
+    };
+    axfs::api::resolve_path(&start, path, resolve, follow_last).map_err(resolve_error)
+}
+
+/// 把 axfs 的 [`ResolveError`] 转换为对应的 Linux 错误码
+pub(crate) fn resolve_error(err: ResolveError) -> LinuxError {
+    match err {
+        ResolveError::CrossDevice => LinuxError::EXDEV,
+        ResolveError::Loop => LinuxError::ELOOP,
+        ResolveError::Ax(err) => err.into(),
+    }
+}
+
 fn handle_empty_path(dir_fd: isize) -> AxResult<String> {
     const AT_FDCWD: isize = -100;
     if dir_fd == AT_FDCWD {
@@ -342,16 +370,16 @@ fn handle_empty_path(dir_fd: isize) -> AxResult<String> {
     }
 
     super::fs::Directory::from_fd(dir_fd as i32)
-        .map(|dir| dir.path().to_string())
+        .map(|dir| dir.path())
         .map_err(|_| AxError::NotFound)
 }
 
 fn handle_relative_path(dir_fd: isize, path: &str) -> AxResult<String> {
     match super::fs::Directory::from_fd(dir_fd as i32) {
         Ok(dir) => {
-            // 假设目录路径以 '/' 结尾，无需手动添加
-            let combined_path = format!("{}{}", dir.path(), path);
-            axlog::info!("处理后的路径: {} (目录: {})", combined_path, dir.path());
+            let dir_path = dir.path();
+            let combined_path = axfs::path::join(&dir_path, &[path]);
+            axlog::info!("处理后的路径: {} (目录: {})", combined_path, dir_path);
             Ok(combined_path)
         }
         Err(_) => {
@@ -1,108 +1,351 @@
-use alloc::sync::Arc;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
 use core::ffi::c_int;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use axerrno::{LinuxError, LinuxResult};
 use axio::PollState;
 use axsync::Mutex;
+#[cfg(feature = "multitask")]
+use axtask::WaitQueue;
 
-use super::fd_ops::{FileLike, add_file_like, close_file_like};
+use super::fd_ops::{FileLike, OpenFile, add_open_file, close_file_like};
 use crate::ctypes;
 
-#[derive(Copy, Clone, PartialEq)]
-enum RingBufferStatus {
-    Full,
-    Empty,
-    Normal,
-}
+const PAGE_SIZE: usize = 4096;
+/// Writes of at most `PIPE_BUF` bytes are atomic, they are never interleaved
+/// with data of other writes.
+pub const PIPE_BUF: usize = 4096;
+/// Default capacity of a pipe, 64 KiB as on Linux.
+const DEFAULT_PIPE_SIZE: usize = 16 * PAGE_SIZE;
+/// Largest capacity `F_SETPIPE_SZ` can set, like `/proc/sys/fs/pipe-max-size`.
+const PIPE_MAX_SIZE: usize = 1024 * 1024;
 
-const RING_BUFFER_SIZE: usize = 256;
+/// A run of data in the buffer: a packet written in packet mode (`O_DIRECT`),
+/// or bytes of ordinary writes, merged into one stream.
+struct Segment {
+    len: usize,
+    packet: bool,
+}
 
+/// A page-backed ring buffer.
 pub struct PipeRingBuffer {
-    arr: [u8; RING_BUFFER_SIZE],
+    /// 按页存放数据，页在写入时才分配，缓冲区读空后只保留第一页
+    pages: Vec<Option<Box<[u8]>>>,
     head: usize,
-    tail: usize,
-    status: RingBufferStatus,
+    len: usize,
+    segments: VecDeque<Segment>,
+    /// 当前打开的读端、写端数量
+    readers: usize,
+    writers: usize,
+    /// 读端、写端累计被打开的次数，用于 FIFO 的阻塞打开
+    read_opens: usize,
+    write_opens: usize,
 }
 
 impl PipeRingBuffer {
-    pub const fn new() -> Self {
+    /// Create an empty buffer of `size` bytes, a multiple of the page size.
+    pub fn new(size: usize) -> Self {
         Self {
-            arr: [0; RING_BUFFER_SIZE],
+            pages: (0..size / PAGE_SIZE).map(|_| None).collect(),
             head: 0,
-            tail: 0,
-            status: RingBufferStatus::Empty,
+            len: 0,
+            segments: VecDeque::new(),
+            readers: 0,
+            writers: 0,
+            read_opens: 0,
+            write_opens: 0,
         }
     }
 
-    pub fn write_byte(&mut self, byte: u8) {
-        self.status = RingBufferStatus::Normal;
-        self.arr[self.tail] = byte;
-        self.tail = (self.tail + 1) % RING_BUFFER_SIZE;
-        if self.tail == self.head {
-            self.status = RingBufferStatus::Full;
+    /// Get the capacity of the buffer
+    pub fn capacity(&self) -> usize {
+        self.pages.len() * PAGE_SIZE
+    }
+
+    /// Get the length of remaining data in the buffer
+    pub const fn available_read(&self) -> usize {
+        self.len
+    }
+
+    /// Get the length of remaining space in the buffer
+    pub fn available_write(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    /// Append `data` to the buffer, as a packet if `packet` is set. The caller
+    /// makes sure there is enough space.
+    pub fn write(&mut self, data: &[u8], packet: bool) {
+        match self.segments.back_mut() {
+            Some(last) if !packet && !last.packet => last.len += data.len(),
+            _ => self.segments.push_back(Segment {
+                len: data.len(),
+                packet,
+            }),
         }
+        self.copy_in(data);
     }
 
-    pub fn read_byte(&mut self) -> u8 {
-        self.status = RingBufferStatus::Normal;
-        let c = self.arr[self.head];
-        self.head = (self.head + 1) % RING_BUFFER_SIZE;
-        if self.head == self.tail {
-            self.status = RingBufferStatus::Empty;
+    /// Read data from the buffer into `buf`.
+    ///
+    /// A packet is read alone, and the part of it that does not fit in `buf`
+    /// is discarded.
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let mut read = 0;
+        while read < buf.len() {
+            let Some(segment) = self.segments.front_mut() else {
+                break;
+            };
+            if segment.packet {
+                if read > 0 {
+                    break;
+                }
+                let len = segment.len;
+                self.segments.pop_front();
+                let n = len.min(buf.len());
+                self.copy_out(&mut buf[..n]);
+                self.advance(len - n);
+                return n;
+            }
+            let n = segment.len.min(buf.len() - read);
+            segment.len -= n;
+            if segment.len == 0 {
+                self.segments.pop_front();
+            }
+            self.copy_out(&mut buf[read..read + n]);
+            read += n;
         }
-        c
+        read
     }
 
-    /// Get the length of remaining data in the buffer
-    pub const fn available_read(&self) -> usize {
-        if matches!(self.status, RingBufferStatus::Empty) {
-            0
-        } else if self.tail > self.head {
-            self.tail - self.head
-        } else {
-            self.tail + RING_BUFFER_SIZE - self.head
+    /// Change the capacity to `size` bytes, a multiple of the page size,
+    /// keeping the data.
+    pub fn resize(&mut self, size: usize) -> LinuxResult {
+        if size < self.len {
+            return Err(LinuxError::EBUSY);
         }
+        let mut data = vec![0; self.len];
+        self.copy_out(&mut data);
+        self.pages = (0..size / PAGE_SIZE).map(|_| None).collect();
+        self.copy_in(&data);
+        Ok(())
     }
 
-    /// Get the length of remaining space in the buffer
-    pub const fn available_write(&self) -> usize {
-        if matches!(self.status, RingBufferStatus::Full) {
-            0
-        } else {
-            RING_BUFFER_SIZE - self.available_read()
+    fn copy_in(&mut self, mut data: &[u8]) {
+        let capacity = self.capacity();
+        let mut pos = (self.head + self.len) % capacity;
+        while !data.is_empty() {
+            let offset = pos % PAGE_SIZE;
+            let n = data.len().min(PAGE_SIZE - offset);
+            let page = self.pages[pos / PAGE_SIZE]
+                .get_or_insert_with(|| vec![0; PAGE_SIZE].into_boxed_slice());
+            page[offset..offset + n].copy_from_slice(&data[..n]);
+            data = &data[n..];
+            pos = (pos + n) % capacity;
+            self.len += n;
+        }
+    }
+
+    fn copy_out(&mut self, buf: &mut [u8]) {
+        let mut done = 0;
+        while done < buf.len() {
+            let offset = self.head % PAGE_SIZE;
+            let n = (buf.len() - done).min(PAGE_SIZE - offset);
+            let page = self.pages[self.head / PAGE_SIZE].as_ref().unwrap();
+            buf[done..done + n].copy_from_slice(&page[offset..offset + n]);
+            done += n;
+            self.advance(n);
+        }
+    }
+
+    /// Drop `n` bytes from the head of the buffer.
+    fn advance(&mut self, n: usize) {
+        self.head = (self.head + n) % self.capacity();
+        self.len -= n;
+        if self.len == 0 {
+            // 读空后释放多余的页
+            self.head = 0;
+            for page in self.pages.iter_mut().skip(1) {
+                *page = None;
+            }
         }
     }
 }
 
+/// The state shared by all ends of a pipe.
+struct PipeInner {
+    buffer: Mutex<PipeRingBuffer>,
+    /// 缓冲区状态每变化一次加一，在持有缓冲区锁时修改，
+    /// 等待者在睡眠前据此判断状态是否已经变化
+    events: AtomicUsize,
+    /// 等待数据或写端打开、关闭的读者
+    #[cfg(feature = "multitask")]
+    read_wq: WaitQueue,
+    /// 等待空间或读端打开、关闭的写者
+    #[cfg(feature = "multitask")]
+    write_wq: WaitQueue,
+}
+
+impl PipeInner {
+    fn new(size: usize) -> Self {
+        Self {
+            buffer: Mutex::new(PipeRingBuffer::new(size)),
+            events: AtomicUsize::new(0),
+            #[cfg(feature = "multitask")]
+            read_wq: WaitQueue::new(),
+            #[cfg(feature = "multitask")]
+            write_wq: WaitQueue::new(),
+        }
+    }
+
+    /// Record a change of the buffer and wake up the readers and/or writers
+    /// waiting for it. Must be called with the buffer locked.
+    fn wake(&self, readers: bool, writers: bool) {
+        self.events.fetch_add(1, Ordering::AcqRel);
+        #[cfg(feature = "multitask")]
+        {
+            if readers {
+                self.read_wq.notify_all(false);
+            }
+            if writers {
+                self.write_wq.notify_all(false);
+            }
+        }
+        #[cfg(not(feature = "multitask"))]
+        let _ = (readers, writers);
+    }
+
+    /// Get the event count to wait on, with the buffer locked.
+    fn events(&self) -> usize {
+        self.events.load(Ordering::Acquire)
+    }
+
+    /// Sleep as a reader or a writer until the buffer changes after the
+    /// event count was `seq`.
+    fn wait(&self, reader: bool, seq: usize) -> LinuxResult {
+        #[cfg(feature = "multitask")]
+        {
+            let wq = if reader { &self.read_wq } else { &self.write_wq };
+            wq.wait_until_interruptible(|| self.events() != seq)
+                .map_err(|_| super::task::erestartsys())
+        }
+        #[cfg(not(feature = "multitask"))]
+        {
+            let _ = (reader, seq);
+            if super::task::interrupted() {
+                return Err(super::task::erestartsys());
+            }
+            crate::sys_sched_yield();
+            Ok(())
+        }
+    }
+}
+
+/// An end of a pipe, or a FIFO opened for reading, writing or both.
 pub struct Pipe {
     readable: bool,
-    buffer: Arc<Mutex<PipeRingBuffer>>,
+    writable: bool,
+    inner: Arc<PipeInner>,
+    nonblocking: AtomicBool,
+    /// 包模式（`O_DIRECT`）：每次写入成为一个包，读取时一次读一个包
+    packet: AtomicBool,
+    /// 打开的 FIFO 文件，匿名管道为 `None`；`stat` 报告它的属性，并保持其挂载忙
+    #[cfg(feature = "fs")]
+    fifo: Option<axfs::fops::File>,
 }
 
 impl Pipe {
     pub fn new() -> (Pipe, Pipe) {
-        let buffer = Arc::new(Mutex::new(PipeRingBuffer::new()));
-        let read_end = Pipe {
-            readable: true,
-            buffer: buffer.clone(),
-        };
-        let write_end = Pipe {
-            readable: false,
-            buffer,
-        };
+        let inner = Arc::new(PipeInner::new(DEFAULT_PIPE_SIZE));
+        let read_end = Pipe::open_end(inner.clone(), true, false);
+        let write_end = Pipe::open_end(inner, false, true);
         (read_end, write_end)
     }
 
+    /// Open an end of the pipe with the shared `inner` state.
+    fn open_end(inner: Arc<PipeInner>, readable: bool, writable: bool) -> Self {
+        let mut ring_buffer = inner.buffer.lock();
+        if readable {
+            ring_buffer.readers += 1;
+            ring_buffer.read_opens += 1;
+        }
+        if writable {
+            ring_buffer.writers += 1;
+            ring_buffer.write_opens += 1;
+        }
+        inner.wake(writable, readable);
+        drop(ring_buffer);
+        Self {
+            readable,
+            writable,
+            inner,
+            nonblocking: AtomicBool::new(false),
+            packet: AtomicBool::new(false),
+            #[cfg(feature = "fs")]
+            fifo: None,
+        }
+    }
+
+    /// Get the pipe behind the file descriptor `fd`.
+    pub fn from_fd(fd: c_int) -> LinuxResult<Arc<Self>> {
+        let f = super::fd_ops::get_file_like(fd)?;
+        f.into_any()
+            .downcast::<Self>()
+            .map_err(|_| LinuxError::EBADF)
+    }
+
     pub const fn readable(&self) -> bool {
         self.readable
     }
 
     pub const fn writable(&self) -> bool {
-        !self.readable
+        self.writable
     }
 
     pub fn write_end_close(&self) -> bool {
-        Arc::strong_count(&self.buffer) == 1
+        self.inner.buffer.lock().writers == 0
+    }
+
+    /// Get the capacity of the pipe, see `F_GETPIPE_SZ`.
+    pub fn capacity(&self) -> usize {
+        self.inner.buffer.lock().capacity()
+    }
+
+    /// Set the capacity of the pipe to at least `size` bytes, see
+    /// `F_SETPIPE_SZ`. Return the capacity actually set, rounded up to a
+    /// power of two pages.
+    ///
+    /// Fails with `EBUSY` if the data in the pipe does not fit, and with
+    /// `EPERM` if the size is above the limit.
+    pub fn set_capacity(&self, size: usize) -> LinuxResult<usize> {
+        if size > 1 << 31 {
+            return Err(LinuxError::EINVAL);
+        }
+        let size = size.max(PAGE_SIZE).next_power_of_two();
+        if size > PIPE_MAX_SIZE {
+            return Err(LinuxError::EPERM);
+        }
+        let mut ring_buffer = self.inner.buffer.lock();
+        ring_buffer.resize(size)?;
+        self.inner.wake(false, true);
+        Ok(size)
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        let mut ring_buffer = self.inner.buffer.lock();
+        if self.readable {
+            ring_buffer.readers -= 1;
+        }
+        if self.writable {
+            ring_buffer.writers -= 1;
+        }
+        // 写端关闭时读者读到 EOF，读端关闭时写者得到 EPIPE
+        self.inner.wake(self.writable, self.readable);
     }
 }
 
@@ -111,104 +354,94 @@ impl FileLike for Pipe {
         if !self.readable() {
             return Err(LinuxError::EPERM);
         }
-        let mut read_size = 0usize;
-        let max_len = buf.len();
+        if buf.is_empty() {
+            return Ok(0);
+        }
         loop {
-            let mut ring_buffer = self.buffer.lock();
-            let loop_read = ring_buffer.available_read();
-            if loop_read == 0 {
-                // 缓冲区为空，检查是否需要返回
-                if self.write_end_close() {
-                    // 写端关闭，返回已读取数据（可能是 0，表示 EOF）
-                    return Ok(read_size);
-                }
-                if read_size > 0 {
-                    // 已读取部分数据，即使写端未关闭也返回
-                    return Ok(read_size);
-                }
-                // 缓冲区为空，写端未关闭，且未读取数据，等待
-                drop(ring_buffer);
-                warn!("pipe waiting for data");
-                crate::sys_sched_yield(); // TODO: 替换为真正的阻塞机制
-                continue;
+            let mut ring_buffer = self.inner.buffer.lock();
+            if ring_buffer.available_read() > 0 {
+                let read_size = ring_buffer.read(buf);
+                self.inner.wake(false, true);
+                return Ok(read_size);
             }
-            // 读取数据
-            for _ in 0..loop_read {
-                if read_size == max_len {
-                    return Ok(read_size);
-                }
-                buf[read_size] = ring_buffer.read_byte();
-                read_size += 1;
+            // 缓冲区为空且写端全部关闭，返回 EOF
+            if ring_buffer.writers == 0 {
+                return Ok(0);
             }
-            // 如果缓冲区已空，检查是否需要返回
-            if ring_buffer.available_read() == 0 {
-                if read_size > 0 || self.write_end_close() {
-                    warn!("buffer empty, reading {read_size} in total");
-                    warn!("{buf:?}");
-                    return Ok(read_size);
-                }
+            if self.nonblocking.load(Ordering::Acquire) {
+                return Err(LinuxError::EAGAIN);
             }
+            let seq = self.inner.events();
+            drop(ring_buffer);
+            self.inner.wait(true, seq)?;
         }
     }
 
-    /*
-     *fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
-     *    if !self.readable() {
-     *        return Err(LinuxError::EPERM);
-     *    }
-     *    let mut read_size = 0usize;
-     *    let max_len = buf.len();
-     *    loop {
-     *        let mut ring_buffer = self.buffer.lock();
-     *        let loop_read = ring_buffer.available_read();
-     *        if loop_read == 0 {
-     *            // 如果已读取部分数据或写端关闭，直接返回
-     *            if self.write_end_close() {
-     *                return Ok(read_size);
-     *            }
-     *            drop(ring_buffer);
-     *            // Data not ready, wait for write end
-     *            crate::sys_sched_yield(); // TODO: use synconize primitive
-     *            continue;
-     *        }
-     *        for _ in 0..loop_read {
-     *            if read_size == max_len {
-     *                return Ok(read_size);
-     *            }
-     *            buf[read_size] = ring_buffer.read_byte();
-     *            read_size += 1;
-     *            warn!("{loop_read:?}, {read_size:?}");
-     *        }
-     *    }
-     *}
-     */
-
     fn write(&self, buf: &[u8]) -> LinuxResult<usize> {
         if !self.writable() {
             return Err(LinuxError::EPERM);
         }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let packet = self.packet.load(Ordering::Acquire);
         let mut write_size = 0usize;
-        let max_len = buf.len();
         loop {
-            let mut ring_buffer = self.buffer.lock();
-            let loop_write = ring_buffer.available_write();
-            if loop_write == 0 {
-                drop(ring_buffer);
-                // Buffer is full, wait for read end to consume
-                crate::sys_sched_yield(); // TODO: use synconize primitive
-                continue;
+            let mut ring_buffer = self.inner.buffer.lock();
+            if ring_buffer.readers == 0 {
+                // 没有读端，由系统调用层发送 SIGPIPE
+                return if write_size > 0 {
+                    Ok(write_size)
+                } else {
+                    Err(LinuxError::EPIPE)
+                };
             }
-            for _ in 0..loop_write {
-                if write_size == max_len {
-                    return Ok(write_size);
+            let last_size = write_size;
+            while write_size < buf.len() {
+                let rest = &buf[write_size..];
+                let free = ring_buffer.available_write();
+                let n = if packet {
+                    // 包不超过 PIPE_BUF，整个写入
+                    rest.len().min(PIPE_BUF)
+                } else if buf.len() <= PIPE_BUF {
+                    // 不超过 PIPE_BUF 的写入是原子的，空间不足时整体等待
+                    rest.len()
+                } else {
+                    rest.len().min(free)
+                };
+                if n == 0 || n > free {
+                    break;
                 }
-                ring_buffer.write_byte(buf[write_size]);
-                write_size += 1;
+                ring_buffer.write(&rest[..n], packet);
+                write_size += n;
+            }
+            if write_size > last_size {
+                self.inner.wake(true, false);
+            }
+            if write_size == buf.len() {
+                return Ok(write_size);
+            }
+            if self.nonblocking.load(Ordering::Acquire) {
+                return if write_size > 0 {
+                    Ok(write_size)
+                } else {
+                    Err(LinuxError::EAGAIN)
+                };
+            }
+            let seq = self.inner.events();
+            drop(ring_buffer);
+            // Interrupted by a signal, return what has been written
+            if let Err(e) = self.inner.wait(false, seq) {
+                return if write_size > 0 { Ok(write_size) } else { Err(e) };
             }
         }
     }
 
     fn stat(&self) -> LinuxResult<ctypes::stat> {
+        #[cfg(feature = "fs")]
+        if let Some(file) = &self.fifo {
+            return Ok(super::fs::attr2stat(file.get_attr()?));
+        }
         let st_mode = 0o10000 | 0o600u32; // S_IFIFO | rw-------
         Ok(ctypes::stat {
             st_ino: 1,
@@ -216,7 +449,7 @@ impl FileLike for Pipe {
             st_mode,
             st_uid: 1000,
             st_gid: 1000,
-            st_blksize: 4096,
+            st_blksize: PAGE_SIZE as _,
             ..Default::default()
         })
     }
@@ -226,34 +459,121 @@ impl FileLike for Pipe {
     }
 
     fn poll(&self) -> LinuxResult<PollState> {
-        let buf = self.buffer.lock();
+        let buf = self.inner.buffer.lock();
+        // 曾经打开过的另一端全部关闭后，读不会阻塞（EOF），写也不会阻塞（EPIPE）
         Ok(PollState {
-            readable: self.readable() && buf.available_read() > 0,
-            writable: self.writable() && buf.available_write() > 0,
+            readable: self.readable()
+                && (buf.available_read() > 0 || (buf.writers == 0 && buf.write_opens > 0)),
+            writable: self.writable() && (buf.available_write() > 0 || buf.readers == 0),
         })
     }
 
-    fn set_nonblocking(&self, _nonblocking: bool) -> LinuxResult {
+    fn set_nonblocking(&self, nonblocking: bool) -> LinuxResult {
+        self.nonblocking.store(nonblocking, Ordering::Release);
+        Ok(())
+    }
+
+    fn set_direct(&self, direct: bool) -> LinuxResult {
+        self.packet.store(direct, Ordering::Release);
         Ok(())
     }
 }
 
+/// Shared states of the FIFOs opened somewhere, by the device number of the
+/// mount and the inode number of the FIFO.
+static FIFOS: Mutex<BTreeMap<((u32, u32), u64), Weak<PipeInner>>> = Mutex::new(BTreeMap::new());
+
+/// Open the FIFO `file` with the open `flags`, attaching to the buffer shared
+/// by all opens of it.
+///
+/// Opening only for reading blocks until the FIFO is opened for writing and
+/// vice versa, unless `O_NONBLOCK` is set. Then opening for writing fails with
+/// `ENXIO` if there is no reader.
+#[cfg(feature = "fs")]
+pub(crate) fn open_fifo(file: axfs::fops::File, flags: u32) -> LinuxResult<Pipe> {
+    let id = file.inode_id()?;
+    let (readable, writable) = match flags & 0b11 {
+        ctypes::O_RDONLY => (true, false),
+        ctypes::O_WRONLY => (false, true),
+        _ => (true, true),
+    };
+    let nonblock = flags & ctypes::O_NONBLOCK != 0;
+    let inner = {
+        let mut fifos = FIFOS.lock();
+        fifos.retain(|_, inner| inner.strong_count() > 0);
+        match fifos.get(&id).and_then(Weak::upgrade) {
+            Some(inner) => inner,
+            None => {
+                let inner = Arc::new(PipeInner::new(DEFAULT_PIPE_SIZE));
+                fifos.insert(id, Arc::downgrade(&inner));
+                inner
+            }
+        }
+    };
+    // 等待另一端被打开，期间打开后又关闭也算
+    let peer = |ring_buffer: &PipeRingBuffer| {
+        if readable {
+            (ring_buffer.writers, ring_buffer.write_opens)
+        } else {
+            (ring_buffer.readers, ring_buffer.read_opens)
+        }
+    };
+    let (peers, opens) = peer(&inner.buffer.lock());
+    if nonblock && !readable && peers == 0 {
+        return Err(LinuxError::ENXIO);
+    }
+    let mut fifo = Pipe::open_end(inner, readable, writable);
+    fifo.fifo = Some(file);
+    fifo.set_nonblocking(nonblock)?;
+    if nonblock || (readable && writable) {
+        return Ok(fifo);
+    }
+    loop {
+        let ring_buffer = fifo.inner.buffer.lock();
+        let (count, now_opens) = peer(&ring_buffer);
+        if count > 0 || now_opens != opens {
+            return Ok(fifo);
+        }
+        let seq = fifo.inner.events();
+        drop(ring_buffer);
+        fifo.inner.wait(readable, seq)?;
+    }
+}
+
 /// Create a pipe
 ///
 /// Return 0 if succeed
 pub fn sys_pipe(fds: &mut [c_int]) -> c_int {
-    debug!("sys_pipe <= {:#x}", fds.as_ptr() as usize);
-    syscall_body!(sys_pipe, {
+    sys_pipe2(fds, 0)
+}
+
+/// Create a pipe with `O_CLOEXEC`, `O_NONBLOCK` and `O_DIRECT` flags
+///
+/// Return 0 if succeed
+pub fn sys_pipe2(fds: &mut [c_int], flags: c_int) -> c_int {
+    debug!("sys_pipe2 <= {:#x} {:#o}", fds.as_ptr() as usize, flags);
+    syscall_body!(sys_pipe2, {
         if fds.len() != 2 {
             return Err(LinuxError::EFAULT);
         }
+        if flags & !((ctypes::O_CLOEXEC | ctypes::O_NONBLOCK | ctypes::O_DIRECT) as c_int) != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let cloexec = flags & ctypes::O_CLOEXEC as c_int != 0;
+        let status = flags as u32 & (ctypes::O_NONBLOCK | ctypes::O_DIRECT);
 
         let (read_end, write_end) = Pipe::new();
-        let read_fd = add_file_like(Arc::new(read_end))?;
-        let write_fd = add_file_like(Arc::new(write_end)).inspect_err(|_| {
+        for end in [&read_end, &write_end] {
+            end.set_nonblocking(status & ctypes::O_NONBLOCK != 0)?;
+            end.set_direct(status & ctypes::O_DIRECT != 0)?;
+        }
+        let read_end = OpenFile::new(Arc::new(read_end), ctypes::O_RDONLY | status);
+        let write_end = OpenFile::new(Arc::new(write_end), ctypes::O_WRONLY | status);
+        let read_fd = add_open_file(Arc::new(read_end), cloexec)?;
+        let write_fd = add_open_file(Arc::new(write_end), cloexec).inspect_err(|_| {
             close_file_like(read_fd).unwrap();
         })?;
-        debug!("sys_pipe => {read_fd:#x}, {write_fd:#x}");
+        debug!("sys_pipe2 => {read_fd:#x}, {write_fd:#x}");
 
         fds[0] = read_fd as c_int;
         fds[1] = write_fd as c_int;
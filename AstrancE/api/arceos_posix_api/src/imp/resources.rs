@@ -101,7 +101,10 @@ pub unsafe fn sys_prlimit64(
             unsafe { sys_getrlimit(resource, old_limit) };
         }
         if !new_limit.is_null() {
-            unsafe { sys_setrlimit(resource, new_limit) };
+            let ret = unsafe { sys_setrlimit(resource, new_limit) };
+            if ret < 0 {
+                return Err(LinuxError::try_from(-ret).unwrap_or(LinuxError::EINVAL));
+            }
         }
         Ok(0)
     })
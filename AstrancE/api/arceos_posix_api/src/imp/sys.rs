@@ -37,7 +37,7 @@ pub fn sys_sysconf(name: c_int) -> c_long {
             ctypes::_SC_AVPHYS_PAGES => Ok(avail_pages),
             // Maximum number of files per process
             #[cfg(feature = "fd")]
-            ctypes::_SC_OPEN_MAX => Ok(super::fd_ops::AX_FILE_LIMIT),
+            ctypes::_SC_OPEN_MAX => Ok(super::fd_ops::get_file_limit()),
             _ => Ok(0),
         }
     })
@@ -1,5 +1,7 @@
 use core::ffi::c_int;
 
+use axerrno::LinuxError;
+
 /// Relinquish the CPU, and switches to another task.
 ///
 /// For single-threaded configuration (`multitask` feature is disabled), we just
@@ -16,6 +18,47 @@ pub fn sys_sched_yield() -> c_int {
     0
 }
 
+/// Whether the current task has been interrupted by a signal, in which case
+/// blocking calls should give up and return `EINTR`.
+pub fn interrupted() -> bool {
+    #[cfg(feature = "multitask")]
+    {
+        axtask::current().is_interrupted()
+    }
+    #[cfg(not(feature = "multitask"))]
+    {
+        false
+    }
+}
+
+/// Returns `EINTR` for a blocking call interrupted by a signal. The call is
+/// restarted if no signal handler runs or the handler has `SA_RESTART`, like
+/// `ERESTARTSYS` of Linux.
+pub fn erestartsys() -> LinuxError {
+    #[cfg(feature = "multitask")]
+    axtask::current().set_syscall_restart(axtask::SyscallRestart::Sys);
+    LinuxError::EINTR
+}
+
+/// Returns `EINTR` for a blocking call interrupted by a signal. The call is
+/// restarted only if no signal handler runs, like `ERESTARTNOHAND` of Linux.
+pub fn erestartnohand() -> LinuxError {
+    #[cfg(feature = "multitask")]
+    axtask::current().set_syscall_restart(axtask::SyscallRestart::NoHand);
+    LinuxError::EINTR
+}
+
+/// Returns `EINTR` for a blocking call interrupted by a signal. If no signal
+/// handler runs, `restart_syscall` continues the call by running `block`, like
+/// `ERESTART_RESTARTBLOCK` of Linux.
+pub fn erestart_restartblock(block: impl FnOnce() -> isize + Send + 'static) -> LinuxError {
+    #[cfg(feature = "multitask")]
+    axtask::current().set_restart_block(block);
+    #[cfg(not(feature = "multitask"))]
+    drop(block);
+    LinuxError::EINTR
+}
+
 /// Get current thread ID.
 pub fn sys_getpid() -> c_int {
     syscall_body!(sys_getpid,
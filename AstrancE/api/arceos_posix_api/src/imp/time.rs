@@ -1,10 +1,31 @@
-use axerrno::LinuxError;
+use axerrno::{LinuxError, LinuxResult};
 use core::ffi::{c_int, c_long};
+use core::sync::atomic::{AtomicI64, Ordering};
 use core::time::Duration;
 
+use axhal::time::NANOS_PER_SEC;
+
 use crate::ctypes;
 use crate::ctypes::{CLOCK_MONOTONIC, CLOCK_REALTIME};
 
+/// Clock ids not exported by the C headers.
+pub const CLOCK_PROCESS_CPUTIME_ID: u32 = 2;
+pub const CLOCK_THREAD_CPUTIME_ID: u32 = 3;
+pub const CLOCK_MONOTONIC_RAW: u32 = 4;
+pub const CLOCK_REALTIME_COARSE: u32 = 5;
+pub const CLOCK_MONOTONIC_COARSE: u32 = 6;
+pub const CLOCK_BOOTTIME: u32 = 7;
+
+/// Offset in nanoseconds added to [`axhal::time::wall_time`] to get the
+/// realtime clock, adjusted by `clock_settime` and `settimeofday`.
+///
+/// The offset is kept here instead of in `axhal` because timers and sleeps
+/// use `wall_time` deadlines and must not jump when the clock is set.
+static REALTIME_OFFSET_NANOS: AtomicI64 = AtomicI64::new(0);
+
+/// Resolution of the `*_COARSE` clocks: one timer tick.
+const COARSE_RES_NANOS: u64 = NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64;
+
 impl From<ctypes::timespec> for Duration {
     fn from(ts: ctypes::timespec) -> Self {
         Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
@@ -35,16 +56,56 @@ impl From<Duration> for ctypes::timeval {
     }
 }
 
-/// Get clock time since booting
+/// Returns the current value of the settable realtime clock.
+pub fn realtime() -> Duration {
+    let nanos = axhal::time::wall_time().as_nanos() as i64
+        + REALTIME_OFFSET_NANOS.load(Ordering::Relaxed);
+    Duration::from_nanos(nanos.max(0) as u64)
+}
+
+/// Sets the realtime clock to `now`.
+///
+/// Only the realtime clock is affected; the monotonic clocks keep counting
+/// from boot.
+pub fn set_realtime(now: Duration) {
+    let offset = now.as_nanos() as i64 - axhal::time::wall_time().as_nanos() as i64;
+    REALTIME_OFFSET_NANOS.store(offset, Ordering::Relaxed);
+}
+
+/// Rounds `time` down to the last timer tick.
+fn coarse(time: Duration) -> Duration {
+    let nanos = time.as_nanos() as u64;
+    Duration::from_nanos(nanos - nanos % COARSE_RES_NANOS)
+}
+
+/// Reads a system-wide clock, CPU-time clocks are handled by the caller.
+fn read_clock(clk: ctypes::clockid_t) -> Option<Duration> {
+    Some(match clk as u32 {
+        CLOCK_REALTIME => realtime(),
+        CLOCK_REALTIME_COARSE => coarse(realtime()),
+        // The kernel does not suspend, so boot time equals monotonic time.
+        CLOCK_MONOTONIC | CLOCK_MONOTONIC_RAW | CLOCK_BOOTTIME => axhal::time::monotonic_time(),
+        CLOCK_MONOTONIC_COARSE => coarse(axhal::time::monotonic_time()),
+        _ => return None,
+    })
+}
+
+fn validate_timespec(ts: &ctypes::timespec) -> LinuxResult<Duration> {
+    if ts.tv_sec < 0 || !(0..NANOS_PER_SEC as c_long).contains(&ts.tv_nsec) {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(Duration::from(*ts))
+}
+
+/// Get the time of a system-wide clock
 pub unsafe fn sys_clock_gettime(clk: ctypes::clockid_t, ts: *mut ctypes::timespec) -> c_int {
     syscall_body!(sys_clock_gettime, {
         if ts.is_null() {
             return Err(LinuxError::EFAULT);
         }
-        let now = match clk as u32 {
-            CLOCK_REALTIME => axhal::time::wall_time().into(),
-            CLOCK_MONOTONIC => axhal::time::monotonic_time().into(),
-            _ => {
+        let now: ctypes::timespec = match read_clock(clk) {
+            Some(now) => now.into(),
+            None => {
                 warn!("Called sys_clock_gettime for unsupported clock {}", clk);
                 return Err(LinuxError::EINVAL);
             }
@@ -55,37 +116,119 @@ pub unsafe fn sys_clock_gettime(clk: ctypes::clockid_t, ts: *mut ctypes::timespe
     })
 }
 
+/// Get the resolution of a system-wide clock
+pub unsafe fn sys_clock_getres(clk: ctypes::clockid_t, res: *mut ctypes::timespec) -> c_int {
+    syscall_body!(sys_clock_getres, {
+        let nanos = match clk as u32 {
+            CLOCK_REALTIME | CLOCK_MONOTONIC | CLOCK_MONOTONIC_RAW | CLOCK_BOOTTIME => 1,
+            CLOCK_REALTIME_COARSE | CLOCK_MONOTONIC_COARSE => COARSE_RES_NANOS,
+            _ => return Err(LinuxError::EINVAL),
+        };
+        if !res.is_null() {
+            unsafe { *res = Duration::from_nanos(nanos).into() };
+        }
+        Ok(0)
+    })
+}
+
+/// Set the time of a clock, only `CLOCK_REALTIME` is settable
+pub unsafe fn sys_clock_settime(clk: ctypes::clockid_t, ts: *const ctypes::timespec) -> c_int {
+    syscall_body!(sys_clock_settime, {
+        if clk as u32 != CLOCK_REALTIME {
+            return Err(LinuxError::EINVAL);
+        }
+        if ts.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        set_realtime(validate_timespec(unsafe { &*ts })?);
+        Ok(0)
+    })
+}
+
+/// Flag of `clock_nanosleep`: `req` is an absolute time on the clock.
+pub const TIMER_ABSTIME: c_int = 1;
+
+/// Sleeps until `deadline` on the [`axhal::time::wall_time`] timeline.
+///
+/// Returns the remaining time if the sleep is interrupted by a signal.
+fn sleep_until(deadline: Duration) -> Result<(), Duration> {
+    #[cfg(feature = "multitask")]
+    if axtask::sleep_until_interruptible(deadline).is_err() {
+        return Err(deadline.saturating_sub(axhal::time::wall_time()));
+    }
+    #[cfg(not(feature = "multitask"))]
+    axhal::time::busy_wait_until(deadline);
+    Ok(())
+}
+
+/// Sleeps until `deadline` for a relative sleep.
+///
+/// If interrupted by a signal, writes the remaining time to `rem` when it is
+/// not null, and `restart_syscall` continues the sleep if no handler runs.
+fn nanosleep_until(deadline: Duration, rem: *mut ctypes::timespec) -> LinuxResult<isize> {
+    if let Err(remain) = sleep_until(deadline) {
+        if !rem.is_null() {
+            unsafe { *rem = remain.into() };
+        }
+        let rem = rem as usize;
+        return Err(super::task::erestart_restartblock(move || {
+            match nanosleep_until(deadline, rem as *mut ctypes::timespec) {
+                Ok(ret) => ret,
+                Err(e) => -e.code() as isize,
+            }
+        }));
+    }
+    Ok(0)
+}
+
 /// Sleep some nanoseconds
 ///
-/// TODO: should be woken by signals, and set errno
+/// Returns `EINTR` and writes the remaining time to `rem` if interrupted by a signal.
 pub unsafe fn sys_nanosleep(req: *const ctypes::timespec, rem: *mut ctypes::timespec) -> c_int {
     syscall_body!(sys_nanosleep, {
-        unsafe {
-            if req.is_null() || (*req).tv_nsec < 0 || (*req).tv_nsec > 999999999 {
-                return Err(LinuxError::EINVAL);
-            }
+        if req.is_null() {
+            return Err(LinuxError::EFAULT);
         }
+        let dur = validate_timespec(unsafe { &*req })?;
+        debug!("sys_nanosleep <= {:?}", dur);
 
-        let dur = unsafe {
-            debug!("sys_nanosleep <= {}.{:09}s", (*req).tv_sec, (*req).tv_nsec);
-            Duration::from(*req)
-        };
-
-        let now = axhal::time::monotonic_time();
-
-        #[cfg(feature = "multitask")]
-        axtask::sleep(dur);
-        #[cfg(not(feature = "multitask"))]
-        axhal::time::busy_wait(dur);
+        nanosleep_until(axhal::time::wall_time() + dur, rem)
+    })
+}
 
-        let after = axhal::time::monotonic_time();
-        let actual = after - now;
+/// Sleep on the given clock, `req` is an absolute time if `flags` contains
+/// `TIMER_ABSTIME`, otherwise a relative interval.
+///
+/// Returns `EINTR` if interrupted by a signal, the remaining time is written to
+/// `rem` only for relative sleeps.
+///
+/// An absolute sleep on `CLOCK_REALTIME` is converted to a deadline when it
+/// starts, setting the clock while sleeping does not affect it.
+pub unsafe fn sys_clock_nanosleep(
+    clk: ctypes::clockid_t,
+    flags: c_int,
+    req: *const ctypes::timespec,
+    rem: *mut ctypes::timespec,
+) -> c_int {
+    syscall_body!(sys_clock_nanosleep, {
+        let now = match clk as u32 {
+            CLOCK_REALTIME | CLOCK_MONOTONIC | CLOCK_BOOTTIME => read_clock(clk).unwrap(),
+            CLOCK_PROCESS_CPUTIME_ID => return Err(LinuxError::EOPNOTSUPP),
+            _ => return Err(LinuxError::EINVAL),
+        };
+        if req.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let req = validate_timespec(unsafe { &*req })?;
+        debug!("sys_clock_nanosleep <= clk: {}, flags: {:#x}, {:?}", clk, flags, req);
 
-        if let Some(diff) = dur.checked_sub(actual) {
-            if !rem.is_null() {
-                unsafe { (*rem) = diff.into() };
-            }
-            return Err(LinuxError::EINTR);
+        let abs = flags & TIMER_ABSTIME != 0;
+        if !abs {
+            return nanosleep_until(axhal::time::wall_time() + req, rem);
+        }
+        // An absolute sleep is restarted with the same arguments.
+        if sleep_until(axhal::time::wall_time() + req.saturating_sub(now)).is_err() {
+            return Err(super::task::erestartnohand());
         }
         Ok(0)
     })
@@ -94,13 +237,26 @@ pub unsafe fn sys_nanosleep(req: *const ctypes::timespec, rem: *mut ctypes::time
 /// Get current system time and store in specific struct
 pub unsafe fn sys_get_time_of_day(ts: *mut ctypes::timeval) -> c_int {
     syscall_body!(sys_get_time_of_day, {
-        let current_us = axhal::time::monotonic_time_nanos() as usize / 1000;
-        unsafe {
-            *ts = ctypes::timeval {
-                tv_sec: (current_us / 1_000_000) as i64,
-                tv_usec: (current_us % 1_000_000) as i64,
-            }
+        if ts.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        unsafe { *ts = realtime().into() };
+        Ok(0)
+    })
+}
+
+/// Set current system time, the timezone argument is obsolete and ignored
+pub unsafe fn sys_settimeofday(tv: *const ctypes::timeval) -> c_int {
+    syscall_body!(sys_settimeofday, {
+        // A null `tv` only sets the timezone, which we do not keep.
+        if tv.is_null() {
+            return Ok(0);
+        }
+        let tv = unsafe { *tv };
+        if tv.tv_sec < 0 || !(0..1_000_000).contains(&tv.tv_usec) {
+            return Err(LinuxError::EINVAL);
         }
+        set_realtime(tv.into());
         Ok(0)
     })
 }
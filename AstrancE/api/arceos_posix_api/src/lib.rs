@@ -36,24 +36,31 @@ pub mod ctypes;
 
 pub use imp::io::{sys_read, sys_readv, sys_write, sys_writev};
 #[cfg(feature = "fs")]
-pub use imp::path_link::{AT_FDCWD, FilePath, HARDLINK_MANAGER, handle_file_path};
+pub use imp::path_link::{
+    AT_FDCWD, FilePath, HARDLINK_MANAGER, handle_file_path, resolve_file_path,
+};
 pub use imp::resources::{sys_getrlimit, sys_prlimit64, sys_setrlimit};
 pub use imp::sys::{UtsName, sys_sysconf, sys_uname};
 pub use imp::task::{sys_exit, sys_getpid, sys_sched_yield};
-pub use imp::time::{sys_clock_gettime, sys_get_time_of_day, sys_nanosleep};
+pub use imp::time::{
+    TIMER_ABSTIME, realtime, set_realtime, sys_clock_getres, sys_clock_gettime,
+    sys_clock_nanosleep, sys_clock_settime, sys_get_time_of_day, sys_nanosleep, sys_settimeofday,
+};
 
 #[cfg(feature = "fd")]
 pub use imp::fd_ops::{
-    FD_TABLE, FileLike, add_file_like, get_file_like, ps2event, sys_close, sys_dup, sys_dup2,
-    sys_fcntl, sys_ppoll,
+    CLOSE_RANGE_CLOEXEC, CLOSE_RANGE_UNSHARE, FD_TABLE, FILE_LIMIT, FdEntry, FdTable, FileLike,
+    OpenFile, SharedFdTable, add_file_like, add_file_like_cloexec, add_open_file, get_file_like, get_open_file, ps2event,
+    sys_close, sys_close_range, sys_dup, sys_dup2, sys_dup3, sys_fcntl, sys_ppoll,
 };
 //#[cfg(feature = "fs")]
 pub use imp::fs::{
-    Directory, File, add_file_or_directory_fd, sys_fgetxattr, sys_fremovexattr, sys_fsetxattr,
-    sys_fstat, sys_fstatat, sys_getcwd, sys_getdents, sys_listxattr, sys_lseek, sys_lstat,
-    sys_mkdirat, sys_mount, sys_open, sys_openat, sys_pread64, sys_pwrite64, sys_rename,
-    sys_renameat, sys_stat, sys_statfs, sys_statx, sys_umount2, sys_unlink, sys_unlinkat,
-    sys_utimensat,
+    Directory, File, add_file_or_directory_fd, fd_link_target, sys_fchmod, sys_fchmodat, sys_fchown,
+    sys_fchownat, sys_fgetxattr, sys_fremovexattr, sys_fsetxattr, sys_fstat, sys_fstatat,
+    sys_fsync, sys_getcwd, sys_getdents, sys_linkat, sys_listxattr, sys_lseek, sys_lstat,
+    sys_mkdirat, sys_mknodat, sys_mount, sys_open, sys_openat, sys_openat2, sys_pread64,
+    sys_pwrite64, sys_readlinkat, sys_rename, sys_renameat, sys_renameat2, sys_stat, sys_statfs,
+    sys_statx, sys_symlinkat, sys_sync, sys_umount2, sys_unlink, sys_unlinkat, sys_utimensat,
 };
 #[cfg(feature = "select")]
 pub use imp::io_mpx::{FdSets, set_fd_set, sys_select, zero_fd_set};
@@ -61,12 +68,12 @@ pub use imp::io_mpx::{FdSets, set_fd_set, sys_select, zero_fd_set};
 pub use imp::io_mpx::{sys_epoll_create, sys_epoll_ctl, sys_epoll_wait};
 #[cfg(feature = "net")]
 pub use imp::net::{
-    sys_accept, sys_bind, sys_connect, sys_freeaddrinfo, sys_getaddrinfo, sys_getpeername,
-    sys_getsockname, sys_listen, sys_recv, sys_recvfrom, sys_send, sys_sendto, sys_shutdown,
-    sys_socket,
+    sys_accept, sys_accept4, sys_bind, sys_connect, sys_freeaddrinfo, sys_getaddrinfo,
+    sys_getpeername, sys_getsockname, sys_listen, sys_recv, sys_recvfrom, sys_send, sys_sendto,
+    sys_shutdown, sys_socket,
 };
 #[cfg(feature = "pipe")]
-pub use imp::pipe::sys_pipe;
+pub use imp::pipe::{sys_pipe, sys_pipe2};
 #[cfg(feature = "multitask")]
 pub use imp::pthread::mutex::{
     sys_pthread_mutex_init, sys_pthread_mutex_lock, sys_pthread_mutex_unlock,
@@ -1,4 +1,5 @@
 use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::sync::{Arc, Weak};
 use core::ffi::{c_char, c_void};
 use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsNodeRef, VfsNodeType};
@@ -9,7 +10,7 @@ use spin::RwLock;
 /// It implements [`axfs_vfs::VfsNodeOps`].
 pub struct DirNode {
     parent: RwLock<Weak<dyn VfsNodeOps>>,
-    children: RwLock<BTreeMap<&'static str, VfsNodeRef>>,
+    children: RwLock<BTreeMap<String, VfsNodeRef>>,
 }
 
 impl DirNode { 
@@ -26,16 +27,16 @@ impl DirNode {
     }
 
     /// Create a subdirectory at this directory.
-    pub fn mkdir(self: &Arc<Self>, name: &'static str) -> Arc<Self> {
+    pub fn mkdir(self: &Arc<Self>, name: &str) -> Arc<Self> {
         let parent = self.clone() as VfsNodeRef;
         let node = Self::new(Some(&parent));
-        self.children.write().insert(name, node.clone());
+        self.children.write().insert(name.into(), node.clone());
         node
     }
 
     /// Add a node to this directory.
-    pub fn add(&self, name: &'static str, node: VfsNodeRef) {
-        self.children.write().insert(name, node);
+    pub fn add(&self, name: &str, node: VfsNodeRef) {
+        self.children.write().insert(name.into(), node);
     }
 }
 
@@ -10,14 +10,18 @@ use alloc::collections::BTreeMap;
 use spin::RwLock;
 
 mod dir;
+mod full;
 mod null;
+mod symlink;
 mod zero;
 // mod sda;
 #[cfg(test)]
 mod tests;
 
 pub use self::dir::DirNode;
+pub use self::full::FullDev;
 pub use self::null::NullDev;
+pub use self::symlink::SymlinkNode;
 pub use self::zero::ZeroDev;
 
 use alloc::sync::Arc;
@@ -41,14 +45,19 @@ impl DeviceFileSystem {
     }
 
     /// Create a subdirectory at the root directory.
-    pub fn mkdir(&self, name: &'static str) -> Arc<DirNode> {
+    pub fn mkdir(&self, name: &str) -> Arc<DirNode> {
         self.root.mkdir(name)
     }
 
+    /// Get the root directory.
+    pub fn root(&self) -> &Arc<DirNode> {
+        &self.root
+    }
+
     /// Add a node to the root directory.
     ///
     /// The node must implement [`axfs_vfs::VfsNodeOps`], and be wrapped in [`Arc`].
-    pub fn add(&self, name: &'static str, node: Arc<dyn VfsNodeOps>) { self.root.add(name, node);}
+    pub fn add(&self, name: &str, node: Arc<dyn VfsNodeOps>) { self.root.add(name, node);}
     
     // Register a device file by name (e.g., "vda2") and insert into dev_map.
     // pub fn register_device_by_name(&self, name: &'static str, major: u32, minor: u32, node: Arc<dyn VfsOps>) -> VfsResult {
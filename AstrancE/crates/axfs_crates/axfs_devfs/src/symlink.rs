@@ -0,0 +1,60 @@
+use alloc::string::String;
+use axfs_vfs::{VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+
+/// A symbolic link in the device filesystem, such as `/dev/stdin`.
+pub struct SymlinkNode {
+    target: String,
+}
+
+impl SymlinkNode {
+    /// Create a symbolic link to `target`.
+    pub fn new(target: &str) -> Self {
+        Self {
+            target: target.into(),
+        }
+    }
+}
+
+impl VfsNodeOps for SymlinkNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            0,
+            VfsNodePerm::from_bits_truncate(0o777),
+            VfsNodeType::SymLink,
+            self.target.len() as _,
+            0,
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ))
+    }
+
+    fn get_attr_x(&self) -> VfsResult<VfsNodeAttrX> {
+        Ok(VfsNodeAttrX::new(
+            0,0,0,1,0,0,
+            VfsNodePerm::from_bits_truncate(0o777),
+            VfsNodeType::SymLink,
+            0,self.target.len() as _,
+            0,0,
+            0,0,0,0,
+            0,0,0, 0,
+            0,0,0,0,
+        ))
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let len = self.target.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.target.as_bytes()[..len]);
+        Ok(len)
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
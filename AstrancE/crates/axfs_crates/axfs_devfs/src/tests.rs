@@ -37,6 +37,18 @@ fn test_devfs_ops(devfs: &DeviceFileSystem) -> VfsResult {
     assert_eq!(buf, [0; N]);
     assert_eq!(node.write_at(0, &buf)?, N);
 
+    let node = devfs.root_dir().lookup("full")?;
+    assert_eq!(node.get_attr()?.file_type(), VfsNodeType::CharDevice);
+    assert_eq!(node.read_at(0, &mut buf)?, N);
+    assert_eq!(buf, [0; N]);
+    assert_eq!(node.write_at(0, &buf).err(), Some(VfsError::StorageFull));
+
+    let node = devfs.root_dir().lookup("stdin")?;
+    assert_eq!(node.get_attr()?.file_type(), VfsNodeType::SymLink);
+    assert_eq!(node.get_attr()?.size(), 15);
+    assert_eq!(node.readlink(&mut buf)?, 15);
+    assert_eq!(&buf[..15], b"/proc/self/fd/0");
+
     let foo = devfs.root_dir().lookup(".///.//././/.////foo")?;
     assert!(foo.get_attr()?.is_dir());
     assert_eq!(
@@ -98,11 +110,15 @@ fn test_devfs() {
     // │   ├── bar
     // │   │   └── f1 (null)
     // │   └── f2 (zero)
+    // ├── full
     // ├── null
+    // ├── stdin -> /proc/self/fd/0
     // └── zero
 
     let devfs = DeviceFileSystem::new();
+    devfs.add("full", Arc::new(FullDev));
     devfs.add("null", Arc::new(NullDev));
+    devfs.add("stdin", Arc::new(SymlinkNode::new("/proc/self/fd/0")));
     devfs.add("zero", Arc::new(ZeroDev));
 
     let dir_foo = devfs.mkdir("foo");
@@ -8,7 +8,7 @@ use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsNodeRef, V
 use axfs_vfs::{VfsError, VfsResult};
 use spin::RwLock;
 
-use crate::file::{ProcDynamicFile, ProcFile, ProcFileGenerator};
+use crate::file::{ProcDynamicFile, ProcFile, ProcFileGenerator, ProcSymlink};
 
 /// 一个函数，用于动态生成目录条目。
 ///
@@ -18,12 +18,13 @@ pub type ProcDirGenerator = dyn Fn() -> VfsResult<Vec<(String, ProcEntry)>> + Se
 
 /// 表示 procfs 目录中的一个条目。
 ///
-/// 它可以是子目录、静态文件或动态文件。
+/// 它可以是子目录、静态文件、动态文件或符号链接。
 #[derive(Clone)]
 pub enum ProcEntry {
     Dir(Arc<ProcDir>),
     File(Arc<ProcFile>),
     DynamicFile(Arc<ProcDynamicFile>),
+    Symlink(Arc<ProcSymlink>),
 }
 
 impl ProcEntry {
@@ -33,6 +34,7 @@ impl ProcEntry {
             ProcEntry::Dir(dir) => dir.clone() as VfsNodeRef,
             ProcEntry::File(file) => file.clone() as VfsNodeRef,
             ProcEntry::DynamicFile(dyn_file) => dyn_file.clone() as VfsNodeRef,
+            ProcEntry::Symlink(link) => link.clone() as VfsNodeRef,
         }
     }
 }
@@ -254,6 +256,7 @@ impl VfsNodeOps for ProcDir {
                         let ty = match entry {
                             ProcEntry::Dir(_) => VfsNodeType::Dir,
                             ProcEntry::File(_) | ProcEntry::DynamicFile(_) => VfsNodeType::File,
+                            ProcEntry::Symlink(_) => VfsNodeType::SymLink,
                         };
                         *ent = VfsDirEntry::new(name, ty);
                     } else {
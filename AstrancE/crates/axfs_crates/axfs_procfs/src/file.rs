@@ -1,10 +1,25 @@
+use alloc::string::String;
 use alloc::sync::Arc;
-use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsResult};
+use axfs_vfs::{
+    impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsNodePerm, VfsNodeType,
+    VfsResult,
+};
 use spin::RwLock;
 
 /// 动态文件生成器类型
 pub type ProcFileGenerator = dyn Fn(u64, &mut [u8]) -> VfsResult<usize> + Send + Sync;
 
+/// 每次读取时调用 `generate` 生成完整文本，再按偏移量截取
+pub fn text_file_generator(generate: fn() -> String) -> Arc<ProcFileGenerator> {
+    Arc::new(move |offset: u64, buf: &mut [u8]| -> VfsResult<usize> {
+        let content = generate();
+        let start = (offset as usize).min(content.len());
+        let len = buf.len().min(content.len() - start);
+        buf[..len].copy_from_slice(&content.as_bytes()[start..start + len]);
+        Ok(len)
+    })
+}
+
 /// 静态内容文件
 pub struct ProcFile {
     content: Arc<[u8]>,
@@ -73,3 +88,60 @@ impl VfsNodeOps for ProcDynamicFile {
 
     impl_vfs_non_dir_default! {}
 }
+
+/// 符号链接，例如 `/proc/self/fd/<fd>`
+pub struct ProcSymlink {
+    target: String,
+}
+
+impl ProcSymlink {
+    pub fn new(target: &str) -> Self {
+        Self {
+            target: target.into(),
+        }
+    }
+}
+
+impl VfsNodeOps for ProcSymlink {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            0,
+            VfsNodePerm::from_bits_truncate(0o777),
+            VfsNodeType::SymLink,
+            self.target.len() as _,
+            0,
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ))
+    }
+
+    fn get_attr_x(&self) -> VfsResult<VfsNodeAttrX> {
+        Ok(VfsNodeAttrX::new(
+            0, 0, 0, 1, 0, 0,
+            VfsNodePerm::from_bits_truncate(0o777),
+            VfsNodeType::SymLink,
+            0, self.target.len() as _,
+            0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+        ))
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let len = self.target.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.target.as_bytes()[..len]);
+        Ok(len)
+    }
+
+    impl_vfs_non_dir_default! {}
+}
@@ -92,6 +92,52 @@ fn test_dynamic_file() {
     );
 }
 
+#[test]
+fn test_text_file() {
+    let procfs = ProcFileSystem::new();
+    let root = procfs.root_dir_node();
+    let vroot = procfs.root_dir();
+
+    root.create_dynamic_file("text", text_file_generator(|| "line 1\nline 2\n".into()))
+        .unwrap();
+
+    // 按偏移量分段读取
+    let text = vroot.lookup("text").unwrap();
+    let mut buf = [0u8; 8];
+    assert_eq!(text.read_at(0, &mut buf).unwrap(), 8);
+    assert_eq!(&buf, b"line 1\nl");
+    assert_eq!(text.read_at(8, &mut buf).unwrap(), 6);
+    assert_eq!(&buf[..6], b"ine 2\n");
+    assert_eq!(text.read_at(14, &mut buf).unwrap(), 0);
+    assert_eq!(text.read_at(100, &mut buf).unwrap(), 0);
+}
+
+#[test]
+fn test_symlink() {
+    let procfs = ProcFileSystem::new();
+    let root = procfs.root_dir_node();
+    let vroot = procfs.root_dir();
+
+    // 由生成器给出的符号链接，类似 /proc/self/fd
+    let fd_dir = root.create_dir("fd").unwrap();
+    fd_dir.add_generator(Arc::new(|| {
+        Ok(vec![(
+            "0".into(),
+            ProcEntry::Symlink(Arc::new(ProcSymlink::new("/dev/console"))),
+        )])
+    }));
+
+    let link = vroot.lookup("fd/0").unwrap();
+    assert_eq!(link.get_attr().unwrap().file_type(), VfsNodeType::SymLink);
+    let mut buf = [0u8; 32];
+    assert_eq!(link.readlink(&mut buf).unwrap(), 12);
+    assert_eq!(&buf[..12], b"/dev/console");
+    assert_eq!(
+        vroot.lookup("fd/0/x").err(),
+        Some(VfsError::NotADirectory)
+    );
+}
+
 #[test]
 fn test_error_handling() {
     let procfs = ProcFileSystem::new();
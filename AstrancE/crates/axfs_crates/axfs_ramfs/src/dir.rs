@@ -1,12 +1,13 @@
 use alloc::collections::BTreeMap;
 use alloc::sync::{Arc, Weak};
-use alloc::{string::String, vec::Vec};
+use alloc::{format, string::String, vec::Vec};
 
-use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
+use axfs_vfs::path::canonicalize;
+use axfs_vfs::{RenameFlags, VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
 use axfs_vfs::{VfsError, VfsResult};
 use spin::RwLock;
 use axfs_vfs::structs::VfsNodeAttrX;
-use crate::file::FileNode;
+use crate::file::{FileNode, WhiteoutNode};
 
 /// The directory node in the RAM filesystem.
 ///
@@ -67,6 +68,66 @@ impl DirNode {
         children.remove(name);
         Ok(())
     }
+
+    /// Looks up the directory containing `path`, returns it with the last
+    /// component of `path`.
+    fn lookup_parent<'a>(&self, path: &'a str) -> VfsResult<(Arc<DirNode>, &'a str)> {
+        let path = path.trim_end_matches('/');
+        let (dir, name) = match path.rsplit_once('/') {
+            Some((dir, name)) => (self.this.upgrade().unwrap().lookup(dir)?, name),
+            None => (self.this.upgrade().unwrap() as VfsNodeRef, path),
+        };
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(VfsError::InvalidInput);
+        }
+        let dir = dir
+            .as_any()
+            .downcast_ref::<DirNode>()
+            .ok_or(VfsError::NotADirectory)?
+            .this
+            .upgrade()
+            .unwrap();
+        Ok((dir, name))
+    }
+}
+
+/// Checks whether `node` can be renamed over `old` with `flags`, returns
+/// `false` if there is nothing to do.
+fn check_rename(
+    node: &VfsNodeRef,
+    old: Option<&VfsNodeRef>,
+    flags: RenameFlags,
+) -> VfsResult<bool> {
+    let Some(old) = old else {
+        if flags.contains(RenameFlags::EXCHANGE) {
+            return Err(VfsError::NotFound);
+        }
+        return Ok(true);
+    };
+    if flags.contains(RenameFlags::NOREPLACE) {
+        return Err(VfsError::AlreadyExists);
+    }
+    if Arc::ptr_eq(node, old) {
+        return Ok(false);
+    }
+    if flags.contains(RenameFlags::EXCHANGE) {
+        return Ok(true);
+    }
+    match (node.get_attr()?.is_dir(), old.get_attr()?.is_dir()) {
+        (true, true) => {
+            let empty = old
+                .as_any()
+                .downcast_ref::<DirNode>()
+                .is_some_and(|dir| dir.children.read().is_empty());
+            if !empty {
+                return Err(VfsError::DirectoryNotEmpty);
+            }
+        }
+        (true, false) => return Err(VfsError::NotADirectory),
+        (false, true) => return Err(VfsError::IsADirectory),
+        (false, false) => {}
+    }
+    Ok(true)
 }
 
 impl VfsNodeOps for DirNode {
@@ -168,9 +229,95 @@ impl VfsNodeOps for DirNode {
         }
     }
 
+    fn rename(&self, src_path: &str, dst_path: &str, flags: RenameFlags) -> VfsResult {
+        log::debug!("rename at ramfs: {} -> {} {:?}", src_path, dst_path, flags);
+        let whiteout = flags.contains(RenameFlags::WHITEOUT);
+        let (src_dir, src_name) = self.lookup_parent(src_path)?;
+        let (dst_dir, dst_name) = self.lookup_parent(dst_path)?;
+
+        // there are no links in ramfs, so a directory contains another one
+        // iff its path is a prefix
+        let src = canonicalize(&format!("/{}", src_path.trim_end_matches('/')));
+        let dst = canonicalize(&format!("/{}", dst_path.trim_end_matches('/')));
+        if src != dst && dst.starts_with(&format!("{}/", src)) {
+            return Err(VfsError::InvalidInput); // move a directory into itself
+        }
+        if src != dst && src.starts_with(&format!("{}/", dst)) {
+            return if flags.contains(RenameFlags::EXCHANGE) {
+                Err(VfsError::InvalidInput)
+            } else {
+                Err(VfsError::DirectoryNotEmpty)
+            };
+        }
+
+        if Arc::ptr_eq(&src_dir, &dst_dir) {
+            let mut children = src_dir.children.write();
+            let node = children.get(src_name).cloned().ok_or(VfsError::NotFound)?;
+            let old = children.get(dst_name).cloned();
+            if check_rename(&node, old.as_ref(), flags)? {
+                match old {
+                    Some(old) if flags.contains(RenameFlags::EXCHANGE) => {
+                        children.insert(src_name.into(), old);
+                    }
+                    _ if whiteout => {
+                        children.insert(src_name.into(), Arc::new(WhiteoutNode));
+                    }
+                    _ => {
+                        children.remove(src_name);
+                    }
+                }
+                children.insert(dst_name.into(), node);
+            }
+            return Ok(());
+        }
+
+        // lock both directories in a fixed order so that concurrent renames
+        // can not deadlock
+        let src_first = Arc::as_ptr(&src_dir) < Arc::as_ptr(&dst_dir);
+        let (mut src_children, mut dst_children) = if src_first {
+            let src_children = src_dir.children.write();
+            (src_children, dst_dir.children.write())
+        } else {
+            let dst_children = dst_dir.children.write();
+            (src_dir.children.write(), dst_children)
+        };
+        let node = src_children
+            .get(src_name)
+            .cloned()
+            .ok_or(VfsError::NotFound)?;
+        let old = dst_children.get(dst_name).cloned();
+        if !check_rename(&node, old.as_ref(), flags)? {
+            return Ok(());
+        }
+        match old {
+            Some(old) if flags.contains(RenameFlags::EXCHANGE) => {
+                reparent(&old, &src_dir);
+                src_children.insert(src_name.into(), old);
+            }
+            _ if whiteout => {
+                src_children.insert(src_name.into(), Arc::new(WhiteoutNode));
+            }
+            _ => {
+                src_children.remove(src_name);
+            }
+        }
+        reparent(&node, &dst_dir);
+        dst_children.insert(dst_name.into(), node);
+        Ok(())
+    }
+
     axfs_vfs::impl_vfs_dir_default! {}
 }
 
+/// Sets the parent of `node` to `dir` if it is a directory.
+fn reparent(node: &VfsNodeRef, dir: &Arc<DirNode>) {
+    if node.get_attr().is_ok_and(|attr| attr.is_dir()) {
+        if let Some(node) = node.as_any().downcast_ref::<DirNode>() {
+            node.set_parent(Some(&(dir.clone() as VfsNodeRef)));
+        }
+    }
+}
+
 fn split_path(path: &str) -> (&str, Option<&str>) {
     let trimmed_path = path.trim_start_matches('/');
     trimmed_path.find('/').map_or((trimmed_path, None), |n| {
@@ -1,5 +1,7 @@
 use alloc::vec::Vec;
-use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsResult};
+use axfs_vfs::{
+    impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult,
+};
 use spin::RwLock;
 use axfs_vfs::structs::VfsNodeAttrX;
 
@@ -59,3 +61,32 @@ impl VfsNodeOps for FileNode {
 
     impl_vfs_non_dir_default! {}
 }
+
+/// The whiteout left at the source of a `RENAME_WHITEOUT` rename, a character
+/// device node with device number 0:0.
+pub struct WhiteoutNode;
+
+impl VfsNodeOps for WhiteoutNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            0,
+            VfsNodePerm::empty(),
+            VfsNodeType::CharDevice,
+            0,
+            0,
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ))
+    }
+
+    impl_vfs_non_dir_default! {}
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
+use axfs_vfs::{RenameFlags, VfsError, VfsNodeType, VfsResult};
 
 use crate::*;
 
@@ -134,3 +134,86 @@ fn test_ramfs() {
     assert_eq!(root.remove("./foo"), Ok(()));
     assert!(ramfs.root_dir_node().get_entries().is_empty());
 }
+
+#[test]
+fn test_rename() {
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.create("f2", VfsNodeType::File).unwrap();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/bar", VfsNodeType::Dir).unwrap();
+    root.create("foo/bar/f3", VfsNodeType::File).unwrap();
+    root.create("baz", VfsNodeType::Dir).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let no = RenameFlags::empty();
+
+    // replace
+    assert_eq!(
+        root.rename("f1", "f2", RenameFlags::NOREPLACE).err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(root.rename("f1", "f2", no), Ok(()));
+    assert!(Arc::ptr_eq(&root.clone().lookup("f2").unwrap(), &f1));
+    assert_eq!(root.clone().lookup("f1").err(), Some(VfsError::NotFound));
+    assert_eq!(root.rename("f2", "f2", no), Ok(()));
+
+    // directories
+    assert_eq!(
+        root.rename("f2", "foo", no).err(),
+        Some(VfsError::IsADirectory)
+    );
+    assert_eq!(
+        root.rename("baz", "f2", no).err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.rename("baz", "foo", no).err(),
+        Some(VfsError::DirectoryNotEmpty)
+    );
+    assert_eq!(
+        root.rename("foo", "foo/bar/qux", no).err(),
+        Some(VfsError::InvalidInput)
+    );
+    assert_eq!(
+        root.rename("foo/bar", "foo", no).err(),
+        Some(VfsError::DirectoryNotEmpty)
+    );
+    assert_eq!(root.rename("foo/bar", "baz", no), Ok(()));
+    let baz = root.clone().lookup("baz").unwrap();
+    assert!(Arc::ptr_eq(&baz.parent().unwrap(), &root));
+    assert!(baz.lookup("f3").is_ok());
+    assert!(root.clone().lookup("foo/bar").is_err());
+
+    // exchange
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1_new = root.clone().lookup("f1").unwrap();
+    assert_eq!(
+        root.rename("f1", "foo/f4", RenameFlags::EXCHANGE).err(),
+        Some(VfsError::NotFound)
+    );
+    assert_eq!(root.rename("f1", "f2", RenameFlags::EXCHANGE), Ok(()));
+    assert!(Arc::ptr_eq(&root.clone().lookup("f1").unwrap(), &f1));
+    assert!(Arc::ptr_eq(&root.clone().lookup("f2").unwrap(), &f1_new));
+    assert_eq!(root.rename("baz", "foo/f2", RenameFlags::NOREPLACE), Ok(()));
+    assert_eq!(root.rename("f2", "foo/f2", RenameFlags::EXCHANGE), Ok(()));
+    assert!(root.clone().lookup("f2/f3").is_ok());
+    assert!(Arc::ptr_eq(
+        &root.clone().lookup("f2").unwrap().parent().unwrap(),
+        &root
+    ));
+    assert!(Arc::ptr_eq(
+        &root.clone().lookup("foo/f2").unwrap(),
+        &f1_new
+    ));
+    assert_eq!(root.rename("f1", "f5", RenameFlags::WHITEOUT), Ok(()));
+    assert!(Arc::ptr_eq(&root.clone().lookup("f5").unwrap(), &f1));
+    let whiteout = root.clone().lookup("f1").unwrap().get_attr().unwrap();
+    assert_eq!(whiteout.file_type(), VfsNodeType::CharDevice);
+    assert_eq!(whiteout.rdev(), 0);
+    assert_eq!(
+        root.rename("f5", "f1", RenameFlags::WHITEOUT | RenameFlags::NOREPLACE)
+            .err(),
+        Some(VfsError::AlreadyExists)
+    );
+}
@@ -31,6 +31,9 @@
 //! | [`create()`](VfsNodeOps::create) | Create a new node with the given path | directory |
 //! | [`remove()`](VfsNodeOps::remove) | Remove the node with the given path | directory |
 //! | [`read_dir()`](VfsNodeOps::read_dir) | Read directory entries | directory |
+//! | [`link()`](VfsNodeOps::link) | Create a hard link to a node | directory |
+//! | [`symlink()`](VfsNodeOps::symlink) | Create a symbolic link | directory |
+//! | [`readlink()`](VfsNodeOps::readlink) | Read the target of a symbolic link | symlink |
 //!
 //! [inodes]: https://en.wikipedia.org/wiki/Inode
 
@@ -47,7 +50,10 @@ use alloc::sync::Arc;
 use alloc::vec::Vec;
 use axerrno::{ax_err, AxError, AxResult};
 pub use crate::structs::VfsNodeAttrX;
-pub use self::structs::{FileSystemInfo, VfsDirEntry, VfsNodeAttr, VfsNodePerm, VfsNodeType};
+pub use self::structs::{
+    major, makedev, minor, FileSystemInfo, RenameFlags, VfsDirEntry, VfsNodeAttr, VfsNodePerm,
+    VfsNodeType,
+};
 
 /// A wrapper of [`Arc<dyn VfsNodeOps>`].
 pub type VfsNodeRef = Arc<dyn VfsNodeOps>;
@@ -147,6 +153,17 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(InvalidInput)
     }
 
+    /// Read data from the file at the given offset like [`read_at`], but
+    /// return [`WouldBlock`](VfsError::WouldBlock) instead of waiting when no
+    /// data is available yet.
+    ///
+    /// Only devices whose reads wait for data need to override it.
+    ///
+    /// [`read_at`]: VfsNodeOps::read_at
+    fn read_at_nonblocking(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        self.read_at(offset, buf)
+    }
+
     /// Write data to the file at the given offset.
     fn write_at(&self, _offset: u64, _buf: &[u8]) -> VfsResult<usize> {
         ax_err!(InvalidInput)
@@ -185,6 +202,12 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(Unsupported)
     }
 
+    /// Create a special file (FIFO, socket or device node) with the given
+    /// `path` in the directory, `rdev` is the device number of a device node.
+    fn mknod(&self, _path: &str, _ty: VfsNodeType, _rdev: u64) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
     /// Remove the node with the given `path` in the directory.
     fn remove(&self, _path: &str) -> VfsResult {
         ax_err!(Unsupported)
@@ -196,9 +219,47 @@ pub trait VfsNodeOps: Send + Sync {
     }
 
     /// Renames or moves existing file or directory.
-    fn rename(&self, _src_path: &str, _dst_path: &str) -> VfsResult {
+    ///
+    /// An existing destination is replaced if it has a compatible type (a
+    /// directory only by an empty directory), unless `flags` ask otherwise.
+    /// The replacement is atomic only where the filesystem supports it, FAT
+    /// for instance removes the destination first.
+    fn rename(&self, _src_path: &str, _dst_path: &str, _flags: RenameFlags) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Create a hard link to `node` at `path` in the directory.
+    fn link(&self, _path: &str, _node: VfsNodeRef) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Create a symbolic link at `path` in the directory, pointing to `target`.
+    fn symlink(&self, _path: &str, _target: &str) -> VfsResult {
         ax_err!(Unsupported)
     }
+
+    /// Read the target of the symbolic link into `buf`.
+    fn readlink(&self, _buf: &mut [u8]) -> VfsResult<usize> {
+        ax_err!(InvalidInput)
+    }
+
+    /// Change the permission mode of the node.
+    fn set_perm(&self, _perm: VfsNodePerm) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Change the owner and group of the node, `None` keeps the current one.
+    fn set_owner(&self, _uid: Option<u32>, _gid: Option<u32>) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Get the memory page holding the file data at page `index`, if the
+    /// filesystem keeps file data in pages that can be mapped directly.
+    ///
+    /// Used by shared memory mappings to avoid copying the data.
+    fn shared_page(&self, _index: u64) -> Option<Arc<dyn core::any::Any + Send + Sync>> {
+        None
+    }
     
     /// Convert `&self` to [`&dyn Any`][1] that can use
     /// [`Any::downcast_ref`][2].
@@ -32,6 +32,26 @@ pub struct FileSystemInfo {
     pub namelen: u64,
 }
 
+/// Builds a device number from its `major` and `minor` parts, in the
+/// encoding of `dev_t` in the C library.
+pub const fn makedev(major: u32, minor: u32) -> u64 {
+    let (major, minor) = (major as u64, minor as u64);
+    ((major & 0xffff_f000) << 32)
+        | ((major & 0xfff) << 8)
+        | ((minor & 0xffff_ff00) << 12)
+        | (minor & 0xff)
+}
+
+/// Returns the major part of the device number `dev`.
+pub const fn major(dev: u64) -> u32 {
+    (((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0xfff)) as u32
+}
+
+/// Returns the minor part of the device number `dev`.
+pub const fn minor(dev: u64) -> u32 {
+    (((dev >> 12) & 0xffff_ff00) | (dev & 0xff)) as u32
+}
+
 /// Node (file/directory) attributes.
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
@@ -58,6 +78,8 @@ pub struct VfsNodeAttr {
     atime_nse:u32,
     ctime_nse:u32,
     mtime_nse:u32,
+    /// Device number of a device special file.
+    rdev: u64,
 }
 
 bitflags::bitflags! {
@@ -84,6 +106,28 @@ bitflags::bitflags! {
         const OTHER_WRITE = 0o2;
         /// Others have execute permission.
         const OTHER_EXEC = 0o1;
+
+        /// Set user ID on execution.
+        const SET_UID = 0o4000;
+        /// Set group ID on execution.
+        const SET_GID = 0o2000;
+        /// Restricted deletion (sticky bit).
+        const STICKY = 0o1000;
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags of [`VfsNodeOps::rename`](crate::VfsNodeOps::rename), as in
+    /// `renameat2(2)`.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct RenameFlags: u32 {
+        /// Fail with `AlreadyExists` instead of replacing the destination.
+        const NOREPLACE = 1 << 0;
+        /// Atomically exchange the source and the destination, which must
+        /// both exist.
+        const EXCHANGE = 1 << 1;
+        /// Leave a whiteout object in place of the source (for overlayfs).
+        const WHITEOUT = 1 << 2;
     }
 }
 
@@ -268,6 +312,7 @@ impl VfsNodeAttr {
             atime_nse:atime_nsec,
             ctime_nse:ctime_nsec,
             mtime_nse:mtime_nsec,
+            rdev: 0,
         }
     }
 
@@ -290,6 +335,7 @@ impl VfsNodeAttr {
             atime_nse:0,
             ctime_nse:0,
             mtime_nse:0,
+            rdev: 0,
         }
     }
 
@@ -313,6 +359,7 @@ impl VfsNodeAttr {
             atime_nse:0,
             ctime_nse:0,
             mtime_nse:0,
+            rdev: 0,
         }
     }
 
@@ -365,6 +412,16 @@ impl VfsNodeAttr {
     pub const fn atime_nse(&self) -> u32 {self.atime_nse}
     pub const fn ctime_nse(&self) -> u32 {self.ctime_nse}
     pub const fn dev(&self) -> u64 {self.dev}
+
+    /// Returns the device number of a device special file.
+    pub const fn rdev(&self) -> u64 {
+        self.rdev
+    }
+
+    /// Sets the device number of a device special file.
+    pub fn set_rdev(&mut self, rdev: u64) {
+        self.rdev = rdev
+    }
 }
 
 // stx_mask 位掩码常量
@@ -605,6 +662,10 @@ impl VfsNodeAttrX {
     pub const fn stx_dev_minor(&self) -> u32 { self.stx_dev_minor }
     // Setters
     pub fn set_perm(&mut self, mode: VfsNodePerm) { self.stx_mode = mode; }
+    pub fn set_rdev(&mut self, major: u32, minor: u32) {
+        self.stx_rdev_major = major;
+        self.stx_rdev_minor = minor;
+    }
     /// Whether the node is a file.
     pub const fn is_file(&self) -> bool {
         self.ty.is_file()
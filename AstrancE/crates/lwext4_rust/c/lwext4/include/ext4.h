@@ -291,6 +291,24 @@ int ext4_flink(const char *path, const char *hardlink_path);
  * @return  Standard error code. */
 int ext4_frename(const char *path, const char *new_path);
 
+/**@brief   Fail if the rename target exists.*/
+#define EXT4_RENAME_NOREPLACE (1 << 0)
+
+/**@brief   Swap the rename source and target.*/
+#define EXT4_RENAME_EXCHANGE (1 << 1)
+
+/**@brief Rename file, replacing an existing target in place.
+ *
+ * Unlike @ref ext4_frename, an existing target is replaced by switching
+ * its directory entry to the source i-node, so the target name refers to
+ * either the old or the new file at every point.
+ *
+ * @param path Source.
+ * @param new_path Destination.
+ * @param flags @ref EXT4_RENAME_NOREPLACE or @ref EXT4_RENAME_EXCHANGE.
+ * @return  Standard error code. */
+int ext4_frename2(const char *path, const char *new_path, uint32_t flags);
+
 /**@brief   File open function.
  *
  * @param   file  File handle.
@@ -214,6 +214,29 @@ static int ext4_has_children(bool *has_children, struct ext4_inode_ref *enode)
 	return EOK;
 }
 
+/**@brief Point the '..' entry of directory @p dir at @p parent_index.*/
+static int ext4_dir_set_parent(struct ext4_inode_ref *dir,
+			       uint32_t parent_index)
+{
+	int r = EOK;
+	struct ext4_dir_search_result res;
+
+	if (!ext4_inode_has_flag(dir->inode, EXT4_INODE_FLAG_INDEX)) {
+		r = ext4_dir_find_entry(&res, dir, "..", strlen(".."));
+		if (r != EOK)
+			return EIO;
+
+		ext4_dir_en_set_inode(res.dentry, parent_index);
+		ext4_dir_set_csum(dir, (void *)res.block.data);
+		ext4_trans_set_block_dirty(res.block.buf);
+		return ext4_dir_destroy_result(dir, &res);
+	}
+#if CONFIG_DIR_INDEX_ENABLE
+	r = ext4_dir_dx_reset_parent_inode(dir, parent_index);
+#endif
+	return r;
+}
+
 static int ext4_link(struct ext4_mountpoint *mp, struct ext4_inode_ref *parent,
 		     struct ext4_inode_ref *ch, const char *n,
 		     uint32_t len, bool rename)
@@ -273,28 +296,9 @@ static int ext4_link(struct ext4_mountpoint *mp, struct ext4_inode_ref *parent,
 	 * we reset the original '..' pointer.
 	 */
 	if (is_dir) {
-		bool idx;
-		idx = ext4_inode_has_flag(ch->inode, EXT4_INODE_FLAG_INDEX);
-		struct ext4_dir_search_result res;
-		if (!idx) {
-			r = ext4_dir_find_entry(&res, ch, "..", strlen(".."));
-			if (r != EOK)
-				return EIO;
-
-			ext4_dir_en_set_inode(res.dentry, parent->index);
-			ext4_trans_set_block_dirty(res.block.buf);
-			r = ext4_dir_destroy_result(ch, &res);
-			if (r != EOK)
-				return r;
-
-		} else {
-#if CONFIG_DIR_INDEX_ENABLE
-			r = ext4_dir_dx_reset_parent_inode(ch, parent->index);
-			if (r != EOK)
-				return r;
-
-#endif
-		}
+		r = ext4_dir_set_parent(ch, parent->index);
+		if (r != EOK)
+			return r;
 
 		ext4_fs_inode_links_count_inc(parent);
 		parent->dirty = true;
@@ -1335,30 +1339,22 @@ Finish:
 
 }
 
-int ext4_frename(const char *path, const char *new_path)
+/**@brief Rename @p path to the free name @p new_path, with the mount point
+ *        lock held.*/
+static int ext4_frename_locked(struct ext4_mountpoint *mp, const char *path,
+			       const char *new_path)
 {
 	int r;
 	ext4_file f;
 	uint32_t name_off;
 	bool parent_loaded = false, child_loaded = false;
 	uint32_t parent_inode, child_inode;
-	struct ext4_mountpoint *mp = ext4_get_mount(path);
 	struct ext4_inode_ref child_ref, parent_ref;
 
-	if (!mp)
-		return ENOENT;
-
-	if (mp->fs.read_only)
-		return EROFS;
-
-	EXT4_MP_LOCK(mp);
-
 	r = ext4_generic_open2(&f, path, O_RDONLY, EXT4_DE_UNKNOWN,
 				&parent_inode, &name_off);
-	if (r != EOK) {
-		EXT4_MP_UNLOCK(mp);
+	if (r != EOK)
 		return r;
-	}
 
 	child_inode = f.inode;
 	ext4_fclose(&f);
@@ -1398,9 +1394,284 @@ Finish:
 	else
 		ext4_trans_stop(mp);
 
+	return r;
+}
+
+int ext4_frename(const char *path, const char *new_path)
+{
+	int r;
+	struct ext4_mountpoint *mp = ext4_get_mount(path);
+
+	if (!mp)
+		return ENOENT;
+
+	if (mp->fs.read_only)
+		return EROFS;
+
+	EXT4_MP_LOCK(mp);
+	r = ext4_frename_locked(mp, path, new_path);
 	EXT4_MP_UNLOCK(mp);
 	return r;
+}
+
+/**@brief Whether @p sub lies below the directory @p path.*/
+static bool ext4_path_below(const char *path, const char *sub)
+{
+	size_t len = strlen(path);
+
+	while (len && path[len - 1] == '/')
+		len--;
+
+	return !strncmp(path, sub, len) && sub[len] == '/';
+}
+
+/**@brief Get the i-node type of the entry @p name of @p parent.*/
+static int ext4_dir_en_type(struct ext4_mountpoint *mp,
+			    struct ext4_inode_ref *parent, const char *name,
+			    uint8_t *type)
+{
+	bool is_goal;
+	struct ext4_dir_search_result res;
+	int r = ext4_dir_find_entry(&res, parent, name,
+				    ext4_path_check(name, &is_goal));
+	if (r != EOK) {
+		ext4_dir_destroy_result(parent, &res);
+		return r;
+	}
+
+	*type = ext4_dir_en_get_inode_type(&mp->fs.sb, res.dentry);
+	return ext4_dir_destroy_result(parent, &res);
+}
+
+/**@brief Point the entry @p name of @p parent at the i-node @p index of
+ *        type @p type, which is replaced by the previous type.*/
+static int ext4_dir_en_repoint(struct ext4_mountpoint *mp,
+			       struct ext4_inode_ref *parent, const char *name,
+			       uint32_t index, uint8_t *type)
+{
+	bool is_goal;
+	uint8_t old_type;
+	struct ext4_dir_search_result res;
+	int r = ext4_dir_find_entry(&res, parent, name,
+				    ext4_path_check(name, &is_goal));
+	if (r != EOK) {
+		ext4_dir_destroy_result(parent, &res);
+		return r;
+	}
+
+	old_type = ext4_dir_en_get_inode_type(&mp->fs.sb, res.dentry);
+	ext4_dir_en_set_inode(res.dentry, index);
+	ext4_dir_en_set_inode_type(&mp->fs.sb, res.dentry, *type);
+	*type = old_type;
+	ext4_dir_set_csum(parent, (void *)res.block.data);
+	ext4_trans_set_block_dirty(res.block.buf);
+	return ext4_dir_destroy_result(parent, &res);
+}
+
+/**@brief Drop the link of a replaced rename target, freeing the i-node
+ *        when it was the last one.*/
+static int ext4_drop_replaced(struct ext4_mountpoint *mp,
+			      struct ext4_inode_ref *parent,
+			      struct ext4_inode_ref *child)
+{
+	int r;
+
+	if (ext4_inode_is_type(&mp->fs.sb, child->inode,
+			       EXT4_INODE_MODE_DIRECTORY)) {
+		ext4_inode_set_links_cnt(child->inode, 0);
+		child->dirty = true;
+		r = ext4_trunc_dir(mp, parent, child);
+		if (r != EOK)
+			return r;
+	} else {
+		ext4_fs_inode_links_count_dec(child);
+		child->dirty = true;
+		if (ext4_inode_get_links_cnt(child->inode))
+			return EOK;
 
+		ext4_block_cache_write_back(mp->fs.bdev, 1);
+		r = ext4_trunc_inode(mp, child->index, 0);
+		ext4_block_cache_write_back(mp->fs.bdev, 0);
+		if (r != EOK)
+			return r;
+	}
+
+	ext4_inode_set_del_time(child->inode, -1L);
+	return ext4_fs_free_inode(child);
+}
+
+int ext4_frename2(const char *path, const char *new_path, uint32_t flags)
+{
+	int r;
+	bool is_goal;
+	ext4_file f;
+	uint8_t type;
+	uint32_t src_off, dst_off;
+	uint32_t src_parent, dst_parent, src_inode, dst_inode;
+	bool src_dir, dst_dir;
+	bool exchange = flags & EXT4_RENAME_EXCHANGE;
+	struct ext4_inode_ref src_pref, dst_pref, src_ref, dst_ref;
+	struct ext4_inode_ref *dst_pp = &dst_pref;
+	struct ext4_mountpoint *mp = ext4_get_mount(path);
+
+	if (!mp)
+		return ENOENT;
+
+	if (mp->fs.read_only)
+		return EROFS;
+
+	if (mp != ext4_get_mount(new_path))
+		return EINVAL;
+
+	if (exchange && (flags & EXT4_RENAME_NOREPLACE))
+		return EINVAL;
+
+	/* A directory can not be moved below itself. */
+	if (ext4_path_below(path, new_path) ||
+	    (exchange && ext4_path_below(new_path, path)))
+		return EINVAL;
+
+	EXT4_MP_LOCK(mp);
+
+	r = ext4_generic_open2(&f, path, O_RDONLY, EXT4_DE_UNKNOWN,
+			       &src_parent, &src_off);
+	if (r != EOK)
+		goto Unlock;
+
+	src_inode = f.inode;
+	ext4_fclose(&f);
+
+	r = ext4_generic_open2(&f, new_path, O_RDONLY, EXT4_DE_UNKNOWN,
+			       &dst_parent, &dst_off);
+	if (r == ENOENT && !exchange) {
+		r = ext4_frename_locked(mp, path, new_path);
+		goto Unlock;
+	}
+	if (r != EOK)
+		goto Unlock;
+
+	dst_inode = f.inode;
+	ext4_fclose(&f);
+
+	if (flags & EXT4_RENAME_NOREPLACE) {
+		r = EEXIST;
+		goto Unlock;
+	}
+
+	/* Both names are links to the same i-node, nothing to do. */
+	if (src_inode == dst_inode)
+		goto Unlock;
+
+	path += src_off;
+	new_path += dst_off;
+	ext4_trans_start(mp);
+
+	r = ext4_fs_get_inode_ref(&mp->fs, src_parent, &src_pref);
+	if (r != EOK)
+		goto Finish;
+
+	if (dst_parent == src_parent) {
+		dst_pp = &src_pref;
+	} else {
+		r = ext4_fs_get_inode_ref(&mp->fs, dst_parent, &dst_pref);
+		if (r != EOK)
+			goto PutSrcParent;
+	}
+
+	r = ext4_fs_get_inode_ref(&mp->fs, src_inode, &src_ref);
+	if (r != EOK)
+		goto PutDstParent;
+
+	r = ext4_fs_get_inode_ref(&mp->fs, dst_inode, &dst_ref);
+	if (r != EOK)
+		goto PutSrc;
+
+	src_dir = ext4_inode_is_type(&mp->fs.sb, src_ref.inode,
+				     EXT4_INODE_MODE_DIRECTORY);
+	dst_dir = ext4_inode_is_type(&mp->fs.sb, dst_ref.inode,
+				     EXT4_INODE_MODE_DIRECTORY);
+
+	if (!exchange && src_dir != dst_dir) {
+		r = src_dir ? ENOTDIR : EISDIR;
+		goto PutDst;
+	}
+
+	if (!exchange && dst_dir) {
+		bool has_children;
+
+		r = ext4_has_children(&has_children, &dst_ref);
+		if (r == EOK && has_children)
+			r = ENOTEMPTY;
+		if (r != EOK)
+			goto PutDst;
+	}
+
+	r = ext4_dir_en_type(mp, &src_pref, path, &type);
+	if (r != EOK)
+		goto PutDst;
+
+	/* The target entry is switched to the source i-node in place, so the
+	 * target name never stops existing. */
+	r = ext4_dir_en_repoint(mp, dst_pp, new_path, src_inode, &type);
+	if (r != EOK)
+		goto PutDst;
+
+	if (exchange) {
+		r = ext4_dir_en_repoint(mp, &src_pref, path, dst_inode, &type);
+		if (r != EOK)
+			goto PutDst;
+	} else {
+		r = ext4_dir_remove_entry(&src_pref, path,
+					  ext4_path_check(path, &is_goal));
+		if (r != EOK)
+			goto PutDst;
+
+		/* The replaced directory takes its '..' link along. */
+		if (dst_dir)
+			ext4_fs_inode_links_count_dec(dst_pp);
+	}
+
+	if (dst_parent != src_parent && src_dir) {
+		r = ext4_dir_set_parent(&src_ref, dst_parent);
+		if (r != EOK)
+			goto PutDst;
+
+		ext4_fs_inode_links_count_dec(&src_pref);
+		ext4_fs_inode_links_count_inc(dst_pp);
+	}
+
+	if (exchange && dst_parent != src_parent && dst_dir) {
+		r = ext4_dir_set_parent(&dst_ref, src_parent);
+		if (r != EOK)
+			goto PutDst;
+
+		ext4_fs_inode_links_count_dec(dst_pp);
+		ext4_fs_inode_links_count_inc(&src_pref);
+	}
+
+	src_pref.dirty = true;
+	dst_pp->dirty = true;
+
+	if (!exchange)
+		r = ext4_drop_replaced(mp, dst_pp, &dst_ref);
+
+PutDst:
+	ext4_fs_put_inode_ref(&dst_ref);
+PutSrc:
+	ext4_fs_put_inode_ref(&src_ref);
+PutDstParent:
+	if (dst_pp != &src_pref)
+		ext4_fs_put_inode_ref(&dst_pref);
+PutSrcParent:
+	ext4_fs_put_inode_ref(&src_pref);
+Finish:
+	if (r != EOK)
+		ext4_trans_abort(mp);
+	else
+		ext4_trans_stop(mp);
+Unlock:
+	EXT4_MP_UNLOCK(mp);
+	return r;
 }
 
 /****************************************************************************/
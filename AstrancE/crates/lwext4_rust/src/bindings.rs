@@ -486,6 +486,8 @@ pub const BUFSIZ: u32 = 1024;
 pub const FILENAME_MAX: u32 = 4096;
 pub const FOPEN_MAX: u32 = 1000;
 pub const TMP_MAX: u32 = 10000;
+pub const EXT4_RENAME_NOREPLACE: u32 = 1;
+pub const EXT4_RENAME_EXCHANGE: u32 = 2;
 pub const L_tmpnam: u32 = 20;
 pub const L_ctermid: u32 = 20;
 pub const P_tmpdir: &[u8; 5] = b"/tmp\0";
@@ -1787,6 +1789,14 @@ extern "C" {
         new_path: *const ::core::ffi::c_char,
     ) -> ::core::ffi::c_int;
 }
+extern "C" {
+    #[doc = "@brief Rename file, replacing an existing target in place.\n\n Unlike @ref ext4_frename, an existing target is replaced by switching\n its directory entry to the source i-node, so the target name refers to\n either the old or the new file at every point.\n\n @param path Source.\n @param new_path Destination.\n @param flags @ref EXT4_RENAME_NOREPLACE or @ref EXT4_RENAME_EXCHANGE.\n @return  Standard error code."]
+    pub fn ext4_frename2(
+        path: *const ::core::ffi::c_char,
+        new_path: *const ::core::ffi::c_char,
+        flags: u32,
+    ) -> ::core::ffi::c_int;
+}
 extern "C" {
     #[doc = "@brief   File open function.\n\n @param   file  File handle.\n @param   path  File path, has to start from mount point:/my_partition/file.\n @param   flags File open flags.\n  |---------------------------------------------------------------|\n  |   r or rb                 O_RDONLY                            |\n  |---------------------------------------------------------------|\n  |   w or wb                 O_WRONLY|O_CREAT|O_TRUNC            |\n  |---------------------------------------------------------------|\n  |   a or ab                 O_WRONLY|O_CREAT|O_APPEND           |\n  |---------------------------------------------------------------|\n  |   r+ or rb+ or r+b        O_RDWR                              |\n  |---------------------------------------------------------------|\n  |   w+ or wb+ or w+b        O_RDWR|O_CREAT|O_TRUNC              |\n  |---------------------------------------------------------------|\n  |   a+ or ab+ or a+b        O_RDWR|O_CREAT|O_APPEND             |\n  |---------------------------------------------------------------|\n\n @return  Standard error code."]
     pub fn ext4_fopen(
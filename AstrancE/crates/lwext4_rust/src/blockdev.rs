@@ -27,11 +27,20 @@ pub struct Ext4BlockWrapper<K: KernelDevOp> {
     //block_dev: K::DevType,
     name: [u8; 16],
     mount_point: [u8; 32],
+    /// Whether `lwext4_mount` succeeded, the drop only unmounts a mounted fs.
+    mounted: bool,
     pd: core::marker::PhantomData<K>,
 }
 
 impl<K: KernelDevOp> Ext4BlockWrapper<K> {
     pub fn new(block_dev: K::DevType, name: &str, mount_point: &str) -> Result<Self, i32> {
+        // The names are kept NUL-terminated in fixed buffers
+        if name.len() >= 16 || mount_point.len() >= 32 {
+            return Err(EINVAL as _);
+        }
+        let c_name = CString::new(name).map_err(|_| EINVAL as i32)?;
+        let c_mountpoint = CString::new(mount_point).map_err(|_| EINVAL as i32)?;
+
         // note this ownership
         let devt_user = Box::into_raw(Box::new(block_dev)) as *mut c_void;
         //let devt_user = devt.as_mut() as *mut _ as *mut c_void;
@@ -70,10 +79,7 @@ impl<K: KernelDevOp> Ext4BlockWrapper<K> {
             journal: null_mut(),
         };
 
-        let c_name = CString::new(name).expect("CString::new ext4_fs failed");
         let c_name = c_name.as_bytes_with_nul(); // + '\0'
-                                                 // let c_mountpoint = CString::new("/mp/").unwrap();
-        let c_mountpoint = CString::new(mount_point).unwrap();
         let c_mountpoint = c_mountpoint.as_bytes_with_nul();
 
         let mut name: [u8; 16] = [0; 16];
@@ -86,6 +92,7 @@ impl<K: KernelDevOp> Ext4BlockWrapper<K> {
             //block_dev,
             name,
             mount_point,
+            mounted: false,
             pd: core::marker::PhantomData,
         };
 
@@ -95,10 +102,12 @@ impl<K: KernelDevOp> Ext4BlockWrapper<K> {
         // ext4_blockdev into static instance
         // lwext4_mount
         // let c_mountpoint = c_mountpoint as *const _ as *const c_char;
+        // A failed mount (e.g. the disk is not an EXT4 file system) is
+        // rolled back by `lwext4_mount`, the drop then only frees the device.
         unsafe {
-            ext4bd
-                .lwext4_mount()
-                .expect("Failed to mount the ext4 file system, perhaps the disk is not an EXT4 file system.");
+            ext4bd.lwext4_mount().inspect_err(|r| {
+                error!("Failed to mount the ext4 file system: rc = {:?}", r);
+            })?;
         }
 
         ext4bd.lwext4_dir_ls();
@@ -231,11 +240,14 @@ impl<K: KernelDevOp> Ext4BlockWrapper<K> {
         let r = ext4_mount(c_name, c_mountpoint, false);
         if r != EOK as i32 {
             error!("ext4_mount: rc = {:?}\n", r);
+            ext4_device_unregister(c_name);
             return Err(r);
         }
         let r = ext4_recover(c_mountpoint);
         if (r != EOK as i32) && (r != ENOTSUP as i32) {
             error!("ext4_recover: rc = {:?}\n", r);
+            ext4_umount(c_mountpoint);
+            ext4_device_unregister(c_name);
             return Err(r);
         }
 
@@ -249,11 +261,14 @@ impl<K: KernelDevOp> Ext4BlockWrapper<K> {
         let r = ext4_journal_start(c_mountpoint);
         if r != EOK as i32 {
             error!("ext4_journal_start: rc = {:?}\n", r);
+            ext4_umount(c_mountpoint);
+            ext4_device_unregister(c_name);
             return Err(r);
         }
         ext4_cache_write_back(c_mountpoint, true);
         // ext4_bcache
 
+        self.mounted = true;
         info!("lwext4 mount Okay");
         self.lwext4_dir_ls();
         Ok(0)
@@ -286,6 +301,7 @@ impl<K: KernelDevOp> Ext4BlockWrapper<K> {
             }
         }
 
+        self.mounted = false;
         info!("lwext4 umount Okay");
         Ok(0)
     }
@@ -386,7 +402,11 @@ impl<K: KernelDevOp> Ext4BlockWrapper<K> {
 impl<K: KernelDevOp> Drop for Ext4BlockWrapper<K> {
     fn drop(&mut self) {
         info!("Drop struct Ext4BlockWrapper");
-        self.lwext4_umount().unwrap();
+        if self.mounted {
+            if let Err(r) = self.lwext4_umount() {
+                error!("Failed to umount the ext4 file system: rc = {:?}", r);
+            }
+        }
         let devtype = unsafe { Box::from_raw((*(&self.value).bdif).p_user as *mut K::DevType) };
         drop(devtype);
     }
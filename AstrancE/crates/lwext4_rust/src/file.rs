@@ -29,6 +29,8 @@ pub struct InodeInfo {
     pub btime_ex: u32,
     pub mtime_ex: u32,
     pub ctime_ex: u32,
+    pub rdev_major: u32,
+    pub rdev_minor: u32,
 }
 
 impl InodeInfo {
@@ -44,6 +46,9 @@ impl InodeInfo {
         };
         let uid = (uid_hi << 16) | uid_lo;
         let gid = (gid_hi << 16) | gid_lo;
+        // 设备号与 Linux 一致：旧格式存于 blocks[0]，新格式存于 blocks[1]
+        let blocks = inode.blocks;
+        let rdev = if blocks[0] != 0 { blocks[0] } else { blocks[1] };
         Self {
             dev: 0,
             st_ino: ino as u64,
@@ -59,6 +64,8 @@ impl InodeInfo {
             btime_ex:u32::from(LittleEndian::read_u32(&inode.crtime_extra.to_ne_bytes())),
             mtime_ex: u32::from(LittleEndian::read_u32(&inode.mtime_extra.to_ne_bytes())),
             ctime_ex: u32::from(LittleEndian::read_u32(&inode.ctime_extra.to_ne_bytes())),
+            rdev_major: (rdev & 0xfff00) >> 8,
+            rdev_minor: (rdev & 0xff) | ((rdev >> 12) & 0xfff00),
         }
     }
     pub fn dev(&self) -> u64 {self.dev}
@@ -75,6 +82,8 @@ impl InodeInfo {
     pub fn ctime_ex(&self) -> u32 {self.ctime_ex}
     pub fn btime(&self) -> u32 {self.btime}
     pub fn btime_ex(&self) -> u32 {self.btime_ex}
+    pub fn rdev_major(&self) -> u32 {self.rdev_major}
+    pub fn rdev_minor(&self) -> u32 {self.rdev_minor}
 }
 
 impl Ext4File {
@@ -328,6 +337,25 @@ impl Ext4File {
         Ok(EOK as usize)
     }
 
+    /// Rename file and directory, replacing or exchanging with an existing
+    /// `new_path` as `flags` (`EXT4_RENAME_*`) say
+    pub fn file_rename2(&mut self, path: &str, new_path: &str, flags: u32) -> Result<usize, i32> {
+        let c_path = CString::new(path).expect("CString::new failed");
+        let c_path = c_path.into_raw();
+        let c_new_path = CString::new(new_path).expect("CString::new failed");
+        let c_new_path = c_new_path.into_raw();
+        let r = unsafe { ext4_frename2(c_path, c_new_path, flags) };
+        unsafe {
+            drop(CString::from_raw(c_path));
+            drop(CString::from_raw(c_new_path));
+        }
+        if r != EOK as i32 {
+            error!("ext4_frename2 error: rc = {}", r);
+            return Err(r);
+        }
+        Ok(EOK as usize)
+    }
+
     /// Remove file by path.
     pub fn file_remove(&mut self, path: &str) -> Result<usize, i32> {
         trace!("file_remove {}", path);
@@ -346,6 +374,33 @@ impl Ext4File {
         Ok(EOK as usize)
     }
 
+    /// Create a special file (FIFO, socket or device node) by path, `major`
+    /// and `minor` are the device number of a device node.
+    pub fn file_mknod(
+        &mut self,
+        path: &str,
+        types: InodeTypes,
+        major: u32,
+        minor: u32,
+    ) -> Result<usize, i32> {
+        trace!("file_mknod {} {:?} {}:{}", path, types, major, minor);
+
+        // the encoding of Linux, which fits in 16 bits for small numbers
+        let dev = (minor & 0xff) | (major << 8) | ((minor & !0xff) << 12);
+        let c_path = CString::new(path).expect("CString::new failed");
+        let c_path = c_path.into_raw();
+
+        let r = unsafe { ext4_mknod(c_path, types as i32, dev) };
+        unsafe {
+            drop(CString::from_raw(c_path));
+        }
+        if r != EOK as i32 {
+            error!("ext4_mknod error: rc = {}", r);
+            return Err(r);
+        }
+        Ok(EOK as usize)
+    }
+
     pub fn file_seek(&mut self, offset: i64, seek_type: u32) -> Result<usize, i32> {
         let mut offset = offset;
         let size = self.file_size() as i64;
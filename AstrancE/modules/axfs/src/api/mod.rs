@@ -5,6 +5,10 @@ mod file;
 
 pub use self::dir::{DirBuilder, DirEntry, ReadDir};
 pub use self::file::{File, FileType, Metadata, OpenOptions, Permissions};
+pub use crate::root::{
+    MountFlags, MountInfo, ResolveError, ResolveFlags, Resolved, ResolvedPath,
+};
+pub use axfs_vfs::RenameFlags;
 
 use alloc::{string::String, vec::Vec};
 use axfs_vfs::VfsNodeRef;
@@ -40,14 +44,6 @@ pub fn read(path: &str) -> io::Result<Vec<u8>> {
     Ok(bytes)
 }
 
-pub fn read_at(dir: &VfsNodeRef, path: &str) -> io::Result<Vec<u8>> {
-    let mut file = File::open_at(dir, path)?;
-    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
-    let mut bytes = Vec::with_capacity(size as usize);
-    file.read_to_end(&mut bytes)?;
-    Ok(bytes)
-}
-
 /// Read the entire contents of a file into a string.
 pub fn read_to_string(path: &str) -> io::Result<String> {
     let mut file = File::open(path)?;
@@ -86,18 +82,151 @@ pub fn remove_dir(path: &str) -> io::Result<()> {
 
 /// Removes a file from the filesystem.
 pub fn remove_file(path: &str) -> io::Result<()> {
-    crate::root::remove_file(None, path)
+    crate::root::remove_file(path)
 }
 
 /// Rename a file or directory to a new name.
-/// Delete the original file if `old` already exists.
+/// Replace `new` if it already exists, which is not atomic on every
+/// filesystem.
 ///
 /// This only works then the new path is in the same mounted fs.
 pub fn rename(old: &str, new: &str) -> io::Result<()> {
-    crate::root::rename(old, new)
+    Ok(crate::root::rename(old, new, RenameFlags::empty())?)
+}
+
+/// Rename a file or directory like [`rename`], with the `RENAME_*` `flags`
+/// of `renameat2(2)`.
+///
+/// Fails with [`ResolveError::CrossDevice`] if the paths are on different
+/// mounts.
+pub fn rename2(old: &str, new: &str, flags: RenameFlags) -> Result<(), ResolveError> {
+    crate::root::rename(old, new, flags)
+}
+
+/// Creates a new hard link `dst` to the file `src`, or to the target of
+/// `src` if it is a symbolic link and `follow` is set.
+///
+/// Fails with [`ResolveError::CrossDevice`] if the paths are on different
+/// mounts.
+pub fn hard_link(src: &str, dst: &str, follow: bool) -> Result<(), ResolveError> {
+    crate::root::link(src, dst, follow)
+}
+
+/// Creates a symbolic link `link` pointing to `target`.
+pub fn symlink(target: &str, link: &str) -> io::Result<()> {
+    crate::root::symlink(target, link)
+}
+
+/// Creates a special file `path`: a FIFO, a socket, or a device node with
+/// the device number `rdev`.
+pub fn mknod(path: &str, ty: FileType, rdev: u64) -> io::Result<()> {
+    crate::root::mknod(path, ty, rdev)
+}
+
+/// Reads the target of the symbolic link `path`.
+pub fn read_link(path: &str) -> io::Result<String> {
+    crate::root::read_link(path)
+}
+
+/// Walks `path` from the directory `start` with the restrictions of
+/// `resolve` (see `openat2(2)`), returning the absolute path without symbolic
+/// links it leads to and the node there. The last component is only followed
+/// if `follow_last` is set, and may not exist.
+///
+/// Open the result with [`fops::File::open_resolved`] or
+/// [`fops::Directory::open_resolved`] to use the node checked by the walk.
+///
+/// [`fops::File::open_resolved`]: crate::fops::File::open_resolved
+/// [`fops::Directory::open_resolved`]: crate::fops::Directory::open_resolved
+pub fn resolve_path(
+    start: &str,
+    path: &str,
+    resolve: ResolveFlags,
+    follow_last: bool,
+) -> Result<ResolvedPath, ResolveError> {
+    let start = crate::root::absolute_path(start)?;
+    crate::root::resolve_path(&start, path, resolve, follow_last)
+}
+
+/// Changes the permission mode of the file or directory at `path`.
+pub fn set_permissions(path: &str, perm: Permissions, follow: bool) -> io::Result<()> {
+    lookup_node(path, follow)?.set_perm(perm)
+}
+
+/// Changes the owner and group of the file or directory at `path`; `None`
+/// keeps the current one.
+pub fn chown(path: &str, uid: Option<u32>, gid: Option<u32>, follow: bool) -> io::Result<()> {
+    lookup_node(path, follow)?.set_owner(uid, gid)
+}
+
+fn lookup_node(path: &str, follow: bool) -> io::Result<VfsNodeRef> {
+    if follow {
+        crate::root::lookup(None, path)
+    } else {
+        crate::root::lookup_link(None, path)
+    }
 }
 
 /// check whether absolute path exists.
 pub fn absolute_path_exists(path: &str) -> bool {
     crate::root::lookup(None, path).is_ok()
 }
+
+/// Mount a filesystem of type `fstype` from `source` at `target`, with the
+/// filesystem specific options `data`.
+pub fn mount(
+    source: &str,
+    target: &str,
+    fstype: &str,
+    flags: MountFlags,
+    data: &str,
+) -> io::Result<()> {
+    crate::root::mount(source, target, fstype, flags, data)
+}
+
+/// Unmount the filesystem at `target`, lazily detaching busy submounts if
+/// `detach` is set.
+pub fn umount(target: &str, detach: bool) -> io::Result<()> {
+    crate::root::umount(target, detach)
+}
+
+/// Get the mount flags of the filesystem containing `path`, following a
+/// symbolic link in the last component if `follow` is set. A path that does
+/// not exist yet gets the flags of the directory it would be created in.
+pub fn mount_flags(path: &str, follow: bool) -> MountFlags {
+    match resolve_path(".", path, ResolveFlags::empty(), follow) {
+        Ok(resolved) => resolved.mount.flags(),
+        Err(_) => MountFlags::empty(),
+    }
+}
+
+/// Filesystem types that can be passed to [`mount`].
+pub fn filesystems() -> Vec<&'static str> {
+    crate::root::filesystems()
+}
+
+/// List all mounts, parents before their children.
+pub fn mounts() -> Vec<MountInfo> {
+    crate::root::ROOT_DIR.mounts()
+}
+
+/// Render the mount table in the format of `/proc/mounts`.
+pub fn proc_mounts() -> String {
+    crate::root::proc_mounts()
+}
+
+/// Render the mount table in the format of `/proc/self/mountinfo`.
+pub fn proc_mountinfo() -> String {
+    crate::root::proc_mountinfo()
+}
+
+/// Write back all file data modified through shared memory mappings.
+pub fn sync() {
+    crate::page_cache::sync_all()
+}
+
+/// Free up to `nr` pages of cached file data that are not mapped anywhere,
+/// to make room when memory runs out. Returns the number of pages freed.
+pub fn shrink_page_cache(nr: usize) -> usize {
+    crate::page_cache::reclaim(nr)
+}
@@ -1,46 +1,100 @@
-// use core::any::Any;
-// use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
-// use axfs_vfs::{VfsError, VfsResult};
-// use alloc::sync::{Arc, Weak};
-// use spin::Mutex;
-// use axdriver::prelude::*;
-//
-// pub struct Blkdev {
-//     dev: AxBlockDevice,
-//     dev_t: (u32, u32),
-//     mount_n: usize,
-// }
-//
-// impl Blkdev {
-//     pub fn new(dev: AxBlockDevice, major: u32, minor: u32) -> Self {
-//         Self{
-//             dev: dev,
-//             dev_t: (major, minor),
-//             mount_n: 0,
-//         }
-//     }
-//     pub fn inc_mount_n(&mut self) {
-//         self.mount_n += 1;
-//     }
-//     pub fn get_dev(self) -> AxBlockDevice {
-//        self.dev.clone()
-//     }
-//     pub fn dev_t(&self) -> (u32, u32) {self.dev_t}
-//     pub fn mount_n(&self) ->usize {self.mount_n}
-//
-// }
-//
-// impl VfsNodeOps for Blkdev {
-//     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-//         //TODO:dev num
-//         Ok(VfsNodeAttr::new_file(4096, 1))
-//     }
-//
-//     fn remove(&self, _path: &str) -> VfsResult {
-//         todo!()
-//     }
-//
-//     fn as_any(&self) -> &dyn Any {
-//         todo!()
-//     }
-// }
+//! Block device files over the disks, such as `/dev/vda` and `/dev/vda1`.
+
+use axfs_vfs::{
+    VfsError, VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult,
+};
+use axsync::Mutex;
+
+use crate::dev::Disk;
+
+const BLOCK_SIZE: u64 = 512;
+
+/// A block device that reads and writes a whole disk or partition at any
+/// byte offset.
+pub(crate) struct BlockDev {
+    disk: Mutex<Disk>,
+}
+
+impl BlockDev {
+    pub(crate) fn new(disk: Disk) -> Self {
+        Self {
+            disk: Mutex::new(disk),
+        }
+    }
+}
+
+impl VfsNodeOps for BlockDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let size = self.disk.lock().size();
+        Ok(VfsNodeAttr::new(
+            0,
+            VfsNodePerm::from_bits_truncate(0o660),
+            VfsNodeType::BlockDevice,
+            size,
+            size / BLOCK_SIZE,
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ))
+    }
+
+    fn get_attr_x(&self) -> VfsResult<VfsNodeAttrX> {
+        let size = self.disk.lock().size();
+        Ok(VfsNodeAttrX::new(
+            0,BLOCK_SIZE as _,0,1,0,0,
+            VfsNodePerm::from_bits_truncate(0o660),
+            VfsNodeType::BlockDevice,
+            0,size,
+            size / BLOCK_SIZE,0,
+            0,0,0,0,
+            0,0,0, 0,
+            0,0,0,0,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut disk = self.disk.lock();
+        disk.set_position(offset);
+        let mut read_len = 0;
+        while read_len < buf.len() {
+            let len = disk
+                .read_one(&mut buf[read_len..])
+                .map_err(|_| VfsError::Io)?;
+            if len == 0 {
+                break;
+            }
+            read_len += len;
+        }
+        Ok(read_len)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let mut disk = self.disk.lock();
+        disk.set_position(offset);
+        let mut write_len = 0;
+        while write_len < buf.len() {
+            let len = disk
+                .write_one(&buf[write_len..])
+                .map_err(|_| VfsError::Io)?;
+            if len == 0 {
+                break;
+            }
+            write_len += len;
+        }
+        Ok(write_len)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
@@ -1,11 +1,20 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use axdriver::prelude::*;
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsResult};
+use axsync::Mutex;
 const BLOCK_SIZE: usize = 512;
 /// A disk device with a cursor.
+///
+/// The underlying block device is shared, so a disk can be cloned into
+/// several handles (e.g. one per mounted filesystem), and a partition is just
+/// a window `[start, start + num_blocks)` over the parent device.
 pub struct Disk {
     block_id: u64,
     offset: usize,
-    dev: AxBlockDevice, //Ramdisk
+    dev: Arc<Mutex<AxBlockDevice>>, //Ramdisk
+    start: u64,
+    num_blocks: u64,
     dev_t: (u8, u8),
 }
 
@@ -13,29 +22,81 @@ impl Disk {
     /// Create a new disk.
     pub fn new(dev: AxBlockDevice, major: u8, minor: u8) -> Self {
         assert_eq!(BLOCK_SIZE, dev.block_size());
+        let num_blocks = dev.num_blocks();
         Self {
             block_id: 0,
             offset: 0,
-            dev,
+            dev: Arc::new(Mutex::new(dev)),
+            start: 0,
+            num_blocks,
             dev_t: (major, minor),
         }
     }
 
+    /// Create a handle to the partition starting at block `start` with
+    /// `num_blocks` blocks, relative to this disk.
+    pub fn partition(&self, start: u64, num_blocks: u64, minor: u8) -> Self {
+        assert!(start + num_blocks <= self.num_blocks);
+        Self {
+            block_id: 0,
+            offset: 0,
+            dev: self.dev.clone(),
+            start: self.start + start,
+            num_blocks,
+            dev_t: (self.dev_t.0, minor),
+        }
+    }
+
+    /// Scan the MBR partition table of the disk.
+    ///
+    /// Returns `(start_block, num_blocks)` of each primary partition. A disk
+    /// that directly holds a filesystem (no partition table) returns nothing.
+    pub fn mbr_partitions(&self) -> Vec<(u64, u64)> {
+        let mut mbr = [0u8; BLOCK_SIZE];
+        let mut parts = Vec::new();
+        if self.read_block(0, &mut mbr).is_err() || mbr[510..512] != [0x55, 0xAA] {
+            return parts;
+        }
+        // A FAT volume boot record also ends with 0x55AA, and its boot code
+        // occupies the partition table area.
+        if &mbr[54..57] == b"FAT" || &mbr[82..85] == b"FAT" {
+            return parts;
+        }
+        for entry in mbr[446..510].chunks_exact(16) {
+            let boot = entry[0];
+            let ty = entry[4];
+            let start = u32::from_le_bytes(entry[8..12].try_into().unwrap()) as u64;
+            let size = u32::from_le_bytes(entry[12..16].try_into().unwrap()) as u64;
+            if (boot != 0 && boot != 0x80) || ty == 0 || ty == 0xee {
+                continue;
+            }
+            if start == 0 || size == 0 || start + size > self.num_blocks {
+                continue;
+            }
+            parts.push((start, size));
+        }
+        parts
+    }
+
     /// Get the size of the disk.
     pub fn size(&self) -> u64 {
-        self.dev.num_blocks() * BLOCK_SIZE as u64
+        self.num_blocks * BLOCK_SIZE as u64
+    }
+
+    fn read_block(&self, block_id: u64, buf: &mut [u8]) -> DevResult {
+        if block_id >= self.num_blocks {
+            return Err(DevError::InvalidParam);
+        }
+        self.dev.lock().read_block(self.start + block_id, buf)
+    }
+
+    fn write_block(&self, block_id: u64, buf: &[u8]) -> DevResult {
+        if block_id >= self.num_blocks {
+            return Err(DevError::InvalidParam);
+        }
+        self.dev.lock().write_block(self.start + block_id, buf)
     }
 
-    // ///Clone disk for filesystem
-    // pub fn get_dev(&self) -> AxBlockDevice {
-    //     let dev = self.dev.deep_clone().expect("Clone failed");
-    //
-    //     // 尝试从 trait object 恢复为具体类型
-    //     dev.as_any()
-    //         .downcast_ref::<AxBlockDevice>()
-    //         .expect("Not a RamDisk")
-    //         .clone()
-    // }
     ///Get dev index
     pub fn dev_t(&self) -> (u8, u8) {
         self.dev_t
@@ -54,10 +115,13 @@ impl Disk {
 
     /// Read within one block, returns the number of bytes read.
     pub fn read_one(&mut self, buf: &mut [u8]) -> DevResult<usize> {
+        if self.block_id >= self.num_blocks {
+            return Ok(0);
+        }
         let read_size = if self.offset == 0 && buf.len() >= BLOCK_SIZE {
             // whole block
             let mut data = [0u8; BLOCK_SIZE];
-            self.dev.read_block(self.block_id, &mut data)?;
+            self.read_block(self.block_id, &mut data)?;
             buf[0..BLOCK_SIZE].copy_from_slice(&data);
             // self.dev
             //     .read_block(self.block_id, &mut buf[0..BLOCK_SIZE])?;
@@ -69,7 +133,7 @@ impl Disk {
             let start = self.offset;
             let count = buf.len().min(BLOCK_SIZE - self.offset);
 
-            self.dev.read_block(self.block_id, &mut data)?;
+            self.read_block(self.block_id, &mut data)?;
             buf[..count].copy_from_slice(&data[start..start + count]);
 
             self.offset += count;
@@ -84,9 +148,12 @@ impl Disk {
 
     /// Write within one block, returns the number of bytes written.
     pub fn write_one(&mut self, buf: &[u8]) -> DevResult<usize> {
+        if self.block_id >= self.num_blocks {
+            return Ok(0);
+        }
         let write_size = if self.offset == 0 && buf.len() >= BLOCK_SIZE {
             // whole block
-            self.dev.write_block(self.block_id, &buf[0..BLOCK_SIZE])?;
+            self.write_block(self.block_id, &buf[0..BLOCK_SIZE])?;
             self.block_id += 1;
             BLOCK_SIZE
         } else {
@@ -95,9 +162,9 @@ impl Disk {
             let start = self.offset;
             let count = buf.len().min(BLOCK_SIZE - self.offset);
 
-            self.dev.read_block(self.block_id, &mut data)?;
+            self.read_block(self.block_id, &mut data)?;
             data[start..start + count].copy_from_slice(&buf[..count]);
-            self.dev.write_block(self.block_id, &data)?;
+            self.write_block(self.block_id, &data)?;
 
             self.offset += count;
             if self.offset >= BLOCK_SIZE {
@@ -114,9 +181,7 @@ impl Disk {
     pub fn read_offset(&mut self, offset: usize) -> [u8; BLOCK_SIZE] {
         let block_id = offset / BLOCK_SIZE;
         let mut block_data = [0u8; BLOCK_SIZE];
-        self.dev
-            .read_block(block_id as u64, &mut block_data)
-            .unwrap();
+        self.read_block(block_id as u64, &mut block_data).unwrap();
         block_data
     }
 
@@ -129,22 +194,24 @@ impl Disk {
         );
         assert!(offset % BLOCK_SIZE == 0);
         let block_id = offset / BLOCK_SIZE;
-        self.dev.write_block(block_id as u64, buf).unwrap();
+        self.write_block(block_id as u64, buf).unwrap();
         Ok(buf.len())
     }
 }
 
-//
-// impl Clone for Disk {
-//     fn clone(&self) -> Self {
-//         Disk {
-//             block_id: self.block_id,
-//             offset: self.offset,
-//             dev: self.get_dev(), // 确保 dev 实现了 Clone
-//             dev_t: self.dev_t,
-//         }
-//     }
-// }
+impl Clone for Disk {
+    /// Create another handle to the same device, with its cursor reset.
+    fn clone(&self) -> Self {
+        Disk {
+            block_id: 0,
+            offset: 0,
+            dev: self.dev.clone(),
+            start: self.start,
+            num_blocks: self.num_blocks,
+            dev_t: self.dev_t,
+        }
+    }
+}
 
 unsafe impl Send for Disk {}
 unsafe impl Sync for Disk {}
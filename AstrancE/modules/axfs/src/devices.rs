@@ -0,0 +1,146 @@
+//! Registry of character and block devices.
+//!
+//! Drivers register the node implementing a device under its device number
+//! and a name. Every registered device shows up in `/dev` under its name, and
+//! opening a character or block device special file, on whatever filesystem
+//! it was created by `mknod`, goes to the device registered under the number
+//! kept in its inode.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use axerrno::{AxResult, ax_err};
+use axfs_vfs::{
+    VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsNodePerm, VfsNodeRef, VfsNodeType, VfsResult,
+};
+use axsync::Mutex;
+
+pub use axfs_vfs::{major, makedev, minor};
+
+struct Device {
+    name: String,
+    node: VfsNodeRef,
+}
+
+static CHAR_DEVICES: Mutex<BTreeMap<u64, Device>> = Mutex::new(BTreeMap::new());
+static BLOCK_DEVICES: Mutex<BTreeMap<u64, Device>> = Mutex::new(BTreeMap::new());
+/// The root directory of devfs once it is populated, where devices registered
+/// later are added. Locked before the device tables.
+#[cfg(feature = "devfs")]
+static DEV_DIR: Mutex<Option<Arc<crate::fs::devfs::DirNode>>> = Mutex::new(None);
+
+fn table(ty: VfsNodeType) -> Option<&'static Mutex<BTreeMap<u64, Device>>> {
+    match ty {
+        VfsNodeType::CharDevice => Some(&CHAR_DEVICES),
+        VfsNodeType::BlockDevice => Some(&BLOCK_DEVICES),
+        _ => None,
+    }
+}
+
+fn register(ty: VfsNodeType, name: &str, major: u32, minor: u32, ops: VfsNodeRef) -> AxResult {
+    #[cfg(feature = "devfs")]
+    let dev_dir = DEV_DIR.lock();
+    let mut devices = table(ty).unwrap().lock();
+    let rdev = makedev(major, minor);
+    if devices.contains_key(&rdev) {
+        return ax_err!(AlreadyExists, "device number in use");
+    }
+    let node: VfsNodeRef = Arc::new(DeviceNode { rdev, ops });
+    devices.insert(
+        rdev,
+        Device {
+            name: name.into(),
+            node: node.clone(),
+        },
+    );
+    #[cfg(feature = "devfs")]
+    if let Some(dir) = dev_dir.as_ref() {
+        dir.add(name, node);
+    }
+    Ok(())
+}
+
+/// Registers the character device `ops` named `name` (as in `/dev/<name>`)
+/// under the number `major:minor`.
+pub fn register_chrdev(name: &str, major: u32, minor: u32, ops: VfsNodeRef) -> AxResult {
+    register(VfsNodeType::CharDevice, name, major, minor, ops)
+}
+
+/// Registers the block device `ops` named `name` (as in `/dev/<name>`)
+/// under the number `major:minor`.
+pub fn register_blkdev(name: &str, major: u32, minor: u32, ops: VfsNodeRef) -> AxResult {
+    register(VfsNodeType::BlockDevice, name, major, minor, ops)
+}
+
+/// Gets the device registered under the device number `rdev`, for a device
+/// special file of type `ty`.
+pub fn lookup_device(ty: VfsNodeType, rdev: u64) -> Option<VfsNodeRef> {
+    Some(table(ty)?.lock().get(&rdev)?.node.clone())
+}
+
+/// Adds all registered devices to the devfs root directory `dir`, and the
+/// devices registered from now on as well.
+#[cfg(feature = "devfs")]
+pub(crate) fn populate_devfs(dir: Arc<crate::fs::devfs::DirNode>) {
+    let mut dev_dir = DEV_DIR.lock();
+    for devices in [&CHAR_DEVICES, &BLOCK_DEVICES] {
+        for device in devices.lock().values() {
+            dir.add(&device.name, device.node.clone());
+        }
+    }
+    *dev_dir = Some(dir);
+}
+
+/// A registered device, which reports its device number as `st_rdev`.
+struct DeviceNode {
+    rdev: u64,
+    ops: VfsNodeRef,
+}
+
+impl VfsNodeOps for DeviceNode {
+    fn open(&self) -> VfsResult {
+        self.ops.open()
+    }
+
+    fn release(&self) -> VfsResult {
+        self.ops.release()
+    }
+
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let mut attr = self.ops.get_attr()?;
+        attr.set_rdev(self.rdev);
+        Ok(attr)
+    }
+
+    fn get_attr_x(&self) -> VfsResult<VfsNodeAttrX> {
+        let mut attr = self.ops.get_attr_x()?;
+        attr.set_rdev(major(self.rdev), minor(self.rdev));
+        Ok(attr)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        self.ops.read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.ops.write_at(offset, buf)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        self.ops.fsync()
+    }
+
+    fn truncate(&self, size: u64) -> VfsResult {
+        self.ops.truncate(size)
+    }
+
+    fn set_perm(&self, perm: VfsNodePerm) -> VfsResult {
+        self.ops.set_perm(perm)
+    }
+
+    fn set_owner(&self, uid: Option<u32>, gid: Option<u32>) -> VfsResult {
+        self.ops.set_owner(uid, gid)
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
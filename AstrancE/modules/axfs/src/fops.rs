@@ -5,16 +5,22 @@ pub use crate::dev::Disk;
 #[cfg(feature = "myfs")]
 pub use crate::fs::myfs::MyFileSystemIf;
 use alloc::collections::BTreeMap;
+use alloc::format;
 use alloc::string::{String, ToString};
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use axerrno::{AxError, AxResult, LinuxError, LinuxResult, ax_err, ax_err_type};
-use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodeRef};
+use axfs_vfs::{RenameFlags, VfsError, VfsNodeAttr, VfsNodeRef};
 use axio::SeekFrom;
 use cap_access::{Cap, WithCap};
+use core::any::Any;
 use core::ffi::{c_char, c_int, c_void};
 use core::fmt;
 use spin::Mutex;
 
+use crate::page_cache::{self, InodeKey};
+use crate::root::{MountFlags, MountRef, ResolveFlags, Resolved, ResolvedPath};
+
 /// Alias of [`axfs_vfs::VfsNodeType`].
 pub type FileType = axfs_vfs::VfsNodeType;
 /// Alias of [`axfs_vfs::VfsDirEntry`].
@@ -29,7 +35,17 @@ pub type FilePerm = axfs_vfs::VfsNodePerm;
 pub struct File {
     pub node: WithCap<VfsNodeRef>,
     is_append: bool,
+    /// Whether reads fail with [`WouldBlock`](AxError::WouldBlock) instead
+    /// of waiting for data.
+    nonblocking: bool,
     offset: u64,
+    /// Key of the file data in the page cache, if it is cached.
+    cache: Option<InodeKey>,
+    /// Whether the file is counted as a writer of its mount, see
+    /// [`MountRef::get_write`].
+    writer: bool,
+    /// The mount the file is on, kept busy while the file is open.
+    mount: Arc<MountRef>,
 }
 
 /// An opened directory object, with open permissions and a cursor for
@@ -37,6 +53,12 @@ pub struct File {
 pub struct Directory {
     node: WithCap<VfsNodeRef>,
     entry_idx: usize,
+    /// Absolute path without symbolic links, used to resolve relative paths
+    /// through the mount tree. Renames keep it up to date, see
+    /// [`track_dir_path`](crate::root::track_dir_path).
+    path: Arc<Mutex<String>>,
+    /// The mount the directory is on, kept busy while it is open.
+    mount: Arc<MountRef>,
 }
 
 /// Options and flags which can be used to configure how a file is opened.
@@ -106,6 +128,10 @@ impl OpenOptions {
     pub fn directory(&mut self, directory: bool) {
         self.directory = directory;
     }
+    /// Sets the permission mode of a newly created file.
+    pub fn mode(&mut self, mode: u32) {
+        self._mode = mode;
+    }
     /// check whether contains directory.
     pub fn has_directory(&self) -> bool {
         self.directory
@@ -156,32 +182,48 @@ impl File {
         self.node.access_or_err(cap, AxError::PermissionDenied)
     }
 
-    fn _open_at(dir: Option<&VfsNodeRef>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
+    fn _open(path: &str, opts: &OpenOptions) -> AxResult<Self> {
         debug!("open file: {} {:?}", path, opts);
         if !opts.is_valid() {
             return ax_err!(InvalidInput);
         }
 
-        let node_option = crate::root::lookup(dir, path);
-        let node = if opts.create || opts.create_new {
+        let node_option = crate::root::lookup_with_mount(path);
+        let mut created = false;
+        let (node, mount) = if opts.create || opts.create_new {
             match node_option {
-                Ok(node) => {
+                Ok(found) => {
                     // already exists
                     if opts.create_new {
                         return ax_err!(AlreadyExists);
                     }
-                    node
+                    found
                 }
                 // not exists, create new
-                Err(VfsError::NotFound) => crate::root::create_file(dir, path)?,
+                Err(VfsError::NotFound) => {
+                    created = true;
+                    crate::root::create_file(None, path)?;
+                    crate::root::lookup_with_mount(path)?
+                }
                 Err(e) => return Err(e),
             }
         } else {
             // just open the existing
             node_option?
         };
+        Self::open_node(node, mount, created, opts)
+    }
 
+    /// Opens the looked up `node`, `created` if it has just been created for
+    /// this open.
+    fn open_node(
+        node: VfsNodeRef,
+        mount: Arc<MountRef>,
+        created: bool,
+        opts: &OpenOptions,
+    ) -> AxResult<Self> {
         let attr = node.get_attr()?;
+        let cache = InodeKey::new(mount.dev(), &attr);
         // if attr.is_dir() {
         //     return ax_err!(IsADirectory);
         // }
@@ -190,40 +232,152 @@ impl File {
             return ax_err!(PermissionDenied);
         }
 
-        node.open()?;
-        if opts.truncate {
-            node.truncate(0)?;
+        if created {
+            // the mode does not restrict the access of the creating open;
+            // not every filesystem keeps modes, so failures are ignored
+            node.set_perm(FilePerm::from_bits_truncate(opts._mode as u16)).ok();
         }
-        Ok(Self {
-            node: WithCap::new(node, access_cap),
+
+        let special = matches!(
+            attr.file_type(),
+            FileType::Fifo | FileType::CharDevice | FileType::BlockDevice | FileType::Socket
+        );
+        // writing to FIFOs and devices does not modify the filesystem
+        let writer = access_cap.contains(Cap::WRITE) && !special;
+        if writer {
+            mount.get_write()?;
+        }
+        if let Err(err) = node.open() {
+            if writer {
+                mount.put_write();
+            }
+            return Err(err);
+        }
+        let file = Self {
+            node: WithCap::new(node.clone(), access_cap),
             is_append: opts.append,
+            nonblocking: false,
             offset: 0,
-        })
+            cache,
+            writer,
+            mount,
+        };
+        // like Linux, `O_TRUNC` is ignored for FIFOs and devices
+        if opts.truncate && !special {
+            node.truncate(0)?;
+            if let Some(key) = cache {
+                page_cache::truncate(key, 0);
+            }
+        }
+        Ok(file)
     }
 
     /// Opens a file at the path relative to the current directory. Returns a
     /// [`File`] object.
     pub fn open(path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_at(None, path, opts)
+        Self::_open(path, opts)
+    }
+
+    /// Opens the file reached by [`resolve_path`](crate::api::resolve_path)
+    /// without looking the path up again. A missing file is created in the
+    /// directory found by the walk if `opts` allow it.
+    pub fn open_resolved(resolved: &ResolvedPath, opts: &OpenOptions) -> AxResult<Self> {
+        debug!("open resolved file: {} {:?}", resolved.path, opts);
+        if !opts.is_valid() {
+            return ax_err!(InvalidInput);
+        }
+        let (node, created) = match &resolved.target {
+            Resolved::Node(_) if opts.create_new => return ax_err!(AlreadyExists),
+            Resolved::Node(node) => (node.clone(), false),
+            Resolved::Missing { .. } if !(opts.create || opts.create_new) => {
+                return ax_err!(NotFound);
+            }
+            Resolved::Missing { parent, name } => {
+                parent.create(name, FileType::File)?;
+                (parent.clone().lookup(name)?, true)
+            }
+        };
+        Self::open_node(node, resolved.mount.clone(), created, opts)
+    }
+
+    /// Identifies the file by the device number of its mount and its inode
+    /// number.
+    pub fn inode_id(&self) -> AxResult<((u32, u32), u64)> {
+        let ino = self.get_attr()?.st_ino();
+        Ok((self.mount.dev(), ino))
     }
 
-    pub fn open_at(dir: &VfsNodeRef, path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_at(Some(dir), path, opts)
+    /// Gets the per-mount flags of the mount the file is on.
+    pub fn mount_flags(&self) -> MountFlags {
+        self.mount.flags()
+    }
+
+    /// Makes this file, a device special file, refer to the device registered
+    /// under its device number, see [`crate::devices`].
+    ///
+    /// Nothing is done if the file is already the registered device, as the
+    /// nodes in `/dev` are. Returns [`NotFound`](AxError::NotFound) if no
+    /// device is registered under the number.
+    pub fn open_device(&mut self) -> AxResult {
+        let attr = self.get_attr()?;
+        let dev = crate::devices::lookup_device(attr.file_type(), attr.rdev())
+            .ok_or(AxError::NotFound)?;
+        let node = unsafe { self.node.access_unchecked() };
+        if Arc::ptr_eq(node, &dev) {
+            return Ok(());
+        }
+        dev.open()?;
+        node.release().ok();
+        let cap = self.node.cap();
+        self.node = WithCap::new(dev, cap);
+        self.cache = None;
+        Ok(())
+    }
+
+    /// Gets the memory page holding the file data at page `index`, to be
+    /// mapped shared. The page is either kept by the filesystem itself or
+    /// taken from the page cache.
+    pub fn shared_page(&self, index: u64) -> Option<Arc<dyn Any + Send + Sync>> {
+        let node = self.access_node(Cap::READ).ok()?;
+        if let Some(page) = node.shared_page(index) {
+            return Some(page);
+        }
+        let frame = page_cache::map_shared(self.cache?, node, index).ok()?;
+        Some(frame)
     }
 
     /// Truncates the file to the specified size.
     pub fn truncate(&self, size: u64) -> AxResult {
         self.access_node(Cap::WRITE)?.truncate(size)?;
+        if let Some(key) = self.cache {
+            page_cache::truncate(key, size);
+        }
         Ok(())
     }
 
+    fn read_node(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+        let node = self.access_node(Cap::READ)?;
+        match self.cache {
+            Some(key) => page_cache::read_at(key, node, offset, buf),
+            None if self.nonblocking => node.read_at_nonblocking(offset, buf),
+            None => node.read_at(offset, buf),
+        }
+    }
+
+    fn write_node(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
+        let node = self.access_node(Cap::WRITE)?;
+        match self.cache {
+            Some(key) => page_cache::write_at(key, node, offset, buf),
+            None => node.write_at(offset, buf),
+        }
+    }
+
     /// Reads the file at the current position. Returns the number of bytes
     /// read.
     ///
     /// After the read, the cursor will be advanced by the number of bytes read.
     pub fn read(&mut self, buf: &mut [u8]) -> AxResult<usize> {
-        let node = self.access_node(Cap::READ)?;
-        let read_len = node.read_at(self.offset, buf)?;
+        let read_len = self.read_node(self.offset, buf)?;
         self.offset += read_len as u64;
         Ok(read_len)
     }
@@ -232,9 +386,7 @@ impl File {
     ///
     /// It does not update the file cursor.
     pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
-        let node = self.access_node(Cap::READ)?;
-        let read_len = node.read_at(offset, buf)?;
-        Ok(read_len)
+        self.read_node(offset, buf)
     }
 
     /// Writes the file at the current position. Returns the number of bytes
@@ -248,8 +400,7 @@ impl File {
         } else {
             self.offset
         };
-        let node = self.access_node(Cap::WRITE)?;
-        let write_len = node.write_at(offset, buf)?;
+        let write_len = self.write_node(offset, buf)?;
         self.offset = offset + write_len as u64;
         Ok(write_len)
     }
@@ -259,14 +410,32 @@ impl File {
     ///
     /// It does not update the file cursor.
     pub fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
-        let node = self.access_node(Cap::WRITE)?;
-        let write_len = node.write_at(offset, buf)?;
-        Ok(write_len)
+        self.write_node(offset, buf)
+    }
+
+    /// Sets the append mode, in which every write goes to the end of the
+    /// file.
+    pub fn set_append(&mut self, append: bool) {
+        self.is_append = append;
+    }
+
+    /// Sets the non-blocking mode, in which reads of devices such as
+    /// `/dev/kmsg` fail with [`WouldBlock`](AxError::WouldBlock) instead of
+    /// waiting for data.
+    pub fn set_nonblocking(&mut self, nonblocking: bool) {
+        self.nonblocking = nonblocking;
     }
 
     /// Flushes the file, writes all buffered data to the underlying device.
+    ///
+    /// This includes the data modified through shared memory mappings.
     pub fn flush(&self) -> AxResult {
-        self.access_node(Cap::WRITE)?.fsync()?;
+        // like `fsync(2)`, a file opened read-only can be synced as well
+        let node = self.access_node(Cap::empty())?;
+        if let Some(key) = self.cache {
+            page_cache::sync(key);
+        }
+        node.fsync()?;
         Ok(())
     }
 
@@ -343,16 +512,30 @@ impl Directory {
         self.node.access_or_err(cap, AxError::PermissionDenied)
     }
 
-    fn _open_dir_at(dir: Option<&VfsNodeRef>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        debug!("open dir: {}", path);
+    fn check_options(opts: &OpenOptions) -> AxResult {
         if !opts.read {
             return ax_err!(InvalidInput);
         }
         if opts.create || opts.create_new || opts.write || opts.append || opts.truncate {
             return ax_err!(InvalidInput);
         }
+        Ok(())
+    }
 
-        let node = crate::root::lookup(dir, path)?;
+    fn _open_dir_at(path: &str, opts: &OpenOptions) -> AxResult<Self> {
+        debug!("open dir: {}", path);
+        let path = crate::root::absolute_path(path)?;
+        let resolved = crate::root::resolve_path("/", &path, ResolveFlags::empty(), true)?;
+        Self::open_resolved(&resolved, opts)
+    }
+
+    /// Opens the looked up directory `node` at the absolute `path`.
+    fn open_node(
+        node: VfsNodeRef,
+        mount: Arc<MountRef>,
+        path: &str,
+        opts: &OpenOptions,
+    ) -> AxResult<Self> {
         let attr = node.get_attr()?;
         if !attr.is_dir() {
             return ax_err!(NotADirectory);
@@ -370,53 +553,84 @@ impl Directory {
             // directories that don't have this permission.
             node: WithCap::new(node, cap),
             entry_idx: 0,
+            path: crate::root::track_dir_path(path),
+            mount,
         })
     }
 
-    fn access_at(&self, path: &str) -> AxResult<Option<&VfsNodeRef>> {
+    /// Resolve `path` relative to this directory into an absolute path.
+    fn access_at(&self, path: &str) -> AxResult<String> {
         if path.starts_with('/') {
-            Ok(None)
+            Ok(path.into())
         } else {
-            Ok(Some(self.access_node(Cap::EXECUTE)?))
+            self.access_node(Cap::EXECUTE)?;
+            Ok(format!("{}/{}", self.path.lock().trim_end_matches('/'), path))
         }
     }
 
+    /// Gets the current absolute path of the directory.
+    pub fn path(&self) -> String {
+        self.path.lock().clone()
+    }
+
+    /// Gets the per-mount flags of the mount the directory is on.
+    pub fn mount_flags(&self) -> MountFlags {
+        self.mount.flags()
+    }
+
     /// Opens a directory at the path relative to the current directory.
     /// Returns a [`Directory`] object.
     pub fn open_dir(path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_dir_at(None, path, opts)
+        Self::_open_dir_at(path, opts)
+    }
+
+    /// Opens the directory reached by
+    /// [`resolve_path`](crate::api::resolve_path) without looking the path up
+    /// again.
+    pub fn open_resolved(resolved: &ResolvedPath, opts: &OpenOptions) -> AxResult<Self> {
+        debug!("open resolved dir: {}", resolved.path);
+        Self::check_options(opts)?;
+        let Resolved::Node(node) = &resolved.target else {
+            return ax_err!(NotFound);
+        };
+        Self::open_node(
+            node.clone(),
+            resolved.mount.clone(),
+            &resolved.path,
+            opts,
+        )
     }
 
     /// Opens a directory at the path relative to this directory. Returns a
     /// [`Directory`] object.
     pub fn open_dir_at(&self, path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_dir_at(self.access_at(path)?, path, opts)
+        Self::_open_dir_at(&self.access_at(path)?, opts)
     }
 
     /// Opens a file at the path relative to this directory. Returns a [`File`]
     /// object.
     pub fn open_file_at(&self, path: &str, opts: &OpenOptions) -> AxResult<File> {
-        File::_open_at(self.access_at(path)?, path, opts)
+        File::_open(&self.access_at(path)?, opts)
     }
 
     /// Creates an empty file at the path relative to this directory.
     pub fn create_file(&self, path: &str) -> AxResult<VfsNodeRef> {
-        crate::root::create_file(self.access_at(path)?, path)
+        crate::root::create_file(None, &self.access_at(path)?)
     }
 
     /// Creates an empty directory at the path relative to this directory.
     pub fn create_dir(&self, path: &str) -> AxResult {
-        crate::root::create_dir(self.access_at(path)?, path)
+        crate::root::create_dir(None, &self.access_at(path)?)
     }
 
     /// Removes a file at the path relative to this directory.
     pub fn remove_file(&self, path: &str) -> AxResult {
-        crate::root::remove_file(self.access_at(path)?, path)
+        crate::root::remove_file(&self.access_at(path)?)
     }
 
     /// Removes a directory at the path relative to this directory.
     pub fn remove_dir(&self, path: &str) -> AxResult {
-        crate::root::remove_dir(self.access_at(path)?, path)
+        crate::root::remove_dir(None, &self.access_at(path)?)
     }
 
     pub fn get_entry_index(&self) -> usize {
@@ -441,11 +655,12 @@ impl Directory {
     }
 
     /// Rename a file or directory to a new name.
-    /// Delete the original file if `old` already exists.
+    /// Replace `new` if it already exists, which is not atomic on every
+    /// filesystem.
     ///
     /// This only works then the new path is in the same mounted fs.
     pub fn rename(&self, old: &str, new: &str) -> AxResult {
-        crate::root::rename(old, new)
+        Ok(crate::root::rename(old, new, RenameFlags::empty())?)
     }
 
     /// Gets the file attributes.
@@ -504,6 +719,9 @@ impl Directory {
 impl Drop for File {
     fn drop(&mut self) {
         unsafe { self.node.access_unchecked().release().ok() };
+        if self.writer {
+            self.mount.put_write();
+        }
     }
 }
 
@@ -1,7 +1,6 @@
-use alloc::sync::Arc;
-use core::cell::UnsafeCell;
+use alloc::sync::{Arc, Weak};
 
-use axfs_vfs::{VfsDirEntry, VfsError, VfsNodeAttrX, VfsNodePerm, VfsResult};
+use axfs_vfs::{RenameFlags, VfsDirEntry, VfsError, VfsNodeAttrX, VfsNodePerm, VfsResult};
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps};
 use axsync::Mutex;
 use fatfs::{Dir, File, LossyOemCpConverter, NullTimeProvider, Read, Seek, SeekFrom, Write};
@@ -10,13 +9,20 @@ use crate::dev::Disk;
 
 const BLOCK_SIZE: usize = 512;
 
+/// Keeps alive the filesystem a node borrows from.
+type FsRef = Arc<dyn VfsOps>;
+
 pub struct FatFileSystem {
     inner: fatfs::FileSystem<Disk, NullTimeProvider, LossyOemCpConverter>,
-    root_dir: UnsafeCell<Option<VfsNodeRef>>,
+    this: Weak<Self>,
 }
 
-pub struct FileWrapper<'a, IO: IoTrait>(Mutex<File<'a, IO, NullTimeProvider, LossyOemCpConverter>>);
-pub struct DirWrapper<'a, IO: IoTrait>(Dir<'a, IO, NullTimeProvider, LossyOemCpConverter>);
+// the file or directory is dropped before the filesystem it borrows from
+pub struct FileWrapper<'a, IO: IoTrait>(
+    Mutex<File<'a, IO, NullTimeProvider, LossyOemCpConverter>>,
+    FsRef,
+);
+pub struct DirWrapper<'a, IO: IoTrait>(Dir<'a, IO, NullTimeProvider, LossyOemCpConverter>, FsRef);
 
 pub trait IoTrait: Read + Write + Seek {}
 
@@ -29,42 +35,50 @@ unsafe impl<'a, IO: IoTrait> Sync for DirWrapper<'a, IO> {}
 
 impl FatFileSystem {
     #[cfg(feature = "use-ramdisk")]
-    pub fn new(mut disk: Disk) -> Self {
+    pub fn new(mut disk: Disk) -> Arc<Self> {
         let opts = fatfs::FormatVolumeOptions::new();
         fatfs::format_volume(&mut disk, opts).expect("failed to format volume");
         let inner = fatfs::FileSystem::new(disk, fatfs::FsOptions::new())
             .expect("failed to initialize FAT filesystem");
-        Self {
+        Arc::new_cyclic(|this| Self {
             inner,
-            root_dir: UnsafeCell::new(None),
-        }
+            this: this.clone(),
+        })
     }
 
     #[cfg(not(feature = "use-ramdisk"))]
-    pub fn new(disk: Disk) -> Self {
+    pub fn new(disk: Disk) -> Arc<Self> {
         let inner = fatfs::FileSystem::new(disk, fatfs::FsOptions::new())
             .expect("failed to initialize FAT filesystem");
-        Self {
+        Arc::new_cyclic(|this| Self {
             inner,
-            root_dir: UnsafeCell::new(None),
-        }
+            this: this.clone(),
+        })
     }
 
-    pub fn init(&'static self) {
-        // must be called before later operations
-        unsafe { *self.root_dir.get() = Some(Self::new_dir(self.inner.root_dir())) }
+    /// Open the FAT volume on `disk`, failing instead of panicking if it is
+    /// not a valid one.
+    pub fn try_new(disk: Disk) -> VfsResult<Arc<Self>> {
+        let inner =
+            fatfs::FileSystem::new(disk, fatfs::FsOptions::new()).map_err(as_vfs_err)?;
+        Ok(Arc::new_cyclic(|this| Self {
+            inner,
+            this: this.clone(),
+        }))
     }
 
     fn new_file<IO: IoTrait>(
         file: File<'_, IO, NullTimeProvider, LossyOemCpConverter>,
-    ) -> Arc<FileWrapper<IO>> {
-        Arc::new(FileWrapper(Mutex::new(file)))
+        fs: FsRef,
+    ) -> Arc<FileWrapper<'_, IO>> {
+        Arc::new(FileWrapper(Mutex::new(file), fs))
     }
 
     fn new_dir<IO: IoTrait>(
         dir: Dir<'_, IO, NullTimeProvider, LossyOemCpConverter>,
-    ) -> Arc<DirWrapper<IO>> {
-        Arc::new(DirWrapper(dir))
+        fs: FsRef,
+    ) -> Arc<DirWrapper<'_, IO>> {
+        Arc::new(DirWrapper(dir, fs))
     }
 }
 
@@ -171,7 +185,7 @@ impl<IO: IoTrait> VfsNodeOps for DirWrapper<'static, IO> {
     fn parent(&self) -> Option<VfsNodeRef> {
         self.0
             .open_dir("..")
-            .map_or(None, |dir| Some(FatFileSystem::new_dir(dir)))
+            .map_or(None, |dir| Some(FatFileSystem::new_dir(dir, self.1.clone())))
     }
 
     fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
@@ -186,9 +200,9 @@ impl<IO: IoTrait> VfsNodeOps for DirWrapper<'static, IO> {
 
         // TODO: use `fatfs::Dir::find_entry`, but it's not public.
         if let Ok(file) = self.0.open_file(path) {
-            Ok(FatFileSystem::new_file(file))
+            Ok(FatFileSystem::new_file(file, self.1.clone()))
         } else if let Ok(dir) = self.0.open_dir(path) {
-            Ok(FatFileSystem::new_dir(dir))
+            Ok(FatFileSystem::new_dir(dir, self.1.clone()))
         } else {
             Err(VfsError::NotFound)
         }
@@ -248,12 +262,42 @@ impl<IO: IoTrait> VfsNodeOps for DirWrapper<'static, IO> {
         Ok(dirents.len())
     }
 
-    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+    fn rename(&self, src_path: &str, dst_path: &str, flags: RenameFlags) -> VfsResult {
         // `src_path` and `dst_path` should in the same mounted fs
         debug!(
-            "rename at fatfs, src_path: {}, dst_path: {}",
-            src_path, dst_path
+            "rename at fatfs, src_path: {}, dst_path: {}, flags: {:?}",
+            src_path, dst_path, flags
         );
+        if !(flags - RenameFlags::NOREPLACE).is_empty() {
+            return Err(VfsError::Unsupported);
+        }
+
+        // fatfs refuses to overwrite an existing destination, remove it first
+        // (so replacing is not atomic on FAT)
+        let dst_is_dir = if self.0.open_file(dst_path).is_ok() {
+            Some(false)
+        } else {
+            self.0.open_dir(dst_path).ok().map(|_| true)
+        };
+        if dst_is_dir.is_some() && flags.contains(RenameFlags::NOREPLACE) {
+            return Err(VfsError::AlreadyExists);
+        }
+        // names are case-insensitive, fatfs handles renaming an entry to itself
+        let same = src_path.trim_matches('/').eq_ignore_ascii_case(dst_path.trim_matches('/'));
+        if let Some(dst_is_dir) = dst_is_dir.filter(|_| !same) {
+            let src_is_dir = if self.0.open_file(src_path).is_ok() {
+                false
+            } else {
+                self.0.open_dir(src_path).map_err(as_vfs_err)?;
+                true
+            };
+            match (src_is_dir, dst_is_dir) {
+                (true, false) => return Err(VfsError::NotADirectory),
+                (false, true) => return Err(VfsError::IsADirectory),
+                _ => {}
+            }
+            self.0.remove(dst_path).map_err(as_vfs_err)?;
+        }
 
         self.0
             .rename(src_path, &self.0, dst_path)
@@ -263,8 +307,10 @@ impl<IO: IoTrait> VfsNodeOps for DirWrapper<'static, IO> {
 
 impl VfsOps for FatFileSystem {
     fn root_dir(&self) -> VfsNodeRef {
-        let root_dir = unsafe { (*self.root_dir.get()).as_ref().unwrap() };
-        root_dir.clone()
+        let fs: FsRef = self.this.upgrade().unwrap();
+        // SAFETY: the nodes hold `fs`, so the filesystem outlives them
+        let inner: &'static fatfs::FileSystem<_, _, _> = unsafe { &*(&self.inner as *const _) };
+        Self::new_dir(inner.root_dir(), fs)
     }
 }
 
@@ -351,13 +397,13 @@ impl Clone for FileWrapper<'static, Disk> {
     fn clone(&self) -> Self {
         let file = self.0.lock();
         let cloned_file = file.clone();
-        Self(Mutex::new(cloned_file))
+        Self(Mutex::new(cloned_file), self.1.clone())
     }
 }
 
 pub struct FatFileSystemFromFile {
     inner: fatfs::FileSystem<FileWrapper<'static, Disk>, NullTimeProvider, LossyOemCpConverter>,
-    root_dir: UnsafeCell<Option<VfsNodeRef>>,
+    this: Weak<Self>,
 }
 
 unsafe impl Sync for FatFileSystemFromFile {}
@@ -365,25 +411,22 @@ unsafe impl Send for FatFileSystemFromFile {}
 
 #[allow(unused)]
 impl FatFileSystemFromFile {
-    pub fn new(file: FileWrapper<'static, Disk>) -> Self {
+    pub fn new(file: FileWrapper<'static, Disk>) -> Arc<Self> {
         let inner = fatfs::FileSystem::new(file, fatfs::FsOptions::new())
             .expect("failed to initialize FAT filesystem");
-        Self {
+        Arc::new_cyclic(|this| Self {
             inner,
-            root_dir: UnsafeCell::new(None),
-        }
-    }
-
-    pub fn init(&'static self) {
-        // must be called before later operations
-        unsafe { *self.root_dir.get() = Some(FatFileSystem::new_dir(self.inner.root_dir())) }
+            this: this.clone(),
+        })
     }
 }
 
 impl VfsOps for FatFileSystemFromFile {
     fn root_dir(&self) -> VfsNodeRef {
-        let root_dir = unsafe { (*self.root_dir.get()).as_ref().unwrap() };
-        root_dir.clone()
+        let fs: FsRef = self.this.upgrade().unwrap();
+        // SAFETY: the nodes hold `fs`, so the filesystem outlives them
+        let inner: &'static fatfs::FileSystem<_, _, _> = unsafe { &*(&self.inner as *const _) };
+        FatFileSystem::new_dir(inner.root_dir(), fs)
     }
 }
 
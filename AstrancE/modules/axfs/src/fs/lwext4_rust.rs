@@ -1,14 +1,15 @@
 use crate::alloc::string::String;
 use alloc::string::ToString;
+use alloc::collections::BTreeSet;
 use alloc::sync::Arc;
 use core::ffi::{c_char, c_void, c_long, c_ulong, c_int};
 use core::{mem, ptr};
-use axerrno::AxError;
-use axfs_vfs::{FileSystemInfo,VfsDirEntry, VfsError, VfsNodePerm, VfsResult};
-use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps};
+use axerrno::{AxError, AxResult};
+use axfs_vfs::{FileSystemInfo, RenameFlags, VfsDirEntry, VfsError, VfsNodePerm, VfsResult};
+use axfs_vfs::{major, makedev, minor, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps};
 use axfs_vfs::structs::{StatxMask, VfsNodeAttrX, STATX_ALL_MASK};
 use axsync::Mutex;
-use lwext4_rust::bindings::{ext4_file, ext4_get_sblock, ext4_getxattr, ext4_inode, ext4_removexattr, ext4_sblock, O_CREAT, O_RDONLY, O_RDWR, O_TRUNC, O_WRONLY, SEEK_CUR, SEEK_END, SEEK_SET};
+use lwext4_rust::bindings::{EACCES, EEXIST, EXT4_RENAME_EXCHANGE, EXT4_RENAME_NOREPLACE, EFBIG, EINVAL, EIO, EISDIR, EMLINK, ENOENT, ENOMEM, ENOSPC, ENOTDIR, ENOTEMPTY, ENOTSUP, EPERM, ERANGE, EROFS, ext4_file, ext4_get_sblock, ext4_getxattr, ext4_inode, ext4_removexattr, ext4_sblock, O_CREAT, O_RDONLY, O_RDWR, O_TRUNC, O_WRONLY, SEEK_CUR, SEEK_END, SEEK_SET};
 use lwext4_rust::{Ext4BlockWrapper, Ext4File, InodeTypes, KernelDevOp};
 
 use crate::dev::Disk;
@@ -19,8 +20,14 @@ pub struct Ext4FileSystem<T: KernelDevOp<DevType = T>> {
     inner: Ext4BlockWrapper<T>,
     root: VfsNodeRef,
     //mount_point: BTreeMap<(String, VfsNodeRef)>,
+    mount_point: String,
 }
 
+/// lwext4 挂载点名称。lwext4 在同名挂载点已挂载时直接返回成功，
+/// 而延迟卸载（`MNT_DETACH`）的文件系统在最后一个引用释放前仍占用其挂载点，
+/// 因此需要自行检查名称是否被占用
+static MOUNT_POINTS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
 unsafe impl<T: KernelDevOp<DevType = T>> Sync for Ext4FileSystem<T> {}
 unsafe impl<T: KernelDevOp<DevType = T>> Send for Ext4FileSystem<T> {}
 /*
@@ -50,6 +57,13 @@ impl<T: KernelDevOp<DevType = T>> Ext4FileSystem<T> {
      */
     //#[cfg(not(feature = "use-ramdisk"))]
     pub fn new(block_dev: T, name: &str, mount_point: &str) -> Self {
+        Self::try_new(block_dev, name, mount_point).expect("failed to initialize EXT4 filesystem")
+    }
+
+    /// Mount the ext4 filesystem on `block_dev` as lwext4 device `name` at
+    /// lwext4 mount point `mount_point`, failing if the disk is not a
+    /// mountable ext4 filesystem or lwext4 runs out of device or mount slots.
+    pub fn try_new(block_dev: T, name: &str, mount_point: &str) -> AxResult<Self> {
         /*
          *info!(
          *    "Got Disk size:{}, position:{}",
@@ -57,14 +71,21 @@ impl<T: KernelDevOp<DevType = T>> Ext4FileSystem<T> {
          *    disk.position()
          *);
          */
+        if !MOUNT_POINTS.lock().insert(mount_point.to_string()) {
+            return Err(AxError::ResourceBusy);
+        }
         let inner = Ext4BlockWrapper::<T>::new(block_dev, &name, mount_point)
-            .expect("failed to initialize EXT4 filesystem");
+            .map_err(ext4_error)
+            .inspect_err(|_| {
+                MOUNT_POINTS.lock().remove(mount_point);
+            })?;
         let root = Arc::new(FileWrapper::new(mount_point, InodeTypes::EXT4_DE_DIR));
-        Self {
+        Ok(Self {
             inner,
             root,
             //mount_point: BTreeMap::new(),
-        }
+            mount_point: mount_point.to_string(),
+        })
     }
 
     pub fn inner(&mut self) -> &mut Ext4BlockWrapper<T> {
@@ -74,6 +95,16 @@ impl<T: KernelDevOp<DevType = T>> Ext4FileSystem<T> {
     //pub fn mount(&mut self, fs: Self, mount_point: &str) { self.mount_point.insert(mount_point.into(), fs); }
 }
 
+impl<T: KernelDevOp<DevType = T>> Drop for Ext4FileSystem<T> {
+    fn drop(&mut self) {
+        // 先卸载再释放挂载点名称
+        if let Err(r) = self.inner.lwext4_umount() {
+            error!("Failed to umount ext4 at {}: rc = {:?}", self.mount_point, r);
+        }
+        MOUNT_POINTS.lock().remove(&self.mount_point);
+    }
+}
+
 /// The [`VfsOps`] trait provides operations on a filesystem.
 impl<T: KernelDevOp<DevType = T>> VfsOps for Ext4FileSystem<T> {
     // mount()
@@ -152,6 +183,48 @@ pub unsafe fn get_filesystem_info(sb: *const ext4_sblock, fs_info: *mut FileSyst
     0 // 成功
 }
 
+/// Convert an lwext4 error code to [`AxError`].
+fn ext4_error(r: i32) -> AxError {
+    match r as u32 {
+        // 设备名或挂载点已被占用
+        EEXIST => AxError::ResourceBusy,
+        // lwext4 的设备表或挂载点表已满
+        ENOMEM | ENOSPC => AxError::NoMemory,
+        EIO => AxError::Io,
+        // 超级块损坏、不支持的特性等
+        _ => AxError::InvalidInput,
+    }
+}
+
+/// Convert the errno returned by an lwext4 file operation to [`VfsError`].
+fn errno_error(r: i32) -> VfsError {
+    match r as u32 {
+        ENOENT => VfsError::NotFound,
+        EEXIST => VfsError::AlreadyExists,
+        ENOTDIR => VfsError::NotADirectory,
+        EISDIR => VfsError::IsADirectory,
+        ENOTEMPTY => VfsError::DirectoryNotEmpty,
+        EPERM | EACCES | EROFS => VfsError::PermissionDenied,
+        ENOMEM => VfsError::NoMemory,
+        ENOSPC | EFBIG | EMLINK => VfsError::StorageFull,
+        EINVAL | ERANGE => VfsError::InvalidInput,
+        ENOTSUP => VfsError::Unsupported,
+        _ => VfsError::Io,
+    }
+}
+
+/// Check whether `disk` holds an ext2/3/4 superblock, so that mounting a
+/// foreign disk fails cleanly rather than inside lwext4.
+pub fn probe(disk: &mut Disk) -> bool {
+    const SUPERBLOCK_OFFSET: usize = 1024;
+    const MAGIC_OFFSET: usize = 0x38;
+    if disk.size() < (SUPERBLOCK_OFFSET + BLOCK_SIZE) as u64 {
+        return false;
+    }
+    let block = disk.read_offset(SUPERBLOCK_OFFSET);
+    u16::from_le_bytes([block[MAGIC_OFFSET], block[MAGIC_OFFSET + 1]]) == 0xEF53
+}
+
 pub struct FileWrapper(Mutex<Ext4File>);
 
 unsafe impl Send for FileWrapper {}
@@ -220,7 +293,7 @@ impl VfsNodeOps for FileWrapper {
             let path = file.get_path();
             let path = path.to_str().unwrap();
             file.file_open(path, O_RDONLY)
-                .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+                .map_err(errno_error)?;
             let fsize = file.file_size();
             let _ = file.file_close();
             fsize
@@ -238,7 +311,7 @@ impl VfsNodeOps for FileWrapper {
             blocks,
         );
 
-        let attr:VfsNodeAttr = if vtype == VfsNodeType::Dir {
+        let mut attr:VfsNodeAttr = if vtype == VfsNodeType::Dir {
             VfsNodeAttr::new(
                 0,
                 perm,
@@ -273,6 +346,9 @@ impl VfsNodeOps for FileWrapper {
                 inode.ctime_ex(),
             )
         };
+        if matches!(vtype, VfsNodeType::CharDevice | VfsNodeType::BlockDevice) {
+            attr.set_rdev(makedev(inode.rdev_major(), inode.rdev_minor()));
+        }
         Ok(attr)
     }
     
@@ -301,7 +377,7 @@ impl VfsNodeOps for FileWrapper {
             let path = file.get_path();
             let path = path.to_str().unwrap();
             file.file_open(path, O_RDONLY)
-                .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+                .map_err(errno_error)?;
             let fsize = file.file_size();
             let _ = file.file_close();
             fsize
@@ -319,6 +395,12 @@ impl VfsNodeOps for FileWrapper {
             blocks,
         );
 
+        // the block pointers of other inodes are not a device number
+        let rdev = if matches!(vtype, VfsNodeType::CharDevice | VfsNodeType::BlockDevice) {
+            (inode.rdev_major(), inode.rdev_minor())
+        } else {
+            (0, 0)
+        };
         let attr:VfsNodeAttrX = if vtype == VfsNodeType::Dir {
             VfsNodeAttrX::new(
                 STATX_ALL_MASK.bits(),
@@ -361,7 +443,7 @@ impl VfsNodeOps for FileWrapper {
                 inode.btime_ex(),
                 inode.ctime_ex(),
                 inode.mtime_ex(),
-                0,0,
+                rdev.0, rdev.1,
                 0,0,
             )
         };
@@ -370,13 +452,13 @@ impl VfsNodeOps for FileWrapper {
     fn set_atime(&self, atime: u32, atime_n: u32) -> VfsResult<usize> {
         let file = self.0.lock();
         file.set_atime(atime, atime_n)
-            .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+            .map_err(errno_error)?;
         Ok(0)
     }
      fn set_mtime(&self, mtime: u32, mtime_n: u32) -> VfsResult<usize> {
          let file = self.0.lock();
          file.set_mtime(mtime, mtime_n)
-             .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+             .map_err(errno_error)?;
          Ok(0)
      }
     fn get_xattr(
@@ -389,7 +471,7 @@ impl VfsNodeOps for FileWrapper {
     ) -> VfsResult<usize> {
         let file = self.0.lock();
         file.get_xattr(name, name_len, buf, buf_size, data_size)
-            .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+            .map_err(errno_error)?;
         Ok(0)
     }
     fn set_xattr(
@@ -401,7 +483,7 @@ impl VfsNodeOps for FileWrapper {
     )->VfsResult<usize>{
         let file = self.0.lock();
         file.set_xattr(name,name_len,data,data_size)
-            .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+            .map_err(errno_error)?;
         Ok(0)
     }
     fn list_xattr(
@@ -412,7 +494,7 @@ impl VfsNodeOps for FileWrapper {
     )->VfsResult<usize>{
         let file = self.0.lock();
         let ret = file.list_xattr(list, size, ret_size)
-            .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+            .map_err(errno_error)?;
         Ok(ret)
     }
     fn remove_xattr(
@@ -422,7 +504,7 @@ impl VfsNodeOps for FileWrapper {
     )->VfsResult<usize>{
         let file = self.0.lock();
         file.remove_xattr(name, name_len)
-            .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+            .map_err(errno_error)?;
         Ok(0)
     }
 
@@ -451,17 +533,40 @@ impl VfsNodeOps for FileWrapper {
             if types == InodeTypes::EXT4_DE_DIR {
                 file.dir_mk(fpath)
                     .map(|_v| ())
-                    .map_err(|e| e.try_into().unwrap())
+                    .map_err(errno_error)
             } else {
                 file.file_open(fpath, O_WRONLY | O_CREAT | O_TRUNC)
                     .expect("create file failed");
                 file.file_close()
                     .map(|_v| ())
-                    .map_err(|e| e.try_into().unwrap())
+                    .map_err(errno_error)
             }
         }
     }
 
+    fn mknod(&self, path: &str, ty: VfsNodeType, rdev: u64) -> VfsResult {
+        info!("mknod {:?} on Ext4fs: {}", ty, path);
+        let fpath = self.path_deal_with(path);
+        let fpath = fpath.as_str();
+
+        let types = match ty {
+            VfsNodeType::Fifo => InodeTypes::EXT4_DE_FIFO,
+            VfsNodeType::CharDevice => InodeTypes::EXT4_DE_CHRDEV,
+            VfsNodeType::BlockDevice => InodeTypes::EXT4_DE_BLKDEV,
+            VfsNodeType::Socket => InodeTypes::EXT4_DE_SOCK,
+            _ => return Err(VfsError::InvalidInput),
+        };
+
+        let mut file = self.0.lock();
+        // ext4_mknod 会直接打开已存在的文件
+        if fpath.is_empty() || file.check_inode_exist(fpath, InodeTypes::EXT4_DE_UNKNOWN) {
+            return Err(VfsError::AlreadyExists);
+        }
+        file.file_mknod(fpath, types, major(rdev), minor(rdev))
+            .map(|_v| ())
+            .map_err(errno_error)
+    }
+
     fn remove(&self, path: &str) -> VfsResult {
         info!("remove ext4fs: {}", path);
         let fpath = self.path_deal_with(path);
@@ -474,11 +579,11 @@ impl VfsNodeOps for FileWrapper {
             // Recursive directory remove
             file.dir_rm(fpath)
                 .map(|_v| ())
-                .map_err(|e| e.try_into().unwrap())
+                .map_err(errno_error)
         } else {
             file.file_remove(fpath)
                 .map(|_v| ())
-                .map_err(|e| e.try_into().unwrap())
+                .map_err(errno_error)
         }
     }
 
@@ -512,15 +617,18 @@ impl VfsNodeOps for FileWrapper {
 
             match itypes {
                 Some(t) => {
-                    let ty = if *t == InodeTypes::EXT4_DE_DIR {
-                        VfsNodeType::Dir
-                    } else if *t == InodeTypes::EXT4_DE_REG_FILE {
-                        VfsNodeType::File
-                    } else if *t == InodeTypes::EXT4_DE_SYMLINK {
-                        VfsNodeType::SymLink
-                    } else {
-                        error!("unknown file type: {:?}", itypes);
-                        unreachable!()
+                    let ty = match t {
+                        InodeTypes::EXT4_DE_DIR => VfsNodeType::Dir,
+                        InodeTypes::EXT4_DE_REG_FILE => VfsNodeType::File,
+                        InodeTypes::EXT4_DE_SYMLINK => VfsNodeType::SymLink,
+                        InodeTypes::EXT4_DE_FIFO => VfsNodeType::Fifo,
+                        InodeTypes::EXT4_DE_CHRDEV => VfsNodeType::CharDevice,
+                        InodeTypes::EXT4_DE_BLKDEV => VfsNodeType::BlockDevice,
+                        InodeTypes::EXT4_DE_SOCK => VfsNodeType::Socket,
+                        _ => {
+                            error!("unknown file type: {:?}", itypes);
+                            unreachable!()
+                        }
                     };
 
                     *out_entry =
@@ -552,6 +660,18 @@ impl VfsNodeOps for FileWrapper {
         } else if file.check_inode_exist(fpath, InodeTypes::EXT4_DE_REG_FILE) {
             trace!("lookup new FILE FileWrapper");
             Ok(Arc::new(Self::new(fpath, InodeTypes::EXT4_DE_REG_FILE)))
+        } else if file.check_inode_exist(fpath, InodeTypes::EXT4_DE_UNKNOWN) {
+            // 特殊文件（FIFO、套接字、设备节点），符号链接仍不支持
+            [
+                InodeTypes::EXT4_DE_FIFO,
+                InodeTypes::EXT4_DE_CHRDEV,
+                InodeTypes::EXT4_DE_BLKDEV,
+                InodeTypes::EXT4_DE_SOCK,
+            ]
+            .into_iter()
+            .find(|ty| file.check_inode_exist(fpath, ty.clone()))
+            .map(|ty| Arc::new(Self::new(fpath, ty)) as VfsNodeRef)
+            .ok_or(VfsError::NotFound)
         } else {
             Err(VfsError::NotFound)
         }
@@ -562,15 +682,15 @@ impl VfsNodeOps for FileWrapper {
         let path = file.get_path();
         let path = path.to_str().unwrap();
         file.file_open(path, O_RDONLY)
-            .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+            .map_err(errno_error)?;
 
         file.file_seek(offset as i64, SEEK_SET)
-            .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+            .map_err(errno_error)?;
 
         let r = file.file_read(buf);
 
         let _ = file.file_close();
-        r.map_err(|e| e.try_into().unwrap())
+        r.map_err(errno_error)
     }
 
     fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
@@ -578,13 +698,13 @@ impl VfsNodeOps for FileWrapper {
         let path = file.get_path();
         let path = path.to_str().unwrap();
         file.file_open(path, O_RDWR)
-            .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+            .map_err(errno_error)?;
 
         file.file_seek(offset as i64, SEEK_SET)
-            .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+            .map_err(errno_error)?;
         let r = file.file_write(buf);
         let _ = file.file_close();
-        r.map_err(|e| e.try_into().unwrap())
+        r.map_err(errno_error)
     }
 
     fn truncate(&self, size: u64) -> VfsResult {
@@ -592,19 +712,37 @@ impl VfsNodeOps for FileWrapper {
         let path = file.get_path();
         let path = path.to_str().unwrap();
         file.file_open(path, O_RDWR | O_CREAT | O_TRUNC)
-            .map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+            .map_err(errno_error)?;
 
         let t = file.file_truncate(size);
 
         let _ = file.file_close();
-        t.map(|_v| ()).map_err(|e| e.try_into().unwrap())
+        t.map(|_v| ()).map_err(errno_error)
     }
 
-    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+    fn rename(&self, src_path: &str, dst_path: &str, flags: RenameFlags) -> VfsResult {
+        let src_path = self.path_deal_with(src_path);
+        let dst_path = self.path_deal_with(dst_path);
+        // ext4_frename2 直接把目标目录项改指向源 inode，替换和交换都在一次事务里完成
+        let mut ext4_flags = 0;
+        if flags.contains(RenameFlags::NOREPLACE) {
+            ext4_flags |= EXT4_RENAME_NOREPLACE;
+        }
+        if flags.contains(RenameFlags::EXCHANGE) {
+            ext4_flags |= EXT4_RENAME_EXCHANGE;
+        }
         let mut file = self.0.lock();
-        file.file_rename(src_path, dst_path)
-            .map(|_v| ())
-            .map_err(|e| e.try_into().unwrap())
+        file.file_rename2(&src_path, &dst_path, ext4_flags)
+            .map_err(errno_error)?;
+
+        // RENAME_WHITEOUT 时在源位置留下白化项（0:0 字符设备），创建失败则把源移回去
+        if flags.contains(RenameFlags::WHITEOUT) {
+            if let Err(e) = file.file_mknod(&src_path, InodeTypes::EXT4_DE_CHRDEV, 0, 0) {
+                let _ = file.file_rename2(&dst_path, &src_path, EXT4_RENAME_NOREPLACE);
+                return Err(errno_error(e));
+            }
+        }
+        Ok(())
     }
 
     fn as_any(&self) -> &dyn core::any::Any {
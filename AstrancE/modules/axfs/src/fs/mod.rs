@@ -16,6 +16,8 @@ pub mod fatfs;
 pub mod lwext4_rust;
 #[cfg(feature = "myfs")]
 pub mod myfs;
+#[cfg(feature = "tmpfs")]
+pub mod tmpfs;
 
 #[cfg(feature = "devfs")]
 pub use axfs_devfs as devfs;
@@ -0,0 +1,813 @@
+//! Temporary filesystem keeping file data in physical page frames.
+//!
+//! Unlike `ramfs`, file contents are stored page by page in frames from the
+//! global page allocator, so shared memory mappings of a file map the very
+//! same frames instead of copies. Memory and inode usage can be limited with
+//! the `size=` and `nr_inodes=` mount options.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use core::any::Any;
+use core::ffi::c_char;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::time::Duration;
+
+use axerrno::ax_err;
+use axfs_vfs::structs::STATX_BASIC_STATS;
+use axfs_vfs::{
+    FileSystemInfo, RenameFlags, VfsDirEntry, VfsError, VfsNodeAttr, VfsNodeAttrX, VfsNodeOps,
+    VfsNodePerm, VfsNodeRef, VfsNodeType, VfsOps, VfsResult, major, minor,
+};
+use axhal::mem::phys_to_virt;
+use axhal::time::wall_time;
+use axmm::{FrameTrackerRef, alloc_frame};
+use spin::{Mutex, RwLock};
+
+const PAGE_SIZE: usize = 4096;
+/// `f_type` reported by `statfs`.
+const TMPFS_MAGIC: u64 = 0x0102_1994;
+const NAME_MAX: usize = 255;
+
+/// Limits and usage counters shared by all nodes of one filesystem.
+struct SuperBlock {
+    max_pages: usize,
+    max_inodes: usize,
+    pages: AtomicUsize,
+    inodes: AtomicUsize,
+    next_ino: AtomicU64,
+    /// Parent of the mount point, the `..` of the root directory.
+    mount_parent: RwLock<Option<Weak<dyn VfsNodeOps>>>,
+}
+
+impl SuperBlock {
+    fn charge(counter: &AtomicUsize, max: usize) -> bool {
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .is_ok()
+    }
+
+    fn alloc_page(&self) -> VfsResult<FrameTrackerRef> {
+        if !Self::charge(&self.pages, self.max_pages) {
+            return ax_err!(StorageFull);
+        }
+        alloc_frame(true).ok_or_else(|| {
+            self.pages.fetch_sub(1, Ordering::SeqCst);
+            VfsError::NoMemory
+        })
+    }
+
+    fn free_pages(&self, count: usize) {
+        self.pages.fetch_sub(count, Ordering::SeqCst);
+    }
+
+    fn alloc_inode(&self) -> VfsResult<u64> {
+        if !Self::charge(&self.inodes, self.max_inodes) {
+            return ax_err!(StorageFull);
+        }
+        Ok(self.next_ino.fetch_add(1, Ordering::SeqCst))
+    }
+}
+
+struct Meta {
+    perm: VfsNodePerm,
+    uid: u32,
+    gid: u32,
+    nlink: u32,
+    atime: Duration,
+    mtime: Duration,
+    ctime: Duration,
+}
+
+struct FileData {
+    size: u64,
+    /// Pages by index, holes are not allocated.
+    pages: BTreeMap<u64, FrameTrackerRef>,
+}
+
+enum Content {
+    File(RwLock<FileData>),
+    Dir(RwLock<BTreeMap<String, Arc<TmpNode>>>),
+    SymLink(String),
+    /// FIFO, socket or device node, with the device number of a device node.
+    Special(VfsNodeType, u64),
+}
+
+/// A file, directory, symbolic link or special file in a [`TmpFileSystem`].
+pub struct TmpNode {
+    this: Weak<TmpNode>,
+    ino: u64,
+    sb: Arc<SuperBlock>,
+    meta: Mutex<Meta>,
+    /// Parent directory, empty for files and for the root directory.
+    parent: RwLock<Weak<TmpNode>>,
+    content: Content,
+}
+
+fn page_slice(frame: &FrameTrackerRef) -> &mut [u8] {
+    unsafe { core::slice::from_raw_parts_mut(phys_to_virt(frame.pa).as_mut_ptr(), PAGE_SIZE) }
+}
+
+fn check_name(name: &str) -> VfsResult {
+    if name.is_empty() || name == "." || name == ".." {
+        ax_err!(InvalidInput)
+    } else if name.len() > NAME_MAX {
+        ax_err!(InvalidInput, "file name too long")
+    } else {
+        Ok(())
+    }
+}
+
+impl TmpNode {
+    fn new(sb: &Arc<SuperBlock>, content: Content, perm: VfsNodePerm) -> VfsResult<Arc<Self>> {
+        let ino = sb.alloc_inode()?;
+        let now = wall_time();
+        let nlink = if matches!(content, Content::Dir(_)) { 2 } else { 1 };
+        Ok(Arc::new_cyclic(|this| Self {
+            this: this.clone(),
+            ino,
+            sb: sb.clone(),
+            meta: Mutex::new(Meta {
+                perm,
+                uid: 0,
+                gid: 0,
+                nlink,
+                atime: now,
+                mtime: now,
+                ctime: now,
+            }),
+            parent: RwLock::new(Weak::new()),
+            content,
+        }))
+    }
+
+    fn new_file(sb: &Arc<SuperBlock>) -> VfsResult<Arc<Self>> {
+        let data = FileData {
+            size: 0,
+            pages: BTreeMap::new(),
+        };
+        Self::new(sb, Content::File(RwLock::new(data)), VfsNodePerm::default_file())
+    }
+
+    fn new_dir(sb: &Arc<SuperBlock>, perm: VfsNodePerm) -> VfsResult<Arc<Self>> {
+        Self::new(sb, Content::Dir(RwLock::new(BTreeMap::new())), perm)
+    }
+
+    fn arc(&self) -> Arc<Self> {
+        self.this.upgrade().unwrap()
+    }
+
+    fn node_type(&self) -> VfsNodeType {
+        match self.content {
+            Content::File(_) => VfsNodeType::File,
+            Content::Dir(_) => VfsNodeType::Dir,
+            Content::SymLink(_) => VfsNodeType::SymLink,
+            Content::Special(ty, _) => ty,
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        matches!(self.content, Content::Dir(_))
+    }
+
+    fn children(&self) -> VfsResult<&RwLock<BTreeMap<String, Arc<TmpNode>>>> {
+        match &self.content {
+            Content::Dir(children) => Ok(children),
+            _ => ax_err!(NotADirectory),
+        }
+    }
+
+    fn data(&self) -> VfsResult<&RwLock<FileData>> {
+        match &self.content {
+            Content::File(data) => Ok(data),
+            Content::Dir(_) => ax_err!(IsADirectory),
+            Content::SymLink(_) | Content::Special(..) => ax_err!(InvalidInput),
+        }
+    }
+
+    /// Size in bytes and number of allocated pages.
+    fn usage(&self) -> (u64, u64) {
+        match &self.content {
+            Content::File(data) => {
+                let data = data.read();
+                (data.size, data.pages.len() as u64)
+            }
+            Content::Dir(_) => (PAGE_SIZE as u64, 0),
+            Content::SymLink(target) => (target.len() as u64, 0),
+            Content::Special(..) => (0, 0),
+        }
+    }
+
+    fn touch(&self, atime: bool, mtime: bool) {
+        let now = wall_time();
+        let mut meta = self.meta.lock();
+        if atime {
+            meta.atime = now;
+        }
+        if mtime {
+            meta.mtime = now;
+        }
+        meta.ctime = now;
+    }
+
+    fn adjust_nlink(&self, inc: bool) {
+        let mut meta = self.meta.lock();
+        meta.nlink = if inc { meta.nlink + 1 } else { meta.nlink.saturating_sub(1) };
+        meta.ctime = wall_time();
+    }
+
+    /// Device number of a device node, 0 for other nodes.
+    fn rdev(&self) -> u64 {
+        match self.content {
+            Content::Special(_, rdev) => rdev,
+            _ => 0,
+        }
+    }
+
+    /// Walk down `path` inside this filesystem.
+    fn walk(self: Arc<Self>, path: &str) -> VfsResult<Arc<TmpNode>> {
+        let mut node = self;
+        for comp in path.split('/') {
+            node = match comp {
+                "" | "." => {
+                    node.children()?;
+                    node
+                }
+                ".." => {
+                    node.children()?;
+                    let parent = node.parent.read().upgrade();
+                    parent.ok_or(VfsError::NotFound)?
+                }
+                name => {
+                    let child = node.children()?.read().get(name).cloned();
+                    child.ok_or(VfsError::NotFound)?
+                }
+            };
+        }
+        Ok(node)
+    }
+
+    /// Walk to the directory containing the last component of `path`.
+    fn walk_parent<'a>(self: Arc<Self>, path: &'a str) -> VfsResult<(Arc<TmpNode>, &'a str)> {
+        let path = path.trim_matches('/');
+        let (dir, name) = match path.rsplit_once('/') {
+            Some((dir, name)) => (self.walk(dir)?, name),
+            None => (self, path),
+        };
+        dir.children()?;
+        check_name(name)?;
+        Ok((dir, name))
+    }
+
+    /// Insert a new entry `name` for `node` in this directory.
+    fn insert(&self, name: &str, node: Arc<TmpNode>) -> VfsResult {
+        let mut children = self.children()?.write();
+        if children.contains_key(name) {
+            return ax_err!(AlreadyExists);
+        }
+        if node.is_dir() {
+            *node.parent.write() = self.this.clone();
+            self.adjust_nlink(true);
+        }
+        children.insert(name.into(), node);
+        drop(children);
+        self.touch(false, true);
+        Ok(())
+    }
+
+    /// The entry of `node` has been removed from `dir`.
+    fn unlinked(&self, dir: &TmpNode) {
+        if self.is_dir() {
+            self.meta.lock().nlink = 0;
+            dir.adjust_nlink(false);
+        } else {
+            self.adjust_nlink(false);
+        }
+    }
+
+    fn is_ancestor_of(&self, node: &Arc<TmpNode>) -> bool {
+        let mut cur = Some(node.clone());
+        while let Some(n) = cur {
+            if core::ptr::eq(Arc::as_ptr(&n), self) {
+                return true;
+            }
+            cur = n.parent.read().upgrade();
+        }
+        false
+    }
+}
+
+impl Drop for TmpNode {
+    fn drop(&mut self) {
+        self.sb.inodes.fetch_sub(1, Ordering::SeqCst);
+        if let Content::File(data) = &mut self.content {
+            self.sb.free_pages(data.get_mut().pages.len());
+        }
+    }
+}
+
+impl VfsNodeOps for TmpNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let (size, pages) = self.usage();
+        let meta = self.meta.lock();
+        let mut attr = VfsNodeAttr::new(
+            0,
+            meta.perm,
+            self.node_type(),
+            size,
+            pages * (PAGE_SIZE as u64 / 512),
+            self.ino,
+            meta.nlink,
+            meta.uid,
+            meta.gid,
+            0,
+            meta.atime.as_secs() as u32,
+            meta.ctime.as_secs() as u32,
+            meta.mtime.as_secs() as u32,
+            meta.atime.subsec_nanos(),
+            meta.mtime.subsec_nanos(),
+            meta.ctime.subsec_nanos(),
+        );
+        attr.set_rdev(self.rdev());
+        Ok(attr)
+    }
+
+    fn get_attr_x(&self) -> VfsResult<VfsNodeAttrX> {
+        let (size, pages) = self.usage();
+        let meta = self.meta.lock();
+        Ok(VfsNodeAttrX::new(
+            STATX_BASIC_STATS,
+            PAGE_SIZE as u32,
+            0,
+            meta.nlink,
+            meta.uid,
+            meta.gid,
+            meta.perm,
+            self.node_type(),
+            self.ino,
+            size,
+            pages * (PAGE_SIZE as u64 / 512),
+            0,
+            meta.atime.as_secs() as u32,
+            0,
+            meta.ctime.as_secs() as u32,
+            meta.mtime.as_secs() as u32,
+            meta.atime.subsec_nanos(),
+            0,
+            meta.ctime.subsec_nanos(),
+            meta.mtime.subsec_nanos(),
+            major(self.rdev()),
+            minor(self.rdev()),
+            0,
+            0,
+        ))
+    }
+
+    fn set_atime(&self, atime: u32, atime_n: u32) -> VfsResult<usize> {
+        let mut meta = self.meta.lock();
+        meta.atime = Duration::new(atime as u64, atime_n);
+        meta.ctime = wall_time();
+        Ok(0)
+    }
+
+    fn set_mtime(&self, mtime: u32, mtime_n: u32) -> VfsResult<usize> {
+        let mut meta = self.meta.lock();
+        meta.mtime = Duration::new(mtime as u64, mtime_n);
+        meta.ctime = wall_time();
+        Ok(0)
+    }
+
+    fn set_perm(&self, perm: VfsNodePerm) -> VfsResult {
+        let mut meta = self.meta.lock();
+        meta.perm = perm;
+        meta.ctime = wall_time();
+        Ok(())
+    }
+
+    fn set_owner(&self, uid: Option<u32>, gid: Option<u32>) -> VfsResult {
+        let mut meta = self.meta.lock();
+        meta.uid = uid.unwrap_or(meta.uid);
+        meta.gid = gid.unwrap_or(meta.gid);
+        meta.ctime = wall_time();
+        Ok(())
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let data = self.data()?.read();
+        if offset >= data.size {
+            return Ok(0);
+        }
+        let len = buf.len().min((data.size - offset) as usize);
+        let mut done = 0;
+        while done < len {
+            let pos = offset + done as u64;
+            let in_page = pos as usize % PAGE_SIZE;
+            let n = (PAGE_SIZE - in_page).min(len - done);
+            let dst = &mut buf[done..done + n];
+            match data.pages.get(&(pos / PAGE_SIZE as u64)) {
+                Some(frame) => dst.copy_from_slice(&page_slice(frame)[in_page..in_page + n]),
+                None => dst.fill(0),
+            }
+            done += n;
+        }
+        drop(data);
+        self.meta.lock().atime = wall_time();
+        Ok(len)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let mut data = self.data()?.write();
+        let mut done = 0;
+        while done < buf.len() {
+            let pos = offset + done as u64;
+            let in_page = pos as usize % PAGE_SIZE;
+            let n = (PAGE_SIZE - in_page).min(buf.len() - done);
+            let index = pos / PAGE_SIZE as u64;
+            let frame = match data.pages.get(&index) {
+                Some(frame) => frame.clone(),
+                None => match self.sb.alloc_page() {
+                    Ok(frame) => data.pages.entry(index).or_insert(frame).clone(),
+                    Err(_) if done > 0 => break,
+                    Err(e) => return Err(e),
+                },
+            };
+            page_slice(&frame)[in_page..in_page + n].copy_from_slice(&buf[done..done + n]);
+            done += n;
+        }
+        data.size = data.size.max(offset + done as u64);
+        drop(data);
+        self.touch(false, true);
+        Ok(done)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        Ok(())
+    }
+
+    fn truncate(&self, size: u64) -> VfsResult {
+        let mut data = self.data()?.write();
+        if size < data.size {
+            let kept = size.div_ceil(PAGE_SIZE as u64);
+            let removed = data.pages.split_off(&kept).len();
+            self.sb.free_pages(removed);
+            // the tail of the last page must read back as zeros if the file grows again
+            let in_page = size as usize % PAGE_SIZE;
+            if in_page != 0 {
+                if let Some(frame) = data.pages.get(&(size / PAGE_SIZE as u64)) {
+                    page_slice(frame)[in_page..].fill(0);
+                }
+            }
+        }
+        data.size = size;
+        drop(data);
+        self.touch(false, true);
+        Ok(())
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        if !self.is_dir() {
+            return None;
+        }
+        match self.parent.read().upgrade() {
+            Some(parent) => Some(parent),
+            None => self.sb.mount_parent.read().as_ref()?.upgrade(),
+        }
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        Ok(self.walk(path)?)
+    }
+
+    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
+        log::debug!("create {:?} at tmpfs: {}", ty, path);
+        let (dir, name) = self.arc().walk_parent(path)?;
+        if dir.children()?.read().contains_key(name) {
+            return ax_err!(AlreadyExists);
+        }
+        let node = match ty {
+            VfsNodeType::File => TmpNode::new_file(&self.sb)?,
+            VfsNodeType::Dir => TmpNode::new_dir(&self.sb, VfsNodePerm::default_dir())?,
+            _ => return ax_err!(Unsupported),
+        };
+        dir.insert(name, node)
+    }
+
+    fn mknod(&self, path: &str, ty: VfsNodeType, rdev: u64) -> VfsResult {
+        log::debug!("mknod {:?} at tmpfs: {}", ty, path);
+        let rdev = match ty {
+            VfsNodeType::CharDevice | VfsNodeType::BlockDevice => rdev,
+            VfsNodeType::Fifo | VfsNodeType::Socket => 0,
+            _ => return ax_err!(InvalidInput),
+        };
+        let (dir, name) = self.arc().walk_parent(path)?;
+        if dir.children()?.read().contains_key(name) {
+            return ax_err!(AlreadyExists);
+        }
+        let node = TmpNode::new(
+            &self.sb,
+            Content::Special(ty, rdev),
+            VfsNodePerm::from_bits_truncate(0o644),
+        )?;
+        dir.insert(name, node)
+    }
+
+    fn remove(&self, path: &str) -> VfsResult {
+        log::debug!("remove at tmpfs: {}", path);
+        let (dir, name) = self.arc().walk_parent(path)?;
+        let mut children = dir.children()?.write();
+        let node = children.get(name).ok_or(VfsError::NotFound)?;
+        if let Content::Dir(grandchildren) = &node.content {
+            if !grandchildren.read().is_empty() {
+                return ax_err!(DirectoryNotEmpty);
+            }
+        }
+        let node = children.remove(name).unwrap();
+        drop(children);
+        node.unlinked(&dir);
+        dir.touch(false, true);
+        Ok(())
+    }
+
+    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        let children = self.children()?.read();
+        let mut children = children.iter().skip(start_idx.max(2) - 2);
+        for (i, ent) in dirents.iter_mut().enumerate() {
+            match i + start_idx {
+                0 => *ent = VfsDirEntry::new(".", VfsNodeType::Dir),
+                1 => *ent = VfsDirEntry::new("..", VfsNodeType::Dir),
+                _ => {
+                    if let Some((name, node)) = children.next() {
+                        *ent = VfsDirEntry::new(name, node.node_type());
+                    } else {
+                        return Ok(i);
+                    }
+                }
+            }
+        }
+        Ok(dirents.len())
+    }
+
+    fn rename(&self, src_path: &str, dst_path: &str, flags: RenameFlags) -> VfsResult {
+        let exchange = flags.contains(RenameFlags::EXCHANGE);
+        // 留在源位置的白化项（0:0 字符设备）在加锁前创建，失败时目录不受影响
+        let whiteout = if flags.contains(RenameFlags::WHITEOUT) {
+            let content = Content::Special(VfsNodeType::CharDevice, 0);
+            Some(TmpNode::new(&self.sb, content, VfsNodePerm::empty())?)
+        } else {
+            None
+        };
+        let (src_dir, src_name) = self.arc().walk_parent(src_path)?;
+        let (dst_dir, dst_name) = self.arc().walk_parent(dst_path)?;
+
+        // 检查和修改期间持有两个目录的写锁，不同目录按地址顺序加锁以免死锁
+        let same_dir = Arc::ptr_eq(&src_dir, &dst_dir);
+        let (mut src_children, mut dst_children) = if same_dir {
+            (src_dir.children()?.write(), None)
+        } else if Arc::as_ptr(&src_dir) < Arc::as_ptr(&dst_dir) {
+            let src_children = src_dir.children()?.write();
+            (src_children, Some(dst_dir.children()?.write()))
+        } else {
+            let dst_children = dst_dir.children()?.write();
+            (src_dir.children()?.write(), Some(dst_children))
+        };
+        let node = src_children.get(src_name).cloned();
+        let node = node.ok_or(VfsError::NotFound)?;
+        let old = match &dst_children {
+            Some(children) => children.get(dst_name).cloned(),
+            None => src_children.get(dst_name).cloned(),
+        };
+        match &old {
+            Some(_) if flags.contains(RenameFlags::NOREPLACE) => return ax_err!(AlreadyExists),
+            None if exchange => return ax_err!(NotFound),
+            _ => {}
+        }
+        if node.is_dir() && node.is_ancestor_of(&dst_dir) {
+            return ax_err!(InvalidInput, "cannot move a directory into itself");
+        }
+        if let Some(old) = &old {
+            if Arc::ptr_eq(old, &node) {
+                return Ok(());
+            }
+            if old.is_dir() && old.is_ancestor_of(&src_dir) {
+                return if exchange {
+                    ax_err!(InvalidInput, "cannot move a directory into itself")
+                } else {
+                    ax_err!(DirectoryNotEmpty)
+                };
+            }
+            if !exchange {
+                match (node.is_dir(), &old.content) {
+                    (true, Content::Dir(children)) if !children.read().is_empty() => {
+                        return ax_err!(DirectoryNotEmpty);
+                    }
+                    (true, Content::Dir(_))
+                    | (false, Content::File(_) | Content::SymLink(_) | Content::Special(..)) => {}
+                    (true, _) => return ax_err!(NotADirectory),
+                    (false, _) => return ax_err!(IsADirectory),
+                }
+            }
+        }
+
+        let replaced = match &mut dst_children {
+            Some(children) => children.insert(dst_name.into(), node.clone()),
+            None => src_children.insert(dst_name.into(), node.clone()),
+        };
+        match (&old, whiteout) {
+            (Some(old), _) if exchange => src_children.insert(src_name.into(), old.clone()),
+            (_, Some(whiteout)) => src_children.insert(src_name.into(), whiteout),
+            _ => src_children.remove(src_name),
+        };
+        drop((src_children, dst_children));
+
+        let mut moved = vec![(&node, &src_dir, &dst_dir)];
+        match (&old, replaced) {
+            (Some(old), _) if exchange => moved.push((old, &dst_dir, &src_dir)),
+            (_, Some(replaced)) => replaced.unlinked(&dst_dir),
+            _ => {}
+        }
+        for (node, from, to) in moved {
+            if node.is_dir() {
+                *node.parent.write() = Arc::downgrade(to);
+                from.adjust_nlink(false);
+                to.adjust_nlink(true);
+            }
+            node.touch(false, false);
+        }
+        src_dir.touch(false, true);
+        dst_dir.touch(false, true);
+        Ok(())
+    }
+
+    fn link(&self, path: &str, node: VfsNodeRef) -> VfsResult {
+        // the caller ensures that `node` is on the same mount
+        let target = node
+            .as_any()
+            .downcast_ref::<TmpNode>()
+            .ok_or(VfsError::InvalidInput)?
+            .arc();
+        if !Arc::ptr_eq(&target.sb, &self.sb) {
+            return ax_err!(InvalidInput, "link across filesystems");
+        }
+        if target.is_dir() {
+            return ax_err!(PermissionDenied, "hard link to a directory");
+        }
+        let (dir, name) = self.arc().walk_parent(path)?;
+        dir.insert(name, target.clone())?;
+        target.adjust_nlink(true);
+        Ok(())
+    }
+
+    fn symlink(&self, path: &str, target: &str) -> VfsResult {
+        let (dir, name) = self.arc().walk_parent(path)?;
+        if dir.children()?.read().contains_key(name) {
+            return ax_err!(AlreadyExists);
+        }
+        let node = TmpNode::new(
+            &self.sb,
+            Content::SymLink(target.into()),
+            VfsNodePerm::from_bits_truncate(0o777),
+        )?;
+        dir.insert(name, node)
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        match &self.content {
+            Content::SymLink(target) => {
+                let len = buf.len().min(target.len());
+                buf[..len].copy_from_slice(&target.as_bytes()[..len]);
+                Ok(len)
+            }
+            _ => ax_err!(InvalidInput),
+        }
+    }
+
+    fn shared_page(&self, index: u64) -> Option<Arc<dyn Any + Send + Sync>> {
+        let mut data = self.data().ok()?.write();
+        if index * PAGE_SIZE as u64 >= data.size {
+            return None;
+        }
+        let frame = match data.pages.get(&index) {
+            Some(frame) => frame.clone(),
+            None => {
+                let frame = self.sb.alloc_page().ok()?;
+                data.pages.insert(index, frame.clone());
+                frame
+            }
+        };
+        Some(frame)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A size-limited in-memory filesystem that implements [`VfsOps`].
+pub struct TmpFileSystem {
+    sb: Arc<SuperBlock>,
+    root: Arc<TmpNode>,
+}
+
+/// Parse a size like `64k`, `16m`, `1g` or `50%` (of `total`).
+fn parse_size(value: &str, total: usize) -> VfsResult<usize> {
+    let (num, unit) = match value.as_bytes().last() {
+        Some(b'k' | b'K') => (&value[..value.len() - 1], 1 << 10),
+        Some(b'm' | b'M') => (&value[..value.len() - 1], 1 << 20),
+        Some(b'g' | b'G') => (&value[..value.len() - 1], 1 << 30),
+        Some(b'%') => {
+            let percent: usize = value[..value.len() - 1]
+                .parse()
+                .map_err(|_| VfsError::InvalidInput)?;
+            return Ok(total / 100 * percent);
+        }
+        _ => (value, 1),
+    };
+    num.parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or(VfsError::InvalidInput)
+}
+
+impl TmpFileSystem {
+    /// Create a tmpfs from comma separated mount options.
+    ///
+    /// Supported options are `size=`, `nr_blocks=`, `nr_inodes=`, `mode=`,
+    /// `uid=` and `gid=`. By default, the filesystem may use half of the
+    /// memory.
+    pub fn new(options: &str) -> VfsResult<Self> {
+        let allocator = axalloc::global_allocator();
+        let total_pages = allocator.used_pages() + allocator.available_pages();
+        let mut max_pages = total_pages / 2;
+        let mut max_inodes = total_pages / 2;
+        let mut perm = VfsNodePerm::from_bits_truncate(0o1777);
+        let (mut uid, mut gid) = (0, 0);
+        for opt in options.split(',').filter(|opt| !opt.is_empty()) {
+            let (key, value) = opt.split_once('=').ok_or(VfsError::InvalidInput)?;
+            let parse_id = |v: &str| v.parse::<u32>().map_err(|_| VfsError::InvalidInput);
+            match key {
+                "size" => {
+                    max_pages = parse_size(value, total_pages * PAGE_SIZE)?.div_ceil(PAGE_SIZE)
+                }
+                "nr_blocks" => max_pages = parse_size(value, total_pages)?,
+                "nr_inodes" => max_inodes = parse_size(value, total_pages)?,
+                "mode" => {
+                    let mode = u16::from_str_radix(value, 8).map_err(|_| VfsError::InvalidInput)?;
+                    perm = VfsNodePerm::from_bits_truncate(mode);
+                }
+                "uid" => uid = parse_id(value)?,
+                "gid" => gid = parse_id(value)?,
+                _ => return ax_err!(InvalidInput, "unknown tmpfs option"),
+            }
+        }
+        // size=0 and nr_inodes=0 mean unlimited
+        if max_pages == 0 {
+            max_pages = usize::MAX;
+        }
+        if max_inodes == 0 {
+            max_inodes = usize::MAX;
+        }
+
+        let sb = Arc::new(SuperBlock {
+            max_pages,
+            max_inodes,
+            pages: AtomicUsize::new(0),
+            inodes: AtomicUsize::new(0),
+            next_ino: AtomicU64::new(1),
+            mount_parent: RwLock::new(None),
+        });
+        let root = TmpNode::new_dir(&sb, perm)?;
+        root.set_owner(Some(uid), Some(gid))?;
+        Ok(Self { sb, root })
+    }
+}
+
+impl VfsOps for TmpFileSystem {
+    fn mount(&self, _path: &str, mount_point: VfsNodeRef) -> VfsResult {
+        *self.sb.mount_parent.write() = mount_point.parent().map(|p| Arc::downgrade(&p));
+        Ok(())
+    }
+
+    fn statfs(&self, _path: *const c_char, fs_info: *mut FileSystemInfo) -> VfsResult<usize> {
+        let sb = &self.sb;
+        let pages = sb.pages.load(Ordering::SeqCst);
+        let inodes = sb.inodes.load(Ordering::SeqCst);
+        let info = unsafe { &mut *fs_info };
+        info.ftype = TMPFS_MAGIC;
+        info.bsize = PAGE_SIZE as u64;
+        info.blocks = sb.max_pages as u64;
+        info.bfree = sb.max_pages.saturating_sub(pages) as u64;
+        info.bavail = info.bfree;
+        info.files = sb.max_inodes as u64;
+        info.ffree = sb.max_inodes.saturating_sub(inodes) as u64;
+        info.fsid = 0;
+        info.namelen = NAME_MAX as u64;
+        Ok(0)
+    }
+
+    fn root_dir(&self) -> VfsNodeRef {
+        self.root.clone()
+    }
+}
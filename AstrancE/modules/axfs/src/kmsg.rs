@@ -0,0 +1,136 @@
+//! `/dev/kmsg`, the interface to the kernel log buffer, and waiting for new
+//! records in it.
+
+use axfs_vfs::{
+    VfsError, VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult,
+};
+use axlog::kmsg::{self, KmsgError};
+
+/// Tasks waiting for new records.
+#[cfg(feature = "multitask")]
+static READERS: axtask::WaitQueue = axtask::WaitQueue::new();
+
+/// Waits until `condition` holds, checking it again whenever new records are
+/// logged.
+///
+/// Returns [`BadState`](VfsError::BadState) if the task is interrupted
+/// before, as there is no `EINTR` in `VfsError`.
+pub fn wait_until(condition: impl Fn() -> bool) -> VfsResult {
+    #[cfg(feature = "multitask")]
+    {
+        READERS
+            .wait_until_interruptible(condition)
+            .map_err(|_| VfsError::BadState)
+    }
+    #[cfg(not(feature = "multitask"))]
+    {
+        while !condition() {
+            core::hint::spin_loop();
+        }
+        Ok(())
+    }
+}
+
+/// Wakes up the tasks in [`wait_until`] if new records have been logged.
+///
+/// It must be called periodically with no lock held, see
+/// [`axlog::kmsg::take_wakeup`].
+pub fn wake_readers() {
+    #[cfg(feature = "multitask")]
+    if kmsg::take_wakeup() {
+        READERS.notify_all(false);
+    }
+}
+
+/// A device behaves like `/dev/kmsg`.
+///
+/// Each read returns one record of the kernel log buffer in the `/dev/kmsg`
+/// format, and each write injects one record. The file offset is a position
+/// in the stream of all records (see [`axlog::kmsg`]), so seeking to the end
+/// skips existing records. Reads at the end of the stream wait for the next
+/// record.
+#[cfg(feature = "devfs")]
+pub(crate) struct KmsgDev;
+
+#[cfg(feature = "devfs")]
+impl VfsNodeOps for KmsgDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            0,
+            VfsNodePerm::from_bits_truncate(0o644),
+            VfsNodeType::CharDevice,
+            kmsg::kmsg_end(),
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ))
+    }
+
+    fn get_attr_x(&self) -> VfsResult<VfsNodeAttrX> {
+        Ok(VfsNodeAttrX::new(
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            VfsNodePerm::from_bits_truncate(0o644),
+            VfsNodeType::CharDevice,
+            0,
+            kmsg::kmsg_end(),
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            1,
+            11,
+            0,
+            0,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        loop {
+            match self.read_at_nonblocking(offset, buf) {
+                Err(VfsError::WouldBlock) => wait_until(|| kmsg::kmsg_end() > offset)?,
+                res => return res,
+            }
+        }
+    }
+
+    fn read_at_nonblocking(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        match kmsg::read_kmsg(offset, buf) {
+            Ok(0) if !buf.is_empty() => Err(VfsError::WouldBlock),
+            res => res.map_err(|err| match err {
+                // no EPIPE in `VfsError`
+                KmsgError::Overwritten => VfsError::InvalidData,
+                KmsgError::InvalidInput => VfsError::InvalidInput,
+            }),
+        }
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        Ok(kmsg::write_user(buf))
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
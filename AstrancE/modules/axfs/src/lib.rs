@@ -31,19 +31,25 @@ use alloc::{
     format,
     string::{String, ToString},
     sync::Arc,
+    vec::Vec,
 };
 pub mod api;
 mod blkdev;
 mod dev;
+pub mod devices;
 pub mod fops;
 pub mod fs;
+pub mod kmsg;
 mod mounts;
+mod page_cache;
 pub mod path;
 pub mod root;
+#[cfg(feature = "devfs")]
+mod tty;
 use api::create_dir;
 use axsync::Mutex;
 use lazyinit::LazyInit;
-pub use root::{CURRENT_DIR, CURRENT_DIR_PATH, ROOT_DIR, PROC_ROOT};
+pub use root::{CURRENT_DIR, CURRENT_DIR_MOUNT, CURRENT_DIR_PATH, ROOT_DIR, PROC_ROOT};
 
 pub use crate::dev::Disk;
 use axdriver::{AxDeviceContainer, prelude::*};
@@ -66,6 +72,15 @@ fn get_device_name(index: u8) -> String {
     name
 }
 
+/// Registers `disk` as the block device `/dev/<name>`.
+fn register_disk(name: &str, disk: &Disk) {
+    let (major, minor) = disk.dev_t();
+    let dev = Arc::new(blkdev::BlockDev::new(disk.clone()));
+    if let Err(e) = devices::register_blkdev(name, major as u32, minor as u32, dev) {
+        warn!("failed to register block device {}: {:?}", name, e);
+    }
+}
+
 /// Initializes filesystems by block devices.
 pub fn init_filesystems(mut blk_devs: AxDeviceContainer<AxBlockDevice>) {
     info!("Initialize filesystems...");
@@ -85,10 +100,34 @@ pub fn init_filesystems(mut blk_devs: AxDeviceContainer<AxBlockDevice>) {
             device_name
         );
         //let a = fs::lwext4_rust::Ext4FileSystem::new(Disk::new(device, 1, 0));
-        disks.insert(device_name, Disk::new(device, 1, 0));
+        // virtio-blk: major 254, 16 minors per disk for its partitions
+        let disk = Disk::new(device, 254, i.wrapping_mul(16));
+        register_disk(&device_name, &disk);
+        disks.insert(device_name, disk);
         i += 1;
     }
     info!("{} disks in total", disks.len());
-    root::init_rootfs(disks.pop_last().expect("No block device found!").1);
+    let (root_name, root_disk) = disks.pop_last().expect("No block device found!");
+    // register primary partitions of the other disks as `vdXN`
+    let parts: Vec<_> = disks
+        .iter()
+        .flat_map(|(name, disk)| {
+            disk.mbr_partitions()
+                .into_iter()
+                .enumerate()
+                .map(|(i, (start, size))| {
+                    let minor = disk.dev_t().1 + i as u8 + 1;
+                    (format!("{}{}", name, i + 1), disk.partition(start, size, minor))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    for (name, part) in parts {
+        warn!("Find partition: {} ({} bytes)", name, part.size());
+        register_disk(&name, &part);
+        disks.insert(name, part);
+    }
+    drop(disks);
+    root::init_rootfs(&root_name, root_disk);
     info!("Initialize device filesystems...");
 }
@@ -7,22 +7,25 @@ use crate::fs;
 
 #[cfg(feature = "devfs")]
 pub(crate) fn devfs() -> Arc<fs::devfs::DeviceFileSystem> {
-    // let null = fs::devfs::NullDev;
-    // let zero = fs::devfs::ZeroDev;
-    // let bar = fs::devfs::ZeroDev;
+    use crate::devices::register_chrdev;
+    use fs::devfs::{FullDev, NullDev, SymlinkNode, ZeroDev};
 
-    let null = Arc::new(fs::devfs::NullDev);
-    let zero = Arc::new(fs::devfs::ZeroDev);
+    let tty = Arc::new(crate::tty::TtyDev);
+    // numbers from Documentation/admin-guide/devices.txt of Linux
+    register_chrdev("null", 1, 3, Arc::new(NullDev)).ok();
+    register_chrdev("zero", 1, 5, Arc::new(ZeroDev)).ok();
+    register_chrdev("full", 1, 7, Arc::new(FullDev)).ok();
+    register_chrdev("kmsg", 1, 11, Arc::new(crate::kmsg::KmsgDev)).ok();
+    register_chrdev("ttyS0", 4, 64, tty.clone()).ok();
+    register_chrdev("tty", 5, 0, tty.clone()).ok();
+    register_chrdev("console", 5, 1, tty).ok();
 
     let devfs = fs::devfs::DeviceFileSystem::new();
-    // let sda1_dir = devfs.mkdir("sda1");
-    // devfs.add("null", Arc::new(null));
-    // devfs.add("zero", Arc::new(zero));
-    devfs.add("null", null.clone());
-    devfs.add("zero", zero.clone());
-    // devfs.register_device_by_name("sda1",8,0,fs).expect("No Device");
-    // devfs.register_device(1, 3, null);
-    // devfs.register_device(1, 5, zero);
+    devfs.add("fd", Arc::new(SymlinkNode::new("/proc/self/fd")));
+    devfs.add("stdin", Arc::new(SymlinkNode::new("/proc/self/fd/0")));
+    devfs.add("stdout", Arc::new(SymlinkNode::new("/proc/self/fd/1")));
+    devfs.add("stderr", Arc::new(SymlinkNode::new("/proc/self/fd/2")));
+    crate::devices::populate_devfs(devfs.root().clone());
     Arc::new(devfs)
 }
 
@@ -31,6 +34,11 @@ pub(crate) fn ramfs() -> Arc<fs::ramfs::RamFileSystem> {
     Arc::new(fs::ramfs::RamFileSystem::new())
 }
 
+#[cfg(feature = "tmpfs")]
+pub(crate) fn tmpfs(options: &str) -> VfsResult<Arc<fs::tmpfs::TmpFileSystem>> {
+    Ok(Arc::new(fs::tmpfs::TmpFileSystem::new(options)?))
+}
+
 #[cfg(feature = "procfs")]
 pub(crate) fn procfs() -> VfsResult<Arc<fs::procfs::ProcFileSystem>> {
     /*
@@ -69,6 +77,15 @@ pub(crate) fn procfs() -> VfsResult<Arc<fs::procfs::ProcFileSystem>> {
     );
 
     proc_root.create_static_file("version", proc_version_string.as_bytes());
+    // /proc/kmsg 与 syslog(SYSLOG_ACTION_READ) 共享读取位置，读取会消耗日志
+    proc_root.create_dynamic_file(
+        "kmsg",
+        Arc::new(|_offset: u64, buf: &mut [u8]| -> VfsResult<usize> {
+            Ok(axlog::kmsg::syslog_read(buf))
+        }),
+    )?;
+    // /proc/mounts 每次读取时根据挂载树重新生成
+    proc_root.create_dynamic_file("mounts", text_file_generator(crate::root::proc_mounts))?;
 
     Ok(Arc::new(procfs))
 }
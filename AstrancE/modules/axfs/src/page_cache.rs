@@ -0,0 +1,450 @@
+//! Page cache of regular files on block device filesystems.
+//!
+//! File data is cached page by page in physical frames, keyed by the device
+//! and inode number of the file and the page index, so every opened
+//! [`File`](crate::fops::File) of an inode and every shared memory mapping of
+//! it see the very same memory.
+//!
+//! Writes through [`write_at`] go to the filesystem at once. Pages handed out
+//! to shared mappings may be modified behind our back, so they are written
+//! back by [`sync`], when they are reclaimed, or when their filesystem is
+//! unmounted. Pages that are not mapped anywhere are reclaimed in
+//! least-recently-used order when the cache grows beyond its limit or the
+//! page allocator runs out of memory.
+//!
+//! The global lock only guards the index of cached pages. Reading a page from
+//! the filesystem and writing it back are done under the lock of that page
+//! alone, so I/O on one file does not stall the others.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use axerrno::{AxError, AxResult};
+use axfs_vfs::{VfsNodeAttr, VfsNodeRef};
+use axhal::mem::phys_to_virt;
+use axmm::{FrameTrackerRef, alloc_frame};
+use axsync::Mutex;
+
+const PAGE_SIZE: usize = 4096;
+/// Number of pages reclaimed at once when the page allocator is exhausted.
+const RECLAIM_BATCH: usize = 32;
+
+/// Identifies a cached file by the device and inode number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InodeKey {
+    dev: (u32, u32),
+    ino: u64,
+}
+
+impl InodeKey {
+    /// Key of a node found on the mount with device number `dev`.
+    ///
+    /// Returns `None` if the data of the node is not cached: only regular
+    /// files with an inode number on block device filesystems are, pseudo
+    /// filesystems have anonymous devices with major number 0 and serve
+    /// their data from memory already.
+    pub(crate) fn new(dev: (u32, u32), attr: &VfsNodeAttr) -> Option<Self> {
+        (dev.0 != 0 && attr.is_file() && attr.st_ino() != 0).then_some(Self {
+            dev,
+            ino: attr.st_ino(),
+        })
+    }
+}
+
+type PageKey = (InodeKey, u64);
+
+struct CachedPage {
+    frame: FrameTrackerRef,
+    /// Held while the page is read from or copied to the file.
+    io: Mutex<()>,
+    /// The page holds the file data, set once it was read.
+    uptodate: AtomicBool,
+    /// Mapped shared, possibly modified through the mapping.
+    dirty: AtomicBool,
+}
+
+impl CachedPage {
+    fn mapped(&self) -> bool {
+        Arc::strong_count(&self.frame) > 1
+    }
+
+    fn data(&self) -> &mut [u8] {
+        page_slice(&self.frame)
+    }
+}
+
+struct Entry {
+    page: Arc<CachedPage>,
+    /// Position in the LRU order.
+    stamp: u64,
+}
+
+struct PageCache {
+    pages: BTreeMap<PageKey, Entry>,
+    /// Pages by the time of their last use.
+    lru: BTreeMap<u64, PageKey>,
+    clock: u64,
+    /// Nodes to write dirty pages back to.
+    nodes: BTreeMap<InodeKey, VfsNodeRef>,
+    /// Soft limit of cached pages, computed at first use.
+    max_pages: usize,
+}
+
+static CACHE: Mutex<PageCache> = Mutex::new(PageCache::new());
+
+fn page_slice(frame: &FrameTrackerRef) -> &mut [u8] {
+    unsafe { core::slice::from_raw_parts_mut(phys_to_virt(frame.pa).as_mut_ptr(), PAGE_SIZE) }
+}
+
+impl PageCache {
+    const fn new() -> Self {
+        Self {
+            pages: BTreeMap::new(),
+            lru: BTreeMap::new(),
+            clock: 0,
+            nodes: BTreeMap::new(),
+            max_pages: 0,
+        }
+    }
+
+    fn limit(&mut self) -> usize {
+        if self.max_pages == 0 {
+            let allocator = axalloc::global_allocator();
+            let total_pages = allocator.used_pages() + allocator.available_pages();
+            self.max_pages = (total_pages / 4).max(RECLAIM_BATCH);
+        }
+        self.max_pages
+    }
+
+    fn touch(&mut self, key: PageKey) -> Option<Arc<CachedPage>> {
+        let entry = self.pages.get_mut(&key)?;
+        self.lru.remove(&entry.stamp);
+        self.clock += 1;
+        entry.stamp = self.clock;
+        self.lru.insert(self.clock, key);
+        Some(entry.page.clone())
+    }
+
+    /// Add `page` to the cache, unless another task has just added the same
+    /// page. Returns the page that is cached.
+    fn insert(&mut self, key: PageKey, page: Arc<CachedPage>) -> Arc<CachedPage> {
+        if let Some(cached) = self.touch(key) {
+            return cached;
+        }
+        self.clock += 1;
+        self.lru.insert(self.clock, key);
+        self.pages.insert(key, Entry {
+            page: page.clone(),
+            stamp: self.clock,
+        });
+        page
+    }
+
+    fn remove(&mut self, key: PageKey) {
+        if let Some(entry) = self.pages.remove(&key) {
+            self.lru.remove(&entry.stamp);
+        }
+        if self.inode_pages(key.0).is_empty() {
+            self.nodes.remove(&key.0);
+        }
+    }
+
+    fn inode_pages(&self, inode: InodeKey) -> Vec<PageKey> {
+        self.pages
+            .range((inode, 0)..=(inode, u64::MAX))
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Pages of `keys` with the nodes to write them back to.
+    fn with_nodes(&self, keys: impl IntoIterator<Item = PageKey>) -> Vec<PageRef> {
+        keys.into_iter()
+            .map(|key| {
+                (
+                    key,
+                    self.pages[&key].page.clone(),
+                    self.nodes.get(&key.0).cloned(),
+                )
+            })
+            .collect()
+    }
+}
+
+type PageRef = (PageKey, Arc<CachedPage>, Option<VfsNodeRef>);
+
+/// Write the page back to `node` if it was modified through a shared
+/// mapping. Must be called without the cache lock held.
+fn write_back(key: PageKey, page: &CachedPage, node: Option<&VfsNodeRef>) {
+    let _io = page.io.lock();
+    // still mapped pages may get dirty again
+    if !page.dirty.swap(page.mapped(), Ordering::AcqRel) {
+        return;
+    }
+    let Some(node) = node else {
+        return;
+    };
+    let offset = key.1 * PAGE_SIZE as u64;
+    let res = node.get_attr().and_then(|attr| {
+        if offset >= attr.size() {
+            return Ok(0);
+        }
+        let len = (attr.size() - offset).min(PAGE_SIZE as u64) as usize;
+        node.write_at(offset, &page.data()[..len])
+    });
+    if let Err(e) = res {
+        warn!(
+            "page cache: write back {:?} page {} failed: {:?}",
+            key.0, key.1, e
+        );
+    }
+}
+
+/// Get the page `index` of the file, reading it from `node` on a miss.
+fn load(key: InodeKey, node: &VfsNodeRef, index: u64) -> AxResult<Arc<CachedPage>> {
+    let mut cache = CACHE.lock();
+    let page = match cache.touch((key, index)) {
+        Some(page) => page,
+        None => {
+            let full = cache.pages.len() >= cache.limit();
+            drop(cache);
+            if full {
+                reclaim(1);
+            }
+            let frame = match alloc_frame(true) {
+                Some(frame) => frame,
+                None => {
+                    reclaim(RECLAIM_BATCH);
+                    alloc_frame(true).ok_or(AxError::NoMemory)?
+                }
+            };
+            let page = Arc::new(CachedPage {
+                frame,
+                io: Mutex::new(()),
+                uptodate: AtomicBool::new(false),
+                dirty: AtomicBool::new(false),
+            });
+            CACHE.lock().insert((key, index), page)
+        }
+    };
+    // checked without the lock first, a copy into this page holding it may
+    // fault on a mapping of the same page
+    if !page.uptodate.load(Ordering::Acquire) {
+        let _io = page.io.lock();
+        if !page.uptodate.load(Ordering::Acquire) {
+            let buf = page.data();
+            let mut read = 0;
+            while read < PAGE_SIZE {
+                let n = node.read_at(index * PAGE_SIZE as u64 + read as u64, &mut buf[read..])?;
+                if n == 0 {
+                    break;
+                }
+                read += n;
+            }
+            page.uptodate.store(true, Ordering::Release);
+        }
+    }
+    Ok(page)
+}
+
+/// Read the file `node` with the cache `key` at `offset`.
+pub(crate) fn read_at(
+    key: InodeKey,
+    node: &VfsNodeRef,
+    offset: u64,
+    buf: &mut [u8],
+) -> AxResult<usize> {
+    let size = node.get_attr()?.size();
+    if offset >= size {
+        return Ok(0);
+    }
+    let len = buf.len().min((size - offset) as usize);
+    let mut done = 0;
+    while done < len {
+        let pos = offset + done as u64;
+        let index = pos / PAGE_SIZE as u64;
+        let start = (pos % PAGE_SIZE as u64) as usize;
+        let n = (PAGE_SIZE - start).min(len - done);
+        let page = load(key, node, index)?;
+        // copy without any lock held, `buf` may fault in file mappings
+        buf[done..done + n].copy_from_slice(&page.data()[start..start + n]);
+        done += n;
+    }
+    Ok(done)
+}
+
+/// Write the file `node` with the cache `key` at `offset`, updating the
+/// cached pages.
+pub(crate) fn write_at(
+    key: InodeKey,
+    node: &VfsNodeRef,
+    offset: u64,
+    buf: &[u8],
+) -> AxResult<usize> {
+    let written = node.write_at(offset, buf)?;
+    let mut done = 0;
+    while done < written {
+        let pos = offset + done as u64;
+        let index = pos / PAGE_SIZE as u64;
+        let start = (pos % PAGE_SIZE as u64) as usize;
+        let n = (PAGE_SIZE - start).min(written - done);
+        let page = CACHE.lock().touch((key, index));
+        if let Some(page) = page {
+            // a concurrent read of the page from the file finishes first
+            let _io = page.io.lock();
+            page.data()[start..start + n].copy_from_slice(&buf[done..done + n]);
+        }
+        done += n;
+    }
+    Ok(written)
+}
+
+/// Drop the cached pages beyond `size` after the file was truncated.
+///
+/// Pages still mapped shared are kept and cleared instead, so the mappings
+/// keep sharing their memory with the file if it grows again.
+pub(crate) fn truncate(key: InodeKey, size: u64) {
+    let first_gone = size.div_ceil(PAGE_SIZE as u64);
+    let mut cache = CACHE.lock();
+    let mut cleared = Vec::new();
+    for page in cache.inode_pages(key) {
+        if page.1 < first_gone {
+            continue;
+        }
+        let entry = &cache.pages[&page];
+        if entry.page.mapped() {
+            cleared.push((entry.page.clone(), 0));
+        } else {
+            cache.remove(page);
+        }
+    }
+    let tail = (size % PAGE_SIZE as u64) as usize;
+    if tail != 0 {
+        if let Some(entry) = cache.pages.get(&(key, size / PAGE_SIZE as u64)) {
+            cleared.push((entry.page.clone(), tail));
+        }
+    }
+    drop(cache);
+    for (page, from) in cleared {
+        let _io = page.io.lock();
+        page.data()[from..].fill(0);
+    }
+}
+
+/// Get the page `index` of the file `node` for a shared memory mapping.
+pub(crate) fn map_shared(
+    key: InodeKey,
+    node: &VfsNodeRef,
+    index: u64,
+) -> AxResult<FrameTrackerRef> {
+    if index * PAGE_SIZE as u64 >= node.get_attr()?.size() {
+        return Err(AxError::InvalidInput);
+    }
+    let page = load(key, node, index)?;
+    page.dirty.store(true, Ordering::Release);
+    CACHE
+        .lock()
+        .nodes
+        .entry(key)
+        .or_insert_with(|| node.clone());
+    Ok(page.frame.clone())
+}
+
+/// Write back the pages of the file `key` modified through shared mappings.
+pub(crate) fn sync(key: InodeKey) {
+    let pages = {
+        let cache = CACHE.lock();
+        cache.with_nodes(cache.inode_pages(key))
+    };
+    for (key, page, node) in pages {
+        write_back(key, &page, node.as_ref());
+    }
+}
+
+/// Write back all pages modified through shared mappings.
+pub(crate) fn sync_all() {
+    let pages = {
+        let cache = CACHE.lock();
+        let dirty = cache
+            .pages
+            .iter()
+            .filter(|(_, entry)| entry.page.dirty.load(Ordering::Acquire))
+            .map(|(key, _)| *key);
+        cache.with_nodes(dirty)
+    };
+    for (key, page, node) in pages {
+        write_back(key, &page, node.as_ref());
+    }
+}
+
+/// Drop the pages of a file that was deleted, its inode number may be reused.
+pub(crate) fn invalidate(key: InodeKey) {
+    let mut cache = CACHE.lock();
+    for page in cache.inode_pages(key) {
+        cache.remove(page);
+    }
+}
+
+/// Write back and drop all pages of the filesystem on device `dev`, before
+/// it is unmounted.
+pub(crate) fn forget_dev(dev: (u32, u32)) {
+    let pages = {
+        let mut cache = CACHE.lock();
+        let keys: Vec<PageKey> = cache
+            .pages
+            .keys()
+            .filter(|(inode, _)| inode.dev == dev)
+            .copied()
+            .collect();
+        let pages = cache.with_nodes(keys.iter().copied());
+        for key in keys {
+            cache.remove(key);
+        }
+        pages
+    };
+    for (key, page, node) in pages {
+        write_back(key, &page, node.as_ref());
+    }
+}
+
+/// Drop up to `nr` cached pages that are not mapped anywhere, least recently
+/// used first. Returns the number of pages freed.
+pub(crate) fn reclaim(nr: usize) -> usize {
+    let victims = {
+        let cache = CACHE.lock();
+        let idle = cache
+            .lru
+            .values()
+            .filter(|key| {
+                let page = &cache.pages[key].page;
+                Arc::strong_count(page) == 1 && !page.mapped()
+            })
+            .take(nr)
+            .copied();
+        cache.with_nodes(idle)
+    };
+    // the victims stay cached while they are written back, so nobody reads
+    // stale data from the file meanwhile
+    for (key, page, node) in victims.iter() {
+        write_back(*key, page, node.as_ref());
+    }
+    let mut cache = CACHE.lock();
+    let mut freed = 0;
+    for (key, page, _) in victims {
+        // skip pages used or mapped again during the write back
+        let unchanged = cache
+            .pages
+            .get(&key)
+            .is_some_and(|entry| Arc::ptr_eq(&entry.page, &page));
+        if unchanged
+            && Arc::strong_count(&page) == 2
+            && !page.mapped()
+            && !page.dirty.load(Ordering::Acquire)
+        {
+            cache.remove(key);
+            freed += 1;
+        }
+    }
+    freed
+}
@@ -1,6 +1,7 @@
 //! Root directory of the filesystem
 //!
-//! TODO: it doesn't work very well if the mount points have containment relationships.
+//! Mounted filesystems form a tree: every mount sits on a directory of its
+//! parent mount, and path lookups walk down the tree component by component.
 
 use crate::DISKS;
 use crate::fs::fatfs::FatFileSystem;
@@ -10,16 +11,26 @@ use crate::{
     dev::Disk,
     fs::{self},
     mounts,
+    page_cache::{self, InodeKey},
 };
 use alloc::borrow::ToOwned;
+use alloc::format;
 use alloc::string::{String, ToString};
-use alloc::{sync::Arc, vec::Vec};
+use alloc::collections::BTreeMap;
+use alloc::{
+    sync::{Arc, Weak},
+    vec,
+    vec::Vec,
+};
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use axdriver::AxBlockDevice;
 use axerrno::{AxError, AxResult, ax_err};
 use axfs_devfs::DeviceFileSystem;
 use axfs_procfs::ProcDir;
 use axfs_vfs::path::canonicalize;
-use axfs_vfs::{VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps, VfsResult};
+use axfs_vfs::{
+    RenameFlags, VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps, VfsResult,
+};
 use axio::Read;
 use axns::{ResArc, def_resource};
 use axsync::Mutex;
@@ -31,6 +42,8 @@ use crate::mounts::devfs;
 def_resource! {
     pub static CURRENT_DIR_PATH: ResArc<Mutex<String>> = ResArc::new();
     pub static CURRENT_DIR: ResArc<Mutex<VfsNodeRef>> = ResArc::new();
+    /// The mount of the current directory, `None` for the root filesystem.
+    pub static CURRENT_DIR_MOUNT: ResArc<Mutex<Option<Arc<MountRef>>>> = ResArc::new();
 }
 
 impl CURRENT_DIR_PATH {
@@ -47,121 +60,613 @@ impl CURRENT_DIR {
     }
 }
 
+impl CURRENT_DIR_MOUNT {
+    /// Return another reference to the mount of the current directory.
+    pub fn copy_inner(&self) -> Mutex<Option<Arc<MountRef>>> {
+        Mutex::new(self.lock().clone())
+    }
+}
+
+bitflags::bitflags! {
+    /// Mount flags, with the same values as Linux `MS_*`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MountFlags: u32 {
+        /// Mount read-only.
+        const RDONLY = 1;
+        /// Ignore set-user-ID and set-group-ID bits.
+        const NOSUID = 2;
+        /// Disallow access to device special files.
+        const NODEV = 4;
+        /// Disallow program execution.
+        const NOEXEC = 8;
+        /// Writes are synced at once.
+        const SYNCHRONOUS = 16;
+        /// Alter flags of a mounted filesystem.
+        const REMOUNT = 32;
+        /// Do not update access times.
+        const NOATIME = 1024;
+        /// Create a bind mount.
+        const BIND = 4096;
+        /// Apply recursively (only meaningful with `BIND`).
+        const REC = 16384;
+    }
+}
+
+impl MountFlags {
+    /// Flags that are remembered per mount, as opposed to the ones that only
+    /// select the operation of `mount(2)`.
+    pub fn per_mount(self) -> Self {
+        self & (Self::RDONLY | Self::NOSUID | Self::NODEV | Self::NOEXEC | Self::NOATIME)
+    }
+}
+
+bitflags::bitflags! {
+    /// Restrictions on path resolution, with the same values as Linux
+    /// `RESOLVE_*` of `openat2(2)`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ResolveFlags: u64 {
+        /// Do not cross mount points.
+        const NO_XDEV = 0x01;
+        /// Do not follow magic links (symbolic links of procfs).
+        const NO_MAGICLINKS = 0x02;
+        /// Do not follow any symbolic links.
+        const NO_SYMLINKS = 0x04;
+        /// Stay beneath the starting directory.
+        const BENEATH = 0x08;
+        /// Treat the starting directory as the root directory.
+        const IN_ROOT = 0x10;
+    }
+}
+
+/// Errors of [`resolve_path`] and [`rename`] that have no [`AxError`]
+/// counterpart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolveError {
+    /// The walk would leave the starting directory or cross a mount point
+    /// (`EXDEV`).
+    CrossDevice,
+    /// A symbolic link is not allowed, or too many were followed (`ELOOP`).
+    Loop,
+    /// Other errors of the lookups.
+    Ax(AxError),
+}
+
+/// What the last component of a path walked by [`resolve_path`] refers to.
+pub enum Resolved {
+    /// The node it names.
+    Node(VfsNodeRef),
+    /// It does not exist, and may be created as `name` in the directory
+    /// `parent`.
+    Missing {
+        /// The directory containing the component.
+        parent: VfsNodeRef,
+        /// The name of the component.
+        name: String,
+    },
+}
+
+/// A path walked by [`resolve_path`].
+///
+/// The node is the one the walk checked against the restrictions, so opening
+/// it does not look the path up again.
+pub struct ResolvedPath {
+    /// The absolute path reached, without symbolic links.
+    pub path: String,
+    /// What the last component refers to.
+    pub target: Resolved,
+    /// The mount the last component is on.
+    pub mount: Arc<MountRef>,
+}
+
+impl From<AxError> for ResolveError {
+    fn from(err: AxError) -> Self {
+        Self::Ax(err)
+    }
+}
+
+impl From<ResolveError> for AxError {
+    fn from(err: ResolveError) -> Self {
+        match err {
+            ResolveError::CrossDevice => AxError::PermissionDenied,
+            ResolveError::Loop => AxError::InvalidInput,
+            ResolveError::Ax(err) => err,
+        }
+    }
+}
+
+/// Mount id of the root filesystem.
+const ROOT_MOUNT_ID: usize = 1;
+
+/// A mounted filesystem instance, shared by all mounts of it (bind mounts
+/// included). The filesystem is unmounted when the last mount and the last
+/// [`MountRef`] on it are gone.
+struct SuperBlock {
+    fs: Arc<dyn VfsOps>,
+    dev: (u32, u32),
+}
+
+/// A reference to a mount, held by open files and working directories.
+///
+/// A mount is busy while it is referenced, and a lazily unmounted
+/// (`MNT_DETACH`) filesystem stays alive until the last reference is dropped.
+pub struct MountRef {
+    sb: Arc<SuperBlock>,
+    /// The per-mount [`MountFlags`].
+    flags: AtomicU32,
+    /// Number of files open for writing through this mount.
+    writers: AtomicUsize,
+}
+
+impl MountRef {
+    fn new(sb: Arc<SuperBlock>, flags: MountFlags) -> Arc<Self> {
+        Arc::new(Self {
+            sb,
+            flags: AtomicU32::new(flags.per_mount().bits()),
+            writers: AtomicUsize::new(0),
+        })
+    }
+
+    /// Device number of the mounted filesystem.
+    pub fn dev(&self) -> (u32, u32) {
+        self.sb.dev
+    }
+
+    /// The per-mount flags, see [`MountFlags::per_mount`].
+    pub fn flags(&self) -> MountFlags {
+        MountFlags::from_bits_truncate(self.flags.load(Ordering::SeqCst))
+    }
+
+    /// Change the per-mount flags. Making the mount read-only fails with
+    /// `ResourceBusy` while files are open for writing on it.
+    fn set_flags(&self, flags: MountFlags) -> AxResult {
+        let old = self.flags.swap(flags.per_mount().bits(), Ordering::SeqCst);
+        // pairs with `get_write`, which counts the writer before checking
+        if flags.contains(MountFlags::RDONLY) && self.writers.load(Ordering::SeqCst) > 0 {
+            self.flags.store(old, Ordering::SeqCst);
+            return ax_err!(ResourceBusy, "files are open for writing");
+        }
+        Ok(())
+    }
+
+    /// Count a file opened for writing, which keeps the mount from being
+    /// remounted read-only until [`put_write`](Self::put_write). Fails with
+    /// `PermissionDenied` if the mount is read-only.
+    pub(crate) fn get_write(&self) -> AxResult {
+        self.writers.fetch_add(1, Ordering::SeqCst);
+        if self.flags().contains(MountFlags::RDONLY) {
+            self.put_write();
+            return ax_err!(PermissionDenied, "read-only mount");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn put_write(&self) {
+        self.writers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 struct MountPoint {
-    path: &'static str,
+    id: usize,
+    parent: usize,
+    /// Absolute path where the filesystem is mounted.
+    path: String,
     fs: Arc<dyn VfsOps>,
+    /// Referenced by everything opened through this mount.
+    mnt: Arc<MountRef>,
+    /// Directory of the filesystem shown at `path` (not `/` for bind mounts).
+    root: String,
+    source: String,
+    fstype: String,
+    dev: (u32, u32),
+    /// Mounts on top of this one, in mount order.
+    children: Vec<usize>,
+}
+
+/// Information about a mount, as listed in `/proc/self/mountinfo`.
+#[derive(Debug, Clone)]
+pub struct MountInfo {
+    pub id: usize,
+    pub parent: usize,
+    pub dev: (u32, u32),
+    pub root: String,
+    pub path: String,
+    pub source: String,
+    pub fstype: String,
+    pub flags: MountFlags,
+}
+
+/// The mount tree, rooted at [`ROOT_MOUNT_ID`].
+struct MountTable {
+    mounts: BTreeMap<usize, MountPoint>,
+    next_id: usize,
 }
 
 pub struct RootDirectory {
     main_fs: Arc<dyn VfsOps>,
-    mounts: RwLock<Vec<MountPoint>>,
+    table: RwLock<MountTable>,
 }
 
 pub static ROOT_DIR: LazyInit<Arc<RootDirectory>> = LazyInit::new();
 
 pub static PROC_ROOT: LazyInit<Arc<ProcDir>> = LazyInit::new();
 
+/// Pseudo filesystems created at boot, shared by every later mount of the
+/// same type (like `proc` and `devtmpfs` on Linux).
+static SHARED_FS: RwLock<Vec<(&'static str, Arc<dyn VfsOps>)>> = RwLock::new(Vec::new());
+
+impl Drop for SuperBlock {
+    fn drop(&mut self) {
+        page_cache::forget_dev(self.dev);
+        self.fs.umount().ok();
+    }
+}
+
 impl MountPoint {
-    pub fn new(path: &'static str, fs: Arc<dyn VfsOps>) -> Self {
-        Self { path, fs }
+    fn info(&self) -> MountInfo {
+        MountInfo {
+            id: self.id,
+            parent: self.parent,
+            dev: self.dev,
+            root: self.root.clone(),
+            path: self.path.clone(),
+            source: self.source.clone(),
+            fstype: self.fstype.clone(),
+            flags: self.mnt.flags(),
+        }
     }
 }
 
-impl Drop for MountPoint {
-    fn drop(&mut self) {
-        self.fs.umount().ok();
+impl MountTable {
+    /// Walk down the mount tree along the canonical absolute `path`.
+    ///
+    /// Returns the mount the path ends up in and the remaining path inside
+    /// that filesystem (without leading `/`).
+    fn resolve<'a>(&self, path: &'a str) -> (usize, &'a str) {
+        let mut id = ROOT_MOUNT_ID;
+        let mut rest = path.trim_start_matches('/');
+        'walk: loop {
+            let mp = &self.mounts[&id];
+            // the latest mount wins if several are stacked on one directory
+            for child in mp.children.iter().rev().map(|c| &self.mounts[c]) {
+                let rel = child.path[mp.path.len()..].trim_start_matches('/');
+                if let Some(after) = rest.strip_prefix(rel) {
+                    if rel.is_empty() || after.is_empty() || after.starts_with('/') {
+                        id = child.id;
+                        rest = after.trim_start_matches('/');
+                        continue 'walk;
+                    }
+                }
+            }
+            return (id, rest);
+        }
+    }
+
+    fn insert(&mut self, mut mp: MountPoint) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        mp.id = id;
+        self.mounts.get_mut(&mp.parent).unwrap().children.push(id);
+        self.mounts.insert(id, mp);
+        id
+    }
+
+    /// Detach the mount `id` and everything mounted on top of it.
+    fn remove(&mut self, id: usize) -> Vec<MountPoint> {
+        let parent = self.mounts[&id].parent;
+        self.mounts
+            .get_mut(&parent)
+            .unwrap()
+            .children
+            .retain(|&c| c != id);
+        let mut removed = Vec::new();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let mp = self.mounts.remove(&id).unwrap();
+            stack.extend(mp.children.iter().copied());
+            removed.push(mp);
+        }
+        removed
+    }
+
+    /// Visit the mounts in tree order.
+    fn walk(&self, id: usize, f: &mut impl FnMut(&MountPoint)) {
+        let mp = &self.mounts[&id];
+        f(mp);
+        for &child in mp.children.iter() {
+            self.walk(child, f);
+        }
+    }
+}
+
+/// A bind mount of a subdirectory: exposes it as a filesystem root.
+struct BindFileSystem {
+    root: VfsNodeRef,
+}
+
+impl VfsOps for BindFileSystem {
+    fn root_dir(&self) -> VfsNodeRef {
+        self.root.clone()
     }
 }
 
 impl RootDirectory {
-    pub const fn new(main_fs: Arc<dyn VfsOps>) -> Self {
+    pub fn new(main_fs: Arc<dyn VfsOps>, source: &str, fstype: &str, dev: (u32, u32)) -> Self {
+        let root = MountPoint {
+            id: ROOT_MOUNT_ID,
+            parent: ROOT_MOUNT_ID,
+            path: "/".into(),
+            fs: main_fs.clone(),
+            mnt: MountRef::new(
+                Arc::new(SuperBlock {
+                    fs: main_fs.clone(),
+                    dev,
+                }),
+                MountFlags::empty(),
+            ),
+            root: "/".into(),
+            source: source.into(),
+            fstype: fstype.into(),
+            dev,
+            children: Vec::new(),
+        };
+        let mut mounts = BTreeMap::new();
+        mounts.insert(ROOT_MOUNT_ID, root);
         Self {
             main_fs,
-            mounts: RwLock::new(Vec::new()),
+            table: RwLock::new(MountTable {
+                mounts,
+                next_id: ROOT_MOUNT_ID + 1,
+            }),
+        }
+    }
+
+    /// Mount `fs` at `path`, creating the directory in the main filesystem
+    /// if it does not exist.
+    pub fn mount(&self, path: &str, fs: Arc<dyn VfsOps>) -> AxResult {
+        self.mount_builtin(path, fs, "none")
+    }
+
+    fn mount_builtin(&self, path: &str, fs: Arc<dyn VfsOps>, fstype: &str) -> AxResult {
+        if path.starts_with('/') && self.table.read().resolve(path).0 == ROOT_MOUNT_ID {
+            self.main_fs.root_dir().create(path, FileType::Dir)?;
         }
+        self.mount_fs(path, fs, fstype, fstype, self.anon_dev(), "/", MountFlags::empty())
     }
 
-    pub fn mount(&self, path: &'static str, fs: Arc<dyn VfsOps>) -> AxResult {
+    /// Allocate a device number for a filesystem without a block device.
+    fn anon_dev(&self) -> (u32, u32) {
+        (0, self.table.read().next_id as u32 + 20)
+    }
+
+    /// Mount `fs` at `path` on top of whatever is visible there.
+    ///
+    /// `source`, `fstype`, `dev`, `root` and `flags` are recorded for the
+    /// mount table.
+    #[allow(clippy::too_many_arguments)]
+    pub fn mount_fs(
+        &self,
+        path: &str,
+        fs: Arc<dyn VfsOps>,
+        source: &str,
+        fstype: &str,
+        dev: (u32, u32),
+        root: &str,
+        flags: MountFlags,
+    ) -> AxResult {
+        let sb = Arc::new(SuperBlock {
+            fs: fs.clone(),
+            dev,
+        });
+        self.insert_mount(path, fs, sb, source, fstype, root, flags)
+    }
+
+    /// Mount `fs`, a view of the filesystem `sb`, at `path`.
+    #[allow(clippy::too_many_arguments)]
+    fn insert_mount(
+        &self,
+        path: &str,
+        fs: Arc<dyn VfsOps>,
+        sb: Arc<SuperBlock>,
+        source: &str,
+        fstype: &str,
+        root: &str,
+        flags: MountFlags,
+    ) -> AxResult {
         if path == "/" {
             return ax_err!(InvalidInput, "cannot mount root filesystem");
         }
         if !path.starts_with('/') {
             return ax_err!(InvalidInput, "mount path must start with '/'");
         }
-        if self.mounts.read().iter().any(|mp| mp.path == path) {
-            return ax_err!(InvalidInput, "mount point already exists");
+        let path = canonicalize(path);
+        let mount_point =
+            self.lookup_mounted_fs(&path, |fs, rest| fs.root_dir().lookup(rest))?;
+        if !mount_point.get_attr()?.is_dir() {
+            return ax_err!(NotADirectory);
+        }
+        fs.mount(&path, mount_point)?;
+        let mut table = self.table.write();
+        let parent = table.resolve(&path).0;
+        table.insert(MountPoint {
+            id: 0,
+            parent,
+            path,
+            fs,
+            root: root.into(),
+            source: source.into(),
+            fstype: fstype.into(),
+            dev: sb.dev,
+            mnt: MountRef::new(sb, flags),
+            children: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Bind the directory `src` to `path`.
+    pub fn bind(&self, src: &str, path: &str, flags: MountFlags) -> AxResult {
+        let src = canonicalize(src);
+        let (fs, sb, root, source, fstype) = {
+            let table = self.table.read();
+            let (id, rest) = table.resolve(&src);
+            let mp = &table.mounts[&id];
+            let root = if rest.is_empty() {
+                mp.root.clone()
+            } else {
+                format!("{}/{}", mp.root.trim_end_matches('/'), rest)
+            };
+            let fs = if rest.is_empty() {
+                mp.fs.clone()
+            } else {
+                let root = mp.fs.root_dir().lookup(rest)?;
+                if !root.get_attr()?.is_dir() {
+                    return ax_err!(NotADirectory);
+                }
+                Arc::new(BindFileSystem { root }) as Arc<dyn VfsOps>
+            };
+            let sb = mp.mnt.sb.clone();
+            (fs, sb, root, mp.source.clone(), mp.fstype.clone())
+        };
+        self.insert_mount(path, fs, sb, &source, &fstype, &root, flags)
+    }
+
+    /// Change the per-mount flags of the filesystem mounted at `path`.
+    ///
+    /// Fails with `ResourceBusy` when making it read-only while files are
+    /// open for writing on it.
+    pub fn remount(&self, path: &str, flags: MountFlags) -> AxResult {
+        let path = canonicalize(path);
+        let table = self.table.write();
+        let (id, rest) = table.resolve(&path);
+        if !rest.is_empty() {
+            return ax_err!(InvalidInput, "not a mount point");
+        }
+        table.mounts[&id].mnt.set_flags(flags)
+    }
+
+    /// Unmount the topmost filesystem mounted at `path`.
+    ///
+    /// Fails with `ResourceBusy` if other filesystems are mounted on top of
+    /// it or anything is open on it, unless `detach` is set. A detached mount
+    /// takes the mounts on top of it along, and the filesystems are only
+    /// unmounted once the files open on them are closed.
+    pub fn umount(&self, path: &str, detach: bool) -> AxResult {
+        let path = canonicalize(path);
+        let mut table = self.table.write();
+        let (id, rest) = table.resolve(&path);
+        if !rest.is_empty() {
+            return ax_err!(InvalidInput, "not a mount point");
+        }
+        if id == ROOT_MOUNT_ID {
+            return ax_err!(ResourceBusy, "cannot unmount root filesystem");
+        }
+        if !detach {
+            let mp = &table.mounts[&id];
+            if !mp.children.is_empty() {
+                return ax_err!(ResourceBusy, "filesystems are mounted below");
+            }
+            // lookups take their references under the table lock
+            if Arc::strong_count(&mp.mnt) > 1 {
+                return ax_err!(ResourceBusy, "filesystem is in use");
+            }
         }
-        // create the mount point in the main filesystem if it does not exist
-        self.main_fs.root_dir().create(path, FileType::Dir)?;
-        fs.mount(path, self.main_fs.root_dir().lookup(path)?)?;
-        self.mounts.write().push(MountPoint::new(path, fs));
+        let removed = table.remove(id);
+        drop(table);
+        // `SuperBlock::drop` unmounts the filesystems no longer referenced
+        drop(removed);
         Ok(())
     }
 
-    pub fn _umount(&self, path: &str) {
-        self.mounts.write().retain(|mp| mp.path != path);
+    /// Whether the block device `source` backs any mounted filesystem.
+    pub fn source_busy(&self, source: &str) -> bool {
+        self.table
+            .read()
+            .mounts
+            .values()
+            .any(|mp| mp.source == source)
+    }
+
+    /// List all mounts in tree order.
+    pub fn mounts(&self) -> Vec<MountInfo> {
+        let table = self.table.read();
+        let mut infos = Vec::new();
+        table.walk(ROOT_MOUNT_ID, &mut |mp| infos.push(mp.info()));
+        infos
     }
 
     pub fn contains(&self, path: &str) -> bool {
-        self.mounts.read().iter().any(|mp| mp.path == path)
+        self.table
+            .read()
+            .mounts
+            .values()
+            .any(|mp| mp.id != ROOT_MOUNT_ID && mp.path == path)
     }
 
     fn lookup_mounted_fs<F, T>(&self, path: &str, f: F) -> AxResult<T>
     where
         F: FnOnce(Arc<dyn VfsOps>, &str) -> AxResult<T>,
     {
-        let path = path.trim_matches('/');
-        if let Some(rest) = path.strip_prefix("./") {
-            return self.lookup_mounted_fs(rest, f);
-        }
+        // `..` is resolved here, so it crosses mount boundaries upwards
+        let path = canonicalize(&format!("/{}", path));
+        let (fs, rest) = {
+            let table = self.table.read();
+            let (id, rest) = table.resolve(&path);
+            (table.mounts[&id].fs.clone(), rest.to_string())
+        };
+        trace!("find fs for {:?}, lookup: {}", path, rest);
+        f(fs, &rest)
+    }
 
-        let mut idx = 0;
-        let mut max_len = 0;
+    /// Look up `path`, also returning a reference to the mount the node is
+    /// on.
+    fn lookup_mount(&self, path: &str) -> AxResult<(VfsNodeRef, Arc<MountRef>)> {
+        let path = canonicalize(&format!("/{}", path));
+        let (fs, rest, mnt) = {
+            let table = self.table.read();
+            let (id, rest) = table.resolve(&path);
+            let mp = &table.mounts[&id];
+            (mp.fs.clone(), rest.to_string(), mp.mnt.clone())
+        };
+        Ok((fs.root_dir().lookup(&rest)?, mnt))
+    }
 
-        // Find the filesystem that has the longest mounted path match
-        // TODO: more efficient, e.g. trie
-        for (i, mp) in self.mounts.read().iter().enumerate() {
-            // skip the first '/'
-            if path.starts_with(&mp.path[1..]) && mp.path.len() - 1 > max_len {
-                max_len = mp.path.len() - 1;
-                idx = i;
-            }
-        }
+    fn root_dir() -> Arc<RootDirectory> {
+        ROOT_DIR.get().expect("ROOT_DIR not initialized").clone()
+    }
 
-        if max_len == 0 {
-            f(self.main_fs.clone(), path) // not matched any mount point
-        } else {
-            debug!(
-                "find fs at {:?}, lookup: {}",
-                &path[..max_len],
-                &path[max_len..]
-            );
-            f(self.mounts.read()[idx].fs.clone(), &path[max_len..]) // matched at `idx`
+    /// Create a hard link `dst` to `src`, both symlink-free absolute paths.
+    ///
+    /// Fails with [`ResolveError::CrossDevice`] if they are on different
+    /// mounts.
+    pub fn hard_link(&self, src: &str, dst: &str) -> Result<(), ResolveError> {
+        let table = self.table.read();
+        let (src_id, src_rest) = table.resolve(src);
+        let (dst_id, dst_rest) = table.resolve(dst);
+        if dst_rest.is_empty() {
+            return Err(AxError::AlreadyExists.into());
         }
+        if src_id != dst_id {
+            return Err(ResolveError::CrossDevice);
+        }
+        let fs = table.mounts[&src_id].fs.clone();
+        let (src_rest, dst_rest) = (src_rest.to_string(), dst_rest.to_string());
+        drop(table);
+        let node = fs.root_dir().lookup(&src_rest)?;
+        Ok(fs.root_dir().link(&dst_rest, node)?)
     }
 
-    fn root_dir() -> Arc<RootDirectory> {
-        ROOT_DIR.get().expect("ROOT_DIR not initialized").clone()
+    /// Get the id and filesystem type of the mount that contains the
+    /// canonical absolute `path`.
+    fn mount_of(&self, path: &str) -> (usize, String) {
+        let table = self.table.read();
+        let id = table.resolve(path).0;
+        (id, table.mounts[&id].fstype.clone())
     }
 
     pub fn find_mountpoint_and_fs(&self, path: &str) -> AxResult<(String, Arc<dyn VfsOps>)> {
         let path = axfs_vfs::path::canonicalize(path);
-        let mounts = self.mounts.read();
-
-        // 默认指向 main_fs，挂载点是 "/"
-        let mut best_match = "/";
-        let mut fs = self.main_fs.clone();
-
-        for mp in mounts.iter() {
-            if path == mp.path || path.starts_with(&(mp.path.to_owned() + "/")) {
-                if mp.path.len() > best_match.len() {
-                    best_match = mp.path;
-                    fs = mp.fs.clone();
-                }
-            }
-        }
-
-        Ok((best_match.to_string(), fs))
+        let table = self.table.read();
+        let mp = &table.mounts[&table.resolve(&path).0];
+        Ok((mp.path.clone(), mp.fs.clone()))
     }
 }
 
@@ -199,43 +704,96 @@ impl VfsNodeOps for RootDirectory {
         })
     }
 
-    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
-        self.lookup_mounted_fs(src_path, |fs, rest_path| {
+    fn rename(&self, src_path: &str, dst_path: &str, flags: RenameFlags) -> VfsResult {
+        let src_path = canonicalize(&format!("/{}", src_path));
+        let dst_path = canonicalize(&format!("/{}", dst_path));
+        let table = self.table.read();
+        let (src_id, src_rest) = table.resolve(&src_path);
+        let (dst_id, dst_rest) = table.resolve(&dst_path);
+        if src_rest.is_empty() || dst_rest.is_empty() {
+            return ax_err!(ResourceBusy); // cannot rename mount points
+        }
+        if src_id != dst_id {
+            return ax_err!(PermissionDenied, "rename across mount points");
+        }
+        // 路径中已没有符号链接，可以按前缀判断目录间的包含关系
+        if dst_rest.starts_with(&format!("{}/", src_rest)) {
+            return ax_err!(InvalidInput, "cannot move a directory into itself");
+        }
+        if src_rest.starts_with(&format!("{}/", dst_rest)) {
+            if flags.contains(RenameFlags::EXCHANGE) {
+                return ax_err!(InvalidInput, "cannot move a directory into itself");
+            }
+            return ax_err!(DirectoryNotEmpty);
+        }
+        let fs = table.mounts[&src_id].fs.clone();
+        let (src_rest, dst_rest) = (src_rest.to_string(), dst_rest.to_string());
+        drop(table);
+        fs.root_dir().rename(&src_rest, &dst_rest, flags)
+    }
+
+    fn symlink(&self, path: &str, target: &str) -> VfsResult {
+        self.lookup_mounted_fs(path, |fs, rest_path| {
             if rest_path.is_empty() {
-                ax_err!(PermissionDenied) // cannot rename mount points
+                ax_err!(AlreadyExists)
             } else {
-                fs.root_dir().rename(rest_path, dst_path)
+                fs.root_dir().symlink(rest_path, target)
+            }
+        })
+    }
+
+    fn mknod(&self, path: &str, ty: VfsNodeType, rdev: u64) -> VfsResult {
+        self.lookup_mounted_fs(path, |fs, rest_path| {
+            if rest_path.is_empty() {
+                ax_err!(AlreadyExists)
+            } else {
+                fs.root_dir().mknod(rest_path, ty, rdev)
             }
         })
     }
 }
 //disk: crate::dev::Disk
-pub(crate) fn init_rootfs(root_disk: crate::dev::Disk) {
+pub(crate) fn init_rootfs(root_name: &str, root_disk: crate::dev::Disk) {
+    let (major, minor) = root_disk.dev_t();
     cfg_if::cfg_if! {
         if #[cfg(feature = "myfs")] { // override the default filesystem
             let main_fs = fs::myfs::new_myfs(root_disk);
+            let fstype = "myfs";
         } else if #[cfg(feature = "lwext4_rs")] {
             static EXT4_FS: LazyInit<Arc<fs::lwext4_rust::Ext4FileSystem<Disk>>> = LazyInit::new();
             EXT4_FS.init_once(Arc::new(fs::lwext4_rust::Ext4FileSystem::new(root_disk, "root", "/")));
             let main_fs = EXT4_FS.clone();
-            // let dev_fs =  EXT4_FS.clone();
+            let fstype = "ext4";
         } else if #[cfg(feature = "fatfs")] {
             static FAT_FS: LazyInit<Arc<fs::fatfs::FatFileSystem>> = LazyInit::new();
-            FAT_FS.init_once(Arc::new(fs::fatfs::FatFileSystem::new(root_disk)));
-            FAT_FS.init();
+            FAT_FS.init_once(fs::fatfs::FatFileSystem::new(root_disk));
             let main_fs = FAT_FS.clone();
+            let fstype = "vfat";
         }
     }
 
-    let root_dir = RootDirectory::new(main_fs);
+    let root_dir = RootDirectory::new(
+        main_fs,
+        &format!("/dev/{}", root_name),
+        fstype,
+        (major as u32, minor as u32),
+    );
 
     #[cfg(feature = "devfs")]
+    {
+        let devfs = mounts::devfs();
+        root_dir
+            .mount_builtin("/dev", devfs.clone(), "devtmpfs")
+            .expect("failed to mount devfs at /dev");
+        SHARED_FS.write().push(("devtmpfs", devfs as Arc<dyn VfsOps>));
+    }
+    #[cfg(feature = "tmpfs")]
     root_dir
-        .mount("/dev", mounts::devfs())
-        .expect("failed to mount devfs at /dev");
-    #[cfg(feature = "ramfs")]
+        .mount_builtin("/tmp", mounts::tmpfs("").unwrap(), "tmpfs")
+        .expect("failed to mount tmpfs at /tmp");
+    #[cfg(all(feature = "ramfs", not(feature = "tmpfs")))]
     root_dir
-        .mount("/tmp", mounts::ramfs())
+        .mount_builtin("/tmp", mounts::ramfs(), "tmpfs")
         .expect("failed to mount ramfs at /tmp");
 
     // Mount another ramfs as procfs
@@ -243,28 +801,38 @@ pub(crate) fn init_rootfs(root_disk: crate::dev::Disk) {
     {
         let proc_root = mounts::procfs().unwrap();
         root_dir // should not fail
-            .mount("/proc", proc_root.clone())
+            .mount_builtin("/proc", proc_root.clone(), "proc")
             .expect("fail to mount procfs at /proc");
         PROC_ROOT.init_once(proc_root.root_dir_node());
+        SHARED_FS.write().push(("proc", proc_root as Arc<dyn VfsOps>));
     }
 
     // Mount another ramfs as sysfs
     #[cfg(feature = "sysfs")]
-    root_dir // should not fail
-        .mount("/sys", mounts::sysfs().unwrap())
-        .expect("fail to mount sysfs at /sys");
+    {
+        let sysfs = mounts::sysfs().unwrap();
+        root_dir // should not fail
+            .mount_builtin("/sys", sysfs.clone(), "sysfs")
+            .expect("fail to mount sysfs at /sys");
+        SHARED_FS.write().push(("sysfs", sysfs as Arc<dyn VfsOps>));
+    }
 
     ROOT_DIR.init_once(Arc::new(root_dir));
     info!("rootfs initialized");
     CURRENT_DIR.init_new(Mutex::new(ROOT_DIR.clone()));
     CURRENT_DIR_PATH.init_new(Mutex::new("/".into()));
+    CURRENT_DIR_MOUNT.init_new(Mutex::new(None));
 }
 
-fn parent_node_of(dir: Option<&VfsNodeRef>, path: &str) -> VfsNodeRef {
-    if path.starts_with('/') {
-        ROOT_DIR.clone()
-    } else {
-        dir.cloned().unwrap_or_else(|| CURRENT_DIR.lock().clone())
+/// Pick the node to resolve `path` from, and the path relative to it.
+///
+/// Paths relative to the current directory are made absolute and resolved
+/// from the root, so that they cross mount points (also upwards through `..`)
+/// the same way absolute paths do.
+fn parent_node_of(dir: Option<&VfsNodeRef>, path: &str) -> (VfsNodeRef, String) {
+    match dir {
+        Some(dir) if !path.starts_with('/') => (dir.clone(), path.into()),
+        _ => (ROOT_DIR.clone(), absolute_path(path).unwrap_or_default()),
     }
 }
 
@@ -277,46 +845,268 @@ pub(crate) fn absolute_path(path: &str) -> AxResult<String> {
     }
 }
 
-pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+/// Maximum number of symbolic links followed while resolving one path.
+const MAX_SYMLINKS: usize = 40;
+
+fn read_link_node(node: &VfsNodeRef) -> AxResult<String> {
+    let mut buf = vec![0; 4096];
+    let len = node.readlink(&mut buf)?;
+    buf.truncate(len);
+    String::from_utf8(buf).map_err(|_| AxError::InvalidData)
+}
+
+/// Replace the symbolic links in the absolute `path` by their targets,
+/// walking it component by component. The last component is only followed
+/// if `follow_last` is set.
+fn resolve_symlinks(path: &str, follow_last: bool) -> AxResult<String> {
+    Ok(resolve_path("/", path, ResolveFlags::empty(), follow_last)?.path)
+}
+
+/// Walk `path` from the directory `start` (an absolute path without symbolic
+/// links) component by component, replacing symbolic links by their targets,
+/// with the restrictions of `resolve`. Returns the absolute path reached,
+/// along with the node it refers to.
+///
+/// `..` goes up from the path walked so far, after the links in it have been
+/// replaced, so links can not be used to escape `start` under `BENEATH` or
+/// `IN_ROOT`. The last component is only followed if `follow_last` is set,
+/// and may not exist (to be created).
+pub(crate) fn resolve_path(
+    start: &str,
+    path: &str,
+    resolve: ResolveFlags,
+    follow_last: bool,
+) -> Result<ResolvedPath, ResolveError> {
+    let confined = resolve.intersects(ResolveFlags::BENEATH | ResolveFlags::IN_ROOT);
+    let root = if confined { canonicalize(start) } else { "/".into() };
+    let mut resolved = canonicalize(start);
+    // components still to walk, in reverse order so links can push their targets
+    let mut pending: Vec<String> = Vec::new();
+    let mut hops = 0;
+
+    // 绝对路径（包括符号链接的绝对目标）从根目录开始，返回是否跳转
+    let jump = |resolved: &mut String, target: &str| -> Result<bool, ResolveError> {
+        if !target.starts_with('/') {
+            return Ok(false);
+        }
+        if resolve.contains(ResolveFlags::BENEATH) {
+            return Err(ResolveError::CrossDevice);
+        }
+        if resolve.contains(ResolveFlags::NO_XDEV)
+            && ROOT_DIR.mount_of(resolved).0 != ROOT_DIR.mount_of(&root).0
+        {
+            return Err(ResolveError::CrossDevice);
+        }
+        *resolved = root.clone();
+        Ok(true)
+    };
+    let push = |pending: &mut Vec<String>, path: &str| {
+        pending.extend(
+            path.rsplit('/')
+                .filter(|c| !c.is_empty() && *c != ".")
+                .map(String::from),
+        );
+    };
+
+    jump(&mut resolved, path)?;
+    push(&mut pending, path);
+    // `resolved` 对应的节点及其所在挂载
+    let (mut node, mut mount) = ROOT_DIR.lookup_mount(&resolved)?;
+    while let Some(comp) = pending.pop() {
+        let last = pending.is_empty();
+        if comp == ".." {
+            if resolved == root {
+                // 在根目录处 `..` 停留在原地，BENEATH 时不允许越过起始目录
+                if resolve.contains(ResolveFlags::BENEATH) {
+                    return Err(ResolveError::CrossDevice);
+                }
+                continue;
+            }
+            let parent = match resolved.rfind('/') {
+                Some(0) | None => "/".into(),
+                Some(pos) => resolved[..pos].to_string(),
+            };
+            if resolve.contains(ResolveFlags::NO_XDEV)
+                && ROOT_DIR.mount_of(&parent).0 != ROOT_DIR.mount_of(&resolved).0
+            {
+                return Err(ResolveError::CrossDevice);
+            }
+            (node, mount) = ROOT_DIR.lookup_mount(&parent)?;
+            resolved = parent;
+            continue;
+        }
+
+        let cur = format!("{}/{}", resolved.trim_end_matches('/'), comp);
+        let (next, next_mount) = match ROOT_DIR.lookup_mount(&cur) {
+            Ok(found) => found,
+            Err(AxError::NotFound) if last => {
+                return Ok(ResolvedPath {
+                    path: cur,
+                    target: Resolved::Missing { parent: node, name: comp },
+                    mount,
+                });
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if resolve.contains(ResolveFlags::NO_XDEV)
+            && ROOT_DIR.mount_of(&cur).0 != ROOT_DIR.mount_of(&resolved).0
+        {
+            return Err(ResolveError::CrossDevice);
+        }
+        if (!last || follow_last) && next.get_attr()?.file_type().is_symlink() {
+            if resolve.contains(ResolveFlags::NO_SYMLINKS)
+                || (resolve.contains(ResolveFlags::NO_MAGICLINKS)
+                    && ROOT_DIR.mount_of(&cur).1 == "proc")
+            {
+                return Err(ResolveError::Loop);
+            }
+            hops += 1;
+            if hops > MAX_SYMLINKS {
+                return Err(ResolveError::Loop);
+            }
+            let target = read_link_node(&next)?;
+            if jump(&mut resolved, &target)? {
+                (node, mount) = ROOT_DIR.lookup_mount(&resolved)?;
+            }
+            push(&mut pending, &target);
+            continue;
+        }
+        (node, mount) = (next, next_mount);
+        resolved = cur;
+    }
+    Ok(ResolvedPath {
+        path: resolved,
+        target: Resolved::Node(node),
+        mount,
+    })
+}
+
+/// Resolve the symbolic links in the directory part of `path`, keeping the
+/// last component as is.
+fn resolve_parent(path: &str) -> AxResult<String> {
+    let path = absolute_path(path)?;
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", &path));
+    let dir = resolve_symlinks(if dir.is_empty() { "/" } else { dir }, true)?;
+    Ok(format!("{}/{}", dir.trim_end_matches('/'), name))
+}
+
+/// Look up `path`, following a symbolic link in the last component only if
+/// `follow` is set.
+///
+/// Also returns a reference to the mount the node is on, or `None` if it was
+/// looked up relative to the directory node `dir`.
+fn lookup_node(
+    dir: Option<&VfsNodeRef>,
+    path: &str,
+    follow: bool,
+) -> AxResult<(VfsNodeRef, Option<Arc<MountRef>>)> {
     if path.is_empty() {
         return ax_err!(NotFound);
     }
-    let node = parent_node_of(dir, path).lookup(path)?;
+    let via_root = dir.is_none() || path.starts_with('/');
+    let (parent, rel) = parent_node_of(dir, path);
+    let found = if via_root {
+        ROOT_DIR.lookup_mount(&rel).map(|(node, mnt)| (node, Some(mnt)))
+    } else {
+        parent.lookup(&rel).map(|node| (node, None))
+    };
+    match found {
+        Ok((node, _)) if follow && via_root && node.get_attr()?.file_type().is_symlink() => {}
+        Err(AxError::NotFound | AxError::NotADirectory) if via_root => {}
+        res => return res,
+    }
+    // the path runs through symbolic links, walk it the slow way
+    let path = if follow {
+        resolve_symlinks(&absolute_path(path)?, true)?
+    } else {
+        resolve_parent(path)?
+    };
+    let (node, mnt) = ROOT_DIR.lookup_mount(&path)?;
+    Ok((node, Some(mnt)))
+}
+
+/// Look up `path` without following a symbolic link in the last component.
+pub(crate) fn lookup_link(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+    lookup_node(dir, path, false).map(|(node, _)| node)
+}
+
+fn lookup_follow(
+    dir: Option<&VfsNodeRef>,
+    path: &str,
+) -> AxResult<(VfsNodeRef, Option<Arc<MountRef>>)> {
+    let (node, mnt) = lookup_node(dir, path, true)?;
     if path.ends_with('/') && !node.get_attr()?.is_dir() {
         ax_err!(NotADirectory)
     } else {
-        Ok(node)
+        Ok((node, mnt))
     }
 }
 
+pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+    lookup_follow(dir, path).map(|(node, _)| node)
+}
+
+/// Look up `path` like [`lookup`], also returning a reference to the mount
+/// the node is on.
+pub(crate) fn lookup_with_mount(path: &str) -> AxResult<(VfsNodeRef, Arc<MountRef>)> {
+    let (node, mnt) = lookup_follow(None, path)?;
+    Ok((node, mnt.expect("looked up through the mount tree")))
+}
+
 pub(crate) fn create_file(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
     if path.is_empty() {
         return ax_err!(NotFound);
     } else if path.ends_with('/') {
         return ax_err!(NotADirectory);
     }
-    let parent = parent_node_of(dir, path);
-    parent.create(path, VfsNodeType::File)?;
-    parent.lookup(path)
+    let (parent, rel) = parent_node_of(dir, path);
+    match parent.create(&rel, VfsNodeType::File) {
+        Err(AxError::NotFound | AxError::NotADirectory) if dir.is_none() || path.starts_with('/') => {
+            let path = resolve_parent(path)?;
+            ROOT_DIR.create(&path, VfsNodeType::File)?;
+            ROOT_DIR.clone().lookup(&path)
+        }
+        res => {
+            res?;
+            parent.lookup(&rel)
+        }
+    }
 }
 
 pub(crate) fn create_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
     match lookup(dir, path) {
         Ok(_) => ax_err!(AlreadyExists),
-        Err(AxError::NotFound) => parent_node_of(dir, path).create(path, VfsNodeType::Dir),
+        Err(AxError::NotFound) => {
+            let (parent, rel) = parent_node_of(dir, path);
+            match parent.create(&rel, VfsNodeType::Dir) {
+                Err(AxError::NotFound | AxError::NotADirectory)
+                    if dir.is_none() || path.starts_with('/') =>
+                {
+                    ROOT_DIR.create(&resolve_parent(path)?, VfsNodeType::Dir)
+                }
+                res => res,
+            }
+        }
         Err(e) => Err(e),
     }
 }
 
-pub(crate) fn remove_file(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
-    let node = lookup(dir, path)?;
+pub(crate) fn remove_file(path: &str) -> AxResult {
+    let (node, mnt) = lookup_node(None, path, false)?;
+    let mnt = mnt.expect("looked up through the mount tree");
     let attr = node.get_attr()?;
     if attr.is_dir() {
         ax_err!(IsADirectory)
     } else if !attr.perm().owner_writable() {
         ax_err!(PermissionDenied)
     } else {
-        parent_node_of(dir, path).remove(path)
+        let (parent, rel) = parent_node_of(None, path);
+        parent.remove(&rel)?;
+        // the inode number may be reused once the last link is gone
+        if let Some(key) = InodeKey::new(mnt.dev(), &attr).filter(|_| attr.nlink() <= 1) {
+            page_cache::invalidate(key);
+        }
+        Ok(())
     }
 }
 
@@ -334,8 +1124,8 @@ pub(crate) fn remove_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
     {
         return ax_err!(InvalidInput);
     }
-    if ROOT_DIR.contains(&absolute_path(path)?) {
-        return ax_err!(PermissionDenied);
+    if dir.is_none() && ROOT_DIR.contains(&absolute_path(path)?) {
+        return ax_err!(ResourceBusy);
     }
 
     let node = lookup(dir, path)?;
@@ -345,7 +1135,8 @@ pub(crate) fn remove_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
     } else if !attr.perm().owner_writable() {
         ax_err!(PermissionDenied)
     } else {
-        parent_node_of(dir, path).remove(path)
+        let (parent, rel) = parent_node_of(dir, path);
+        parent.remove(&rel)
     }
 }
 
@@ -361,10 +1152,11 @@ pub(crate) fn set_current_dir(path: &str) -> AxResult {
     if abs_path == "/" {
         *CURRENT_DIR.lock() = ROOT_DIR.clone();
         *CURRENT_DIR_PATH.lock() = "/".into();
+        *CURRENT_DIR_MOUNT.lock() = None;
         return Ok(());
     }
 
-    let node = lookup(None, &abs_path)?;
+    let (node, mnt) = lookup_with_mount(&abs_path)?;
     let attr = node.get_attr()?;
     if !attr.is_dir() {
         ax_err!(NotADirectory)
@@ -373,73 +1165,268 @@ pub(crate) fn set_current_dir(path: &str) -> AxResult {
     } else {
         *CURRENT_DIR.lock() = node;
         *CURRENT_DIR_PATH.lock() = abs_path;
+        // the current directory keeps its mount busy
+        *CURRENT_DIR_MOUNT.lock() = Some(mnt);
         Ok(())
     }
 }
 
-pub(crate) fn rename(old: &str, new: &str) -> AxResult {
-    if parent_node_of(None, new).lookup(new).is_ok() {
-        warn!("dst file already exist, now remove it");
-        remove_file(None, new)?;
-    }
-    parent_node_of(None, old).rename(old, new)
-}
-
-// pub fn mount(source: &'static str, target: &'static str, flags: usize) -> AxResult {
-//     let img = crate::api::File::open(source)?;
-//     warn!("mounting {} to {}", source, target);
-//     let fs = fs::lwext4_rust::Ext4FileSystem::new(img);
-//
-//     ROOT_DIR.mount(&target, Arc::new(fs));
-//     Ok(())
-// }
-
-// pub fn mount_dev(_path: &str, mnt: &str, fstype: &str) -> AxResult
-// {
-//     //TODO:cheack _path availble
-//     if _path.is_empty() || !_path.starts_with("/dev/") {
-//         return ax_err!(NotFound);
-//     }
-//
-//     if !mnt.starts_with('/') || mnt.len() < 2 {
-//         return ax_err!(InvalidInput);
-//     }
-//     //TODO:fix lookup the mounted fs
-//     let root = ROOT_DIR.clone();
-//     let devfs = lookup_mounted_fs(root,"/dev");
-//     let dev_path = _path.strip_prefix("/dev/").ok_or(AxError::NotFound)?;
-//     let dev = devfs.root_dir().lookup(dev_path)?;
-//     let disk = Disk::new(dev.get_dev());
-//
-//     let fs:Arc<dyn VfsOps>=match fstype {
-//         "ext4" => Arc::new(Ext4FileSystem::new(disk)),
-//         "vfat" => Arc::new(FatFileSystem::new(disk)),
-//         _ => return ax_err!(UnsupportedFs),
-//     };
-//     //TODO:cheack mnt availble
-//     ROOT_DIR.mount(mnt, fs)?;
-//     Ok(())
-// }
-// pub fn umount_dev(_path: &str) -> AxResult
-// {
-//     //TODO:check _path and drop the mounted fs
-//     ROOT_DIR._umount(_path);
-//     Ok(())
-// }
-
-// fn mount_device(dev_path: &str, mount_path: &str, fstype: &str) -> Result<()> {
-//     let root  = ROOT_DIR.clone();
-//     let dev_node = root.lookup(dev_path)?;
-//     let dev_file = dev_node.clone();
-//
-//     let disk = dev_file.inner.lock().clone(); // 需要 Disk 实现 Clone，或重新构造
-//
-//     let fs: Arc<dyn VfsOps> = match fstype {
-//         "ext4" => Arc::new(Ext4FileSystem::new(disk)),
-//         // 其他文件系统……
-//         _ => return Err(err_invalid()),
-//     };
-//
-//     ROOT_DIR.mount(mount_path, fs)?;
-//     Ok(())
-// }
+/// Rename `old` to `new` with the `renameat2(2)` `flags`, replacing `new` if
+/// it exists and the flags allow it. The replacement is not atomic on every
+/// filesystem, see [`VfsNodeOps::rename`].
+///
+/// Fails with [`ResolveError::CrossDevice`] if the directories of `old` and
+/// `new` are on different mounts.
+pub(crate) fn rename(old: &str, new: &str, flags: RenameFlags) -> Result<(), ResolveError> {
+    let old = resolve_parent(old)?;
+    let new = resolve_parent(new)?;
+    let mount_of_dir = |path: &str| {
+        let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        ROOT_DIR.mount_of(if dir.is_empty() { "/" } else { dir }).0
+    };
+    if mount_of_dir(&old) != mount_of_dir(&new) {
+        return Err(ResolveError::CrossDevice);
+    }
+    ROOT_DIR.rename(&old, &new, flags)?;
+    move_dir_paths(&old, &new, flags.contains(RenameFlags::EXCHANGE));
+    Ok(())
+}
+
+/// Absolute paths of the open directories, see [`track_dir_path`].
+static DIR_PATHS: spin::Mutex<Vec<Weak<spin::Mutex<String>>>> = spin::Mutex::new(Vec::new());
+
+/// Track the absolute `path` (without symbolic links) of an open directory,
+/// so that it follows the directory when the directory or one above it is
+/// renamed.
+pub(crate) fn track_dir_path(path: &str) -> Arc<spin::Mutex<String>> {
+    let path = match path.trim_end_matches('/') {
+        "" => "/",
+        path => path,
+    };
+    let path = Arc::new(spin::Mutex::new(path.to_string()));
+    let mut paths = DIR_PATHS.lock();
+    paths.retain(|path| path.strong_count() > 0);
+    paths.push(Arc::downgrade(&path));
+    path
+}
+
+/// Move the tracked directory paths at or below `old` to `new`, and the ones
+/// at or below `new` to `old` if the two were exchanged.
+fn move_dir_paths(old: &str, new: &str, exchange: bool) {
+    let (old, new) = (old.trim_end_matches('/'), new.trim_end_matches('/'));
+    let moved = |path: &str, from: &str, to: &str| {
+        let rest = path.strip_prefix(from)?;
+        (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", to, rest))
+    };
+    for path in DIR_PATHS.lock().iter().filter_map(Weak::upgrade) {
+        let mut path = path.lock();
+        let renamed = match moved(&path, old, new) {
+            None if exchange => moved(&path, new, old),
+            renamed => renamed,
+        };
+        if let Some(renamed) = renamed {
+            *path = renamed;
+        }
+    }
+}
+
+/// Escape whitespace and backslashes in a mount table field, as Linux does.
+fn escape_mount_field(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            ' ' => out.push_str("\\040"),
+            '\t' => out.push_str("\\011"),
+            '\n' => out.push_str("\\012"),
+            '\\' => out.push_str("\\134"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Per-mount options in the form used by the mount tables.
+fn mount_options(flags: MountFlags) -> String {
+    let mut opts = String::from(if flags.contains(MountFlags::RDONLY) { "ro" } else { "rw" });
+    for (flag, name) in [
+        (MountFlags::NOSUID, "nosuid"),
+        (MountFlags::NODEV, "nodev"),
+        (MountFlags::NOEXEC, "noexec"),
+    ] {
+        if flags.contains(flag) {
+            opts.push(',');
+            opts.push_str(name);
+        }
+    }
+    opts.push_str(if flags.contains(MountFlags::NOATIME) { ",noatime" } else { ",relatime" });
+    opts
+}
+
+/// Contents of `/proc/mounts`.
+pub(crate) fn proc_mounts() -> String {
+    let mut out = String::new();
+    for mp in ROOT_DIR.mounts() {
+        out += &format!(
+            "{} {} {} {} 0 0\n",
+            escape_mount_field(&mp.source),
+            escape_mount_field(&mp.path),
+            mp.fstype,
+            mount_options(mp.flags)
+        );
+    }
+    out
+}
+
+/// Contents of `/proc/self/mountinfo`.
+pub(crate) fn proc_mountinfo() -> String {
+    let mut out = String::new();
+    for mp in ROOT_DIR.mounts() {
+        let super_opts = if mp.flags.contains(MountFlags::RDONLY) { "ro" } else { "rw" };
+        out += &format!(
+            "{} {} {}:{} {} {} {} - {} {} {}\n",
+            mp.id,
+            mp.parent,
+            mp.dev.0,
+            mp.dev.1,
+            escape_mount_field(&mp.root),
+            escape_mount_field(&mp.path),
+            mount_options(mp.flags),
+            mp.fstype,
+            escape_mount_field(&mp.source),
+            super_opts
+        );
+    }
+    out
+}
+
+/// Create a hard link `new` to the file `old`.
+///
+/// Fails with [`ResolveError::CrossDevice`] if they are on different mounts.
+pub(crate) fn link(old: &str, new: &str, follow: bool) -> Result<(), ResolveError> {
+    let old = resolve_symlinks(&absolute_path(old)?, follow)?;
+    ROOT_DIR.hard_link(&old, &resolve_parent(new)?)
+}
+
+/// Create a symbolic link `path` pointing to `target`.
+pub(crate) fn symlink(target: &str, path: &str) -> AxResult {
+    if target.is_empty() {
+        return ax_err!(NotFound);
+    }
+    ROOT_DIR.symlink(&resolve_parent(path)?, target)
+}
+
+/// Create a special file `path` of type `ty`, with the device number `rdev`
+/// if it is a device node.
+pub(crate) fn mknod(path: &str, ty: VfsNodeType, rdev: u64) -> AxResult {
+    if path.ends_with('/') {
+        return ax_err!(NotFound);
+    }
+    ROOT_DIR.mknod(&resolve_parent(path)?, ty, rdev)
+}
+
+/// Read the target of the symbolic link `path`.
+pub(crate) fn read_link(path: &str) -> AxResult<String> {
+    read_link_node(&lookup_link(None, path)?)
+}
+
+/// Filesystem types accepted by [`mount`].
+pub(crate) fn filesystems() -> Vec<&'static str> {
+    let mut types = Vec::new();
+    #[cfg(feature = "lwext4_rs")]
+    types.extend(["ext4", "ext3", "ext2"]);
+    #[cfg(feature = "fatfs")]
+    types.extend(["vfat", "msdos"]);
+    #[cfg(any(feature = "tmpfs", feature = "ramfs"))]
+    types.push("tmpfs");
+    types.extend(SHARED_FS.read().iter().map(|(ty, _)| *ty));
+    types
+}
+
+/// Open the block device named by `source`, e.g. `/dev/vdb` or `/dev/vda2`.
+fn open_disk(source: &str) -> AxResult<Disk> {
+    let name = source.strip_prefix("/dev/").ok_or(AxError::NotFound)?;
+    DISKS.lock().get(name).cloned().ok_or(AxError::NotFound)
+}
+
+/// Create a filesystem for [`mount`], along with its device number if it is
+/// backed by a block device.
+fn new_filesystem(
+    source: &str,
+    fstype: &str,
+    data: &str,
+) -> AxResult<(Arc<dyn VfsOps>, Option<(u32, u32)>)> {
+    if let Some((_, fs)) = SHARED_FS.read().iter().find(|(ty, _)| *ty == fstype) {
+        return Ok((fs.clone(), None));
+    }
+    match fstype {
+        #[cfg(feature = "tmpfs")]
+        "tmpfs" => Ok((mounts::tmpfs(data)?, None)),
+        #[cfg(all(feature = "ramfs", not(feature = "tmpfs")))]
+        "tmpfs" => Ok((mounts::ramfs(), None)),
+        #[cfg(feature = "lwext4_rs")]
+        "ext4" | "ext3" | "ext2" => {
+            let mut disk = open_disk(source)?;
+            if !fs::lwext4_rust::probe(&mut disk) {
+                return ax_err!(InvalidData, "not an ext2/3/4 filesystem");
+            }
+            // lwext4 resolves files by absolute path under its own mount point,
+            // which has to be unique and shorter than 32 bytes, so it is not
+            // the target path but a short name of its own
+            static NEXT_EXT4_ID: AtomicUsize = AtomicUsize::new(1);
+            let mount_point = format!("/ext4-{}/", NEXT_EXT4_ID.fetch_add(1, Ordering::Relaxed));
+            let (major, minor) = disk.dev_t();
+            let name = source.trim_start_matches("/dev/");
+            let fs = Arc::new(Ext4FileSystem::try_new(disk, name, &mount_point)?);
+            Ok((fs, Some((major as u32, minor as u32))))
+        }
+        #[cfg(feature = "fatfs")]
+        "vfat" | "msdos" => {
+            let disk = open_disk(source)?;
+            let (major, minor) = disk.dev_t();
+            let fs = FatFileSystem::try_new(disk)?;
+            Ok((fs, Some((major as u32, minor as u32))))
+        }
+        _ => ax_err!(Unsupported, "unknown filesystem type"),
+    }
+}
+
+/// Mount a filesystem, see `mount(2)`.
+///
+/// `source` is a block device path for disk filesystems, the source directory
+/// for `MountFlags::BIND`, and ignored otherwise. `data` holds the
+/// filesystem specific options.
+pub(crate) fn mount(
+    source: &str,
+    target: &str,
+    fstype: &str,
+    flags: MountFlags,
+    data: &str,
+) -> AxResult {
+    let target = absolute_path(target)?;
+    if flags.contains(MountFlags::REMOUNT) {
+        return ROOT_DIR.remount(&target, flags);
+    }
+    if flags.contains(MountFlags::BIND) {
+        return ROOT_DIR.bind(&absolute_path(source)?, &target, flags);
+    }
+    let node = lookup(None, &target)?;
+    if !node.get_attr()?.is_dir() {
+        return ax_err!(NotADirectory);
+    }
+    if source.starts_with("/dev/") && ROOT_DIR.source_busy(source) {
+        return ax_err!(ResourceBusy, "device is already mounted");
+    }
+    let (fs, dev) = new_filesystem(source, fstype, data)?;
+    let dev = dev.unwrap_or_else(|| ROOT_DIR.anon_dev());
+    let source = if source.is_empty() { fstype } else { source };
+    ROOT_DIR.mount_fs(&target, fs, source, fstype, dev, "/", flags)
+}
+
+/// Unmount the filesystem mounted at `target`, see `umount2(2)`.
+///
+/// Without `detach`, a mount with files open or a working directory on it
+/// (in any process) is busy.
+pub(crate) fn umount(target: &str, detach: bool) -> AxResult {
+    let target = absolute_path(target)?;
+    ROOT_DIR.umount(&target, detach)
+}
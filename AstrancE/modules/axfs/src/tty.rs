@@ -0,0 +1,91 @@
+//! `/dev/tty`, `/dev/console` and the serial ports, all on the console.
+
+use axfs_vfs::{
+    VfsError, VfsNodeAttr, VfsNodeAttrX, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult,
+};
+
+/// How often a blocked read polls the console, which raises no interrupt on
+/// input.
+#[cfg(feature = "multitask")]
+const POLL_INTERVAL: core::time::Duration = core::time::Duration::from_millis(10);
+
+/// A terminal device on the console of `axhal`.
+///
+/// Reads block until some input arrives, and return what is available then,
+/// with `\r` turned into `\n`. A read interrupted by
+/// [`axtask::interrupt_task`] before any input fails with
+/// [`BadState`](VfsError::BadState), as there is no `EINTR` in `VfsError`.
+pub(crate) struct TtyDev;
+
+impl VfsNodeOps for TtyDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            0,
+            VfsNodePerm::from_bits_truncate(0o620),
+            VfsNodeType::CharDevice,
+            0,
+            0,
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ))
+    }
+
+    fn get_attr_x(&self) -> VfsResult<VfsNodeAttrX> {
+        Ok(VfsNodeAttrX::new(
+            0,0,0,1,0,0,
+            VfsNodePerm::from_bits_truncate(0o620),
+            VfsNodeType::CharDevice,
+            0,0,
+            0,0,
+            0,0,0,0,
+            0,0,0, 0,
+            0,0,0,0,
+        ))
+    }
+
+    fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let len = axhal::console::read_bytes(buf);
+            if len > 0 {
+                for c in &mut buf[..len] {
+                    if *c == b'\r' {
+                        *c = b'\n';
+                    }
+                }
+                return Ok(len);
+            }
+            #[cfg(feature = "multitask")]
+            {
+                let deadline = axhal::time::wall_time() + POLL_INTERVAL;
+                if axtask::sleep_until_interruptible(deadline).is_err() {
+                    return Err(VfsError::BadState);
+                }
+            }
+            #[cfg(not(feature = "multitask"))]
+            core::hint::spin_loop();
+        }
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        axhal::console::write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
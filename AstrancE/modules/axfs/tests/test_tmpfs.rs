@@ -0,0 +1,214 @@
+#![cfg(feature = "tmpfs")]
+
+use std::alloc::{Layout, alloc};
+use std::sync::{Arc, Once};
+
+use axfs::fs::tmpfs::TmpFileSystem;
+use axfs_vfs::{RenameFlags, VfsDirEntry, VfsError, VfsNodeRef, VfsNodeType, VfsOps};
+
+const PAGE_SIZE: usize = 4096;
+
+/// File pages come from the global page allocator, give it some memory.
+fn init_allocator() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        const HEAP_SIZE: usize = 4 << 20;
+        let heap = unsafe { alloc(Layout::from_size_align(HEAP_SIZE, PAGE_SIZE).unwrap()) };
+        axalloc::global_init(heap as usize, HEAP_SIZE);
+    });
+}
+
+fn new_tmpfs(options: &str) -> TmpFileSystem {
+    init_allocator();
+    TmpFileSystem::new(options).unwrap()
+}
+
+fn entries(dir: &VfsNodeRef) -> Vec<String> {
+    let mut dirents = [const { VfsDirEntry::default() }; 4];
+    let mut names = Vec::new();
+    loop {
+        let n = dir.read_dir(names.len(), &mut dirents).unwrap();
+        if n == 0 {
+            return names;
+        }
+        for ent in &dirents[..n] {
+            names.push(String::from_utf8(ent.name_as_bytes().to_vec()).unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_create_unlink() {
+    let tmpfs = new_tmpfs("");
+    let root = tmpfs.root_dir();
+    assert_eq!(root.create("f1", VfsNodeType::File), Ok(()));
+    assert_eq!(root.create("dir", VfsNodeType::Dir), Ok(()));
+    assert_eq!(root.create("dir/f2", VfsNodeType::File), Ok(()));
+    assert_eq!(
+        root.create("f1", VfsNodeType::File).err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(
+        root.create("f1/f3", VfsNodeType::File).err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.create("none/f3", VfsNodeType::File).err(),
+        Some(VfsError::NotFound)
+    );
+
+    let f1 = root.clone().lookup("f1").unwrap();
+    assert_eq!(f1.get_attr().unwrap().file_type(), VfsNodeType::File);
+    assert_eq!(f1.write_at(0, b"hello"), Ok(5));
+    let dir = root.clone().lookup("./dir/").unwrap();
+    assert!(dir.get_attr().unwrap().is_dir());
+    assert!(Arc::ptr_eq(&dir.parent().unwrap(), &root));
+
+    // an unlinked file stays usable through open references
+    assert_eq!(root.remove("f1"), Ok(()));
+    assert_eq!(root.clone().lookup("f1").err(), Some(VfsError::NotFound));
+    let mut buf = [0; 8];
+    assert_eq!(f1.read_at(0, &mut buf), Ok(5));
+    assert_eq!(&buf[..5], b"hello");
+    assert_eq!(root.remove("f1").err(), Some(VfsError::NotFound));
+
+    assert_eq!(root.remove("dir").err(), Some(VfsError::DirectoryNotEmpty));
+    assert_eq!(root.remove("dir/f2"), Ok(()));
+    assert_eq!(root.remove("dir"), Ok(()));
+    assert_eq!(entries(&root), [".", ".."]);
+}
+
+#[test]
+fn test_rename() {
+    let tmpfs = new_tmpfs("");
+    let root = tmpfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.create("f2", VfsNodeType::File).unwrap();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/bar", VfsNodeType::Dir).unwrap();
+    root.create("baz", VfsNodeType::Dir).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let no = RenameFlags::empty();
+
+    assert_eq!(
+        root.rename("f1", "f2", RenameFlags::NOREPLACE).err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(root.rename("f1", "f2", no), Ok(()));
+    assert!(Arc::ptr_eq(&root.clone().lookup("f2").unwrap(), &f1));
+    assert_eq!(root.clone().lookup("f1").err(), Some(VfsError::NotFound));
+    assert_eq!(root.rename("f1", "f3", no).err(), Some(VfsError::NotFound));
+
+    assert_eq!(
+        root.rename("f2", "foo", no).err(),
+        Some(VfsError::IsADirectory)
+    );
+    assert_eq!(
+        root.rename("baz", "f2", no).err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.rename("baz", "foo", no).err(),
+        Some(VfsError::DirectoryNotEmpty)
+    );
+    assert_eq!(
+        root.rename("foo", "foo/bar/qux", no).err(),
+        Some(VfsError::InvalidInput)
+    );
+
+    // moving a directory updates its parent
+    assert_eq!(root.rename("foo/bar", "baz/bar", no), Ok(()));
+    let bar = root.clone().lookup("baz/bar").unwrap();
+    assert!(Arc::ptr_eq(
+        &bar.parent().unwrap(),
+        &root.clone().lookup("baz").unwrap()
+    ));
+    assert_eq!(root.rename("baz", "foo", no), Ok(()));
+    assert!(root.clone().lookup("foo/bar").is_ok());
+
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1_new = root.clone().lookup("f1").unwrap();
+    assert_eq!(root.rename("f1", "f2", RenameFlags::EXCHANGE), Ok(()));
+    assert!(Arc::ptr_eq(&root.clone().lookup("f1").unwrap(), &f1));
+    assert!(Arc::ptr_eq(&root.clone().lookup("f2").unwrap(), &f1_new));
+    assert_eq!(
+        root.rename("f1", "f4", RenameFlags::EXCHANGE).err(),
+        Some(VfsError::NotFound)
+    );
+}
+
+#[test]
+fn test_truncate() {
+    let tmpfs = new_tmpfs("");
+    let root = tmpfs.root_dir();
+    root.create("f", VfsNodeType::File).unwrap();
+    let file = root.clone().lookup("f").unwrap();
+
+    let data = [0xaa; PAGE_SIZE + 100];
+    assert_eq!(file.write_at(0, &data), Ok(data.len()));
+    assert_eq!(file.get_attr().unwrap().size(), data.len() as u64);
+
+    // the truncated tail reads back as zeros when the file grows again
+    assert_eq!(file.truncate(10), Ok(()));
+    assert_eq!(file.get_attr().unwrap().size(), 10);
+    let mut buf = [1; 32];
+    assert_eq!(file.read_at(0, &mut buf), Ok(10));
+    assert_eq!(buf[..10], [0xaa; 10]);
+    assert_eq!(file.truncate(2 * PAGE_SIZE as u64), Ok(()));
+    assert_eq!(file.read_at(0, &mut buf), Ok(32));
+    assert_eq!(buf[..10], [0xaa; 10]);
+    assert_eq!(buf[10..], [0; 22]);
+    assert_eq!(file.read_at(PAGE_SIZE as u64, &mut buf), Ok(32));
+    assert_eq!(buf, [0; 32]);
+
+    assert_eq!(file.truncate(0), Ok(()));
+    assert_eq!(file.read_at(0, &mut buf), Ok(0));
+    assert_eq!(root.truncate(0).err(), Some(VfsError::IsADirectory));
+}
+
+#[test]
+fn test_read_dir() {
+    let tmpfs = new_tmpfs("");
+    let root = tmpfs.root_dir();
+    for name in ["e", "a", "d", "b", "c"] {
+        root.create(name, VfsNodeType::File).unwrap();
+    }
+    root.create("sub", VfsNodeType::Dir).unwrap();
+    assert_eq!(entries(&root), [".", "..", "a", "b", "c", "d", "e", "sub"]);
+
+    let mut dirents = [const { VfsDirEntry::default() }; 2];
+    assert_eq!(root.read_dir(7, &mut dirents), Ok(1));
+    assert_eq!(dirents[0].name_as_bytes(), b"sub");
+    assert_eq!(dirents[0].entry_type(), VfsNodeType::Dir);
+    assert_eq!(root.read_dir(8, &mut dirents), Ok(0));
+
+    let file = root.clone().lookup("a").unwrap();
+    assert!(file.read_dir(0, &mut dirents).is_err());
+}
+
+#[test]
+fn test_size_limit() {
+    let tmpfs = new_tmpfs("size=8k,nr_inodes=3");
+    let root = tmpfs.root_dir();
+    root.create("f", VfsNodeType::File).unwrap();
+    let file = root.clone().lookup("f").unwrap();
+    let data = [1; PAGE_SIZE];
+    assert_eq!(file.write_at(0, &data), Ok(PAGE_SIZE));
+    assert_eq!(file.write_at(PAGE_SIZE as u64, &data), Ok(PAGE_SIZE));
+    assert_eq!(
+        file.write_at(2 * PAGE_SIZE as u64, &data).err(),
+        Some(VfsError::StorageFull)
+    );
+    // truncating gives the pages back
+    assert_eq!(file.truncate(0), Ok(()));
+    assert_eq!(file.write_at(PAGE_SIZE as u64, &data), Ok(PAGE_SIZE));
+
+    // the root and `f` already use two of the three inodes
+    assert_eq!(root.create("g", VfsNodeType::File), Ok(()));
+    assert_eq!(
+        root.create("h", VfsNodeType::File).err(),
+        Some(VfsError::StorageFull)
+    );
+    assert_eq!(root.remove("g"), Ok(()));
+    assert_eq!(root.create("h", VfsNodeType::File), Ok(()));
+}
@@ -65,6 +65,10 @@ pub struct TrapFrame {
     pub prmd: usize,
     /// Exception Return Address
     pub era: usize,
+    /// Original `a0` of the syscall, which is overwritten by the return value.
+    pub orig_a0: usize,
+    /// Whether the trap is a syscall, set by the trap handler.
+    pub in_syscall: bool,
 }
 
 impl TrapFrame {
@@ -178,6 +182,30 @@ impl TrapFrame {
         self.regs.tp = tls_area;
     }
 
+    /// Marks the trap as a syscall, saving the original `a0` so that the
+    /// syscall can be restarted.
+    pub const fn enter_syscall(&mut self) {
+        self.orig_a0 = self.regs.a0;
+        self.in_syscall = true;
+    }
+
+    /// Whether the trap is a syscall.
+    pub const fn in_syscall(&self) -> bool {
+        self.in_syscall
+    }
+
+    /// Rewinds to the syscall instruction with the original arguments, so the
+    /// syscall is executed again when returning to user space.
+    pub const fn restart_syscall(&mut self) {
+        self.regs.a0 = self.orig_a0;
+        self.era -= 4;
+    }
+
+    /// Sets the syscall number.
+    pub const fn set_syscall_num(&mut self, sysno: usize) {
+        self.regs.a7 = sysno;
+    }
+
     pub const fn get_user_sp(&self) -> usize {
         self.regs.sp as _
     }
@@ -3,9 +3,12 @@ use loongArch64::register::{
     badv,
     estat::{self, Exception, Trap},
 };
+use memory_addr::VirtAddr;
 use page_table_entry::MappingFlags;
 
 use crate::trap::{post_trap, pre_trap};
+#[cfg(feature = "uspace")]
+use crate::trap::{USER_EXCEPTION, UserException};
 
 core::arch::global_asm!(
     include_asm_macros!(),
@@ -35,9 +38,43 @@ fn handle_page_fault(tf: &TrapFrame, mut access_flags: MappingFlags, is_user: bo
     }
 }
 
+#[cfg(feature = "uspace")]
+fn handle_user_exception(tf: &mut TrapFrame, exception: UserException, vaddr: VirtAddr) {
+    if !handle_trap!(USER_EXCEPTION, tf, exception, vaddr) {
+        panic!(
+            "Unhandled user exception {:?} @ {:#x}, vaddr={:#x}:\n{:#x?}",
+            exception, tf.era, vaddr, tf
+        );
+    }
+}
+
+/// Code of the `break` instruction for integer division by zero.
+#[cfg(feature = "uspace")]
+const BRK_DIVZERO: u32 = 7;
+/// Code of the `break` instruction for integer overflow.
+#[cfg(feature = "uspace")]
+const BRK_OVERFLOW: u32 = 6;
+
+/// Checks the `break` instruction from user space, compilers emit it with
+/// special codes for arithmetic errors.
+#[cfg(feature = "uspace")]
+fn user_break_exception(tf: &TrapFrame) -> Option<UserException> {
+    // break: 0000 0000 0010 1010 0 | code[14:0]
+    let insn = unsafe { (tf.era as *const u32).read_volatile() };
+    if insn & 0xffff_8000 != 0x002a_0000 {
+        return None;
+    }
+    match insn & 0x7fff {
+        BRK_DIVZERO => Some(UserException::IntegerDivideByZero),
+        BRK_OVERFLOW => Some(UserException::IntegerOverflow),
+        _ => None,
+    }
+}
+
 #[unsafe(no_mangle)]
 fn loongarch64_trap_handler(tf: &mut TrapFrame, from_user: bool) {
     pre_trap(tf, from_user);
+    tf.in_syscall = false;
     let estat = estat::read();
     let trap = estat.cause();
 
@@ -49,6 +86,7 @@ fn loongarch64_trap_handler(tf: &mut TrapFrame, from_user: bool) {
     match trap {
         #[cfg(feature = "uspace")]
         Trap::Exception(Exception::Syscall) => {
+            tf.enter_syscall();
             tf.era += 4;
             tf.regs.a0 = crate::trap::handle_syscall(tf, tf.regs.a7) as usize;
         }
@@ -64,9 +102,37 @@ fn loongarch64_trap_handler(tf: &mut TrapFrame, from_user: bool) {
         | Trap::Exception(Exception::PageNonExecutableFault) => {
             handle_page_fault(tf, MappingFlags::EXECUTE, from_user);
         }
+        #[cfg(feature = "uspace")]
+        Trap::Exception(Exception::Breakpoint) if from_user && user_break_exception(tf).is_some() => {
+            let exception = user_break_exception(tf).unwrap();
+            let pc = va!(tf.era);
+            handle_user_exception(tf, exception, pc);
+        }
         Trap::Exception(Exception::Breakpoint) => {
             handle_breakpoint(&mut tf.era);
         }
+        #[cfg(feature = "uspace")]
+        Trap::Exception(Exception::InstructionNotExist) if from_user => {
+            let pc = va!(tf.era);
+            handle_user_exception(tf, UserException::IllegalInstruction, pc);
+        }
+        #[cfg(feature = "uspace")]
+        Trap::Exception(Exception::InstructionPrivilegeIllegal) if from_user => {
+            let pc = va!(tf.era);
+            handle_user_exception(tf, UserException::PrivilegedInstruction, pc);
+        }
+        #[cfg(feature = "uspace")]
+        Trap::Exception(Exception::AddressNotAligned) if from_user => {
+            handle_user_exception(tf, UserException::Misaligned, va!(badv::read().raw()));
+        }
+        #[cfg(feature = "uspace")]
+        Trap::Exception(
+            Exception::PagePrivilegeIllegal
+            | Exception::FetchInstructionAddressError
+            | Exception::MemoryAccessAddressError,
+        ) if from_user => {
+            handle_user_exception(tf, UserException::AccessFault, va!(badv::read().raw()));
+        }
         Trap::Interrupt(_) => {
             let irq_num: usize = estat.is().trailing_zeros() as usize;
             handle_trap!(IRQ, irq_num);
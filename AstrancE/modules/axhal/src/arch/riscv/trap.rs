@@ -7,6 +7,8 @@ use riscv::register::{scause, stval};
 use super::TrapFrame;
 
 use crate::trap::{PAGE_FAULT, post_trap, pre_trap};
+#[cfg(feature = "uspace")]
+use crate::trap::{USER_EXCEPTION, UserException};
 
 core::arch::global_asm!(
     include_asm_macros!(),
@@ -40,10 +42,21 @@ fn handle_page_fault(
     }
 }
 
+#[cfg(feature = "uspace")]
+fn handle_user_exception(tf: &mut TrapFrame, exception: UserException, vaddr: VirtAddr) {
+    if !handle_trap!(USER_EXCEPTION, tf, exception, vaddr) {
+        panic!(
+            "Unhandled user exception {:?} @ {:#x}, vaddr={:#x}:\n{:#x?}",
+            exception, tf.sepc, vaddr, tf
+        );
+    }
+}
+
 #[unsafe(no_mangle)]
 fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
     let scause = scause::read();
     pre_trap(tf, from_user);
+    tf.in_syscall = false;
     trace!("trap from {:x?}", tf.get_ip());
     if let Ok(cause) = scause.cause().try_into::<I, E>() {
         // Interrupts modify the value of `stval`, which must be saved before the
@@ -55,6 +68,7 @@ fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
         match cause {
             #[cfg(feature = "uspace")]
             Trap::Exception(E::UserEnvCall) => {
+                tf.enter_syscall();
                 tf.sepc += 4;
                 tf.regs.a0 = crate::trap::handle_syscall(tf, tf.regs.a7) as usize;
             }
@@ -70,6 +84,21 @@ fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
             Trap::Exception(E::Breakpoint) => {
                 handle_breakpoint(&mut tf.sepc);
             }
+            #[cfg(feature = "uspace")]
+            Trap::Exception(E::IllegalInstruction) if from_user => {
+                let pc = va!(tf.sepc);
+                handle_user_exception(tf, UserException::IllegalInstruction, pc);
+            }
+            #[cfg(feature = "uspace")]
+            Trap::Exception(E::InstructionMisaligned | E::LoadMisaligned | E::StoreMisaligned)
+                if from_user =>
+            {
+                handle_user_exception(tf, UserException::Misaligned, vaddr);
+            }
+            #[cfg(feature = "uspace")]
+            Trap::Exception(E::InstructionFault | E::LoadFault | E::StoreFault) if from_user => {
+                handle_user_exception(tf, UserException::AccessFault, vaddr);
+            }
             Trap::Interrupt(_) => {
                 handle_trap!(IRQ, scause.bits());
             }
@@ -21,6 +21,34 @@ pub static PAGE_FAULT: [fn(VirtAddr, MappingFlags, bool) -> bool];
 #[def_trap_handler]
 pub static SYSCALL: [fn(&mut TrapFrame, usize) -> Option<isize>];
 
+/// Exceptions from user space that the kernel does not handle by itself, they
+/// are reported to the user program (e.g., as signals).
+#[cfg(feature = "uspace")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserException {
+    /// Illegal instruction.
+    IllegalInstruction,
+    /// Privileged instruction executed in user mode.
+    PrivilegedInstruction,
+    /// Misaligned memory access or instruction fetch.
+    Misaligned,
+    /// Access fault not caused by a missing page, e.g., a PMP violation or an
+    /// illegal address.
+    AccessFault,
+    /// Integer division by zero.
+    IntegerDivideByZero,
+    /// Integer overflow.
+    IntegerOverflow,
+}
+
+/// A slice of handler functions for exceptions from user space, called with
+/// the trap frame, the exception and the faulting address.
+///
+/// Returns `true` if the exception is reported to the user program.
+#[cfg(feature = "uspace")]
+#[def_trap_handler]
+pub static USER_EXCEPTION: [fn(&mut TrapFrame, UserException, VirtAddr) -> bool];
+
 #[def_trap_handler]
 pub static PRE_TRAP: [fn(&mut TrapFrame, bool) -> bool];
 
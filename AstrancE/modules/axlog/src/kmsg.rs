@@ -0,0 +1,659 @@
+//! The kernel log ring buffer.
+//!
+//! Every log line is recorded into a fixed-size ring buffer along with its
+//! level, timestamp, CPU ID and task ID, so that it can be read back later
+//! through `syslog(2)` or `/dev/kmsg`, in the same formats as Linux.
+//!
+//! Records are identified by a sequence number which increases monotonically.
+//! When the buffer is full, the oldest records are overwritten.
+//!
+//! For `/dev/kmsg`, all records ever logged are regarded as a byte stream, each
+//! record occupying one line in the `/dev/kmsg` format. A file offset is a
+//! position in this stream, so readers with their own offsets can follow the
+//! log independently.
+
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+
+use kspin::SpinNoIrq;
+
+/// Number of records kept in the ring buffer.
+pub const KMSG_RECORDS: usize = 1024;
+/// Maximum length of the text of a record, longer texts are truncated.
+pub const KMSG_TEXT_MAX: usize = 256;
+/// Size of the ring buffer reported to `syslog(SYSLOG_ACTION_SIZE_BUFFER)`.
+pub const LOG_BUF_LEN: usize = KMSG_RECORDS * KMSG_TEXT_MAX;
+
+/// Maximum length of a formatted line. Each invalid UTF-8 byte in the text may
+/// be expanded to a 3-byte replacement character, plus the header.
+const LINE_MAX: usize = KMSG_TEXT_MAX * 3 + 128;
+
+/// Syslog facility of user-space messages (`LOG_USER`).
+const LOG_USER: u8 = 1;
+/// Level of user-space messages without a `<N>` prefix (`LOG_WARNING`).
+const DEFAULT_MESSAGE_LEVEL: u8 = 4;
+/// Messages with a level lower than this are printed to the console.
+const DEFAULT_CONSOLE_LEVEL: u8 = 8;
+/// Console level while the console is turned off.
+const MINIMUM_CONSOLE_LEVEL: u8 = 1;
+
+/// Errors of reading the log buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KmsgError {
+    /// The requested record has been overwritten by newer ones.
+    Overwritten,
+    /// An argument is out of range.
+    InvalidInput,
+}
+
+/// A record in the kernel log buffer.
+#[derive(Clone, Copy)]
+pub struct KmsgRecord {
+    seq: u64,
+    facility: u8,
+    level: u8,
+    timestamp: Duration,
+    cpu_id: Option<usize>,
+    tid: Option<u64>,
+    len: usize,
+    text: [u8; KMSG_TEXT_MAX],
+    /// Position of this record in the `/dev/kmsg` stream.
+    stream_off: u64,
+    /// Length of this record in the `/dev/kmsg` format.
+    kmsg_len: usize,
+}
+
+impl KmsgRecord {
+    const EMPTY: Self = Self {
+        seq: 0,
+        facility: 0,
+        level: 0,
+        timestamp: Duration::ZERO,
+        cpu_id: None,
+        tid: None,
+        len: 0,
+        text: [0; KMSG_TEXT_MAX],
+        stream_off: 0,
+        kmsg_len: 0,
+    };
+
+    /// The sequence number.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// The syslog level, from `0` (`LOG_EMERG`) to `7` (`LOG_DEBUG`).
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// The syslog facility, `0` for kernel messages.
+    pub fn facility(&self) -> u8 {
+        self.facility
+    }
+
+    /// The time when the record was logged.
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    /// The CPU on which the record was logged.
+    pub fn cpu_id(&self) -> Option<usize> {
+        self.cpu_id
+    }
+
+    /// The task that logged the record.
+    pub fn tid(&self) -> Option<u64> {
+        self.tid
+    }
+
+    /// The message text, without the trailing newline.
+    pub fn text(&self) -> &[u8] {
+        &self.text[..self.len]
+    }
+
+    fn prefix(&self) -> u16 {
+        ((self.facility as u16) << 3) | self.level as u16
+    }
+
+    fn write_caller(&self, f: &mut impl Write) -> fmt::Result {
+        match (self.tid, self.cpu_id) {
+            (Some(tid), _) => write!(f, "T{tid}"),
+            (None, Some(cpu_id)) => write!(f, "C{cpu_id}"),
+            (None, None) => Ok(()),
+        }
+    }
+
+    /// Formats the record in the `/dev/kmsg` format:
+    /// `prefix,seq,timestamp_us,-,caller=T<tid>;text\n`.
+    pub fn write_kmsg(&self, f: &mut impl Write) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},-",
+            self.prefix(),
+            self.seq,
+            self.timestamp.as_micros()
+        )?;
+        if self.tid.is_some() || self.cpu_id.is_some() {
+            f.write_str(",caller=")?;
+            self.write_caller(f)?;
+        }
+        f.write_char(';')?;
+        write_text(f, self.text())?;
+        f.write_char('\n')
+    }
+
+    /// Formats the record in the `syslog(2)` format:
+    /// `<prefix>[secs.usecs][  T<tid>] text\n`.
+    pub fn write_syslog(&self, f: &mut impl Write) -> fmt::Result {
+        write!(
+            f,
+            "<{}>[{:>5}.{:06}]",
+            self.prefix(),
+            self.timestamp.as_secs(),
+            self.timestamp.subsec_micros()
+        )?;
+        if self.tid.is_some() || self.cpu_id.is_some() {
+            let mut caller = LineBuf::<24>::new();
+            self.write_caller(&mut caller)?;
+            write!(f, "[{:>6}]", caller.as_str())?;
+        }
+        f.write_char(' ')?;
+        write_text(f, self.text())?;
+        f.write_char('\n')
+    }
+
+    fn syslog_len(&self) -> usize {
+        let mut counter = Counter(0);
+        let _ = self.write_syslog(&mut counter);
+        counter.0
+    }
+
+    fn format_syslog(&self) -> LineBuf<LINE_MAX> {
+        let mut line = LineBuf::new();
+        let _ = self.write_syslog(&mut line);
+        line
+    }
+
+    fn format_kmsg(&self) -> LineBuf<LINE_MAX> {
+        let mut line = LineBuf::new();
+        let _ = self.write_kmsg(&mut line);
+        line
+    }
+}
+
+/// Writes the text, replacing invalid UTF-8 sequences.
+fn write_text(f: &mut impl Write, text: &[u8]) -> fmt::Result {
+    for chunk in text.utf8_chunks() {
+        f.write_str(chunk.valid())?;
+        if !chunk.invalid().is_empty() {
+            f.write_char(char::REPLACEMENT_CHARACTER)?;
+        }
+    }
+    Ok(())
+}
+
+/// Counts the bytes written.
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// A fixed-size buffer on the stack, silently truncating what does not fit.
+pub(crate) struct LineBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> LineBuf<N> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    fn as_str(&self) -> &str {
+        // Only whole `str`s are written in `write_str`.
+        core::str::from_utf8(self.as_bytes()).unwrap_or_default()
+    }
+}
+
+impl<const N: usize> Write for LineBuf<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut n = s.len().min(N - self.len);
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        if n < s.len() { Err(fmt::Error) } else { Ok(()) }
+    }
+}
+
+/// The ring buffer itself.
+struct LogBuf {
+    records: [KmsgRecord; KMSG_RECORDS],
+    /// Sequence number of the next record.
+    next_seq: u64,
+    /// Position of the next record in the `/dev/kmsg` stream.
+    next_stream_off: u64,
+    /// Next record to be read by `SYSLOG_ACTION_READ`.
+    syslog_seq: u64,
+    /// First record to be read by `SYSLOG_ACTION_READ_ALL`.
+    clear_seq: u64,
+    /// Messages with a level lower than this are printed to the console.
+    console_level: u8,
+    /// Console level saved by `SYSLOG_ACTION_CONSOLE_OFF`.
+    saved_console_level: Option<u8>,
+}
+
+impl LogBuf {
+    const fn new() -> Self {
+        Self {
+            records: [KmsgRecord::EMPTY; KMSG_RECORDS],
+            next_seq: 0,
+            next_stream_off: 0,
+            syslog_seq: 0,
+            clear_seq: 0,
+            console_level: DEFAULT_CONSOLE_LEVEL,
+            saved_console_level: None,
+        }
+    }
+
+    fn first_seq(&self) -> u64 {
+        self.next_seq.saturating_sub(KMSG_RECORDS as u64)
+    }
+
+    fn get(&self, seq: u64) -> &KmsgRecord {
+        &self.records[(seq % KMSG_RECORDS as u64) as usize]
+    }
+
+    fn push(&mut self, mut record: KmsgRecord) {
+        record.seq = self.next_seq;
+        record.stream_off = self.next_stream_off;
+        let mut counter = Counter(0);
+        let _ = record.write_kmsg(&mut counter);
+        record.kmsg_len = counter.0;
+
+        self.next_seq += 1;
+        self.next_stream_off += record.kmsg_len as u64;
+        self.records[(record.seq % KMSG_RECORDS as u64) as usize] = record;
+    }
+
+    /// Reads the `/dev/kmsg` stream at `offset`, returning at most one record.
+    fn read_stream(&self, offset: u64, buf: &mut [u8]) -> Result<usize, KmsgError> {
+        if offset >= self.next_stream_off {
+            return Ok(0);
+        }
+        let (first, next) = (self.first_seq(), self.next_seq);
+        if first == next || offset < self.get(first).stream_off {
+            return Err(KmsgError::Overwritten);
+        }
+        // Records are sorted by their stream offsets.
+        let (mut lo, mut hi) = (first, next);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if self.get(mid).stream_off <= offset {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let record = self.get(lo);
+        let line = record.format_kmsg();
+        let skip = ((offset - record.stream_off) as usize).min(line.as_bytes().len());
+        let src = &line.as_bytes()[skip..];
+        let len = src.len().min(buf.len());
+        buf[..len].copy_from_slice(&src[..len]);
+        Ok(len)
+    }
+
+    /// Reads records starting from `syslog_seq`, consuming what is read.
+    fn read_syslog(&mut self, buf: &mut [u8]) -> usize {
+        let mut seq = self.syslog_seq.max(self.first_seq());
+        let mut pos = 0;
+        while seq < self.next_seq {
+            let line = self.get(seq).format_syslog();
+            let line = line.as_bytes();
+            if pos + line.len() > buf.len() {
+                if pos == 0 {
+                    // The buffer cannot hold even one line, truncate it.
+                    pos = buf.len();
+                    buf.copy_from_slice(&line[..pos]);
+                    seq += 1;
+                }
+                break;
+            }
+            buf[pos..pos + line.len()].copy_from_slice(line);
+            pos += line.len();
+            seq += 1;
+        }
+        self.syslog_seq = seq;
+        pos
+    }
+
+    /// Reads the most recent records that fit in `buf`, since the last clear.
+    fn read_syslog_all(&self, buf: &mut [u8]) -> usize {
+        let first = self.clear_seq.max(self.first_seq());
+        // Skip the oldest records that do not fit.
+        let mut total: usize = (first..self.next_seq)
+            .map(|seq| self.get(seq).syslog_len())
+            .sum();
+        let mut seq = first;
+        while total > buf.len() {
+            total -= self.get(seq).syslog_len();
+            seq += 1;
+        }
+        let mut pos = 0;
+        for seq in seq..self.next_seq {
+            let line = self.get(seq).format_syslog();
+            let line = line.as_bytes();
+            buf[pos..pos + line.len()].copy_from_slice(line);
+            pos += line.len();
+        }
+        pos
+    }
+
+    fn unread_syslog_len(&self) -> usize {
+        (self.syslog_seq.max(self.first_seq())..self.next_seq)
+            .map(|seq| self.get(seq).syslog_len())
+            .sum()
+    }
+}
+
+static LOG_BUF: SpinNoIrq<LogBuf> = SpinNoIrq::new(LogBuf::new());
+
+/// Whether records have been appended since readers were last woken up.
+static WAKEUP_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Maps a [`log::Level`] to the syslog level.
+pub(crate) fn syslog_level(level: log::Level) -> u8 {
+    match level {
+        log::Level::Error => 3,
+        log::Level::Warn => 4,
+        log::Level::Info => 6,
+        log::Level::Debug | log::Level::Trace => 7,
+    }
+}
+
+/// Gets the time, CPU ID and task ID of the caller.
+pub(crate) fn caller_info() -> (Duration, Option<usize>, Option<u64>) {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "std")] {
+            (Duration::ZERO, None, None)
+        } else {
+            use crate_interface::call_interface;
+            (
+                call_interface!(crate::LogIf::current_time),
+                call_interface!(crate::LogIf::current_cpu_id),
+                call_interface!(crate::LogIf::current_task_id),
+            )
+        }
+    }
+}
+
+/// Appends a record to the log buffer.
+///
+/// `level` is the syslog level, and `facility` is `0` for kernel messages.
+/// Texts longer than [`KMSG_TEXT_MAX`] are truncated.
+pub fn append(
+    facility: u8,
+    level: u8,
+    timestamp: Duration,
+    cpu_id: Option<usize>,
+    tid: Option<u64>,
+    text: &[u8],
+) {
+    let len = text.len().min(KMSG_TEXT_MAX);
+    let mut record = KmsgRecord {
+        facility,
+        level: level & 7,
+        timestamp,
+        cpu_id,
+        tid,
+        len,
+        ..KmsgRecord::EMPTY
+    };
+    record.text[..len].copy_from_slice(&text[..len]);
+    LOG_BUF.lock().push(record);
+    WAKEUP_PENDING.store(true, Ordering::Release);
+}
+
+/// Returns whether records have been appended since the last call, and so
+/// readers waiting for new records should be woken up.
+///
+/// Records may be appended with any lock held, including those of the
+/// scheduler, so readers can not be woken up by [`append`] itself. Instead,
+/// this is polled from a context that holds no lock, such as the timer
+/// interrupt.
+pub fn take_wakeup() -> bool {
+    WAKEUP_PENDING.swap(false, Ordering::AcqRel)
+}
+
+/// Appends a record written by user space to `/dev/kmsg`.
+///
+/// The message may start with a `<N>` prefix specifying the level and the
+/// facility, as in `syslog(3)`. Kernel facility is not allowed, and is
+/// replaced by `LOG_USER`.
+pub fn write_user(buf: &[u8]) -> usize {
+    let mut facility = LOG_USER;
+    let mut level = DEFAULT_MESSAGE_LEVEL;
+    let mut text = buf;
+    if let Some(rest) = text.strip_prefix(b"<") {
+        let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 && rest.get(digits) == Some(&b'>') {
+            let prefix = core::str::from_utf8(&rest[..digits])
+                .ok()
+                .and_then(|s| s.parse::<u32>().ok())
+                .unwrap_or(0);
+            level = (prefix & 7) as u8;
+            if (prefix >> 3) & 0x7f != 0 {
+                facility = ((prefix >> 3) & 0x7f) as u8;
+            }
+            text = &rest[digits + 1..];
+        }
+    }
+    let text = text.strip_suffix(b"\n").unwrap_or(text);
+    let (now, cpu_id, tid) = caller_info();
+    append(facility, level, now, cpu_id, tid, text);
+    buf.len()
+}
+
+/// Reads the `/dev/kmsg` stream at the given offset.
+///
+/// At most one record is returned in each read. Returns `Ok(0)` if there is no
+/// record after `offset` yet, or [`KmsgError::Overwritten`] if the record at
+/// `offset` has been overwritten, like `EPIPE` on Linux.
+pub fn read_kmsg(offset: u64, buf: &mut [u8]) -> Result<usize, KmsgError> {
+    LOG_BUF.lock().read_stream(offset, buf)
+}
+
+/// Returns the end of the `/dev/kmsg` stream, that is, the offset of the next
+/// record to be logged.
+pub fn kmsg_end() -> u64 {
+    LOG_BUF.lock().next_stream_off
+}
+
+/// Returns the offset of the oldest record still in the `/dev/kmsg` stream.
+pub fn kmsg_start() -> u64 {
+    let log_buf = LOG_BUF.lock();
+    if log_buf.next_seq == 0 {
+        0
+    } else {
+        log_buf.get(log_buf.first_seq()).stream_off
+    }
+}
+
+/// Reads and consumes records, as `SYSLOG_ACTION_READ` does.
+///
+/// Returns `0` if there is nothing to read. The caller is responsible for
+/// waiting for new records, see [`take_wakeup`].
+pub fn syslog_read(buf: &mut [u8]) -> usize {
+    LOG_BUF.lock().read_syslog(buf)
+}
+
+/// Reads the most recent records that fit in the buffer, as
+/// `SYSLOG_ACTION_READ_ALL` does. Records are cleared after reading if `clear`
+/// is true (`SYSLOG_ACTION_READ_CLEAR`).
+pub fn syslog_read_all(buf: &mut [u8], clear: bool) -> usize {
+    let mut log_buf = LOG_BUF.lock();
+    let len = log_buf.read_syslog_all(buf);
+    if clear {
+        log_buf.clear_seq = log_buf.next_seq;
+    }
+    len
+}
+
+/// Clears the records for `SYSLOG_ACTION_READ_ALL`.
+///
+/// Records are still kept for `/dev/kmsg` and `SYSLOG_ACTION_READ`.
+pub fn syslog_clear() {
+    let mut log_buf = LOG_BUF.lock();
+    log_buf.clear_seq = log_buf.next_seq;
+}
+
+/// Returns the number of bytes unread by `SYSLOG_ACTION_READ`.
+pub fn syslog_unread_len() -> usize {
+    LOG_BUF.lock().unread_syslog_len()
+}
+
+/// Returns the current console level. Messages with a level lower than it are
+/// printed to the console.
+pub fn console_level() -> u8 {
+    LOG_BUF.lock().console_level
+}
+
+/// Sets the console level, as `SYSLOG_ACTION_CONSOLE_LEVEL` does.
+pub fn set_console_level(level: u8) -> Result<(), KmsgError> {
+    if !(1..=8).contains(&level) {
+        return Err(KmsgError::InvalidInput);
+    }
+    let mut log_buf = LOG_BUF.lock();
+    log_buf.console_level = level;
+    log_buf.saved_console_level = None;
+    Ok(())
+}
+
+/// Disables printing to the console except for emergency messages, as
+/// `SYSLOG_ACTION_CONSOLE_OFF` does.
+pub fn console_off() {
+    let mut log_buf = LOG_BUF.lock();
+    if log_buf.saved_console_level.is_none() {
+        log_buf.saved_console_level = Some(log_buf.console_level);
+        log_buf.console_level = MINIMUM_CONSOLE_LEVEL;
+    }
+}
+
+/// Restores the console level saved by [`console_off`], as
+/// `SYSLOG_ACTION_CONSOLE_ON` does.
+pub fn console_on() {
+    let mut log_buf = LOG_BUF.lock();
+    if let Some(level) = log_buf.saved_console_level.take() {
+        log_buf.console_level = level;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(text: &str) -> KmsgRecord {
+        let mut record = KmsgRecord {
+            level: 6,
+            timestamp: Duration::from_micros(1_500_000),
+            tid: Some(5),
+            len: text.len(),
+            ..KmsgRecord::EMPTY
+        };
+        record.text[..text.len()].copy_from_slice(text.as_bytes());
+        record
+    }
+
+    #[test]
+    fn test_formats() {
+        let mut log_buf = Box::new(LogBuf::new());
+        log_buf.push(record("hello"));
+        let record = log_buf.get(0);
+        assert_eq!(record.format_kmsg().as_bytes(), b"6,0,1500000,-,caller=T5;hello\n");
+        assert_eq!(
+            record.format_syslog().as_bytes(),
+            b"<6>[    1.500000][    T5] hello\n"
+        );
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut log_buf = Box::new(LogBuf::new());
+        for i in 0..KMSG_RECORDS + 2 {
+            log_buf.push(record(if i % 2 == 0 { "even" } else { "odd" }));
+        }
+        let mut buf = [0; LINE_MAX];
+        assert_eq!(log_buf.read_stream(0, &mut buf), Err(KmsgError::Overwritten));
+
+        let mut offset = log_buf.get(log_buf.first_seq()).stream_off;
+        let mut count = 0;
+        loop {
+            let len = log_buf.read_stream(offset, &mut buf).unwrap();
+            if len == 0 {
+                break;
+            }
+            assert_eq!(buf[len - 1], b'\n');
+            offset += len as u64;
+            count += 1;
+        }
+        assert_eq!(count, KMSG_RECORDS);
+        assert_eq!(offset, log_buf.next_stream_off);
+
+        // Partial reads continue from the middle of a record.
+        let start = log_buf.get(log_buf.first_seq()).stream_off;
+        let len = log_buf.read_stream(start, &mut buf[..4]).unwrap();
+        assert_eq!(&buf[..len], b"6,2,");
+        let len = log_buf.read_stream(start + 4, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"1500000,-,caller=T5;even\n");
+    }
+
+    #[test]
+    fn test_syslog() {
+        let mut log_buf = Box::new(LogBuf::new());
+        log_buf.push(record("first"));
+        log_buf.push(record("second"));
+        let line_len = log_buf.get(0).syslog_len();
+        assert_eq!(log_buf.unread_syslog_len(), line_len * 2 + 1);
+
+        // `READ_ALL` returns the most recent records that fit.
+        let mut buf = [0; 64];
+        let len = log_buf.read_syslog_all(&mut buf[..line_len + 2]);
+        assert!(buf[..len].ends_with(b"] second\n"));
+        assert_eq!(log_buf.unread_syslog_len(), line_len * 2 + 1);
+
+        // `READ` consumes records.
+        let len = log_buf.read_syslog(&mut buf[..line_len]);
+        assert!(buf[..len].ends_with(b"] first\n"));
+        let len = log_buf.read_syslog(&mut buf);
+        assert!(buf[..len].ends_with(b"] second\n"));
+        assert_eq!(log_buf.read_syslog(&mut buf), 0);
+        assert_eq!(log_buf.unread_syslog_len(), 0);
+    }
+
+    #[test]
+    fn test_write_user() {
+        let mut buf = [0; LINE_MAX];
+        let end = kmsg_end();
+        write_user(b"<3>oops\n");
+        let len = read_kmsg(end, &mut buf).unwrap();
+        let line = core::str::from_utf8(&buf[..len]).unwrap();
+        // LOG_USER | LOG_ERR
+        assert!(line.starts_with("11,"));
+        assert!(line.ends_with(";oops\n"));
+    }
+}
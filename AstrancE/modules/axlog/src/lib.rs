@@ -5,7 +5,8 @@
 //! [`info!`], [`debug!`], and [`trace!`].
 //!
 //! If it is used in `no_std` environment, the users need to implement the
-//! [`LogIf`] to provide external functions such as console output.
+//! [`LogIf`] to provide external functions such as console output. Log lines
+//! are also recorded into the kernel log buffer, see [`kmsg`].
 //!
 //! To use in the `std` environment, please enable the `std` feature:
 //!
@@ -50,6 +51,8 @@
 
 extern crate log;
 
+pub mod kmsg;
+
 use core::fmt::{self, Write};
 use core::str::FromStr;
 
@@ -164,8 +167,19 @@ impl Log for Logger {
             Level::Trace => ColorCode::BrightBlack,
         };
 
+        // record the line into the kernel log buffer
+        let (now, cpu_id, tid) = kmsg::caller_info();
+        let syslog_level = kmsg::syslog_level(level);
+        let mut text = kmsg::LineBuf::<{ kmsg::KMSG_TEXT_MAX }>::new();
+        let _ = text.write_fmt(*record.args());
+        kmsg::append(0, syslog_level, now, cpu_id, tid, text.as_bytes());
+        if syslog_level >= kmsg::console_level() {
+            return;
+        }
+
         cfg_if::cfg_if! {
             if #[cfg(feature = "std")] {
+                let _ = (now, cpu_id, tid); // the local time is shown instead
                 __print_impl(with_color!(
                     ColorCode::White,
                     "[{time} {path}:{line}] {args}\n",
@@ -175,9 +189,6 @@ impl Log for Logger {
                     args = with_color!(args_color, "{}", record.args()),
                 ));
             } else {
-                let cpu_id = call_interface!(LogIf::current_cpu_id);
-                let tid = call_interface!(LogIf::current_task_id);
-                let now = call_interface!(LogIf::current_time);
                 if let Some(cpu_id) = cpu_id {
                     if let Some(tid) = tid {
                         // show CPU ID and task ID
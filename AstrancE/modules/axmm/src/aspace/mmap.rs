@@ -1,4 +1,4 @@
-use alloc::sync::Arc;
+use alloc::{string::String, sync::Arc};
 use axerrno::{AxError, AxResult, ax_err};
 use axhal::{
     mem::{MemoryAddr, phys_to_virt},
@@ -12,7 +12,7 @@ use memory_set::MemoryArea; // <--- 引入 Mutex
 use crate::{
     AddrSpace,
     Backend,
-    backend::{VmAreaType, alloc::alloc_frame},
+    backend::{FrameTrackerRef, VmAreaType, alloc::alloc_frame},
     mapping_err_to_ax_err,
     shm::ShmSegment, // <--- 引入 ShmSegment
 };
@@ -75,6 +75,16 @@ pub trait MmapIO: Send + Sync {
     fn read(&self, va: usize, buf: &mut [u8]) -> AxResult<usize>;
     fn write(&self, va: usize, data: &[u8]) -> AxResult<usize>;
     fn flags(&self) -> MmapFlags;
+    /// Returns the path and offset of the mapped file, or `None` for
+    /// anonymous mappings.
+    fn file_info(&self) -> Option<(String, usize)> {
+        None
+    }
+    /// Returns the frame holding the file data at `va` for shared mappings
+    /// of files kept in memory, so that it is mapped instead of copied.
+    fn shared_frame(&self, _va: usize) -> Option<FrameTrackerRef> {
+        None
+    }
 }
 
 /// TODO: 限制mmap大小
@@ -225,6 +235,17 @@ impl AddrSpace {
     ) -> AxResult {
         let vaddr = vaddr.align_down_4k();
         //warn!("areas: {:#?}", self.areas);
+        if mmio.flags().contains(MmapFlags::MAP_SHARED) {
+            if let Some(frame) = mmio.shared_frame(vaddr.as_usize()) {
+                let area = self.areas.find_mut(vaddr).ok_or(AxError::BadAddress)?;
+                area.insert_frame(vaddr, frame.clone());
+                return self
+                    .page_table()
+                    .map(vaddr, frame.pa, size, flags)
+                    .map(|tlb| tlb.flush())
+                    .map_err(|_| AxError::BadAddress);
+            }
+        }
         if let Some(frame) = alloc_frame(true) {
             let area = self.areas.find_mut(vaddr).ok_or(AxError::BadAddress)?;
             debug!(
@@ -36,7 +36,8 @@ pub(crate) fn alloc_nframe(num_pages: usize, zeroed: bool) -> Option<Vec<FrameTr
  *    Some(paddr)
  *}
  */
-pub(crate) fn alloc_frame(zeroed: bool) -> Option<FrameTrackerRef> {
+/// Allocate a physical frame that is freed when the last reference drops.
+pub fn alloc_frame(zeroed: bool) -> Option<FrameTrackerRef> {
     let vaddr = VirtAddr::from(global_allocator().alloc_pages(1, PAGE_SIZE_4K).ok()?);
     if zeroed {
         unsafe { core::ptr::write_bytes(vaddr.as_mut_ptr(), 0, PAGE_SIZE_4K) };
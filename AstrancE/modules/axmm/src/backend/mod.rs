@@ -9,6 +9,7 @@ use memory_set::MappingBackend;
 use crate::{AddrSpace, aspace::mmap::MmapIO, shm::ShmSegment};
 
 pub(super) mod alloc;
+pub use self::alloc::alloc_frame;
 pub mod frame;
 pub use frame::*;
 mod linear;
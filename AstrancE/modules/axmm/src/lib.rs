@@ -15,7 +15,7 @@ pub mod shm;
 pub use self::aspace::AddrSpace;
 pub use self::backend::{
     Backend, FrameTrackerImpl as FrameTracker, FrameTrackerMap, FrameTrackerRef, FrameTrackerWeak,
-    TrackedPhysAddr,
+    TrackedPhysAddr, alloc_frame,
 };
 pub use aspace::mmap::{MmapFlags, MmapIO, MmapPerm};
 pub use shm::{shm_get, shm_at, shm_dt, shm_ctl, ShmError, ShmSegment, IPC_PRIVATE, IPC_CREAT, IPC_EXCL, IPC_RMID};
@@ -260,6 +260,8 @@ fn init_interrupt() {
         update_timer();
         #[cfg(feature = "multitask")]
         axtask::on_timer_tick();
+        #[cfg(feature = "fs")]
+        axfs::kmsg::wake_readers();
     });
 
     // Enable IRQs before starting app
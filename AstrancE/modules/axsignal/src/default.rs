@@ -2,7 +2,7 @@ use axtask::{current, exit};
 
 use crate::*;
 
-pub fn default_signal_handler(signal: Signal, ctx: &mut SignalContext) {
+pub fn default_signal_handler(signal: Signal, _blocked: SignalSet) {
     match signal {
         Signal::SIGINT | Signal::SIGKILL => {
             // 杀死进程
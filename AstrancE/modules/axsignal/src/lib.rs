@@ -27,7 +27,7 @@ use core::{
     u64,
 };
 
-use alloc::boxed::Box;
+use alloc::{boxed::Box, collections::VecDeque};
 use axerrno::{LinuxError, LinuxResult};
 use axhal::arch::{TaskContext, TrapFrame, UspaceContext};
 use bitflags::*;
@@ -76,7 +76,38 @@ numeric_enum! {
         SIGPWR = SIGPWR as usize,
         SIGUNUSED = SIGUNUSED as usize,
         SIGRTMIN = SIGRTMIN as usize,
-        SIGRTMIN1 = SIGRTMIN as usize + 1
+        SIGRTMIN1 = SIGRTMIN as usize + 1,
+        SIGRTMIN2 = SIGRTMIN as usize + 2,
+        SIGRTMIN3 = SIGRTMIN as usize + 3,
+        SIGRTMIN4 = SIGRTMIN as usize + 4,
+        SIGRTMIN5 = SIGRTMIN as usize + 5,
+        SIGRTMIN6 = SIGRTMIN as usize + 6,
+        SIGRTMIN7 = SIGRTMIN as usize + 7,
+        SIGRTMIN8 = SIGRTMIN as usize + 8,
+        SIGRTMIN9 = SIGRTMIN as usize + 9,
+        SIGRTMIN10 = SIGRTMIN as usize + 10,
+        SIGRTMIN11 = SIGRTMIN as usize + 11,
+        SIGRTMIN12 = SIGRTMIN as usize + 12,
+        SIGRTMIN13 = SIGRTMIN as usize + 13,
+        SIGRTMIN14 = SIGRTMIN as usize + 14,
+        SIGRTMIN15 = SIGRTMIN as usize + 15,
+        SIGRTMIN16 = SIGRTMIN as usize + 16,
+        SIGRTMIN17 = SIGRTMIN as usize + 17,
+        SIGRTMIN18 = SIGRTMIN as usize + 18,
+        SIGRTMIN19 = SIGRTMIN as usize + 19,
+        SIGRTMIN20 = SIGRTMIN as usize + 20,
+        SIGRTMIN21 = SIGRTMIN as usize + 21,
+        SIGRTMIN22 = SIGRTMIN as usize + 22,
+        SIGRTMIN23 = SIGRTMIN as usize + 23,
+        SIGRTMIN24 = SIGRTMIN as usize + 24,
+        SIGRTMIN25 = SIGRTMIN as usize + 25,
+        SIGRTMIN26 = SIGRTMIN as usize + 26,
+        SIGRTMIN27 = SIGRTMIN as usize + 27,
+        SIGRTMIN28 = SIGRTMIN as usize + 28,
+        SIGRTMIN29 = SIGRTMIN as usize + 29,
+        SIGRTMIN30 = SIGRTMIN as usize + 30,
+        SIGRTMIN31 = SIGRTMIN as usize + 31,
+        SIGRTMAX = SIGRTMAX as usize
     }
 }
 
@@ -84,7 +115,7 @@ numeric_enum! {
 //pub struct Signal(u32);
 impl Signal {
     pub fn from_u32(n: u32) -> Option<Self> {
-        if n as usize >= NSIG {
+        if n as usize > NSIG {
             None
         } else {
             if let Ok(sig) = Self::try_from(n as usize) {
@@ -94,6 +125,11 @@ impl Signal {
             }
         }
     }
+
+    /// 是否为实时信号 (SIGRTMIN..=SIGRTMAX)，实时信号按到达顺序排队，不会合并
+    pub fn is_realtime(&self) -> bool {
+        (SIGRTMIN as usize..=SIGRTMAX as usize).contains(&(*self as usize))
+    }
 }
 
 impl TryFrom<c_int> for Signal {
@@ -103,7 +139,7 @@ impl TryFrom<c_int> for Signal {
         if value < 0 || value as usize > NSIG {
             Err(SignalError::InvalidSignal)
         } else {
-            Ok(unsafe { core::mem::transmute(value as usize) })
+            Self::try_from(value as usize).map_err(|_| SignalError::InvalidSignal)
         }
     }
 }
@@ -164,6 +200,9 @@ bitflags! {
         const SIGUNUSED  = 1 << (SIGUNUSED  - 1);
         const SIGRTMIN  = 1 << (SIGRTMIN - 1);
         const SIGRTMIN1  = 1 << (SIGRTMIN);
+        const SIGRTMAX   = 1 << (SIGRTMAX - 1);
+        // 其余实时信号没有单独命名，取反等操作不能截断这些位
+        const _ = !0;
     }
 }
 
@@ -256,7 +295,18 @@ pub enum SigHandler {
     // this is for capabilites, since the fn won't be called directly
     //Action(unsafe extern "C" fn(c_int, *mut siginfo_t, *mut c_void)),
     Action(unsafe extern "C" fn(c_int)),
-    Default(fn(Signal, &mut SignalContext)),
+    /// 默认处理动作，参数为信号与处理该信号时线程的阻塞掩码
+    Default(fn(Signal, SignalSet)),
+}
+
+/// [`handle_pending_signals`] 取出的信号需要执行的动作
+pub enum SignalDelivery {
+    /// 切换到用户态执行信号处理函数，包含用户上下文和内核栈顶
+    Handler(UspaceContext, VirtAddr),
+    /// 执行默认处理动作，包含处理函数、信号与当前的阻塞掩码
+    ///
+    /// 默认动作可能终止进程而不再返回，调用者需先释放持有的信号上下文的锁再执行
+    Default(fn(Signal, SignalSet), Signal, SignalSet),
 }
 
 impl Default for SigHandler {
@@ -414,23 +464,34 @@ pub unsafe extern "C" fn sigreturn_trampoline() {
     );
 }
 
+/// 实时信号个数 (SIGRTMIN..=SIGRTMAX)
+const NRTSIG: usize = (SIGRTMAX - SIGRTMIN + 1) as usize;
+/// 每个信号上下文中最多排队的实时信号实例数，对应 RLIMIT_SIGPENDING
+pub const RT_QUEUE_MAX: usize = 1024;
+
 // 进程信号上下文
 pub struct SignalContext {
     stack: SignalFrameManager,
-    actions: [SigAction; NSIG],     // 信号处理表
-    infos: [Option<SigInfo>; NSIG], // 信号处理表
-    blocked: SignalSet,             // 被阻塞的信号
-    pending: SignalSet,             // 待处理信号
+    actions: [SigAction; NSIG + 1],     // 信号处理表
+    infos: [Option<SigInfo>; NSIG + 1], // 标准信号的附加信息，同一信号多次到达会合并
+    rt_queue: [VecDeque<Option<SigInfo>>; NRTSIG], // 实时信号队列，按到达顺序排队
+    rt_queued: usize,                   // 已排队的实时信号实例总数
+    blocked: SignalSet,                 // 被阻塞的信号
+    pending: SignalSet,                 // 待处理信号
+    saved_mask: Option<SignalSet>,      // 处理信号后恢复的掩码 (sigsuspend 等临时替换掩码时)
 }
 
 impl Default for SignalContext {
     fn default() -> Self {
         let mut default = Self {
             stack: Default::default(),
-            actions: [Default::default(); NSIG],
-            infos: [None; NSIG],
+            actions: [Default::default(); NSIG + 1],
+            infos: [None; NSIG + 1],
+            rt_queue: [const { VecDeque::new() }; NRTSIG],
+            rt_queued: 0,
             blocked: Default::default(),
             pending: Default::default(),
+            saved_mask: None,
         };
         #[cfg(feature = "default_handler")]
         {
@@ -442,20 +503,86 @@ impl Default for SignalContext {
 
 impl SignalContext {
     /// 向进程发送信号
+    ///
+    /// 实时信号队列已满时丢弃新的实例，需要报告错误时使用 [`Self::queue_signal`]
     pub fn send_signal(&mut self, sig: Signal, info: Option<SigInfo>) {
         debug!(
             "send signal: {:?}, pending: {:?}, blocked: {:?}",
             sig, self.pending, self.blocked
         );
+        if sig.is_realtime() {
+            if self.queue_signal(sig, info).is_err() {
+                warn!("rt signal queue full, drop {sig:?}");
+            }
+            return;
+        }
         self.pending.insert(sig.into());
         self.infos[sig as usize] = info;
     }
 
+    /// 排队一个实时信号实例，队列已满时返回 [`SignalError::QueueFull`]
+    ///
+    /// 标准信号不排队，与 [`Self::send_signal`] 相同
+    pub fn queue_signal(&mut self, sig: Signal, info: Option<SigInfo>) -> SignalResult<()> {
+        if !sig.is_realtime() {
+            self.pending.insert(sig.into());
+            self.infos[sig as usize] = info;
+            return Ok(());
+        }
+        if self.rt_queued >= RT_QUEUE_MAX {
+            return Err(SignalError::QueueFull);
+        }
+        self.rt_queue[sig as usize - SIGRTMIN as usize].push_back(info);
+        self.rt_queued += 1;
+        self.pending.insert(sig.into());
+        Ok(())
+    }
+
+    /// 取出一个待处理信号及其附加信息
+    ///
+    /// 实时信号只有在队列中所有实例都取出后才清除待处理位
+    fn dequeue(&mut self, sig: Signal) -> Option<SigInfo> {
+        if !sig.is_realtime() {
+            self.pending.remove(sig.into());
+            return self.infos[sig as usize].take();
+        }
+        let queue = &mut self.rt_queue[sig as usize - SIGRTMIN as usize];
+        let info = queue.pop_front();
+        if info.is_some() {
+            self.rt_queued -= 1;
+        }
+        if queue.is_empty() {
+            self.pending.remove(sig.into());
+        }
+        info.flatten()
+    }
+
+    /// 取出编号最小的、位于 `filter` 中的待处理信号及其附加信息
+    ///
+    /// 标准信号先于实时信号，同一实时信号按到达顺序取出
+    pub fn dequeue_in(&mut self, filter: SignalSet) -> Option<(Signal, Option<SigInfo>)> {
+        let sig = self.pending.get_one_in(filter)?;
+        let info = self.dequeue(sig);
+        Some((sig, info))
+    }
+
     /// 检查是否有待处理信号
     pub fn has_pending(&self) -> bool {
         !self.pending.is_empty()
     }
 
+    /// 检查信号是否会被忽略：处理动作为忽略，或默认动作为忽略的信号
+    pub fn is_ignored(&self, sig: Signal) -> bool {
+        match self.actions[sig as usize].handler {
+            SigHandler::Ignore => true,
+            SigHandler::Default(_) => matches!(
+                sig,
+                Signal::SIGCHLD | Signal::SIGCONT | Signal::SIGURG | Signal::SIGWINCH
+            ),
+            _ => false,
+        }
+    }
+
     /// 获取信号处理动作，返回之前的动作
     pub fn get_action(&mut self, sig: Signal) -> &mut SigAction {
         &mut self.actions[sig as usize]
@@ -489,20 +616,20 @@ impl SignalContext {
     }
 
     pub fn deliver_one(&mut self) -> Option<Signal> {
-        self.pending
-            .take_one_in(self.blocked.not())
+        self.dequeue_in(self.blocked.not())
+            .map(|(sig, _)| sig)
             .inspect(|sig| debug!("deliver signal: {:?}", sig))
     }
 
     pub fn consume_one(&mut self) -> Option<Signal> {
-        self.pending
-            .take_one()
+        self.dequeue_in(SignalSet::all())
+            .map(|(sig, _)| sig)
             .inspect(|sig| debug!("consume signal: {:?}", sig))
     }
 
     pub fn consume_one_in(&mut self, filter: SignalSet) -> Option<Signal> {
-        self.pending
-            .take_one_in(filter)
+        self.dequeue_in(filter)
+            .map(|(sig, _)| sig)
             .inspect(|sig| debug!("consume signal: {:?}", sig))
     }
 
@@ -530,6 +657,22 @@ impl SignalContext {
         old
     }
 
+    /// 临时替换掩码，处理信号后恢复原掩码（同 Linux 的 `set_restore_sigmask`）
+    ///
+    /// 运行处理函数时原掩码保存在信号栈帧中，由 sigreturn 恢复；没有处理函数运行时，
+    /// 调用者需在返回用户态前通过 [`Self::restore_saved_mask`] 恢复
+    pub fn set_temporary_mask(&mut self, mask: SignalSet) {
+        self.saved_mask.get_or_insert(self.blocked);
+        self.blocked = mask;
+    }
+
+    /// 恢复 [`Self::set_temporary_mask`] 保存的掩码
+    pub fn restore_saved_mask(&mut self) {
+        if let Some(mask) = self.saved_mask.take() {
+            self.blocked = mask;
+        }
+    }
+
     /// 加载当前信号栈帧，返回之前的sscratch
     /// 用户不能手动调用
     fn load(&mut self, scratch: usize, data: SignalFrameData) -> SignalResult<usize> {
@@ -538,21 +681,26 @@ impl SignalContext {
     }
 
     /// 释放当前信号栈帧，恢复blocked，返回原scratch(原陷入栈)，必须和load成对
+    /// 若处理函数入口处因 SS_AUTODISARM 清除了备用栈，一并返回原备用栈
     /// 用户需要在sigreturn中手动调用
-    pub fn unload(&mut self) -> SignalResult<(usize, TrapFrame)> {
+    pub fn unload(&mut self) -> SignalResult<(usize, TrapFrame, Option<SignalAltStack>)> {
         let curr_frame = self.current_frame()?;
         let (
             SignalFrameData {
                 signal,
                 uc_sigmask,
                 orig_frame,
+                altstack,
                 ..
             },
             trap_frame,
         ) = curr_frame.unload()?;
         self.blocked = uc_sigmask;
-        self.pending.remove(signal.into());
-        Ok((trap_frame, orig_frame))
+        // 处理期间又到达的实时信号仍在排队，不能清除
+        if !signal.is_realtime() {
+            self.pending.remove(signal.into());
+        }
+        Ok((trap_frame, orig_frame, altstack))
     }
 }
 
@@ -566,6 +714,7 @@ pub enum SignalError {
     InvalidFlags,       // 无效的标志位组合
     InvalidSignal,      // 无效的信号编号
     PermissionDenied,   // 权限不足(如设置SIGKILL)
+    QueueFull,          // 实时信号队列已满
 }
 impl From<SignalError> for LinuxError {
     fn from(err: SignalError) -> LinuxError {
@@ -576,6 +725,7 @@ impl From<SignalError> for LinuxError {
             SignalError::InvalidFlags => LinuxError::EINVAL, // 无效标志
             SignalError::InvalidSignal => LinuxError::EINVAL, // 无效信号号
             SignalError::PermissionDenied => LinuxError::EPERM,
+            SignalError::QueueFull => LinuxError::EAGAIN, // 超出排队上限
             _ => panic!("{err:?}"),
         }
     }
@@ -596,6 +746,55 @@ pub struct SignalFrameData {
     pub sigmask: SignalSet,
     pub flags: SigFlags,
     pub orig_frame: TrapFrame,
+    /// 因 SS_AUTODISARM 被清除的备用栈，sigreturn 时恢复
+    pub altstack: Option<SignalAltStack>,
+}
+
+/// 用户态信号备用栈 (sigaltstack)，每个线程独立
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignalAltStack {
+    pub sp: usize,
+    pub size: usize,
+    /// 只记录 SS_DISABLE 与 SS_AUTODISARM，SS_ONSTACK 在查询时根据 sp 计算
+    pub flags: u32,
+}
+
+impl Default for SignalAltStack {
+    fn default() -> Self {
+        Self {
+            sp: 0,
+            size: 0,
+            flags: SS_DISABLE,
+        }
+    }
+}
+
+impl SignalAltStack {
+    /// 备用栈是否可用
+    pub fn is_enabled(&self) -> bool {
+        self.flags & SS_DISABLE == 0 && self.size != 0
+    }
+
+    /// 用户栈指针是否位于备用栈上
+    pub fn on_stack(&self, sp: usize) -> bool {
+        self.is_enabled() && sp > self.sp && sp - self.sp <= self.size
+    }
+
+    /// 返回给用户的 ss_flags
+    pub fn flags_at(&self, sp: usize) -> u32 {
+        if !self.is_enabled() {
+            SS_DISABLE
+        } else if self.on_stack(sp) {
+            SS_ONSTACK | (self.flags & SS_AUTODISARM)
+        } else {
+            self.flags & SS_AUTODISARM
+        }
+    }
+
+    /// 备用栈栈顶
+    pub fn top(&self) -> usize {
+        self.sp + self.size
+    }
 }
 
 #[derive(Debug)]
@@ -793,9 +992,12 @@ impl SignalFrameManager {
 /// * `thread_tf` - 当前线程的陷阱帧
 /// * `trampoline` - 信号返回跳板的虚拟地址
 /// * `actionctx` - 可选的额外信号上下文，用于获取信号处理动作
+/// * `altstack` - 可选的当前线程备用信号栈，SA_ONSTACK 的处理函数在其上运行
+/// * `restart` - 被打断的系统调用是否在 SA_RESTART 的处理函数返回后重新执行 (ERESTARTSYS)
 ///
 /// # 返回
-/// * `Ok(Some((UspaceContext, VirtAddr)))` - 需要切换到用户态执行信号处理函数，返回用户上下文和内核栈顶
+/// * `Ok(Some(SignalDelivery::Handler(..)))` - 需要切换到用户态执行信号处理函数，返回用户上下文和内核栈顶
+/// * `Ok(Some(SignalDelivery::Default(..)))` - 需要执行默认处理动作，由调用者在释放锁后执行
 /// * `Ok(None)` - 没有需要处理的信号
 /// * `Err(SignalError)` - 处理信号时发生错误
 pub fn handle_pending_signals(
@@ -803,9 +1005,11 @@ pub fn handle_pending_signals(
     thread_tf: &TrapFrame,
     trampoline: VirtAddr,
     mut actionctx: Option<&mut SignalContext>,
-) -> SignalResult<Option<(UspaceContext, VirtAddr)>> {
+    mut altstack: Option<&mut SignalAltStack>,
+    restart: bool,
+) -> SignalResult<Option<SignalDelivery>> {
     // 尝试获取一个待处理且未被阻塞的信号
-    while let Some(sig) = sigctx.deliver_one() {
+    while let Some((sig, info)) = sigctx.dequeue_in(sigctx.blocked.not()) {
         debug!("handle signal: {sig:?}");
         let old_mask = sigctx.blocked;
 
@@ -816,7 +1020,6 @@ pub fn handle_pending_signals(
             *sigctx.get_action(sig) // 从主上下文获取处理动作
         };
 
-        let info = sigctx.infos[sig as usize];
 
         let SigAction {
             handler,
@@ -827,8 +1030,8 @@ pub fn handle_pending_signals(
 
         match handler {
             SigHandler::Default(f) => {
-                // 执行默认处理函数
-                f(sig, &mut *sigctx);
+                // 默认处理函数交给调用者在释放锁后执行
+                return Ok(Some(SignalDelivery::Default(f, sig, old_mask)));
             }
             SigHandler::Ignore => {
                 // 直接忽略信号
@@ -847,19 +1050,50 @@ pub fn handle_pending_signals(
                 // 更新信号掩码
                 sigctx.blocked = mask;
 
+                // 选择处理函数使用的用户栈：设置了SA_ONSTACK且不在备用栈上时切换到备用栈
+                let mut user_sp = thread_tf.get_sp();
+                let mut disarmed = None;
+                if flags.contains(SigFlags::ON_STACK) {
+                    if let Some(alt) = altstack.as_deref() {
+                        if alt.is_enabled() && !alt.on_stack(user_sp) {
+                            user_sp = alt.top() & !0xf; // 16字节对齐
+                            if alt.flags & SS_AUTODISARM != 0 {
+                                disarmed = Some(*alt);
+                            }
+                        }
+                    }
+                }
+
+                // 处理函数设置了SA_RESTART时，返回后重新执行被打断的系统调用，否则返回EINTR
+                let mut orig_frame = *thread_tf;
+                if restart && flags.contains(SigFlags::RESTART) {
+                    orig_frame.restart_syscall();
+                }
+
+                // sigreturn 时恢复的掩码：临时替换过掩码时恢复为替换前的值
+                let uc_sigmask = sigctx.saved_mask.take().unwrap_or(old_mask);
+
                 // 保存当前上下文到信号栈帧
                 assert_eq!(
                     sigctx.load(unsafe { axhal::arch::read_trap_frame() }, SignalFrameData {
                         signal: sig,
-                        uc_sigmask: old_mask, // 保存原始掩码用于恢复
-                        sigmask: mask,        // 当前使用的掩码
+                        uc_sigmask,    // 保存原始掩码用于恢复
+                        sigmask: mask, // 当前使用的掩码
                         flags,
-                        orig_frame: *thread_tf, // 保存原始陷阱帧
+                        orig_frame, // 保存原始陷阱帧
+                        altstack: disarmed,
                     })?,
                     0,
                     "signal stack scratch is not empty"
                 );
 
+                // SS_AUTODISARM：处理函数运行期间清除备用栈，sigreturn时恢复
+                if disarmed.is_some() {
+                    if let Some(alt) = altstack.as_deref_mut() {
+                        *alt = SignalAltStack::default();
+                    }
+                }
+
                 // 获取当前信号栈帧
                 let current_frame: &mut SignalFrame = sigctx.current_frame()?;
                 let kstack_top = current_frame.ptr();
@@ -874,7 +1108,7 @@ pub fn handle_pending_signals(
                     // 1. 分配并准备siginfo_t结构
                     // 注意：这里需要在用户空间分配siginfo_t结构
                     // 假设我们已经在用户栈上分配了空间，并获取了指针
-                    let siginfo_ptr = thread_tf.sp() - core::mem::size_of::<siginfo_t>();
+                    let siginfo_ptr = user_sp - core::mem::size_of::<siginfo_t>();
                     let siginfo_ptr = VirtAddr::from(siginfo_ptr & !0x7); // 8字节对齐
                     if let Some(info) = info {
                         unsafe {
@@ -917,11 +1151,7 @@ pub fn handle_pending_signals(
                     }
                 } else {
                     // 处理普通情况：只传递信号编号
-                    uctx = UspaceContext::new(
-                        handler_fn as usize,
-                        thread_tf.get_sp().into(),
-                        sig as usize,
-                    );
+                    uctx = UspaceContext::new(handler_fn as usize, user_sp.into(), sig as usize);
                 }
 
                 // 设置线程本地存储和全局指针
@@ -953,11 +1183,11 @@ pub fn handle_pending_signals(
                 }
 
                 // 返回用户上下文和内核栈顶，切换到用户态执行信号处理函数
-                return Ok(Some((uctx, kstack_top)));
+                return Ok(Some(SignalDelivery::Handler(uctx, kstack_top)));
             }
         };
 
-        // 如果信号被忽略或执行了默认处理，恢复原始掩码
+        // 如果信号被忽略，恢复原始掩码
         sigctx.blocked = old_mask;
     }
 
@@ -965,13 +1195,13 @@ pub fn handle_pending_signals(
     Ok(None)
 }
 
-fn handle_default_signal(sig: Signal, ctx: &mut SignalContext) {
+fn handle_default_signal(sig: Signal, blocked: SignalSet) {
     #[cfg(feature = "default_handler")]
     {
-        default_signal_handler(sig, ctx);
+        default_signal_handler(sig, blocked);
     }
     #[cfg(not(feature = "default_handler"))]
     {
-        warn!("Unhandled default signal: {sig:?}")
+        warn!("Unhandled default signal: {sig:?}, blocked: {blocked:?}")
     }
 }
@@ -217,8 +217,9 @@ pub enum SigInfoData {
     },
     /// 实时信号 (sigqueue)
     Realtime {
-        value: i32,    // 携带的整数值
-        ptr: VirtAddr, // 携带的指针值
+        pid: Pid,
+        uid: Uid,
+        value: usize, // 携带的 sigval，整数与指针共用
     },
     /// I/O 事件 (SIGPOLL/SIGIO)
     PollIO { fd: Fd, band: Band },
@@ -283,13 +284,61 @@ impl SigInfo {
             data: SigInfoData::MemoryAccess { addr },
         }
     }
+    /// 创建一个算术错误相关的 SigInfo 实例 (SIGFPE)
+    pub fn new_fpe_error(signo: Signal, code: SigCode, addr: VirtAddr) -> Self {
+        SigInfo {
+            signo,
+            errno: 0,
+            code,
+            data: SigInfoData::FPEError { addr },
+        }
+    }
+    /// 创建一个非法指令相关的 SigInfo 实例 (SIGILL)
+    pub fn new_illegal_instruction(signo: Signal, code: SigCode, addr: VirtAddr) -> Self {
+        SigInfo {
+            signo,
+            errno: 0,
+            code,
+            data: SigInfoData::IllegalInstruction { addr },
+        }
+    }
+    /// 创建一个总线错误相关的 SigInfo 实例 (SIGBUS)
+    pub fn new_bus_error(signo: Signal, code: SigCode, addr: VirtAddr) -> Self {
+        SigInfo {
+            signo,
+            errno: 0,
+            code,
+            data: SigInfoData::BusError { addr },
+        }
+    }
     /// 创建一个实时信号相关的 SigInfo 实例
-    pub fn new_realtime(signo: Signal, code: SigCode, value: i32, ptr: VirtAddr) -> Self {
+    pub fn new_realtime(signo: Signal, code: SigCode, pid: Pid, uid: Uid, value: usize) -> Self {
         SigInfo {
             signo,
             errno: 0,
             code,
-            data: SigInfoData::Realtime { value, ptr },
+            data: SigInfoData::Realtime { pid, uid, value },
+        }
+    }
+    /// 从用户传入的 siginfo_t 创建 SigInfo 实例 (rt_sigqueueinfo)
+    ///
+    /// 只保留 `_rt` 字段中的发送者与 sigval，`si_signo` 以实际发送的信号为准
+    pub fn from_raw_queued(signo: Signal, raw_siginfo: &siginfo_t) -> Self {
+        let siginfo_ = unsafe { &raw_siginfo.__bindgen_anon_1.__bindgen_anon_1 };
+        let code = match SigCodeCommon::try_from(siginfo_.si_code) {
+            Ok(code) => SigCode::Common(code),
+            Err(_) => SigCode::UnknownCode(siginfo_.si_code),
+        };
+        let rt = unsafe { &siginfo_._sifields._rt };
+        SigInfo {
+            signo,
+            errno: siginfo_.si_errno,
+            code,
+            data: SigInfoData::Realtime {
+                pid: rt._pid,
+                uid: rt._uid,
+                value: unsafe { rt._sigval.sival_ptr } as usize,
+            },
         }
     }
     /// 创建一个 I/O 事件相关的 SigInfo 实例
@@ -359,11 +408,10 @@ impl SigInfo {
             SigInfoData::BusError { addr } => {
                 sifields._sigfault._addr = unsafe { addr.as_ptr() } as *mut c_void;
             }
-            SigInfoData::Realtime { value, ptr } => {
-                sifields._rt._pid = 0; // 可能需要根据实际情况设置
-                sifields._rt._uid = 0; // 可能需要根据实际情况设置
-                sifields._rt._sigval.sival_int = *value;
-                sifields._rt._sigval.sival_ptr = unsafe { ptr.as_ptr() } as *mut c_void;
+            SigInfoData::Realtime { pid, uid, value } => {
+                sifields._rt._pid = *pid;
+                sifields._rt._uid = *uid;
+                sifields._rt._sigval.sival_ptr = *value as *mut c_void;
             }
             SigInfoData::PollIO { fd, band } => {
                 sifields._sigpoll._fd = *fd;
@@ -104,12 +104,21 @@ syscall_handler_def!(
         renameat => [old_dirfd, old_path, new_dirfd, new_path, ..] {
             apply!(syscall_imp::fs::sys_renameat, old_dirfd, old_path, new_dirfd, new_path)
         }
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        renameat2 => [old_dirfd, old_path, new_dirfd, new_path, flags, ..] {
+            apply!(syscall_imp::fs::sys_renameat2, old_dirfd, old_path, new_dirfd, new_path, flags)
+        }
         // 文件操作相关系统调用
         #[cfg(all(feature = "fs", feature = "fd"))]
         openat => [dirfd, fname, flags, mode, ..] {
             apply!(syscall_imp::fs::sys_openat, dirfd, fname, flags, mode)
         }
 
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        openat2 => [dirfd, fname, how, size, ..] {
+            apply!(syscall_imp::fs::sys_openat2, dirfd, fname, how, size)
+        }
+
         #[cfg(all(feature = "fs", feature = "fd"))]
         close => [fd, ..] {
             apply!(syscall_imp::fd::sys_close, fd)
@@ -130,6 +139,31 @@ syscall_handler_def!(
              apply!(syscall_imp::fs::sys_unlinkat, dirfd, path_name)
         }
 
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        linkat => [old_dirfd, old_path, new_dirfd, new_path, flags, ..] {
+            apply!(syscall_imp::fs::sys_linkat, old_dirfd, old_path, new_dirfd, new_path, flags)
+        }
+
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        symlinkat => [target, new_dirfd, link_path, ..] {
+            apply!(syscall_imp::fs::sys_symlinkat, target, new_dirfd, link_path)
+        }
+
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        readlinkat => [dirfd, path, buf, bufsiz, ..] {
+            apply!(syscall_imp::fs::sys_readlinkat, dirfd, path, buf, bufsiz)
+        }
+
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        fchmodat => [dirfd, path, mode, flags, ..] {
+            apply!(syscall_imp::fs::sys_fchmodat, dirfd, path, mode, flags)
+        }
+
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        fchmod => [fd, mode, ..] {
+            apply!(syscall_imp::fs::sys_fchmod, fd, mode)
+        }
+
         /* TODO:
          *#[cfg(all(feature = "fs", feature = "fd"))]
          *statfs => _args {
@@ -145,6 +179,10 @@ syscall_handler_def!(
             apply!(syscall_imp::fs::sys_mkdirat, dir_fd, path, perm)
         }
         #[cfg(all(feature = "fs", feature = "fd"))]
+        mknodat => [dirfd, path, mode, dev, ..] {
+            apply!(syscall_imp::fs::sys_mknodat, dirfd, path, mode, dev)
+        }
+        #[cfg(all(feature = "fs", feature = "fd"))]
         getdents64 => [fd, buf, count, ..] {
             //apply!(sys_getdents, fd, buf, count)
             validate_ptr!(buf, ctypes::dirent, MappingFlags::WRITE);
@@ -206,8 +244,13 @@ syscall_handler_def!(
         }
 
         #[cfg(all(feature = "fs", feature = "fd"))]
-        dup3 => [old_fd, new_fd, ..] {
-            apply!(syscall_imp::fd::sys_dup3, old_fd, new_fd)
+        dup3 => [old_fd, new_fd, flags, ..] {
+            apply!(syscall_imp::fd::sys_dup3, old_fd, new_fd, flags)
+        }
+
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        close_range => [first, last, flags, ..] {
+            apply!(syscall_imp::fd::sys_close_range, first, last, flags)
         }
 
         #[cfg(all(feature = "fs", feature = "fd"))]
@@ -221,9 +264,9 @@ syscall_handler_def!(
             apply!(syscall_imp::fd::sys_ppoll, fds, nfds, timeout, sigmask)
         }
         #[cfg(feature = "pipe")]
-        pipe2 => [fds, ..] {
+        pipe2 => [fds, flags, ..] {
             let fds = unsafe { core::slice::from_raw_parts_mut(fds as *mut c_int, 2) };
-            syscall_imp::pipe::sys_pipe(fds)
+            syscall_imp::pipe::sys_pipe2(fds, flags as c_int)
         }
         pread64 => [fd, buf_ptr, size, off_t, ..] {
             fs::sys_pread64(fd as c_int, buf_ptr as *mut u8, size, off_t as isize)
@@ -231,12 +274,24 @@ syscall_handler_def!(
         pwrite64 => [fd, buf_ptr, size, off_t,..] {
             fs::sys_pwrite64(fd as c_int, buf_ptr as *mut u8, size, off_t as isize)
         }
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        fsync => [fd, ..] {
+            apply!(fs::sys_fsync, fd)
+        }
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        fdatasync => [fd, ..] {
+            apply!(fs::sys_fsync, fd)
+        }
+        #[cfg(feature = "fs")]
+        sync => _ {
+            fs::sys_sync()
+        }
 
-        mount => [src, mnt, fstype, mntflag,..]{
-            apply!(fs::sys_mount, src, mnt, fstype, mntflag)
+        mount => [src, mnt, fstype, mntflag, data, ..]{
+            apply!(fs::sys_mount, src, mnt, fstype, mntflag, data)
         }
-        umount2=> [mnt,..]{
-            apply!(fs::sys_umount2, mnt)
+        umount2=> [mnt, flags, ..]{
+            apply!(fs::sys_umount2, mnt, flags)
         }
         // 虚拟内存管理
         brk => [new_heap_top, ..] {
@@ -265,6 +320,18 @@ syscall_handler_def!(
         rt_sigreturn => _ {
             syscall_imp::signal::sys_rt_sigreturn()
         }
+        restart_syscall => _ {
+            syscall_imp::signal::sys_restart_syscall()
+        }
+        rt_sigqueueinfo => [tgid, sig, uinfo, ..] {
+            syscall_imp::signal::sys_rt_sigqueueinfo(tgid as i32, sig as u32, uinfo)
+        }
+        rt_tgsigqueueinfo => [tgid, tid, sig, uinfo, ..] {
+            syscall_imp::signal::sys_rt_tgsigqueueinfo(tgid as i32, tid as i32, sig as u32, uinfo)
+        }
+        sigaltstack => [ss, old_ss, ..] {
+            syscall_imp::signal::sys_sigaltstack(ss, old_ss)
+        }
         rt_sigsuspend => [mask_ptr, sigsetsize, ..] {
             syscall_imp::signal::sys_rt_sigsuspend(mask_ptr, sigsetsize)
         }
@@ -287,6 +354,12 @@ syscall_handler_def!(
         set_tid_address => [tidptr, ..] {
             syscall_imp::process::sys_set_tid_address(tidptr)
         }
+        set_robust_list => [head, len, ..] {
+            syscall_imp::process::sys_set_robust_list(head, len)
+        }
+        get_robust_list => [pid, head_ptr, len_ptr, ..] {
+            apply!(syscall_imp::process::sys_get_robust_list, pid, head_ptr, len_ptr)
+        }
         getpid => _ {
             syscall_imp::process::sys_getpid()
         }
@@ -296,6 +369,9 @@ syscall_handler_def!(
         getppid => _ {
             syscall_imp::process::sys_getppid()
         }
+        prctl => [option, arg2, ..] {
+            apply!(syscall_imp::process::sys_prctl, option, arg2)
+        }
         getgid => _ {
             syscall_imp::process::sys_getgid()
         }
@@ -315,6 +391,40 @@ syscall_handler_def!(
             syscall_imp::process::sys_setxattr()
         }
         sched_yield => _ syscall_imp::task::sys_yield()
+        sched_setaffinity => [pid, len, mask, ..] {
+            apply!(syscall_imp::task::sys_sched_setaffinity, pid, len, mask)
+        }
+        sched_getaffinity => [pid, len, mask, ..] {
+            apply!(syscall_imp::task::sys_sched_getaffinity, pid, len, mask)
+        }
+        sched_setscheduler => [pid, policy, param, ..] {
+            apply!(syscall_imp::task::sys_sched_setscheduler, pid, policy, param)
+        }
+        sched_getscheduler => [pid, ..] {
+            apply!(syscall_imp::task::sys_sched_getscheduler, pid)
+        }
+        sched_setparam => [pid, param, ..] {
+            apply!(syscall_imp::task::sys_sched_setparam, pid, param)
+        }
+        sched_getparam => [pid, param, ..] {
+            apply!(syscall_imp::task::sys_sched_getparam, pid, param)
+        }
+        sched_get_priority_max => [policy, ..] {
+            apply!(syscall_imp::task::sys_sched_get_priority_max, policy)
+        }
+        sched_get_priority_min => [policy, ..] {
+            apply!(syscall_imp::task::sys_sched_get_priority_min, policy)
+        }
+        setpriority => [which, who, prio, ..] {
+            apply!(syscall_imp::task::sys_setpriority, which, who, prio)
+        }
+        getpriority => [which, who, ..] {
+            apply!(syscall_imp::task::sys_getpriority, which, who)
+        }
+        #[cfg(target_arch = "x86_64")]
+        nice => [inc, ..] {
+            apply!(syscall_imp::task::sys_nice, inc)
+        }
 
         // 时间相关系统调用
         times => [tms_ptr, ..] {
@@ -324,27 +434,34 @@ syscall_handler_def!(
             validate_ptr!(ts, ctypes::timespec, MappingFlags::WRITE);
             apply!(syscall_imp::time::sys_clock_gettime, clk_id, ts)
         }
-        clock_gettime64 => args {
-            let cls = args[0];
-            let ts: *mut ctypes::timespec = args[1] as *mut ctypes::timespec;
-            syscall_imp::time::sys_clock_gettime(cls as ctypes::clockid_t, ts)
+        clock_gettime64 => [clk_id, ts, ..] {
+            apply!(syscall_imp::time::sys_clock_gettime, clk_id, ts)
+        }
+        clock_getres => [clk_id, res, ..] {
+            apply!(syscall_imp::time::sys_clock_getres, clk_id, res)
+        }
+        clock_settime => [clk_id, ts, ..] {
+            validate_ptr!(ts, ctypes::timespec, MappingFlags::READ);
+            apply!(syscall_imp::time::sys_clock_settime, clk_id, ts)
         }
         gettimeofday => [ts, ..] {
             validate_ptr!(ts, ctypes::timeval, MappingFlags::WRITE);
             apply!(syscall_imp::time::sys_get_time_of_day, ts)
         }
+        settimeofday => [tv, ..] {
+            validate_ptr!(tv, ctypes::timeval, MappingFlags::READ, nullable);
+            apply!(syscall_imp::time::sys_settimeofday, tv)
+        }
         nanosleep => args {
             let req: *const ctypes::timespec = args[0] as *const ctypes::timespec;
             let rem: *mut ctypes::timespec = args[1] as *mut ctypes::timespec;
             syscall_imp::time::sys_nanosleep(req, rem)
         }
-        clock_nanosleep_time64 => args {
-            // TODO: handle clock_id and flags
-            let _clock_id = args[0];
-            let _flags = args[1];
-            let req: *const ctypes::timespec = args[2] as *const ctypes::timespec;
-            let rem: *mut ctypes::timespec = args[3] as *mut ctypes::timespec;
-            syscall_imp::time::sys_nanosleep(req, rem)
+        clock_nanosleep => [clk_id, flags, req, rem, ..] {
+            apply!(syscall_imp::time::sys_clock_nanosleep, clk_id, flags, req, rem)
+        }
+        clock_nanosleep_time64 => [clk_id, flags, req, rem, ..] {
+            apply!(syscall_imp::time::sys_clock_nanosleep, clk_id, flags, req, rem)
         }
         //资源相关系统调用
         getrlimit => [resource, rlimit, ..] {
@@ -362,6 +479,8 @@ syscall_handler_def!(
         }
         // 其他系统调用
         uname => [buf, ..] apply!(sys_uname, buf),
+        sysinfo => [info, ..] apply!(syscall_imp::sys::sys_sysinfo, info),
+        syslog => [action, buf, len, ..] apply!(syscall_imp::sys::sys_syslog, action, buf, len),
         ioctl=>_{
             Ok(0)
         }
@@ -415,6 +534,12 @@ syscall_handler_def!(
             unsafe { apply!(syscall_imp::net::sys_accept, fd, addr, addrlen) }
         }
 
+        #[cfg(feature = "net")]
+        // fd, addr, addrlen, flags
+        accept4 => [fd, addr, addrlen, flags, ..] {
+            unsafe { apply!(syscall_imp::net::sys_accept4, fd, addr, addrlen, flags) }
+        }
+
         #[cfg(feature = "net")]
         // fd, how
         shutdown => [fd, how, ..] {
@@ -432,14 +557,13 @@ syscall_handler_def!(
         getpeername => [fd, addr, addrlen, ..] {
             unsafe { apply!(syscall_imp::net::sys_getpeername, fd, addr, addrlen) }
         }
-        sched_getaffinity => _ {
-            Ok(0)
-        }
-        fchown => _ {
-            Ok(0)
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        fchown => [fd, owner, group, ..] {
+            apply!(syscall_imp::fs::sys_fchown, fd, owner, group)
         }
-        fchownat => _ {
-            Ok(0)
+        #[cfg(all(feature = "fs", feature = "fd"))]
+        fchownat => [dirfd, path, owner, group, flags, ..] {
+            apply!(syscall_imp::fs::sys_fchownat, dirfd, path, owner, group, flags)
         }
         futex => [uaddr, futex_op, val, timeout, uaddr2, val3, ..] {
             error!("exit futex");
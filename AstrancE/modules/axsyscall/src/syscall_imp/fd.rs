@@ -29,8 +29,14 @@ pub fn sys_fcntl(fd: c_int, cmd: c_int, arg: usize) -> SyscallResult {
 
 #[cfg(feature = "fd")]
 #[inline]
-pub fn sys_dup3(old_fd: c_int, new_fd: c_int) -> SyscallResult {
-    api::sys_dup2(old_fd, new_fd).to_linux_result()
+pub fn sys_dup3(old_fd: c_int, new_fd: c_int, flags: c_int) -> SyscallResult {
+    api::sys_dup3(old_fd, new_fd, flags).to_linux_result()
+}
+
+#[cfg(feature = "fd")]
+#[inline]
+pub fn sys_close_range(first: u32, last: u32, flags: u32) -> SyscallResult {
+    api::sys_close_range(first, last, flags).to_linux_result()
 }
 
 #[inline]
@@ -3,7 +3,7 @@ use arceos_posix_api::ctypes::{blkcnt_t, blksize_t, dev_t, gid_t, ino_t, mode_t,
 use arceos_posix_api::{self as api, char_ptr_to_str, ctypes};
 use axfs::api::set_current_dir;
 use axlog::debug;
-use core::ffi::{c_char, c_int, c_long, c_longlong, c_void};
+use core::ffi::{c_char, c_int, c_long, c_longlong, c_uint, c_void};
 use axfs_vfs::FileSystemInfo;
 // use ctype_my::statx;
 use api::ctype_my::statx;
@@ -63,6 +63,16 @@ pub fn sys_openat(
     api::sys_openat(dirfd, filename, flags, mode).to_linux_result()
 }
 
+#[inline]
+pub fn sys_openat2(
+    dirfd: c_int,
+    filename: *const c_char,
+    how: *const api::ctype_my::open_how,
+    size: usize,
+) -> SyscallResult {
+    api::sys_openat2(dirfd, filename, how, size)
+}
+
 #[inline]
 pub fn sys_lseek(fd: c_int, offset: ctypes::off_t, whence: c_int) -> SyscallResult {
     (api::sys_lseek(fd, offset, whence) as isize).to_linux_result()
@@ -98,7 +108,18 @@ pub fn sys_renameat(
     newdfd: c_int,
     new: *const c_char,
 ) -> SyscallResult {
-    api::sys_renameat(olddfd, old, newdfd, new).to_linux_result()
+    api::sys_renameat2(olddfd, old, newdfd, new, 0)
+}
+
+#[inline]
+pub fn sys_renameat2(
+    olddfd: c_int,
+    old: *const c_char,
+    newdfd: c_int,
+    new: *const c_char,
+    flags: c_uint,
+) -> SyscallResult {
+    api::sys_renameat2(olddfd, old, newdfd, new, flags)
 }
 
 
@@ -147,6 +168,11 @@ pub fn sys_mkdirat(dir_fd: usize, dir_path: *const c_char, mode: usize) -> Sysca
         .to_linux_result()
 }
 
+#[inline]
+pub fn sys_mknodat(dirfd: c_int, path: *const c_char, mode: mode_t, dev: u64) -> SyscallResult {
+    api::sys_mknodat(dirfd, path, mode, dev)
+}
+
 #[inline]
 pub fn sys_chdir(path: *const c_char) -> SyscallResult {
     let ret = char_ptr_to_str(path).map(|chdir_path| set_current_dir(&chdir_path));
@@ -171,6 +197,62 @@ pub fn sys_unlinkat(dir_fd: c_int, path: *const c_char) -> SyscallResult {
     api::sys_unlinkat(dir_fd, path)
 }
 
+#[inline]
+pub fn sys_linkat(
+    old_dirfd: c_int,
+    old_path: *const c_char,
+    new_dirfd: c_int,
+    new_path: *const c_char,
+    flags: c_int,
+) -> SyscallResult {
+    api::sys_linkat(old_dirfd, old_path, new_dirfd, new_path, flags)
+}
+
+#[inline]
+pub fn sys_symlinkat(
+    target: *const c_char,
+    new_dirfd: c_int,
+    link_path: *const c_char,
+) -> SyscallResult {
+    api::sys_symlinkat(target, new_dirfd, link_path)
+}
+
+#[inline]
+pub fn sys_readlinkat(
+    dirfd: c_int,
+    path: *const c_char,
+    buf: *mut c_char,
+    bufsiz: usize,
+) -> SyscallResult {
+    api::sys_readlinkat(dirfd, path, buf, bufsiz)
+}
+
+#[inline]
+pub fn sys_fchmodat(dirfd: c_int, path: *const c_char, mode: u32, flags: c_int) -> SyscallResult {
+    api::sys_fchmodat(dirfd, path, mode as _, flags)
+}
+
+#[inline]
+pub fn sys_fchmod(fd: c_int, mode: u32) -> SyscallResult {
+    api::sys_fchmod(fd, mode as _)
+}
+
+#[inline]
+pub fn sys_fchownat(
+    dirfd: c_int,
+    path: *const c_char,
+    owner: u32,
+    group: u32,
+    flags: c_int,
+) -> SyscallResult {
+    api::sys_fchownat(dirfd, path, owner, group, flags)
+}
+
+#[inline]
+pub fn sys_fchown(fd: c_int, owner: u32, group: u32) -> SyscallResult {
+    api::sys_fchown(fd, owner, group)
+}
+
 pub fn sys_fgetxattr(
     fd: c_int,
     name: *const c_char,
@@ -201,12 +283,18 @@ pub fn sys_fremovexattr(
     api::sys_fremovexattr(fd, name).to_linux_result()
 }
 
-pub fn sys_mount(src: *const c_char, mnt: *const c_char, fstype: *const c_char, mntflag: usize) -> SyscallResult {
-    api::sys_mount(src, mnt, fstype, mntflag)
+pub fn sys_mount(
+    src: *const c_char,
+    mnt: *const c_char,
+    fstype: *const c_char,
+    mntflag: usize,
+    data: *const c_void,
+) -> SyscallResult {
+    api::sys_mount(src, mnt, fstype, mntflag, data)
 }
 
-pub fn sys_umount2(mnt: *const c_char) -> SyscallResult {
-    api::sys_umount2(mnt)
+pub fn sys_umount2(mnt: *const c_char, flags: c_int) -> SyscallResult {
+    api::sys_umount2(mnt, flags)
 }
 
 pub fn sys_utimesat(
@@ -237,6 +325,16 @@ pub fn sys_pwrite64(
     api::sys_pwrite64(fd, buf, count, offset)?.to_linux_result()
 }
 
+#[inline]
+pub fn sys_fsync(fd: c_int) -> SyscallResult {
+    api::sys_fsync(fd)
+}
+
+#[inline]
+pub fn sys_sync() -> SyscallResult {
+    api::sys_sync()
+}
+
 pub fn sys_statfs(
     _path: *const c_char,
     stat_fs:*mut FileSystemInfo
@@ -1,6 +1,7 @@
 use crate::SyscallResult;
 use crate::ToLinuxResult;
 use arceos_posix_api::{self as api, ctypes};
+use axerrno::LinuxError;
 use core::ffi::c_char;
 use core::ffi::c_int;
 use core::ffi::c_void;
@@ -14,14 +15,24 @@ pub fn sys_readv(fd: c_int, iov: *const ctypes::iovec, iocnt: c_int) -> SyscallR
     unsafe { api::sys_readv(fd, iov, iocnt).to_linux_result() }
 }
 
+/// 向没有读端的管道写入返回 `EPIPE` 时，同时向当前线程发送 `SIGPIPE`
+fn raise_sigpipe(result: SyscallResult) -> SyscallResult {
+    if matches!(result, Err(LinuxError::EPIPE)) {
+        axmono::task::send_sigpipe_current();
+    }
+    result
+}
+
 #[inline]
 pub fn sys_write(fd: usize, buf: &[u8]) -> SyscallResult {
-    api::sys_write(fd as i32, buf.as_ptr() as *mut c_void, buf.len()).to_linux_result()
+    raise_sigpipe(
+        api::sys_write(fd as i32, buf.as_ptr() as *mut c_void, buf.len()).to_linux_result(),
+    )
 }
 
 #[inline]
 pub fn sys_writev(fd: c_int, iov: *const ctypes::iovec, iocnt: c_int) -> SyscallResult {
-    unsafe { api::sys_writev(fd, iov, iocnt) }.to_linux_result()
+    raise_sigpipe(unsafe { api::sys_writev(fd, iov, iocnt) }.to_linux_result())
 }
 
 #[inline]
@@ -82,6 +82,17 @@ pub unsafe fn sys_accept(
     unsafe { api::sys_accept(socket_fd, addr, addrlen) }.to_linux_result()
 }
 #[cfg(feature = "net")]
+// Connection acceptance with flags
+#[inline]
+pub unsafe fn sys_accept4(
+    socket_fd: c_int,
+    addr: *mut ctypes::sockaddr,
+    addrlen: *mut ctypes::socklen_t,
+    flags: c_int,
+) -> SyscallResult {
+    unsafe { api::sys_accept4(socket_fd, addr, addrlen, flags) }.to_linux_result()
+}
+#[cfg(feature = "net")]
 // Socket shutdown
 #[inline]
 pub fn sys_shutdown(socket_fd: c_int, _how: c_int) -> SyscallResult {
@@ -11,3 +11,11 @@ pub fn sys_pipe(fds: &mut [c_int]) -> SyscallResult {
     debug!("pipe: fds = {:?}", fds);
     api::sys_pipe(fds).to_linux_result()
 }
+
+#[cfg(feature = "pipe")]
+#[inline]
+pub fn sys_pipe2(fds: &mut [c_int], flags: c_int) -> SyscallResult {
+    use crate::ToLinuxResult;
+    debug!("pipe2: fds = {:?}, flags = {:#o}", fds, flags);
+    api::sys_pipe2(fds, flags).to_linux_result()
+}
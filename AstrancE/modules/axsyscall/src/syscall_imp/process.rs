@@ -37,6 +37,16 @@ pub fn sys_set_tid_address(tidptr: usize) -> SyscallResult {
     axmono::syscall::process::sys_set_tid_address(tidptr)
 }
 
+#[inline]
+pub fn sys_set_robust_list(head: usize, len: usize) -> SyscallResult {
+    axmono::syscall::pthread::sys_set_robust_list(head, len)
+}
+
+#[inline]
+pub fn sys_get_robust_list(pid: u32, head_ptr: usize, len_ptr: usize) -> SyscallResult {
+    axmono::syscall::pthread::sys_get_robust_list(pid, head_ptr, len_ptr)
+}
+
 #[inline]
 pub fn sys_getpid() -> SyscallResult {
     axmono::syscall::process::sys_getpid()
@@ -86,3 +96,8 @@ pub fn sys_setxattr() -> SyscallResult {
 pub fn sys_futex() -> SyscallResult {
     axmono::syscall::process::sys_futex()
 }
+
+#[inline]
+pub fn sys_prctl(option: u32, arg2: usize) -> SyscallResult {
+    axmono::syscall::process::sys_prctl(option, arg2)
+}
@@ -21,6 +21,26 @@ pub fn sys_rt_sigreturn() -> SyscallResult {
     axmono::syscall::signal::sys_rt_sigreturn()
 }
 
+#[inline]
+pub fn sys_rt_sigqueueinfo(tgid: i32, sig: u32, uinfo: usize) -> SyscallResult {
+    axmono::syscall::signal::sys_rt_sigqueueinfo(tgid, sig, uinfo)
+}
+
+#[inline]
+pub fn sys_rt_tgsigqueueinfo(tgid: i32, tid: i32, sig: u32, uinfo: usize) -> SyscallResult {
+    axmono::syscall::signal::sys_rt_tgsigqueueinfo(tgid, tid, sig, uinfo)
+}
+
+#[inline]
+pub fn sys_sigaltstack(ss: usize, old_ss: usize) -> SyscallResult {
+    axmono::syscall::signal::sys_sigaltstack(ss, old_ss)
+}
+
+#[inline]
+pub fn sys_restart_syscall() -> SyscallResult {
+    axmono::syscall::signal::sys_restart_syscall()
+}
+
 #[inline]
 pub fn sys_rt_sigsuspend(mask_ptr: usize, sigsetsize: usize) -> SyscallResult {
     axmono::syscall::signal::sys_rt_sigsuspend(mask_ptr, sigsetsize)
@@ -1,13 +1,20 @@
 use crate::{SyscallResult, ToLinuxResult};
 use arceos_posix_api::ctypes::pid_t;
 use arceos_posix_api::{self as api, ctypes};
+use axerrno::LinuxError;
 use core::ffi::c_int;
 
 pub fn sys_getrlimit(resource: c_int, rlimits: *mut ctypes::rlimit) -> SyscallResult {
+    if resource as u32 == ctypes::RLIMIT_CORE {
+        return axmono::syscall::sys::sys_prlimit_core(0.into(), (rlimits as usize).into());
+    }
     unsafe { api::sys_getrlimit(resource, rlimits).to_linux_result() }
 }
 
 pub fn sys_setrlimit(resource: c_int, rlimits: *mut ctypes::rlimit) -> SyscallResult {
+    if resource as u32 == ctypes::RLIMIT_CORE {
+        return axmono::syscall::sys::sys_prlimit_core((rlimits as usize).into(), 0.into());
+    }
     unsafe { api::sys_setrlimit(resource, rlimits).to_linux_result() }
 }
 
@@ -17,5 +24,14 @@ pub fn sys_prlimit64(
     new_limit: *mut ctypes::rlimit,
     old_limit: *mut ctypes::rlimit,
 ) -> SyscallResult {
+    if resource as u32 == ctypes::RLIMIT_CORE {
+        if pid != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        return axmono::syscall::sys::sys_prlimit_core(
+            (new_limit as usize).into(),
+            (old_limit as usize).into(),
+        );
+    }
     unsafe { api::sys_prlimit64(pid, resource, new_limit, old_limit).to_linux_result() }
 }
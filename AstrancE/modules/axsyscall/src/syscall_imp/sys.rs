@@ -7,3 +7,13 @@ use crate::{SyscallResult, ToLinuxResult};
 pub fn sys_uname(buf: *mut u8) -> SyscallResult {
     arceos_posix_api::sys_uname(buf as *mut UtsName).to_linux_result()
 }
+
+#[inline]
+pub fn sys_sysinfo(info: usize) -> SyscallResult {
+    axmono::syscall::sys::sys_sysinfo(info.into())
+}
+
+#[inline]
+pub fn sys_syslog(action: i32, buf: usize, len: i32) -> SyscallResult {
+    axmono::syscall::sys::sys_syslog(action, buf.into(), len)
+}
@@ -27,3 +27,58 @@ pub fn sys_exit(code: c_int) -> SyscallResult {
 pub fn sys_yield() -> SyscallResult {
     api::sys_sched_yield().to_linux_result()
 }
+
+#[inline]
+pub fn sys_sched_setaffinity(pid: c_int, len: usize, mask: usize) -> SyscallResult {
+    axmono::syscall::sched::sys_sched_setaffinity(pid, len, mask.into())
+}
+
+#[inline]
+pub fn sys_sched_getaffinity(pid: c_int, len: usize, mask: usize) -> SyscallResult {
+    axmono::syscall::sched::sys_sched_getaffinity(pid, len, mask.into())
+}
+
+#[inline]
+pub fn sys_sched_setscheduler(pid: c_int, policy: c_int, param: usize) -> SyscallResult {
+    axmono::syscall::sched::sys_sched_setscheduler(pid, policy, param.into())
+}
+
+#[inline]
+pub fn sys_sched_getscheduler(pid: c_int) -> SyscallResult {
+    axmono::syscall::sched::sys_sched_getscheduler(pid)
+}
+
+#[inline]
+pub fn sys_sched_setparam(pid: c_int, param: usize) -> SyscallResult {
+    axmono::syscall::sched::sys_sched_setparam(pid, param.into())
+}
+
+#[inline]
+pub fn sys_sched_getparam(pid: c_int, param: usize) -> SyscallResult {
+    axmono::syscall::sched::sys_sched_getparam(pid, param.into())
+}
+
+#[inline]
+pub fn sys_sched_get_priority_max(policy: c_int) -> SyscallResult {
+    axmono::syscall::sched::sys_sched_get_priority_max(policy)
+}
+
+#[inline]
+pub fn sys_sched_get_priority_min(policy: c_int) -> SyscallResult {
+    axmono::syscall::sched::sys_sched_get_priority_min(policy)
+}
+
+#[inline]
+pub fn sys_setpriority(which: c_int, who: c_int, prio: c_int) -> SyscallResult {
+    axmono::syscall::sched::sys_setpriority(which, who, prio)
+}
+
+#[inline]
+pub fn sys_getpriority(which: c_int, who: c_int) -> SyscallResult {
+    axmono::syscall::sched::sys_getpriority(which, who)
+}
+
+#[inline]
+pub fn sys_nice(inc: c_int) -> SyscallResult {
+    axmono::syscall::sched::sys_nice(inc)
+}
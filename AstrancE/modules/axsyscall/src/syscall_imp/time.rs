@@ -1,9 +1,20 @@
 use crate::{SyscallResult, ToLinuxResult};
 use arceos_posix_api::{self as api, ctypes};
+use core::ffi::c_int;
 
 #[inline]
-pub fn sys_clock_gettime(clk: ctypes::clockid_t, ts: *mut ctypes::timespec) -> SyscallResult {
-    unsafe { api::sys_clock_gettime(clk, ts).to_linux_result() }
+pub fn sys_clock_gettime(clk: i32, ts: usize) -> SyscallResult {
+    axmono::syscall::time::sys_clock_gettime(clk, ts.into())
+}
+
+#[inline]
+pub fn sys_clock_getres(clk: i32, res: usize) -> SyscallResult {
+    axmono::syscall::time::sys_clock_getres(clk, res.into())
+}
+
+#[inline]
+pub fn sys_clock_settime(clk: ctypes::clockid_t, ts: *const ctypes::timespec) -> SyscallResult {
+    unsafe { api::sys_clock_settime(clk, ts) }.to_linux_result()
 }
 
 #[inline]
@@ -11,11 +22,26 @@ pub fn sys_nanosleep(req: *const ctypes::timespec, rem: *mut ctypes::timespec) -
     unsafe { api::sys_nanosleep(req, rem) }.to_linux_result()
 }
 
+#[inline]
+pub fn sys_clock_nanosleep(
+    clk: ctypes::clockid_t,
+    flags: c_int,
+    req: *const ctypes::timespec,
+    rem: *mut ctypes::timespec,
+) -> SyscallResult {
+    unsafe { api::sys_clock_nanosleep(clk, flags, req, rem) }.to_linux_result()
+}
+
 #[inline]
 pub fn sys_get_time_of_day(ts: *mut ctypes::timeval) -> SyscallResult {
     unsafe { api::sys_get_time_of_day(ts) }.to_linux_result()
 }
 
+#[inline]
+pub fn sys_settimeofday(tv: *const ctypes::timeval) -> SyscallResult {
+    unsafe { api::sys_settimeofday(tv) }.to_linux_result()
+}
+
 #[inline]
 pub fn sys_times(tms_ptr: usize) -> SyscallResult {
     axmono::syscall::time::sys_times(tms_ptr)
@@ -12,11 +12,14 @@ use kernel_guard::NoPreemptIrqSave;
 pub(crate) use crate::run_queue::{current_run_queue, select_run_queue};
 
 #[doc(cfg(feature = "multitask"))]
-pub use crate::task::{CurrentTask, TaskId, TaskInner};
+pub use crate::task::{CurrentTask, RestartBlock, SyscallRestart, TaskId, TaskInner};
 #[doc(cfg(feature = "multitask"))]
 pub use crate::task_ext::{TaskExtMut, TaskExtRef};
 #[doc(cfg(feature = "multitask"))]
-pub use crate::wait_queue::WaitQueue;
+pub use crate::wait_queue::{Interrupted, WaitQueue};
+#[cfg(feature = "irq")]
+#[doc(cfg(feature = "irq"))]
+pub use crate::loadavg::{FIXED_1, FSHIFT, load_average};
 
 /// The reference type of a task.
 pub type AxTaskRef = Arc<AxTask>;
@@ -29,18 +32,35 @@ pub use crate::task::TaskState;
 /// The wrapper type for [`cpumask::CpuMask`] with SMP configuration.
 pub type AxCpuMask = cpumask::CpuMask<{ axconfig::SMP }>;
 
+/// The kinds of the underlying scheduler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedulerKind {
+    /// First-in-first-out, without preemption.
+    Fifo,
+    /// Round-robin with fixed time slices.
+    RoundRobin,
+    /// Completely fair scheduler, with nice values as priorities.
+    Cfs,
+}
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "sched_rr")] {
         const MAX_TIME_SLICE: usize = 5;
         pub(crate) type AxTask = scheduler::RRTask<TaskInner, MAX_TIME_SLICE>;
         pub(crate) type Scheduler = scheduler::RRScheduler<TaskInner, MAX_TIME_SLICE>;
+        /// The kind of the compiled scheduler.
+        pub const SCHEDULER_KIND: SchedulerKind = SchedulerKind::RoundRobin;
     } else if #[cfg(feature = "sched_cfs")] {
         pub(crate) type AxTask = scheduler::CFSTask<TaskInner>;
         pub(crate) type Scheduler = scheduler::CFScheduler<TaskInner>;
+        /// The kind of the compiled scheduler.
+        pub const SCHEDULER_KIND: SchedulerKind = SchedulerKind::Cfs;
     } else {
         // If no scheduler features are set, use FIFO as the default.
         pub(crate) type AxTask = scheduler::FifoTask<TaskInner>;
         pub(crate) type Scheduler = scheduler::FifoScheduler<TaskInner>;
+        /// The kind of the compiled scheduler.
+        pub const SCHEDULER_KIND: SchedulerKind = SchedulerKind::Fifo;
     }
 }
 
@@ -104,11 +124,23 @@ pub fn init_scheduler_secondary() {
 pub fn on_timer_tick() {
     use kernel_guard::NoOp;
     crate::timers::check_events();
+    crate::loadavg::update();
     // Since irq and preemption are both disabled here,
     // we can get current run queue with the default `kernel_guard::NoOp`.
     current_run_queue::<NoOp>().scheduler_timer_tick();
 }
 
+/// Returns the number of runnable tasks, including the ones currently
+/// running on CPUs but excluding idle tasks.
+pub fn nr_running() -> usize {
+    crate::run_queue::nr_running()
+}
+
+/// Returns the total time all CPUs have spent idle since boot.
+pub fn idle_time() -> core::time::Duration {
+    core::time::Duration::from_nanos(crate::run_queue::idle_time_nanos())
+}
+
 /// Adds the given task to the run queue, returns the task reference.
 pub fn spawn_task(task: TaskInner) -> AxTaskRef {
     let task_ref = task.into_arc();
@@ -157,11 +189,46 @@ pub fn set_priority(prio: isize) -> bool {
     current_run_queue::<NoPreemptIrqSave>().set_current_priority(prio)
 }
 
+/// Set the priority for the given task.
+///
+/// See [`set_priority`] for the range of the priority.
+///
+/// Returns `true` if the priority is set successfully.
+pub fn set_task_priority(task: &AxTaskRef, prio: isize) -> bool {
+    if current().ptr_eq(task) {
+        return set_priority(prio);
+    }
+    // Set it through the run queue the task is in, as the scheduler there may
+    // be updating the task. Retry if the task is moved in the meantime.
+    loop {
+        let mut rq = crate::run_queue::task_run_queue::<NoPreemptIrqSave>(task);
+        if let Some(res) = rq.set_task_priority(task, prio) {
+            return res;
+        }
+    }
+}
+
+/// Set the affinity for the given task.
+///
+/// If `task` is the current task, it is the same as [`set_current_affinity`].
+/// Otherwise, the new affinity takes effect when the task is put into a run
+/// queue next time, e.g., after being woken up.
+///
+/// Returns `true` if the affinity is set successfully.
+pub fn set_task_affinity(task: &AxTaskRef, cpumask: AxCpuMask) -> bool {
+    if cpumask.is_empty() {
+        false
+    } else if current().ptr_eq(task) {
+        set_current_affinity(cpumask)
+    } else {
+        task.set_cpumask(cpumask);
+        true
+    }
+}
+
 /// Set the affinity for the current task.
 /// [`AxCpuMask`] is used to specify the CPU affinity.
 /// Returns `true` if the affinity is set successfully.
-///
-/// TODO: support set the affinity for other tasks.
 pub fn set_current_affinity(cpumask: AxCpuMask) -> bool {
     if cpumask.is_empty() {
         false
@@ -214,6 +281,44 @@ pub fn sleep_until(deadline: axhal::time::TimeValue) {
     axhal::time::busy_wait_until(deadline);
 }
 
+/// Current task is going to sleep until the given deadline like [`sleep_until`],
+/// but it can be woken up early by [`interrupt_task`].
+///
+/// Returns [`Err(Interrupted)`](Interrupted) if the sleep is interrupted
+/// before the deadline.
+pub fn sleep_until_interruptible(deadline: axhal::time::TimeValue) -> Result<(), Interrupted> {
+    let curr = current();
+    if curr.is_interrupted() {
+        return Err(Interrupted);
+    }
+    curr.set_interruptible(true);
+    sleep_until(deadline);
+    curr.set_interruptible(false);
+    if curr.is_interrupted() && axhal::time::wall_time() < deadline {
+        Err(Interrupted)
+    } else {
+        Ok(())
+    }
+}
+
+/// Interrupts the given task.
+///
+/// The task is marked as interrupted until it calls
+/// [`TaskInner::clear_interrupt`], and is woken up if it is sleeping in an
+/// interruptible wait, such as [`sleep_until_interruptible`] and
+/// [`WaitQueue::wait_interruptible`]. Interruptible waits started while the
+/// task is marked as interrupted return immediately.
+///
+/// The monolithic kernel uses this to wake up tasks when a signal arrives.
+pub fn interrupt_task(task: &AxTaskRef) {
+    task.set_interrupted();
+    // Pairs with the fence in `cancel_interrupted_block()` of the run queue.
+    core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+    if task.is_interruptible() {
+        select_run_queue::<NoPreemptIrqSave>(task).unblock_task(task.clone(), true);
+    }
+}
+
 /// Exits the current task.
 pub fn exit(exit_code: i32) -> ! {
     current_run_queue::<NoPreemptIrqSave>().exit_current(exit_code)
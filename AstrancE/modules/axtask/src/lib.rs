@@ -48,6 +48,8 @@ cfg_if::cfg_if! {
 
         #[cfg(feature = "irq")]
         mod timers;
+        #[cfg(feature = "irq")]
+        mod loadavg;
 
         #[doc(cfg(feature = "multitask"))]
         pub use self::api::*;
@@ -0,0 +1,65 @@
+//! System load average, maintained the same way as Linux does.
+//!
+//! Every [`LOAD_FREQ`] the number of runnable tasks is sampled, and the 1, 5
+//! and 15-minute averages are updated with an exponentially-decayed moving
+//! average in fixed-point arithmetic.
+
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use axhal::time::{NANOS_PER_SEC, monotonic_time_nanos};
+
+/// Number of bits of precision of the fixed-point load values.
+pub const FSHIFT: usize = 11;
+/// `1.0` in fixed-point representation.
+pub const FIXED_1: usize = 1 << FSHIFT;
+
+/// Interval between two samples, 5 seconds.
+const LOAD_FREQ: u64 = 5 * NANOS_PER_SEC;
+/// `1 / exp(5sec / 1min)` in fixed-point.
+const EXP_1: usize = 1884;
+/// `1 / exp(5sec / 5min)` in fixed-point.
+const EXP_5: usize = 2014;
+/// `1 / exp(5sec / 15min)` in fixed-point.
+const EXP_15: usize = 2037;
+
+static AVENRUN: [AtomicUsize; 3] = [const { AtomicUsize::new(0) }; 3];
+static NEXT_SAMPLE: AtomicU64 = AtomicU64::new(LOAD_FREQ);
+
+fn calc_load(load: usize, exp: usize, active: usize) -> usize {
+    let mut newload = load * exp + active * (FIXED_1 - exp);
+    if active >= load {
+        newload += FIXED_1 - 1;
+    }
+    newload / FIXED_1
+}
+
+/// Samples the run queues and updates the load averages if a new sampling
+/// period has begun. Called on every timer tick.
+pub(crate) fn update() {
+    let now = monotonic_time_nanos();
+    let next = NEXT_SAMPLE.load(Ordering::Relaxed);
+    if now < next
+        || NEXT_SAMPLE
+            .compare_exchange(next, next + LOAD_FREQ, Ordering::AcqRel, Ordering::Relaxed)
+            .is_err()
+    {
+        // Not the time yet, or another CPU has taken this sample.
+        return;
+    }
+
+    let active = crate::run_queue::nr_running() * FIXED_1;
+    for (avg, exp) in AVENRUN.iter().zip([EXP_1, EXP_5, EXP_15]) {
+        let load = avg.load(Ordering::Relaxed);
+        avg.store(calc_load(load, exp, active), Ordering::Relaxed);
+    }
+}
+
+/// Returns the 1, 5 and 15-minute load averages in fixed-point
+/// representation, with [`FSHIFT`] bits of fraction.
+pub fn load_average() -> [usize; 3] {
+    [
+        AVENRUN[0].load(Ordering::Relaxed),
+        AVENRUN[1].load(Ordering::Relaxed),
+        AVENRUN[2].load(Ordering::Relaxed),
+    ]
+}
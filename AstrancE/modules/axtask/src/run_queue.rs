@@ -1,6 +1,7 @@
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
 use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 
 #[cfg(feature = "smp")]
 use alloc::sync::Weak;
@@ -53,6 +54,43 @@ static mut RUN_QUEUES: [MaybeUninit<&'static mut AxRunQueue>; axconfig::SMP] =
 #[allow(clippy::declare_interior_mutable_const)] // It's ok because it's used only for initialization `RUN_QUEUES`.
 const ARRAY_REPEAT_VALUE: MaybeUninit<&'static mut AxRunQueue> = MaybeUninit::uninit();
 
+/// Number of ready tasks held by the scheduler of each run queue, indexed by cpu_id.
+static NR_QUEUED: [AtomicUsize; axconfig::SMP] = [const { AtomicUsize::new(0) }; axconfig::SMP];
+/// Whether each CPU is currently running a task other than its idle task.
+static CPU_BUSY: [AtomicBool; axconfig::SMP] = [const { AtomicBool::new(false) }; axconfig::SMP];
+/// Accumulated nanoseconds each CPU has spent in its idle task.
+static IDLE_NANOS: [AtomicU64; axconfig::SMP] = [const { AtomicU64::new(0) }; axconfig::SMP];
+/// Timestamp at which each CPU last switched to its idle task.
+static IDLE_SINCE: [AtomicU64; axconfig::SMP] = [const { AtomicU64::new(0) }; axconfig::SMP];
+
+/// Returns the number of runnable tasks in the system, i.e. tasks waiting in
+/// any run queue plus tasks currently running on a CPU (idle tasks excluded).
+pub(crate) fn nr_running() -> usize {
+    let queued: usize = NR_QUEUED.iter().map(|n| n.load(Ordering::Relaxed)).sum();
+    let busy = CPU_BUSY
+        .iter()
+        .filter(|b| b.load(Ordering::Relaxed))
+        .count();
+    queued + busy
+}
+
+/// Returns the total nanoseconds spent by all CPUs in their idle tasks.
+pub(crate) fn idle_time_nanos() -> u64 {
+    let now = axhal::time::monotonic_time_nanos();
+    (0..axconfig::SMP)
+        .map(|cpu_id| {
+            let mut idle = IDLE_NANOS[cpu_id].load(Ordering::Relaxed);
+            if !CPU_BUSY[cpu_id].load(Ordering::Relaxed) {
+                let since = IDLE_SINCE[cpu_id].load(Ordering::Relaxed);
+                if since != 0 {
+                    idle += now.saturating_sub(since);
+                }
+            }
+            idle
+        })
+        .sum()
+}
+
 /// Returns a reference to the current run queue in [`CurrentRunQueueRef`].
 ///
 /// ## Safety
@@ -178,6 +216,29 @@ pub(crate) fn select_run_queue<G: BaseGuard>(task: &AxTaskRef) -> AxRunQueueRef<
     }
 }
 
+/// Returns a reference to the run queue the given task was last put into, see
+/// [`TaskInner::cpu_id`].
+///
+/// The task may be moved to another run queue at any time before the
+/// scheduler of the returned run queue is locked, so the caller should check
+/// that it is still there after locking.
+#[inline]
+pub(crate) fn task_run_queue<G: BaseGuard>(task: &AxTaskRef) -> AxRunQueueRef<'static, G> {
+    let irq_state = G::acquire();
+    #[cfg(not(feature = "smp"))]
+    let inner = {
+        let _ = task;
+        unsafe { RUN_QUEUE.current_ref_mut_raw() }
+    };
+    #[cfg(feature = "smp")]
+    let inner = get_run_queue(task.cpu_id());
+    AxRunQueueRef {
+        inner,
+        state: irq_state,
+        _phantom: core::marker::PhantomData,
+    }
+}
+
 /// [`AxRunQueue`] represents a run queue for global system or a specific CPU.
 pub(crate) struct AxRunQueue {
     /// The ID of the CPU this run queue is associated with.
@@ -237,7 +298,23 @@ impl<G: BaseGuard> AxRunQueueRef<'_, G> {
             self.inner.cpu_id
         );
         assert!(task.is_ready());
+        #[cfg(feature = "smp")]
+        task.set_cpu_id(self.inner.cpu_id);
         self.inner.scheduler.lock().add_task(task);
+        self.inner.account_enqueue();
+    }
+
+    /// Sets the priority of the given task with the scheduler of this run
+    /// queue locked, see [`task_run_queue`].
+    ///
+    /// Returns `None` if the task has been moved to another run queue.
+    pub fn set_task_priority(&mut self, task: &AxTaskRef, prio: isize) -> Option<bool> {
+        let mut scheduler = self.inner.scheduler.lock();
+        #[cfg(feature = "smp")]
+        if task.cpu_id() != self.inner.cpu_id {
+            return None;
+        }
+        Some(scheduler.set_priority(task, prio))
     }
 
     /// Unblock one task by inserting it into the run queue.
@@ -415,6 +492,10 @@ impl<G: BaseGuard> CurrentRunQueueRef<'_, G> {
         // Note that the state may have been set as `Ready` in `unblock_task()`,
         // see `unblock_task()` for details.
 
+        if cancel_interrupted_block(curr) {
+            // Still in the wait queue, the caller will remove it.
+            return;
+        }
         debug!("task block: {}", curr.id_name());
         self.inner.resched();
     }
@@ -430,7 +511,12 @@ impl<G: BaseGuard> CurrentRunQueueRef<'_, G> {
         if now < deadline {
             crate::timers::set_alarm_wakeup(deadline, curr.clone());
             curr.set_state(TaskState::Blocked);
-            self.inner.resched();
+            if !cancel_interrupted_block(curr) {
+                self.inner.resched();
+            }
+            // The task may be woken up by `interrupt_task()` before the deadline,
+            // expire the timer event so that it will not wake up the task later.
+            curr.timer_ticket_expired();
         }
     }
 
@@ -442,6 +528,21 @@ impl<G: BaseGuard> CurrentRunQueueRef<'_, G> {
     }
 }
 
+/// Cancels the blocking of an interruptible task which has been interrupted
+/// before it was marked as `Blocked`, since [`crate::interrupt_task()`] may
+/// have missed it.
+///
+/// Returns `true` if the task is set back to `Running` and must not be
+/// rescheduled.
+fn cancel_interrupted_block(curr: &CurrentTask) -> bool {
+    // Pairs with the fence in `interrupt_task()`: either the interrupter sees
+    // the `Blocked` state, or we see the interruption here.
+    core::sync::atomic::fence(Ordering::SeqCst);
+    curr.is_interruptible()
+        && curr.is_interrupted()
+        && curr.transition_state(TaskState::Blocked, TaskState::Running)
+}
+
 impl AxRunQueue {
     /// Create a new run queue for the specified CPU.
     /// The run queue is initialized with a per-CPU gc task in its scheduler.
@@ -452,12 +553,40 @@ impl AxRunQueue {
 
         let mut scheduler = Scheduler::new();
         scheduler.add_task(gc_task);
+        NR_QUEUED[cpu_id].fetch_add(1, Ordering::Relaxed);
         Self {
             cpu_id,
             scheduler: SpinRaw::new(scheduler),
         }
     }
 
+    /// Records that a task has been inserted into the scheduler of this run queue.
+    #[inline]
+    fn account_enqueue(&self) {
+        NR_QUEUED[self.cpu_id].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a task has been picked out of the scheduler of this run queue.
+    #[inline]
+    fn account_dequeue(&self) {
+        NR_QUEUED[self.cpu_id].fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Updates the busy/idle statistics of this CPU when switching from
+    /// `prev_task` to `next_task`.
+    fn account_switch(&self, prev_task: &AxTaskRef, next_task: &AxTaskRef) {
+        let cpu_id = self.cpu_id;
+        let now = axhal::time::monotonic_time_nanos();
+        if prev_task.is_idle() {
+            let since = IDLE_SINCE[cpu_id].load(Ordering::Relaxed);
+            IDLE_NANOS[cpu_id].fetch_add(now.saturating_sub(since), Ordering::Relaxed);
+        }
+        if next_task.is_idle() {
+            IDLE_SINCE[cpu_id].store(now, Ordering::Relaxed);
+        }
+        CPU_BUSY[cpu_id].store(!next_task.is_idle(), Ordering::Relaxed);
+    }
+
     /// Puts target task into current run queue with `Ready` state
     /// if its state matches `current_state` (except idle task).
     ///
@@ -494,7 +623,10 @@ impl AxRunQueue {
                 }
             }
             // TODO: priority
+            #[cfg(feature = "smp")]
+            task.set_cpu_id(self.cpu_id);
             self.scheduler.lock().put_prev_task(task, preempt);
+            self.account_enqueue();
             true
         } else {
             false
@@ -508,6 +640,7 @@ impl AxRunQueue {
             .scheduler
             .lock()
             .pick_next_task()
+            .inspect(|_| self.account_dequeue())
             .unwrap_or_else(|| unsafe {
                 // Safety: IRQs must be disabled at this time.
                 IDLE_TASK.current_ref_raw().get_unchecked().clone()
@@ -541,6 +674,7 @@ impl AxRunQueue {
         if prev_task.ptr_eq(&next_task) {
             return;
         }
+        self.account_switch(prev_task.as_task_ref(), &next_task);
 
         // Claim the task as running, we do this before switching to it
         // such that any running task will have this set.
@@ -606,11 +740,10 @@ fn gc_entry() {
 /// then puts the task to the scheduler of target run queue.
 #[cfg(feature = "smp")]
 pub(crate) fn migrate_entry(migrated_task: AxTaskRef) {
-    select_run_queue::<kernel_guard::NoPreemptIrqSave>(&migrated_task)
-        .inner
-        .scheduler
-        .lock()
-        .put_prev_task(migrated_task, false)
+    let rq = select_run_queue::<kernel_guard::NoPreemptIrqSave>(&migrated_task);
+    migrated_task.set_cpu_id(rq.inner.cpu_id);
+    rq.inner.scheduler.lock().put_prev_task(migrated_task, false);
+    rq.inner.account_enqueue();
 }
 
 /// Clear the `on_cpu` field of previous task running on this CPU.
@@ -640,6 +773,7 @@ pub(crate) fn init() {
     let main_task = TaskInner::new_init("main".into()).into_arc();
     main_task.set_state(TaskState::Running);
     unsafe { CurrentTask::init_current(main_task) }
+    CPU_BUSY[cpu_id].store(true, Ordering::Relaxed);
 
     RUN_QUEUE.with_current(|rq| {
         rq.init_once(AxRunQueue::new(cpu_id));
@@ -659,6 +793,7 @@ pub(crate) fn init_secondary() {
         i.init_once(idle_task.clone());
     });
     unsafe { CurrentTask::init_current(idle_task) }
+    IDLE_SINCE[cpu_id].store(axhal::time::monotonic_time_nanos(), Ordering::Relaxed);
 
     RUN_QUEUE.with_current(|rq| {
         rq.init_once(AxRunQueue::new(cpu_id));
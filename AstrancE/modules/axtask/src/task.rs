@@ -3,7 +3,7 @@ use core::ops::Deref;
 use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU8, AtomicU64, Ordering};
 use core::{alloc::Layout, cell::UnsafeCell, fmt, ptr::NonNull};
 
-#[cfg(feature = "preempt")]
+#[cfg(any(feature = "preempt", feature = "smp"))]
 use core::sync::atomic::AtomicUsize;
 
 use kspin::SpinNoIrq;
@@ -35,6 +35,45 @@ pub enum TaskState {
     Exited = 4,
 }
 
+/// How a syscall interrupted by a signal is restarted, like the `-ERESTART*`
+/// codes of Linux.
+///
+/// Set by the interrupted syscall before it returns `EINTR`, and consumed on
+/// the way back to user space after signals are handled.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SyscallRestart {
+    /// Return `EINTR` to user space.
+    None = 0,
+    /// Restart if no signal handler runs, or the handler has `SA_RESTART`.
+    Sys = 1,
+    /// Always restart, even after a signal handler runs.
+    NoIntr = 2,
+    /// Restart only if no signal handler runs.
+    NoHand = 3,
+    /// Continue with the restart block via `restart_syscall` if no signal
+    /// handler runs.
+    RestartBlock = 4,
+}
+
+impl From<u8> for SyscallRestart {
+    #[inline]
+    fn from(mode: u8) -> Self {
+        match mode {
+            1 => Self::Sys,
+            2 => Self::NoIntr,
+            3 => Self::NoHand,
+            4 => Self::RestartBlock,
+            _ => Self::None,
+        }
+    }
+}
+
+/// A function continuing an interrupted syscall, called by `restart_syscall`.
+///
+/// It returns the raw syscall result, a negative value is an error code.
+pub type RestartBlock = Box<dyn FnOnce() -> isize + Send>;
+
 /// The inner task structure.
 pub struct TaskInner {
     id: TaskId,
@@ -51,9 +90,22 @@ pub struct TaskInner {
     /// Mark whether the task is in the wait queue.
     in_wait_queue: AtomicBool,
 
+    /// Set by [`interrupt_task()`](crate::interrupt_task), cleared by [`TaskInner::clear_interrupt()`].
+    interrupted: AtomicBool,
+    /// Mark whether the task is in an interruptible wait, which can be woken
+    /// up early by [`interrupt_task()`](crate::interrupt_task).
+    interruptible: AtomicBool,
+    /// How the interrupted syscall is restarted, see [`SyscallRestart`].
+    syscall_restart: AtomicU8,
+    /// Continues an interrupted syscall for [`SyscallRestart::RestartBlock`].
+    restart_block: SpinNoIrq<Option<RestartBlock>>,
+
     /// Used to indicate whether the task is running on a CPU.
     #[cfg(feature = "smp")]
     on_cpu: AtomicBool,
+    /// The CPU whose run queue the task was last put into.
+    #[cfg(feature = "smp")]
+    cpu_id: AtomicUsize,
 
     /// A ticket ID used to identify the timer event.
     /// Set by `set_timer_ticket()` when creating a timer event in `set_alarm_wakeup()`,
@@ -160,6 +212,16 @@ impl TaskInner {
         Some(self.exit_code.load(Ordering::Acquire))
     }
 
+    /// Wait for the task to exit for at most `dur`, and return the exit code.
+    ///
+    /// Returns [`None`] if the task has not exited before the timeout.
+    #[cfg(feature = "irq")]
+    pub fn join_timeout(&self, dur: core::time::Duration) -> Option<i32> {
+        self.wait_for_exit
+            .wait_timeout_until(dur, || self.state() == TaskState::Exited);
+        (self.state() == TaskState::Exited).then(|| self.exit_code.load(Ordering::Acquire))
+    }
+
     /// Returns the pointer to the user-defined task extended data.
     ///
     /// # Safety
@@ -245,10 +307,16 @@ impl TaskInner {
             // By default, the task is allowed to run on all CPUs.
             cpumask: SpinNoIrq::new(AxCpuMask::full()),
             in_wait_queue: AtomicBool::new(false),
+            interrupted: AtomicBool::new(false),
+            interruptible: AtomicBool::new(false),
+            syscall_restart: AtomicU8::new(SyscallRestart::None as u8),
+            restart_block: SpinNoIrq::new(None),
             #[cfg(feature = "irq")]
             timer_ticket_id: AtomicU64::new(0),
             #[cfg(feature = "smp")]
             on_cpu: AtomicBool::new(false),
+            #[cfg(feature = "smp")]
+            cpu_id: AtomicUsize::new(0),
             #[cfg(feature = "preempt")]
             need_resched: AtomicBool::new(false),
             #[cfg(feature = "preempt")]
@@ -275,7 +343,10 @@ impl TaskInner {
         let mut t = Self::new_common(TaskId::new(), name);
         t.is_init = true;
         #[cfg(feature = "smp")]
-        t.set_on_cpu(true);
+        {
+            t.set_on_cpu(true);
+            t.set_cpu_id(axhal::cpu::this_cpu_id());
+        }
         if t.name() == "idle" {
             t.is_idle = true;
         }
@@ -344,6 +415,64 @@ impl TaskInner {
         self.in_wait_queue.store(in_wait_queue, Ordering::Release);
     }
 
+    /// Returns `true` if the task has been interrupted and the interruption
+    /// has not been cleared.
+    ///
+    /// Interruptible waits return early when this is set.
+    #[inline]
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+
+    /// Clears the interruption set by [`interrupt_task()`](crate::interrupt_task).
+    #[inline]
+    pub fn clear_interrupt(&self) {
+        self.interrupted.store(false, Ordering::SeqCst);
+    }
+
+    /// Sets how the current syscall, which returns `EINTR` because of a
+    /// signal, is restarted.
+    #[inline]
+    pub fn set_syscall_restart(&self, mode: SyscallRestart) {
+        self.syscall_restart.store(mode as u8, Ordering::Relaxed);
+    }
+
+    /// Takes the restart mode set by [`TaskInner::set_syscall_restart()`],
+    /// resetting it to [`SyscallRestart::None`].
+    #[inline]
+    pub fn take_syscall_restart(&self) -> SyscallRestart {
+        self.syscall_restart
+            .swap(SyscallRestart::None as u8, Ordering::Relaxed)
+            .into()
+    }
+
+    /// Sets the function to continue the current syscall, and marks it to be
+    /// restarted with [`SyscallRestart::RestartBlock`].
+    pub fn set_restart_block(&self, block: impl FnOnce() -> isize + Send + 'static) {
+        *self.restart_block.lock() = Some(Box::new(block));
+        self.set_syscall_restart(SyscallRestart::RestartBlock);
+    }
+
+    /// Takes the function set by [`TaskInner::set_restart_block()`].
+    pub fn take_restart_block(&self) -> Option<RestartBlock> {
+        self.restart_block.lock().take()
+    }
+
+    #[inline]
+    pub(crate) fn set_interrupted(&self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+    }
+
+    #[inline]
+    pub(crate) fn is_interruptible(&self) -> bool {
+        self.interruptible.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    pub(crate) fn set_interruptible(&self, interruptible: bool) {
+        self.interruptible.store(interruptible, Ordering::SeqCst);
+    }
+
     /// Returns task's current timer ticket ID.
     #[inline]
     #[cfg(feature = "irq")]
@@ -417,8 +546,15 @@ impl TaskInner {
         self.wait_for_exit.notify_all(false);
     }
 
+    /// Returns a raw pointer to the task context.
+    ///
+    /// # Safety
+    ///
+    /// The context is saved and restored on context switches, so it may only
+    /// be modified by the task itself while it is running (e.g. to switch to a
+    /// new page table), or by the scheduler.
     #[inline]
-    pub(crate) const unsafe fn ctx_mut_ptr(&self) -> *mut TaskContext {
+    pub const unsafe fn ctx_mut_ptr(&self) -> *mut TaskContext {
         self.ctx.get()
     }
 
@@ -440,6 +576,23 @@ impl TaskInner {
     pub(crate) fn set_on_cpu(&self, on_cpu: bool) {
         self.on_cpu.store(on_cpu, Ordering::Release)
     }
+
+    /// Returns the CPU whose run queue the task was last put into.
+    ///
+    /// The task is in the scheduler of that run queue, or is running on that
+    /// CPU, or is blocked.
+    #[cfg(feature = "smp")]
+    #[inline]
+    pub(crate) fn cpu_id(&self) -> usize {
+        self.cpu_id.load(Ordering::Acquire)
+    }
+
+    /// Sets the CPU whose run queue the task is going to be put into.
+    #[cfg(feature = "smp")]
+    #[inline]
+    pub(crate) fn set_cpu_id(&self, cpu_id: usize) {
+        self.cpu_id.store(cpu_id, Ordering::Release)
+    }
 }
 
 impl fmt::Debug for TaskInner {
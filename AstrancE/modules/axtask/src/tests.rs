@@ -128,3 +128,31 @@ fn test_task_join() {
         assert_eq!(tasks[i].join(), Some(i as _));
     }
 }
+
+#[test]
+fn test_interrupt_blocked_join() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    // A sibling blocked on a condition that never holds, like a signal
+    // interrupting a thread that sleeps in `wait4()`.
+    static WQ: WaitQueue = WaitQueue::new();
+
+    let task = axtask::spawn_raw(
+        || {
+            let res = WQ.wait_until_interruptible(|| false);
+            assert!(res.is_err());
+            assert!(!current().in_wait_queue());
+            axtask::exit(7);
+        },
+        "interrupt_blocked".into(),
+        0x1000,
+    );
+
+    while WQ.is_empty() {
+        axtask::yield_now();
+    }
+    axtask::interrupt_task(&task);
+    assert_eq!(task.join(), Some(7));
+    assert!(WQ.is_empty());
+}
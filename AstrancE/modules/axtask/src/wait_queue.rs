@@ -33,6 +33,11 @@ pub struct WaitQueue {
     queue: SpinNoIrq<VecDeque<AxTaskRef>>,
 }
 
+/// The error returned by interruptible waits when the waiting task is
+/// interrupted by [`interrupt_task()`](crate::interrupt_task).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interrupted;
+
 pub(crate) type WaitQueueGuard<'a> = SpinNoIrqGuard<'a, VecDeque<AxTaskRef>>;
 
 impl WaitQueue {
@@ -165,6 +170,105 @@ impl WaitQueue {
         timeout
     }
 
+    /// Blocks the current task and put it into the wait queue, until other task
+    /// notifies it, or it is interrupted by [`interrupt_task()`](crate::interrupt_task).
+    ///
+    /// Returns [`Err(Interrupted)`](Interrupted) if the task is interrupted
+    /// without being notified.
+    pub fn wait_interruptible(&self) -> Result<(), Interrupted> {
+        let curr = crate::current();
+        if curr.is_interrupted() {
+            return Err(Interrupted);
+        }
+        curr.set_interruptible(true);
+        current_run_queue::<NoPreemptIrqSave>().blocked_resched(self.queue.lock());
+        curr.set_interruptible(false);
+
+        // `notify()` removes the task from the wait queue, an interruption does not.
+        let notified = !curr.in_wait_queue();
+        let interrupted = curr.is_interrupted();
+        self.cancel_events(curr, false);
+        if notified || !interrupted {
+            Ok(())
+        } else {
+            Err(Interrupted)
+        }
+    }
+
+    /// Blocks the current task and put it into the wait queue, until the given
+    /// `condition` becomes true, or it is interrupted by
+    /// [`interrupt_task()`](crate::interrupt_task).
+    ///
+    /// The condition is checked with the wait queue locked, so a notification
+    /// sent after making it true is never missed.
+    ///
+    /// Returns [`Err(Interrupted)`](Interrupted) if the task is interrupted
+    /// before the condition becomes true.
+    pub fn wait_until_interruptible<F>(&self, condition: F) -> Result<(), Interrupted>
+    where
+        F: Fn() -> bool,
+    {
+        let curr = crate::current();
+        let mut result = Ok(());
+        loop {
+            let mut rq = current_run_queue::<NoPreemptIrqSave>();
+            let wq = self.queue.lock();
+            if condition() {
+                break;
+            }
+            if curr.is_interrupted() {
+                result = Err(Interrupted);
+                break;
+            }
+            curr.set_interruptible(true);
+            rq.blocked_resched(wq);
+            curr.set_interruptible(false);
+            // Preemption may occur here.
+        }
+        self.cancel_events(curr, false);
+        result
+    }
+
+    /// Blocks the current task and put it into the wait queue, until other tasks
+    /// notify it, the given duration has elapsed, or it is interrupted by
+    /// [`interrupt_task()`](crate::interrupt_task).
+    ///
+    /// Returns `Ok(true)` on timeout, `Ok(false)` if notified, and
+    /// [`Err(Interrupted)`](Interrupted) if interrupted.
+    #[cfg(feature = "irq")]
+    pub fn wait_timeout_interruptible(
+        &self,
+        dur: core::time::Duration,
+    ) -> Result<bool, Interrupted> {
+        let curr = crate::current();
+        if curr.is_interrupted() {
+            return Err(Interrupted);
+        }
+        let mut rq = current_run_queue::<NoPreemptIrqSave>();
+        let deadline = axhal::time::wall_time() + dur;
+        debug!(
+            "task wait_timeout_interruptible: {} deadline={:?}",
+            curr.id_name(),
+            deadline
+        );
+        crate::timers::set_alarm_wakeup(deadline, curr.clone());
+        curr.set_interruptible(true);
+        rq.blocked_resched(self.queue.lock());
+        curr.set_interruptible(false);
+
+        let notified = !curr.in_wait_queue();
+        let interrupted = curr.is_interrupted();
+        // Always try to remove the task from the timer list.
+        self.cancel_events(curr, true);
+        if notified {
+            Ok(false)
+        } else if interrupted {
+            Err(Interrupted)
+        } else {
+            Ok(true)
+        }
+    }
+
     /// Wakes up one task in the wait queue, usually the first one.
     ///
     /// If `resched` is true, the current task will be preempted when the
@@ -56,6 +56,8 @@
 
 #define FD_CLOEXEC      1
 #define F_DUPFD_CLOEXEC 1030
+#define F_SETPIPE_SZ    1031
+#define F_GETPIPE_SZ    1032
 
 #define F_RDLCK 0
 #define F_WRLCK 1
@@ -5,9 +5,12 @@ use alloc::{
     sync::Arc,
     vec::Vec,
 };
+use arceos_posix_api::{FD_TABLE, fd_link_target};
 use axfs::{
     PROC_ROOT, VfsError, VfsResult,
-    proc::{ProcDir, ProcDirGenerator, ProcEntry, ProcFileGenerator},
+    proc::{
+        ProcDir, ProcDirGenerator, ProcEntry, ProcFileGenerator, ProcSymlink, text_file_generator,
+    },
 };
 use axprocess::Process;
 use axtask::{TaskExtRef, current};
@@ -15,17 +18,19 @@ use core::fmt::Write;
 use memory_addr::PAGE_SIZE_4K;
 use axalloc::global_allocator;
 use axhal::mem::{memory_regions, MemRegionFlags};
+use axhal::time::monotonic_time;
+use axtask::{FIXED_1, FSHIFT, load_average, nr_running};
 use axmm::{backend::VmAreaType, MmapFlags};
 
-use crate::task::{PROCESS_TABLE, ProcessData};
+use crate::task::{PROCESS_TABLE, ProcessData, THREAD_TABLE};
 
 /// 此函数在内部被文件生成器调用，它会一次性创建所有数据，
 fn generate_smaps_content(process: Arc<Process>) -> VfsResult<String> {
     let aspace = process
         .data::<ProcessData>()
         .ok_or(VfsError::InvalidData)?
-        .aspace
-        .lock();
+        .aspace();
+    let aspace = aspace.lock();
     let mut output = String::new();
 
     for area in aspace.areas.iter() {
@@ -116,6 +121,22 @@ fn create_pid_dir_generator() -> Arc<ProcDirGenerator> {
     })
 }
 
+/// 创建 /proc/self/fd 的目录生成器：当前进程的每个描述符对应一个指向所打开文件的符号链接
+fn create_fd_dir_generator() -> Arc<ProcDirGenerator> {
+    Arc::new(|| {
+        // 先取出描述符列表再逐个查询，避免持有描述符表的锁
+        let fds: Vec<usize> = FD_TABLE.table().read().ids().collect();
+        Ok(fds
+            .into_iter()
+            .filter_map(|fd| {
+                let target = fd_link_target(fd as _).ok()?;
+                let link = ProcSymlink::new(&target);
+                Some((fd.to_string(), ProcEntry::Symlink(Arc::new(link))))
+            })
+            .collect())
+    })
+}
+
 /// 生成 /proc/meminfo 内容
 fn generate_meminfo_content() -> VfsResult<String> {
     let mut output = String::new();
@@ -146,7 +167,8 @@ fn generate_meminfo_content() -> VfsResult<String> {
     for (_pid, process) in PROCESS_TABLE.read().iter() {
         if let Some(proc_data) = process.data::<ProcessData>() {
             process_count += 1;
-            let aspace = proc_data.aspace.lock();
+            let aspace = proc_data.aspace();
+            let aspace = aspace.lock();
             for area in aspace.areas.iter() {
                 let npages = area.size() / PAGE_SIZE_4K;
                 if let Some(vm_type) = area.backend().get_vm_type() {
@@ -272,11 +294,17 @@ fn create_meminfo_file_generator() -> Arc<ProcFileGenerator> {
     })
 }
 
-/// 预留：其他常见 /proc 文件生成器
-/// 例如 /proc/uptime
+/// 生成 /proc/uptime 内容：系统启动以来的秒数，以及所有 CPU 空闲时间之和
 fn generate_uptime_content() -> VfsResult<String> {
-    // TODO: 获取系统启动到现在的秒数
-    Ok("0.00 0.00\n".to_string())
+    let uptime = monotonic_time();
+    let idle = axtask::idle_time();
+    Ok(format!(
+        "{}.{:02} {}.{:02}\n",
+        uptime.as_secs(),
+        uptime.subsec_millis() / 10,
+        idle.as_secs(),
+        idle.subsec_millis() / 10
+    ))
 }
 
 fn create_uptime_file_generator() -> Arc<ProcFileGenerator> {
@@ -293,10 +321,30 @@ fn create_uptime_file_generator() -> Arc<ProcFileGenerator> {
     })
 }
 
-/// 例如 /proc/loadavg
+/// 将定点数表示的负载转换为整数部分和两位小数部分
+pub(crate) fn load_int_frac(load: usize) -> (usize, usize) {
+    // 加上 0.005 以四舍五入到两位小数
+    let load = load + FIXED_1 / 200;
+    (load >> FSHIFT, ((load & (FIXED_1 - 1)) * 100) >> FSHIFT)
+}
+
+/// 生成 /proc/loadavg 内容
 fn generate_loadavg_content() -> VfsResult<String> {
-    // TODO: 获取系统负载
-    Ok("0.00 0.00 0.00 1/1 1\n".to_string())
+    let [avg1, avg5, avg15] = load_average().map(load_int_frac);
+    let nr_threads = THREAD_TABLE.read().len();
+    let last_pid = THREAD_TABLE.read().keys().max().copied().unwrap_or(0);
+    Ok(format!(
+        "{}.{:02} {}.{:02} {}.{:02} {}/{} {}\n",
+        avg1.0,
+        avg1.1,
+        avg5.0,
+        avg5.1,
+        avg15.0,
+        avg15.1,
+        nr_running(),
+        nr_threads,
+        last_pid
+    ))
 }
 
 fn create_loadavg_file_generator() -> Arc<ProcFileGenerator> {
@@ -329,7 +377,7 @@ pub fn init_fs() -> VfsResult<()> {
     // 为 /proc/self/smaps 创建一个特殊的、符合签名的文件生成器。
     let self_smaps_generator = Arc::new(|offset: u64, buf: &mut [u8]| -> VfsResult<usize> {
         let curr = current();
-        let current_process = curr.task_ext().thread.process().clone();
+        let current_process = curr.task_ext().thread().process().clone();
         let full_content = generate_smaps_content(current_process)?;
         let start = offset as usize;
         if start >= full_content.len() {
@@ -341,6 +389,11 @@ pub fn init_fs() -> VfsResult<()> {
         Ok(slice_to_copy.len())
     });
     self_dir.create_dynamic_file("smaps", self_smaps_generator)?;
+    // /proc/self/mounts 与 /proc/self/mountinfo 由挂载树生成（所有进程共享同一挂载命名空间）
+    self_dir.create_dynamic_file("mounts", text_file_generator(axfs::api::proc_mounts))?;
+    self_dir.create_dynamic_file("mountinfo", text_file_generator(axfs::api::proc_mountinfo))?;
+    // /proc/self/fd，/dev/fd 与 /dev/stdin 等链接都指向这里
+    self_dir.create_dir("fd")?.add_generator(create_fd_dir_generator());
 
     // === /proc/meminfo ===
     proc_root.create_dynamic_file("meminfo", create_meminfo_file_generator())?;
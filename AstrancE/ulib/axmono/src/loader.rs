@@ -1,6 +1,5 @@
 use arceos_posix_api::Directory;
 use axerrno::{AxError, AxResult};
-use axfs::CURRENT_DIR;
 use axhal::mem::VirtAddr;
 use xmas_elf::ElfFile;
 
@@ -41,7 +40,6 @@ pub fn load_app_elf(idx: usize) -> ElfFile<'static> {
 }
 
 pub fn load_elf_from_disk(app_path: &str) -> AxResult<OwnedElfFile> {
-    let dir = CURRENT_DIR.lock().clone();
-    let content = axfs::api::read_at(&dir, app_path)?;
+    let content = axfs::api::read(app_path)?;
     OwnedElfFile::new(app_path, content)
 }
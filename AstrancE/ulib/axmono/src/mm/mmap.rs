@@ -1,15 +1,16 @@
 use core::ffi::{c_int, c_void};
 
 use alloc::{
+    string::String,
     sync::Arc,
     vec::{self, Vec},
 };
 use arceos_posix_api::{File, FileLike, ctypes, get_file_like, sys_lseek, sys_read};
 use axerrno::{AxError, AxResult, LinuxResult, ax_err};
 use axio::SeekFrom;
-use axmm::{MmapFlags, MmapIO};
+use axmm::{FrameTracker, FrameTrackerRef, MmapFlags, MmapIO};
 use axsync::Mutex;
-use memory_addr::VirtAddr;
+use memory_addr::{PAGE_SIZE_4K, VirtAddr};
 use spin::RwLock;
 
 pub(crate) enum MmapResource {
@@ -61,16 +62,9 @@ impl MmapIO for MmapIOImpl {
             }
             MmapResource::File(file) => {
                 debug!("mmap read file at 0x{start:x}");
-                let mut file = file.inner().lock();
-                let prev = file.seek(SeekFrom::Current(0))?;
-                let result = file
-                    .seek(SeekFrom::Start(start as u64))
-                    .and_then(|_| file.read(buf));
-                //debug!("mmap read buf = \n{:?}", buf);
+                // 经由页缓存读取，不改变文件偏移
+                let result = file.inner().lock().read_at(start as u64, buf);
                 debug!("mmap read result={:?}", result);
-
-                // recover cursor
-                file.seek(SeekFrom::Start(prev))?;
                 result
             }
             MmapResource::FileLike(_) => todo!(),
@@ -103,4 +97,27 @@ impl MmapIO for MmapIOImpl {
     fn flags(&self) -> axmm::MmapFlags {
         self.flags
     }
+
+    fn file_info(&self) -> Option<(String, usize)> {
+        match &self.resource {
+            MmapResource::File(file) => Some((file.path().into(), self.file_offset)),
+            _ => None,
+        }
+    }
+
+    fn shared_frame(&self, va: usize) -> Option<FrameTrackerRef> {
+        // 仅 MAP_SHARED 的文件映射直接共享页缓存（或 tmpfs）的页帧
+        if !self.flags.contains(MmapFlags::MAP_SHARED) {
+            return None;
+        }
+        let MmapResource::File(file) = &self.resource else {
+            return None;
+        };
+        let offset = va - *self.base.read() + self.file_offset;
+        let page = file
+            .inner()
+            .lock()
+            .shared_page((offset / PAGE_SIZE_4K) as u64)?;
+        page.downcast::<FrameTracker>().ok()
+    }
 }
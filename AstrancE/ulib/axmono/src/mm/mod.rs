@@ -14,15 +14,16 @@ use axhal::{
     paging::MappingFlags,
 };
 use axmm::AddrSpace;
+use axsignal::{SigCodeSigSegv, Signal};
 use axtask::{TaskExtRef, current};
 use kernel_elf_parser::{AuxvEntry, AuxvType};
-use linux_raw_sys::general::{AT_ENTRY, AT_PHDR, AT_PHENT, AT_PHNUM};
+use linux_raw_sys::general::{AT_ENTRY, AT_NULL, AT_PHDR, AT_PHENT, AT_PHNUM};
 use memory_addr::va;
 use xmas_elf::ElfFile;
 
 use crate::dynamic::{find_interpreter, load_interpreter, relocate_interpreter_segments};
 use crate::elf::{check_segments_overlap, find_safe_base_address, get_program_address_range};
-use crate::task::{self, read_trapframe_from_kstack};
+use crate::task::{self, SigInfo_, read_trapframe_from_kstack};
 use crate::utils::get_pwd_from_envs;
 use crate::{
     copy_from_kernel,
@@ -102,6 +103,37 @@ pub fn load_elf_to_mem(
     }
 }
 
+/// 从程序的初始用户栈读取辅助向量，包含结尾的 AT_NULL
+///
+/// 初始栈的布局为 argc、argv 数组、envp 数组，之后是辅助向量
+pub fn read_auxv(uspace: &AddrSpace, sp: VirtAddr) -> AxResult<Vec<usize>> {
+    let read_word = |addr: VirtAddr| -> AxResult<usize> {
+        let mut word = [0u8; size_of::<usize>()];
+        uspace.read(addr, &mut word)?;
+        Ok(usize::from_ne_bytes(word))
+    };
+    let word = size_of::<usize>();
+    let argc = read_word(sp)?;
+    // 跳过 argc、argv 与其结尾的 NULL
+    let mut addr = sp + (argc + 2) * word;
+    while read_word(addr)? != 0 {
+        addr += word;
+    }
+    addr += word;
+
+    let mut auxv = Vec::new();
+    loop {
+        let ty = read_word(addr)?;
+        auxv.push(ty);
+        auxv.push(read_word(addr + word)?);
+        addr += 2 * word;
+        if ty == AT_NULL as usize {
+            break;
+        }
+    }
+    Ok(auxv)
+}
+
 /// 设置用户栈并返回栈指针偏移
 fn setup_user_stack(
     uspace: &mut AddrSpace,
@@ -423,6 +455,9 @@ pub fn access_user_memory<R>(f: impl FnOnce() -> R) -> R {
 pub fn is_accessing_user_memory() -> bool {
     ACCESSING_USER_MEM.read_current()
 }
+/// 缺页处理内存不足时一次回收的页缓存页数
+const PAGE_CACHE_RECLAIM_BATCH: usize = 256;
+
 #[register_trap_handler(PAGE_FAULT)]
 fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool) -> bool {
     trace!(
@@ -430,9 +465,26 @@ fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool)
         vaddr, access_flags, is_user
     );
     let current = current();
-    let mut aspace = current.task_ext().process_data().aspace.lock();
-    let result = aspace.handle_page_fault(vaddr, access_flags);
+    let aspace = current.task_ext().process_data().aspace();
+    let mut aspace = aspace.lock();
+    let mut result = aspace.handle_page_fault(vaddr, access_flags);
+    // 访问合法却处理失败一般是内存不足，回收未映射的页缓存后重试一次
+    if !result
+        && aspace
+            .areas
+            .find(vaddr)
+            .is_some_and(|area| area.flags().contains(access_flags))
+        && axfs::api::shrink_page_cache(PAGE_CACHE_RECLAIM_BATCH) > 0
+    {
+        result = aspace.handle_page_fault(vaddr, access_flags);
+    }
     if !result && is_user {
+        // 地址已映射说明是权限不足，否则为未映射的地址
+        let code = if aspace.areas.find(vaddr).is_some() {
+            SigCodeSigSegv::SEGV_ACCERR
+        } else {
+            SigCodeSigSegv::SEGV_MAPERR
+        };
         error!(
             "Unhandled user page fault at {:#x?}, access_flags: {access_flags:?}",
             vaddr
@@ -443,9 +495,9 @@ fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool)
                 read_trapframe_from_kstack(kstack)
             );
         }
-        // TODO: Send SIGSEGV
         drop(aspace);
-        task::sys_exit(-139);
+        task::force_signal_current(Signal::SIGSEGV, SigInfo_::MemoryAccess(code, vaddr));
+        return true;
     }
     result
 }
@@ -1,16 +1,23 @@
-use core::{ptr, time::Duration};
+use core::{
+    ptr,
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
 
 use alloc::{collections::btree_map::BTreeMap, sync::Arc};
 use axerrno::{LinuxError, LinuxResult};
 use axhal::mem::phys_to_virt;
 use axmm::{FrameTracker, FrameTrackerRef, TrackedPhysAddr};
-use axtask::{TaskExtRef, WaitQueue, current};
+use axprocess::Pid;
+use axtask::{SyscallRestart, TaskExtRef, WaitQueue, current};
 use bitflags::bitflags;
 use linux_raw_sys::general::*;
 use memory_addr::{MemoryAddr, VirtAddr};
 use numeric_enum_macro::numeric_enum;
 use spin::RwLock;
 
+use crate::ptr::{PtrWrapper, UserConstPtr, UserPtr};
+
 numeric_enum! {
     #[repr(u8)] // 基本操作码通常只占用低位，u8 足够
     #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -144,12 +151,20 @@ pub fn futex(
                     .or_insert_with(|| Arc::new(WaitQueue::new()))
                     .clone()
             }; // 写锁在这里自动释放
-            // 4. 让当前线程在该 WaitQueue 上等待
-            // condition 闭包在等待前和每次被唤醒后都会被检查
+            // 4. 让当前线程在该 WaitQueue 上等待，被信号打断时返回 EINTR
+            //    没有超时的等待在信号处理后重新执行；有超时的只在没有运行处理函数时重新执行
             if let Some(timeout_duration) = timeout {
-                wait_queue_instance.wait_timeout(timeout_duration);
-            } else {
-                wait_queue_instance.wait();
+                match wait_queue_instance.wait_timeout_interruptible(timeout_duration) {
+                    Ok(false) => {}
+                    Ok(true) => return Err(LinuxError::ETIMEDOUT),
+                    Err(_) => {
+                        current().set_syscall_restart(SyscallRestart::NoHand);
+                        return Err(LinuxError::EINTR);
+                    }
+                }
+            } else if wait_queue_instance.wait_interruptible().is_err() {
+                current().set_syscall_restart(SyscallRestart::Sys);
+                return Err(LinuxError::EINTR);
             }
             warn!("task wake!");
 
@@ -182,3 +197,63 @@ pub fn futex(
         }
     }
 }
+
+/// 遍历 robust futex 链表的项数上限，防止用户态构造出环形链表，同 Linux 的 `ROBUST_LIST_LIMIT`
+const ROBUST_LIST_LIMIT: usize = 2048;
+
+/// 释放退出的线程仍持有的 robust futex
+///
+/// 链表上（以及正在加锁或解锁的 `list_op_pending` 项）由 `tid` 持有的 futex
+/// 被置位 `FUTEX_OWNER_DIED`，并唤醒一个等待者，使其发现持有者已经退出。
+/// 链表项的最低位是 PI 标记，需要去掉
+pub fn exit_robust_list(head: usize, tid: Pid) {
+    let Ok(head_ptr) = UserConstPtr::<robust_list_head>::from(head).get() else {
+        return;
+    };
+    let head_val = unsafe { head_ptr.read() };
+    let offset = head_val.futex_offset as isize;
+    let pending = head_val.list_op_pending as usize & !1;
+
+    let mut entry = head_val.list.next as usize & !1;
+    for _ in 0..ROBUST_LIST_LIMIT {
+        if entry == head || entry == 0 {
+            break;
+        }
+        // 先取出下一项，唤醒等待者后链表可能被修改
+        let Ok(next) = UserConstPtr::<usize>::from(entry).get() else {
+            return;
+        };
+        let next = unsafe { next.read() } & !1;
+        if entry != pending {
+            handle_futex_death(entry.wrapping_add_signed(offset), tid);
+        }
+        entry = next;
+    }
+    if pending != 0 {
+        handle_futex_death(pending.wrapping_add_signed(offset), tid);
+    }
+}
+
+/// 持有 `uaddr` 处 futex 的线程 `tid` 已退出
+fn handle_futex_death(uaddr: usize, tid: Pid) {
+    let Ok(ptr) = UserPtr::<u32>::from(uaddr).get() else {
+        return;
+    };
+    // 与用户态的加锁操作并发，需要原子地修改
+    let word = unsafe { AtomicU32::from_ptr(ptr) };
+    let mut uval = word.load(Ordering::Acquire);
+    loop {
+        if uval & FUTEX_TID_MASK != tid {
+            return;
+        }
+        // 保留 FUTEX_WAITERS，清除持有者
+        let new = (uval & FUTEX_WAITERS) | FUTEX_OWNER_DIED;
+        match word.compare_exchange(uval, new, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => break,
+            Err(cur) => uval = cur,
+        }
+    }
+    if uval & FUTEX_WAITERS != 0 {
+        let _ = futex(VirtAddr::from(uaddr), FutexOp::Wake, 1, FutexFlags::empty(), None);
+    }
+}
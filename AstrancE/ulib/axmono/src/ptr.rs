@@ -31,7 +31,8 @@ fn check_region(start: VirtAddr, layout: Layout, access_flags: MappingFlags) ->
     }
 
     let task = current();
-    let mut aspace = task.task_ext().process_data().aspace.lock();
+    let aspace = task.task_ext().process_data().aspace();
+    let mut aspace = aspace.lock();
 
     if !aspace.check_region_access(
         VirtAddrRange::from_start_size(start, layout.size()),
@@ -81,7 +82,8 @@ fn check_null_terminated<T: Eq + Default>(
                 // querying the page table since the page might has not been
                 // allocated yet.
                 let task = current();
-                let aspace = task.task_ext().process_data().aspace.lock();
+                let aspace = task.task_ext().process_data().aspace();
+                let aspace = aspace.lock();
                 if !aspace.check_region_access(
                     VirtAddrRange::from_start_size(page, PAGE_SIZE_4K),
                     access_flags,
@@ -276,7 +278,8 @@ pub fn validate_uspace(
         return Ok(());
     }
     let curr = current();
-    let aspace = curr.task_ext().process_data().aspace.lock();
+    let aspace = curr.task_ext().process_data().aspace();
+    let aspace = aspace.lock();
     let range = VirtAddrRange::from_start_size(VirtAddr::from(ptr), len);
     if !aspace.check_region_access(range, flags | MappingFlags::USER) {
         return ax_err!(BadAddress, "invalid user ptr")
@@ -7,7 +7,7 @@ use arceos_posix_api::{
 };
 use axerrno::{LinuxError, LinuxResult};
 use axhal::time::monotonic_time;
-use axtask::{current, yield_now, TaskExtRef};
+use axtask::{SyscallRestart, current, yield_now};
 use linux_raw_sys::general::*;
 
 use crate::task::sys_sigprocmask;
@@ -97,14 +97,18 @@ pub unsafe fn sys_pselect(
             }
         }
 
-        // 检查是否有待处理的信号
-        if let Some(old_mask) = old_sigmask {
-            let curr = current();
-            if curr.task_ext().process_data().signal.lock().has_pending() {
-                // 恢复信号掩码
+        // 被信号打断时返回 EINTR，并写回剩余的超时时间
+        if current().is_interrupted() {
+            if let Some(deadline) = deadline {
+                let remain = deadline.saturating_sub(monotonic_time());
+                unsafe { *(timeout as *mut ctypes::timespec) = remain.into() };
+            }
+            // 恢复信号掩码
+            if let Some(old_mask) = old_sigmask {
                 sys_sigprocmask(SIG_SETMASK as i32, &old_mask as *const _ as _, null_mut())?;
-                return Err(LinuxError::EINTR);
             }
+            current().set_syscall_restart(SyscallRestart::NoHand);
+            return Err(LinuxError::EINTR);
         }
 
         // 让出CPU
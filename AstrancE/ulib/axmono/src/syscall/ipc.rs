@@ -59,7 +59,8 @@ pub fn sys_shmat(shmid: c_int, shmaddr: *const c_void, shmflg: c_int) -> LinuxRe
     drop(manager); // Release manager lock early
 
     let curr = current();
-    let mut aspace = curr.task_ext().process_data().aspace.lock(); // Get current process's address space
+    let aspace = curr.task_ext().process_data().aspace();
+    let mut aspace = aspace.lock(); // Get current process's address space
 
     axmm_shm_at(shm_segment_arc, shmaddr as usize, shmflg, &mut aspace)
         .map(|vaddr| vaddr.as_usize() as isize) // Return virtual address as isize
@@ -80,7 +81,8 @@ pub fn sys_shmdt(shmaddr: *const c_void) -> LinuxResult<isize> {
     debug!("sys_shmdt <= shmaddr:{:#x}", shmaddr as usize);
 
     let curr = current();
-    let mut aspace = curr.task_ext().process_data().aspace.lock(); // Get current process's address space
+    let aspace = curr.task_ext().process_data().aspace();
+    let mut aspace = aspace.lock(); // Get current process's address space
 
     axmm_shm_dt((shmaddr as usize).into(), &mut aspace)
         .map(|_| 0 as isize) // Return 0 on success
@@ -12,7 +12,8 @@ use crate::mm::mmap::{MmapIOImpl, MmapResource};
 // pub(crate) fn sys_brk(new_heap_top: usize) -> LinuxResult<isize> {
 pub fn sys_brk(new_heap_top: usize) -> LinuxResult<isize> {
     let current_task = current();
-    let mut aspace = current_task.task_ext().process_data().aspace.lock();
+    let aspace = current_task.task_ext().process_data().aspace();
+    let mut aspace = aspace.lock();
     let old_top = aspace.heap().top();
     if (new_heap_top != 0) {
         // TODO: Validate heap address
@@ -27,7 +28,8 @@ pub fn sys_brk(new_heap_top: usize) -> LinuxResult<isize> {
 // pub(crate) fn sys_mprotect(addr: usize, size: usize, prot: usize) -> LinuxResult<isize> {
 pub fn sys_mprotect(addr: usize, size: usize, prot: usize) -> LinuxResult<isize> {
     let curr = current();
-    let mut aspace = curr.task_ext().process_data().aspace.lock();
+    let aspace = curr.task_ext().process_data().aspace();
+    let mut aspace = aspace.lock();
     let prot = MappingFlags::from_bits(prot).ok_or(LinuxError::EINVAL)?;
     debug!(
         "mprotect: addr={:#x}, size={:#x}, prot={:?}",
@@ -55,7 +57,8 @@ pub fn sys_mmap(
     offset: usize,
 ) -> LinuxResult<isize> {
     let curr = current();
-    let mut aspace = curr.task_ext().process_data().aspace.lock();
+    let aspace = curr.task_ext().process_data().aspace();
+    let mut aspace = aspace.lock();
 
     if len == 0 {
         return Err(LinuxError::EINVAL);
@@ -109,7 +112,8 @@ pub fn sys_mmap(
 
 pub fn sys_munmap(start: usize, size: usize) -> LinuxResult<isize> {
     let curr = current();
-    let mut aspace = curr.task_ext().process_data().aspace.lock();
+    let aspace = curr.task_ext().process_data().aspace();
+    let mut aspace = aspace.lock();
     let start = start.into();
     let size = size.align_up_4k();
     if aspace.munmap(start, size).is_ok() {
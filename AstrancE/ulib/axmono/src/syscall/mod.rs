@@ -30,7 +30,9 @@ pub mod ipc;
 pub mod mm;
 pub mod pthread;
 pub mod process;
+pub mod sched;
 pub mod signal;
+pub mod sys;
 pub mod time;
 
 pub use mm::*;
@@ -66,7 +68,7 @@ syscall_handler_def!(
                 child_tid,
                 tls,
             )?;
-            Ok(child_task.task_ext().thread.process().pid() as isize)
+            Ok(child_task.task_ext().thread().process().pid() as isize)
         }
         wait4 => [pid, wstatus, options, reusage, ..] {
             let curr = current();
@@ -93,7 +95,7 @@ syscall_handler_def!(
         }
         set_tid_address => args {
                 let tidptr = args[0];
-                let tid: usize = current().task_ext().thread.tid() as _;
+                let tid: usize = current().task_ext().thread().tid() as _;
                 current().task_ext().thread_data().set_clear_child_tid(tidptr);
                 Ok(tid as isize)
         }
@@ -102,7 +104,8 @@ syscall_handler_def!(
         }
         munmap => args {
             let curr = current();
-            let mut aspace = curr.task_ext().process_data().aspace.lock();
+            let aspace = curr.task_ext().process_data().aspace();
+            let mut aspace = aspace.lock();
             let start = args[0].into();
             let size = args[1].align_up_4k();
             if aspace.munmap(start, size).is_ok() {
@@ -116,21 +119,21 @@ syscall_handler_def!(
             apply!(mm::sys_mprotect, addr, size, prot)
         }
         getpid => _ {
-            Ok(current().task_ext().thread.process().pid() as _)
+            Ok(current().task_ext().thread().process().pid() as _)
         }
         gettid => _ {
-            Ok(current().task_ext().thread.tid() as _)
+            Ok(current().task_ext().thread().tid() as _)
         }
         getppid => _ {
-            current().task_ext().thread.process().parent().map(|p|p.pid() as _).ok_or(LinuxError::EINVAL)
+            current().task_ext().thread().process().parent().map(|p|p.pid() as _).ok_or(LinuxError::EINVAL)
         }
         getgid => _ {
-            Ok(current().task_ext().thread.process().group().pgid() as _)
+            Ok(current().task_ext().thread().process().group().pgid() as _)
         }
         getuid => _{
             Ok(0)
             //TODO: to acomplish the puid()
-            // Ok(current().task_ext().thread.process().group().puid() as _)
+            // Ok(current().task_ext().thread().process().group().puid() as _)
         }
         geteuid => _{
             //TODO:geteuid ::returns the effective user ID of the calling process
@@ -170,6 +173,12 @@ syscall_handler_def!(
         rt_sigreturn => _ {
             task::signal::sys_sigreturn()
         }
+        restart_syscall => _ {
+            task::signal::sys_restart_syscall()
+        }
+        sigaltstack => [ss, old_ss, ..] {
+            task::signal::sys_sigaltstack(ss.into(), old_ss.into())
+        }
         rt_sigsuspend => [mask_ptr,sigsetsize,..]{
             task::signal::sys_rt_sigsuspend(mask_ptr as _,sigsetsize as _)
         }
@@ -182,6 +191,12 @@ syscall_handler_def!(
         tgkill => [tgid, tid, sig, ..] { // 对应 sys_tgkill
             task::signal::sys_tgkill(tgid as _, tid as _, sig as _)
         }
+        rt_sigqueueinfo => [tgid, sig, uinfo, ..] {
+            task::signal::sys_rt_sigqueueinfo(tgid as _, sig as _, uinfo.into())
+        }
+        rt_tgsigqueueinfo => [tgid, tid, sig, uinfo, ..] {
+            task::signal::sys_rt_tgsigqueueinfo(tgid as _, tid as _, sig as _, uinfo.into())
+        }
         //FIXME incomplete！
         setxattr => _ {
             Ok(0)
@@ -6,6 +6,8 @@ use arceos_posix_api::{char_ptr_to_str, str_vec_ptr_to_str};
 use axerrno::{AxError, LinuxError, LinuxResult};
 use axtask::{TaskExtRef, current};
 use core::ffi::c_char;
+use core::sync::atomic::Ordering;
+use linux_raw_sys::prctl::{PR_GET_DUMPABLE, PR_SET_DUMPABLE};
 
 pub fn sys_exit(code: i32) -> LinuxResult<isize> {
     task::sys_exit(code);
@@ -54,7 +56,7 @@ pub fn sys_clone(
         tls,
     )?;
     //let child_task = task::clone_task(if sp != 0 { Some(sp) } else { None }, clone_flags, true)?;
-    Ok(child_task.task_ext().thread.process().pid() as isize)
+    Ok(child_task.task_ext().thread().process().pid() as isize)
 }
 
 pub fn sys_wait4(pid: i32, wstatus: usize, options: u32) -> LinuxResult<isize> {
@@ -77,7 +79,7 @@ pub fn sys_execve(pathname: usize, argv: usize, envp: usize) -> LinuxResult<isiz
 }
 
 pub fn sys_set_tid_address(tidptr: usize) -> LinuxResult<isize> {
-    let tid: usize = current().task_ext().thread.tid() as _;
+    let tid: usize = current().task_ext().thread().tid() as _;
     current()
         .task_ext()
         .thread_data()
@@ -86,17 +88,17 @@ pub fn sys_set_tid_address(tidptr: usize) -> LinuxResult<isize> {
 }
 
 pub fn sys_getpid() -> LinuxResult<isize> {
-    Ok(current().task_ext().thread.process().pid() as _)
+    Ok(current().task_ext().thread().process().pid() as _)
 }
 
 pub fn sys_gettid() -> LinuxResult<isize> {
-    Ok(current().task_ext().thread.tid() as _)
+    Ok(current().task_ext().thread().tid() as _)
 }
 
 pub fn sys_getppid() -> LinuxResult<isize> {
     current()
         .task_ext()
-        .thread
+        .thread()
         .process()
         .parent()
         .map(|p| p.pid() as _)
@@ -104,13 +106,13 @@ pub fn sys_getppid() -> LinuxResult<isize> {
 }
 
 pub fn sys_getgid() -> LinuxResult<isize> {
-    Ok(current().task_ext().thread.process().group().pgid() as _)
+    Ok(current().task_ext().thread().process().group().pgid() as _)
 }
 
 pub fn sys_getuid() -> LinuxResult<isize> {
     Ok(0)
     // TODO: 完善 puid
-    // Ok(current().task_ext().thread.process().group().puid() as _)
+    // Ok(current().task_ext().thread().process().group().puid() as _)
 }
 
 pub fn sys_geteuid() -> LinuxResult<isize> {
@@ -136,3 +138,25 @@ pub fn sys_futex() -> LinuxResult<isize> {
     task::sys_exit(-1);
 }
 
+
+pub fn sys_prctl(option: u32, arg2: usize) -> LinuxResult<isize> {
+    let curr = current();
+    let proc_data = curr.task_ext().process_data();
+    match option {
+        PR_GET_DUMPABLE => Ok(proc_data.dumpable.load(Ordering::Relaxed) as isize),
+        PR_SET_DUMPABLE => {
+            // SUID_DUMP_ROOT (2) 只能由内核设置
+            let dumpable = match arg2 {
+                0 => false,
+                1 => true,
+                _ => return Err(LinuxError::EINVAL),
+            };
+            proc_data.dumpable.store(dumpable, Ordering::Relaxed);
+            Ok(0)
+        }
+        _ => {
+            warn!("prctl option {} not supported", option);
+            Err(LinuxError::EINVAL)
+        }
+    }
+}
@@ -1,7 +1,14 @@
 use core::{isize, time::Duration};
 
-use crate::pthread::{FutexFlags, FutexOp, futex};
+use crate::{
+    pthread::{FutexFlags, FutexOp, futex},
+    ptr::{PtrWrapper, UserPtr},
+    task::{ThreadData, get_thread},
+};
 use arceos_posix_api::ctypes;
+use axprocess::Pid;
+use axtask::{TaskExtRef, current};
+use linux_raw_sys::general::robust_list_head;
 // 导入 futex 函数和 FutexOp 枚举
 use axerrno::{LinuxError, LinuxResult};
 use bitflags::Flags;
@@ -35,6 +42,35 @@ pub fn sys_futex(
     futex(vaddr, op, val_u32, flags, timeout).map(|_| 0)
 }
 
+/// set_robust_list: 设置当前线程的 robust futex 链表头
+pub fn sys_set_robust_list(head: usize, len: usize) -> LinuxResult<isize> {
+    if len != size_of::<robust_list_head>() {
+        return Err(LinuxError::EINVAL);
+    }
+    current().task_ext().thread_data().set_robust_list(head);
+    Ok(0)
+}
+
+/// get_robust_list: 读取线程的 robust futex 链表头，`pid` 为 0 时为当前线程
+pub fn sys_get_robust_list(pid: Pid, head_ptr: usize, len_ptr: usize) -> LinuxResult<isize> {
+    let thread = if pid == 0 {
+        current().task_ext().thread().clone()
+    } else {
+        get_thread(pid)?
+    };
+    let head = thread
+        .data::<ThreadData>()
+        .ok_or(LinuxError::ESRCH)?
+        .robust_list();
+    let head_ptr = UserPtr::<usize>::from(head_ptr).get()?;
+    let len_ptr = UserPtr::<usize>::from(len_ptr).get()?;
+    unsafe {
+        head_ptr.write(head);
+        len_ptr.write(size_of::<robust_list_head>());
+    }
+    Ok(0)
+}
+
 // 示例的 sys_pthread 函数，如果不需要可以删除或修改
 pub fn sys_pthread(_arg: usize) -> LinuxResult<isize> {
     // 这是一个占位符，如果你的 pthread 系统调用有其他功能，可以在这里实现
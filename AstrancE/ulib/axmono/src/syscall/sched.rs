@@ -0,0 +1,216 @@
+//! 调度相关的系统调用：CPU 亲和性、调度策略与优先级。
+
+use alloc::{sync::Arc, vec::Vec};
+use axerrno::{LinuxError, LinuxResult};
+use axprocess::{Pid, Thread};
+use axtask::{AxCpuMask, AxTaskRef, TaskExtRef, current};
+
+use crate::ptr::{PtrWrapper, UserConstPtr, UserPtr};
+use crate::task::{ThreadData, THREAD_TABLE, get_thread, sched::*};
+
+const PRIO_PROCESS: i32 = 0;
+const PRIO_PGRP: i32 = 1;
+const PRIO_USER: i32 = 2;
+
+/// 用户态 `cpu_set_t` 中有效部分的字节数，按 `usize` 对齐
+const CPU_MASK_SIZE: usize =
+    axconfig::SMP.div_ceil(usize::BITS as usize) * core::mem::size_of::<usize>();
+
+/// 根据 tid 查找线程，0 表示当前线程
+fn find_thread(tid: i32) -> LinuxResult<Arc<Thread>> {
+    match tid {
+        0 => Ok(current().task_ext().thread().clone()),
+        tid if tid > 0 => get_thread(tid as Pid),
+        _ => Err(LinuxError::EINVAL),
+    }
+}
+
+fn thread_data(thread: &Thread) -> LinuxResult<&ThreadData> {
+    thread.data::<ThreadData>().ok_or(LinuxError::ESRCH)
+}
+
+fn thread_task(thread: &Thread) -> LinuxResult<AxTaskRef> {
+    thread_data(thread)?.task().ok_or(LinuxError::ESRCH)
+}
+
+pub fn sys_sched_setaffinity(tid: i32, len: usize, mask: UserConstPtr<u8>) -> LinuxResult<isize> {
+    let task = thread_task(&find_thread(tid)?)?;
+    let len = len.min(CPU_MASK_SIZE);
+    let bytes = unsafe { core::slice::from_raw_parts(mask.get_as_bytes(len)?, len) };
+
+    let mut cpumask = AxCpuMask::new();
+    for cpu in 0..axconfig::SMP.min(len * 8) {
+        if bytes[cpu / 8] & (1 << (cpu % 8)) != 0 {
+            cpumask.set(cpu, true);
+        }
+    }
+    if !axtask::set_task_affinity(&task, cpumask) {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(0)
+}
+
+pub fn sys_sched_getaffinity(tid: i32, len: usize, mask: UserPtr<u8>) -> LinuxResult<isize> {
+    if len < CPU_MASK_SIZE || len % core::mem::size_of::<usize>() != 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let cpumask = thread_task(&find_thread(tid)?)?.cpumask();
+
+    let mut bytes = vec![0u8; CPU_MASK_SIZE];
+    for cpu in (0..axconfig::SMP).filter(|&cpu| cpumask.get(cpu)) {
+        bytes[cpu / 8] |= 1 << (cpu % 8);
+    }
+    let dst = mask.get_as_bytes(CPU_MASK_SIZE)?;
+    unsafe { core::ptr::copy_nonoverlapping(bytes.as_ptr(), dst, CPU_MASK_SIZE) };
+    Ok(CPU_MASK_SIZE as isize)
+}
+
+/// 检查实时优先级是否与调度策略相符
+fn check_rt_priority(policy: u32, prio: i32) -> LinuxResult<u32> {
+    let valid = if is_rt_policy(policy) {
+        (1..=MAX_RT_PRIO as i32).contains(&prio)
+    } else {
+        prio == 0
+    };
+    if valid { Ok(prio as u32) } else { Err(LinuxError::EINVAL) }
+}
+
+fn read_sched_param(param: UserConstPtr<i32>) -> LinuxResult<i32> {
+    if param.address().as_usize() == 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(unsafe { *param.get()? })
+}
+
+pub fn sys_sched_setscheduler(
+    tid: i32,
+    policy: i32,
+    param: UserConstPtr<i32>,
+) -> LinuxResult<isize> {
+    let policy = u32::try_from(policy).map_err(|_| LinuxError::EINVAL)?;
+    let reset_on_fork = policy & SCHED_RESET_ON_FORK != 0;
+    let policy = policy & !SCHED_RESET_ON_FORK;
+    if !is_valid_policy(policy) {
+        return Err(LinuxError::EINVAL);
+    }
+    let prio = check_rt_priority(policy, read_sched_param(param)?)?;
+
+    let thread = find_thread(tid)?;
+    let data = thread_data(&thread)?;
+    data.sched.set_scheduler(policy, prio, reset_on_fork);
+    if let Some(task) = data.task() {
+        data.sched.apply(&task);
+    }
+    Ok(0)
+}
+
+pub fn sys_sched_getscheduler(tid: i32) -> LinuxResult<isize> {
+    let thread = find_thread(tid)?;
+    Ok(thread_data(&thread)?.sched.policy_with_flags() as isize)
+}
+
+pub fn sys_sched_setparam(tid: i32, param: UserConstPtr<i32>) -> LinuxResult<isize> {
+    let prio = read_sched_param(param)?;
+    let thread = find_thread(tid)?;
+    let data = thread_data(&thread)?;
+    let sched = &data.sched;
+    let policy = sched.policy();
+    let prio = check_rt_priority(policy, prio)?;
+    sched.set_scheduler(
+        policy,
+        prio,
+        sched.policy_with_flags() & SCHED_RESET_ON_FORK != 0,
+    );
+    if let Some(task) = data.task() {
+        sched.apply(&task);
+    }
+    Ok(0)
+}
+
+pub fn sys_sched_getparam(tid: i32, param: UserPtr<i32>) -> LinuxResult<isize> {
+    if param.address().as_usize() == 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let thread = find_thread(tid)?;
+    let prio = thread_data(&thread)?.sched.rt_priority();
+    unsafe { *param.get()? = prio as i32 };
+    Ok(0)
+}
+
+pub fn sys_sched_get_priority_max(policy: i32) -> LinuxResult<isize> {
+    match policy as u32 {
+        SCHED_FIFO | SCHED_RR => Ok(MAX_RT_PRIO as isize),
+        SCHED_OTHER | SCHED_BATCH | SCHED_IDLE => Ok(0),
+        _ => Err(LinuxError::EINVAL),
+    }
+}
+
+pub fn sys_sched_get_priority_min(policy: i32) -> LinuxResult<isize> {
+    match policy as u32 {
+        SCHED_FIFO | SCHED_RR => Ok(1),
+        SCHED_OTHER | SCHED_BATCH | SCHED_IDLE => Ok(0),
+        _ => Err(LinuxError::EINVAL),
+    }
+}
+
+/// 找出 `setpriority`/`getpriority` 作用的所有线程
+fn priority_targets(which: i32, who: i32) -> LinuxResult<Vec<Arc<Thread>>> {
+    if who < 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let threads = match which {
+        PRIO_PROCESS => vec![find_thread(who).map_err(|_| LinuxError::ESRCH)?],
+        PRIO_PGRP => {
+            let pgid = if who == 0 {
+                current().task_ext().thread().process().group().pgid()
+            } else {
+                who as Pid
+            };
+            THREAD_TABLE
+                .read()
+                .values()
+                .filter(|thread| thread.process().group().pgid() == pgid)
+                .collect()
+        }
+        // 只有 root 用户
+        PRIO_USER if who == 0 => THREAD_TABLE.read().values().collect(),
+        PRIO_USER => Vec::new(),
+        _ => return Err(LinuxError::EINVAL),
+    };
+    if threads.is_empty() {
+        return Err(LinuxError::ESRCH);
+    }
+    Ok(threads)
+}
+
+fn set_thread_nice(thread: &Thread, nice: i32) -> LinuxResult<()> {
+    let data = thread_data(thread)?;
+    data.sched.set_nice(nice);
+    if let Some(task) = data.task() {
+        data.sched.apply(&task);
+    }
+    Ok(())
+}
+
+pub fn sys_setpriority(which: i32, who: i32, prio: i32) -> LinuxResult<isize> {
+    for thread in priority_targets(which, who)? {
+        set_thread_nice(&thread, prio)?;
+    }
+    Ok(0)
+}
+
+/// 返回 `20 - nice`，取所有目标中最高的优先级，与 Linux 的系统调用一致
+pub fn sys_getpriority(which: i32, who: i32) -> LinuxResult<isize> {
+    let mut nice = MAX_NICE;
+    for thread in priority_targets(which, who)? {
+        nice = nice.min(thread_data(&thread)?.sched.nice());
+    }
+    Ok((20 - nice) as isize)
+}
+
+pub fn sys_nice(inc: i32) -> LinuxResult<isize> {
+    let thread = current().task_ext().thread().clone();
+    let nice = thread_data(&thread)?.sched.nice();
+    set_thread_nice(&thread, nice.saturating_add(inc))?;
+    Ok(0)
+}
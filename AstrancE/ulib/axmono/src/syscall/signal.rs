@@ -17,6 +17,22 @@ pub fn sys_rt_sigreturn() -> LinuxResult<isize> {
     crate::task::signal::sys_sigreturn()
 }
 
+pub fn sys_rt_sigqueueinfo(tgid: i32, sig: u32, uinfo: usize) -> LinuxResult<isize> {
+    crate::task::signal::sys_rt_sigqueueinfo(tgid as _, sig, uinfo.into())
+}
+
+pub fn sys_rt_tgsigqueueinfo(tgid: i32, tid: i32, sig: u32, uinfo: usize) -> LinuxResult<isize> {
+    crate::task::signal::sys_rt_tgsigqueueinfo(tgid as _, tid as _, sig, uinfo.into())
+}
+
+pub fn sys_sigaltstack(ss: usize, old_ss: usize) -> LinuxResult<isize> {
+    crate::task::signal::sys_sigaltstack(ss.into(), old_ss.into())
+}
+
+pub fn sys_restart_syscall() -> LinuxResult<isize> {
+    crate::task::signal::sys_restart_syscall()
+}
+
 pub fn sys_rt_sigsuspend(mask_ptr: usize, sigsetsize: usize) -> LinuxResult<isize> {
     crate::task::signal::sys_rt_sigsuspend(mask_ptr as _, sigsetsize as _)
 }
\ No newline at end of file
@@ -0,0 +1,139 @@
+use axerrno::{LinuxError, LinuxResult};
+use axhal::time::monotonic_time;
+use axlog::kmsg;
+use axtask::{FSHIFT, load_average};
+use axtask::{SyscallRestart, TaskExtRef, current};
+use linux_raw_sys::{general::rlimit, system::sysinfo};
+use memory_addr::PAGE_SIZE_4K;
+
+use crate::ptr::{PtrWrapper, UserConstPtr, UserPtr};
+use crate::task::THREAD_TABLE;
+
+/// sysinfo 中负载的定点小数位数
+const SI_LOAD_SHIFT: usize = 16;
+
+pub fn sys_sysinfo(info: UserPtr<sysinfo>) -> LinuxResult<isize> {
+    let info = info.get()?;
+
+    let allocator = axalloc::global_allocator();
+    let used_pages = allocator.used_pages();
+    let available_pages = allocator.available_pages();
+
+    // SAFETY: sysinfo 是纯数据结构，全零是合法值
+    let mut si: sysinfo = unsafe { core::mem::zeroed() };
+    si.uptime = monotonic_time().as_secs() as _;
+    for (dst, load) in si.loads.iter_mut().zip(load_average()) {
+        *dst = (load << (SI_LOAD_SHIFT - FSHIFT)) as _;
+    }
+    si.totalram = ((used_pages + available_pages) * PAGE_SIZE_4K) as _;
+    si.freeram = (available_pages * PAGE_SIZE_4K) as _;
+    si.procs = THREAD_TABLE.read().len().min(u16::MAX as usize) as _;
+    si.mem_unit = 1;
+
+    unsafe { info.write(si) };
+    Ok(0)
+}
+
+const SYSLOG_ACTION_CLOSE: i32 = 0;
+const SYSLOG_ACTION_OPEN: i32 = 1;
+const SYSLOG_ACTION_READ: i32 = 2;
+const SYSLOG_ACTION_READ_ALL: i32 = 3;
+const SYSLOG_ACTION_READ_CLEAR: i32 = 4;
+const SYSLOG_ACTION_CLEAR: i32 = 5;
+const SYSLOG_ACTION_CONSOLE_OFF: i32 = 6;
+const SYSLOG_ACTION_CONSOLE_ON: i32 = 7;
+const SYSLOG_ACTION_CONSOLE_LEVEL: i32 = 8;
+const SYSLOG_ACTION_SIZE_UNREAD: i32 = 9;
+const SYSLOG_ACTION_SIZE_BUFFER: i32 = 10;
+
+/// 读取内核日志时的最大长度，足以容纳日志缓冲区中的全部记录
+const SYSLOG_READ_MAX: usize = 2 * kmsg::LOG_BUF_LEN;
+
+/// 将内核缓冲区中的日志复制到用户空间
+///
+/// 日志缓冲区由关中断的自旋锁保护，不能在持锁时访问可能缺页的用户内存，
+/// 因此先读到内核缓冲区中
+fn syslog_copy_out(
+    buf: UserPtr<u8>,
+    len: usize,
+    read: impl FnOnce(&mut [u8]) -> usize,
+) -> LinuxResult<isize> {
+    let ubuf = buf.get_as_bytes(len)?;
+    let mut kbuf = vec![0u8; len.min(SYSLOG_READ_MAX)];
+    let n = read(&mut kbuf);
+    unsafe { core::ptr::copy_nonoverlapping(kbuf.as_ptr(), ubuf, n) };
+    Ok(n as isize)
+}
+
+pub fn sys_syslog(action: i32, buf: UserPtr<u8>, len: i32) -> LinuxResult<isize> {
+    match action {
+        SYSLOG_ACTION_CLOSE | SYSLOG_ACTION_OPEN => Ok(0),
+        SYSLOG_ACTION_READ | SYSLOG_ACTION_READ_ALL | SYSLOG_ACTION_READ_CLEAR => {
+            if buf.address().as_usize() == 0 || len < 0 {
+                return Err(LinuxError::EINVAL);
+            }
+            if len == 0 {
+                return Ok(0);
+            }
+            let len = len as usize;
+            if action == SYSLOG_ACTION_READ {
+                // 等待新日志，被信号打断时返回
+                if axfs::kmsg::wait_until(|| kmsg::syslog_unread_len() != 0).is_err() {
+                    current().set_syscall_restart(SyscallRestart::Sys);
+                    return Err(LinuxError::EINTR);
+                }
+                syslog_copy_out(buf, len, kmsg::syslog_read)
+            } else {
+                let clear = action == SYSLOG_ACTION_READ_CLEAR;
+                syslog_copy_out(buf, len, |kbuf| kmsg::syslog_read_all(kbuf, clear))
+            }
+        }
+        SYSLOG_ACTION_CLEAR => {
+            kmsg::syslog_clear();
+            Ok(0)
+        }
+        SYSLOG_ACTION_CONSOLE_OFF => {
+            kmsg::console_off();
+            Ok(0)
+        }
+        SYSLOG_ACTION_CONSOLE_ON => {
+            kmsg::console_on();
+            Ok(0)
+        }
+        SYSLOG_ACTION_CONSOLE_LEVEL => {
+            let level = u8::try_from(len).map_err(|_| LinuxError::EINVAL)?;
+            kmsg::set_console_level(level).map_err(|_| LinuxError::EINVAL)?;
+            Ok(0)
+        }
+        SYSLOG_ACTION_SIZE_UNREAD => Ok(kmsg::syslog_unread_len() as isize),
+        SYSLOG_ACTION_SIZE_BUFFER => Ok(kmsg::LOG_BUF_LEN as isize),
+        _ => Err(LinuxError::EINVAL),
+    }
+}
+
+/// 读取并设置 RLIMIT_CORE，core 文件大小限制按进程保存
+pub fn sys_prlimit_core(
+    new_limit: UserConstPtr<rlimit>,
+    old_limit: UserPtr<rlimit>,
+) -> LinuxResult<isize> {
+    let new_limit = new_limit.nullable(|ptr| Ok(unsafe { ptr.get()?.read() }))?;
+    if let Some(new) = new_limit {
+        if new.rlim_cur > new.rlim_max {
+            return Err(LinuxError::EINVAL);
+        }
+    }
+
+    let curr = current();
+    let mut limit = curr.task_ext().process_data().core_limit.write();
+    let old = *limit;
+    if let Some(new) = new_limit {
+        *limit = new;
+    }
+    drop(limit);
+
+    old_limit.nullable(|ptr| {
+        unsafe { ptr.get()?.write(old) };
+        Ok(())
+    })?;
+    Ok(0)
+}
@@ -1,8 +1,133 @@
-use axerrno::{AxError, LinuxResult};
+use axerrno::{AxError, LinuxError, LinuxResult};
+use axprocess::{Pid, Thread};
 use axtask::{current, TaskExtRef};
 use axhal::time::nanos_to_ticks;
-use arceos_posix_api::ctypes::tms;
+use arceos_posix_api::{self as api, ctypes, ctypes::tms};
 use core::convert::TryInto;
+use core::time::Duration;
+
+use crate::ptr::{PtrWrapper, UserPtr};
+use crate::task::{ThreadData, get_process, get_thread};
+
+/// CPU 时间时钟的类别，编码在时钟 id 的低两位
+const CPUCLOCK_PROF: i32 = 0;
+const CPUCLOCK_VIRT: i32 = 1;
+const CPUCLOCK_SCHED: i32 = 2;
+const CPUCLOCK_CLOCK_MASK: i32 = 3;
+/// 时钟 id 第 2 位表示线程时钟
+const CPUCLOCK_PERTHREAD_MASK: i32 = 4;
+
+/// CPU 时间时钟的统计对象
+enum CpuClockTarget {
+    Process(Pid),
+    Thread(Pid),
+}
+
+/// 解析 CPU 时间时钟，返回统计对象与时钟类别
+///
+/// `CLOCK_PROCESS_CPUTIME_ID` 与 `CLOCK_THREAD_CPUTIME_ID` 对应当前进程与线程；
+/// 负数时钟 id 由 `clock_getcpuclockid`/`pthread_getcpuclockid` 生成，
+/// 编码方式为 `(~pid << 3) | perthread << 2 | which`，pid 为 0 表示当前进程或线程。
+fn cpu_clock(clk: i32) -> Option<(CpuClockTarget, i32)> {
+    let curr = current();
+    let thread = curr.task_ext().thread();
+    match clk as u32 {
+        api::CLOCK_PROCESS_CPUTIME_ID => {
+            return Some((CpuClockTarget::Process(thread.process().pid()), CPUCLOCK_SCHED));
+        }
+        api::CLOCK_THREAD_CPUTIME_ID => {
+            return Some((CpuClockTarget::Thread(thread.tid()), CPUCLOCK_SCHED));
+        }
+        _ => {}
+    }
+    if clk >= 0 {
+        return None;
+    }
+    let pid = !(clk >> 3) as Pid;
+    let which = clk & CPUCLOCK_CLOCK_MASK;
+    if clk & CPUCLOCK_PERTHREAD_MASK != 0 {
+        let tid = if pid == 0 { thread.tid() } else { pid };
+        Some((CpuClockTarget::Thread(tid), which))
+    } else {
+        let pid = if pid == 0 { thread.process().pid() } else { pid };
+        Some((CpuClockTarget::Process(pid), which))
+    }
+}
+
+/// 线程已消耗的用户态与内核态时间（纳秒）
+fn thread_cpu_time(thread: &Thread) -> (usize, usize) {
+    let Some(task) = thread.data::<ThreadData>().and_then(|data| data.task()) else {
+        return (0, 0);
+    };
+    task.task_ext().cpu_time()
+}
+
+/// 读取 CPU 时间时钟
+///
+/// 与 Linux 一致，时钟类别非法、目标不存在或线程时钟的目标不属于当前进程时
+/// 都返回 `EINVAL`
+fn read_cpu_clock(target: CpuClockTarget, which: i32) -> LinuxResult<Duration> {
+    if which == CPUCLOCK_CLOCK_MASK {
+        return Err(LinuxError::EINVAL);
+    }
+    let (utime, stime) = match target {
+        CpuClockTarget::Thread(tid) => {
+            let thread = get_thread(tid).map_err(|_| LinuxError::EINVAL)?;
+            let curr = current();
+            if thread.process().pid() != curr.task_ext().thread().process().pid() {
+                return Err(LinuxError::EINVAL);
+            }
+            thread_cpu_time(&thread)
+        }
+        CpuClockTarget::Process(pid) => get_process(pid)
+            .map_err(|_| LinuxError::EINVAL)?
+            .threads()
+            .into_iter()
+            .map(|thread| thread_cpu_time(&thread))
+            .fold((0, 0), |(u, s), (tu, ts)| (u + tu, s + ts)),
+    };
+    let nanos = match which {
+        CPUCLOCK_VIRT => utime,
+        CPUCLOCK_PROF | CPUCLOCK_SCHED => utime + stime,
+        _ => unreachable!(),
+    };
+    Ok(Duration::from_nanos(nanos as u64))
+}
+
+/// 将 posix 接口返回的负错误码转换为 [`LinuxResult`]
+fn posix_result(ret: core::ffi::c_int) -> LinuxResult<isize> {
+    if ret < 0 {
+        Err(LinuxError::try_from(-ret).unwrap_or(LinuxError::EINVAL))
+    } else {
+        Ok(ret as isize)
+    }
+}
+
+pub fn sys_clock_gettime(clk: i32, ts: UserPtr<ctypes::timespec>) -> LinuxResult<isize> {
+    let Some((target, which)) = cpu_clock(clk) else {
+        return posix_result(unsafe { api::sys_clock_gettime(clk as _, ts.get()?) });
+    };
+    let now = read_cpu_clock(target, which)?;
+    unsafe { *ts.get()? = now.into() };
+    Ok(0)
+}
+
+pub fn sys_clock_getres(clk: i32, res: UserPtr<ctypes::timespec>) -> LinuxResult<isize> {
+    let res = if res.address().as_usize() == 0 {
+        core::ptr::null_mut()
+    } else {
+        res.get()?
+    };
+    let Some((target, which)) = cpu_clock(clk) else {
+        return posix_result(unsafe { api::sys_clock_getres(clk as _, res) });
+    };
+    // 检查时钟是否有效
+    read_cpu_clock(target, which)?;
+    if !res.is_null() {
+        unsafe { *res = Duration::from_nanos(1).into() };
+    }
+    Ok(0)
+}
 
 pub fn sys_times(tms_ptr: usize) -> LinuxResult<isize> {
     let curr_task = current();
@@ -0,0 +1,412 @@
+//! 进程被信号终止时生成 ELF core 文件
+//!
+//! 文件布局与 Linux 相同：ELF 头、程序头（一个 PT_NOTE 和每个内存区域一个 PT_LOAD）、
+//! 注释段（NT_PRSTATUS、NT_PRPSINFO、NT_AUXV、NT_FILE），最后是按页对齐的内存内容。
+//! 生成的文件可以直接用宿主机上的 gdb 加载。
+
+use core::sync::atomic::Ordering;
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use axfs::api::File;
+use axhal::{arch::TrapFrame, paging::MappingFlags};
+use axio::Write;
+use axmm::{AddrSpace, MmapFlags, backend::VmAreaType};
+use axprocess::{Process, Thread};
+use axsignal::{Signal, SignalSet};
+use axtask::{AxTaskRef, TaskExtRef, current};
+use linux_raw_sys::general::RLIM_INFINITY;
+use memory_addr::{MemoryAddr, PAGE_SIZE_4K, VirtAddr};
+
+use super::{ThreadData, read_trapframe_from_kstack};
+
+/// core 文件名，与 Linux 默认的 core_pattern 相同，写入进程的当前目录
+const CORE_FILE_NAME: &str = "core";
+
+const EI_NIDENT: usize = 16;
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u8 = 1;
+const ET_CORE: u16 = 4;
+#[cfg(target_arch = "riscv64")]
+const EM_ARCH: u16 = 243; // EM_RISCV
+#[cfg(target_arch = "loongarch64")]
+const EM_ARCH: u16 = 258; // EM_LOONGARCH
+
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+const NT_PRSTATUS: u32 = 1;
+const NT_PRPSINFO: u32 = 3;
+const NT_AUXV: u32 = 6;
+const NT_FILE: u32 = 0x4649_4c45;
+
+const EHDR_SIZE: usize = 64;
+const PHDR_SIZE: usize = 56;
+
+/// `elf_gregset_t` 中的寄存器个数
+#[cfg(target_arch = "riscv64")]
+const ELF_NGREG: usize = 32;
+#[cfg(target_arch = "loongarch64")]
+const ELF_NGREG: usize = 45;
+
+/// `struct elf_prpsinfo` 中的 `pr_fname` 和 `pr_psargs` 长度
+const ELF_PRFNAME_LEN: usize = 16;
+const ELF_PRARGSZ: usize = 80;
+
+/// 按 `elf_gregset_t` 的布局导出用户态寄存器
+#[cfg(target_arch = "riscv64")]
+fn elf_gregs(tf: &TrapFrame) -> [usize; ELF_NGREG] {
+    // user_regs_struct: pc, x1..x31，与 GeneralRegisters 的顺序一致
+    let mut regs = [0; ELF_NGREG];
+    regs[0] = tf.sepc;
+    let gprs: &[usize; ELF_NGREG - 1] = unsafe { core::mem::transmute(&tf.regs) };
+    regs[1..].copy_from_slice(gprs);
+    regs
+}
+
+/// 按 `elf_gregset_t` 的布局导出用户态寄存器
+#[cfg(target_arch = "loongarch64")]
+fn elf_gregs(tf: &TrapFrame) -> [usize; ELF_NGREG] {
+    // user_pt_regs: r0..r31, orig_a0, csr_era, csr_badv, reserved[10]
+    let mut regs = [0; ELF_NGREG];
+    let gprs: &[usize; 32] = unsafe { core::mem::transmute(&tf.regs) };
+    regs[..32].copy_from_slice(gprs);
+    regs[32] = tf.orig_a0;
+    regs[33] = tf.era;
+    regs
+}
+
+/// 小端序的字节缓冲区，用于拼装 ELF 头与注释
+#[derive(Default)]
+struct ElfBuf(Vec<u8>);
+
+impl ElfBuf {
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn u64(&mut self, v: u64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn usize(&mut self, v: usize) {
+        self.u64(v as u64);
+    }
+    fn bytes(&mut self, v: &[u8]) {
+        self.0.extend_from_slice(v);
+    }
+    /// 写入定长字符串，超出部分截断，不足补零
+    fn str_fixed(&mut self, s: &str, len: usize) {
+        let n = s.len().min(len - 1);
+        self.bytes(&s.as_bytes()[..n]);
+        self.zero(len - n);
+    }
+    fn zero(&mut self, n: usize) {
+        self.0.resize(self.0.len() + n, 0);
+    }
+    fn align(&mut self, align: usize) {
+        self.zero(self.0.len().next_multiple_of(align) - self.0.len());
+    }
+
+    /// 写入一条注释，名字统一为 "CORE"
+    fn note(&mut self, ty: u32, desc: &[u8]) {
+        const NAME: &[u8] = b"CORE\0";
+        self.u32(NAME.len() as u32);
+        self.u32(desc.len() as u32);
+        self.u32(ty);
+        self.bytes(NAME);
+        self.align(4);
+        self.bytes(desc);
+        self.align(4);
+    }
+}
+
+/// 核心转储中的一个内存段
+struct Segment {
+    start: VirtAddr,
+    size: usize,
+    flags: MappingFlags,
+    /// 是否写入内存内容，未修改的文件映射的内容由 NT_FILE 指向的文件提供
+    dump: bool,
+    /// 文件映射的路径与偏移
+    file: Option<(String, usize)>,
+}
+
+fn collect_segments(aspace: &AddrSpace) -> Vec<Segment> {
+    aspace
+        .areas
+        .iter()
+        .filter(|area| area.flags().contains(MappingFlags::USER))
+        .map(|area| {
+            let (file, private) = match area.backend().get_vm_type() {
+                Some(VmAreaType::Mmap(io)) => {
+                    (io.file_info(), !io.flags().contains(MmapFlags::MAP_SHARED))
+                }
+                _ => (None, false),
+            };
+            // 可写的私有文件映射（如 .data 与 .bss）可能已被修改，与文件内容不同
+            let modified = private && area.flags().contains(MappingFlags::WRITE);
+            Segment {
+                start: area.start(),
+                size: area.size(),
+                flags: area.flags(),
+                dump: area.flags().contains(MappingFlags::READ) && (file.is_none() || modified),
+                file,
+            }
+        })
+        .collect()
+}
+
+/// NT_PRSTATUS：线程的寄存器与信号状态 (`struct elf_prstatus`)
+fn prstatus(sig: Signal, thread: &Thread, tf: &TrapFrame, sighold: SignalSet) -> Vec<u8> {
+    let process = thread.process();
+    let mut buf = ElfBuf::default();
+    // pr_info: si_signo, si_code, si_errno
+    buf.u32(sig as u32);
+    buf.u32(0);
+    buf.u32(0);
+    // pr_cursig
+    buf.u16(sig as u16);
+    buf.align(8);
+    // pr_sigpend, pr_sighold
+    buf.u64(0);
+    buf.u64(sighold.bits());
+    // pr_pid, pr_ppid, pr_pgrp, pr_sid
+    buf.u32(thread.tid());
+    buf.u32(process.parent().map_or(0, |p| p.pid()));
+    buf.u32(process.group().pgid());
+    buf.u32(process.group().session().sid());
+    // pr_utime, pr_stime, pr_cutime, pr_cstime
+    buf.zero(4 * 16);
+    for reg in elf_gregs(tf) {
+        buf.usize(reg);
+    }
+    // pr_fpvalid
+    buf.u32(0);
+    buf.align(8);
+    buf.0
+}
+
+/// NT_PRPSINFO：进程信息 (`struct elf_prpsinfo`)
+fn prpsinfo(process: &Process, exe_path: &str) -> Vec<u8> {
+    let mut buf = ElfBuf::default();
+    // pr_state, pr_sname, pr_zomb, pr_nice
+    buf.bytes(&[0, b'R', 0, 0]);
+    buf.align(8);
+    // pr_flag
+    buf.u64(0);
+    // pr_uid, pr_gid
+    buf.u32(0);
+    buf.u32(0);
+    // pr_pid, pr_ppid, pr_pgrp, pr_sid
+    buf.u32(process.pid());
+    buf.u32(process.parent().map_or(0, |p| p.pid()));
+    buf.u32(process.group().pgid());
+    buf.u32(process.group().session().sid());
+    let fname = exe_path.rsplit('/').next().unwrap_or(exe_path);
+    buf.str_fixed(fname, ELF_PRFNAME_LEN);
+    buf.str_fixed(exe_path, ELF_PRARGSZ);
+    buf.0
+}
+
+/// NT_FILE：文件映射表
+fn file_note(segments: &[Segment]) -> Vec<u8> {
+    let files: Vec<_> = segments
+        .iter()
+        .filter_map(|seg| seg.file.as_ref().map(|file| (seg, file)))
+        .collect();
+    let mut buf = ElfBuf::default();
+    buf.usize(files.len());
+    buf.usize(PAGE_SIZE_4K);
+    for (seg, (_, offset)) in files.iter() {
+        buf.usize(seg.start.as_usize());
+        buf.usize(seg.start.as_usize() + seg.size);
+        buf.usize(offset / PAGE_SIZE_4K);
+    }
+    for (_, (path, _)) in files.iter() {
+        buf.bytes(path.as_bytes());
+        buf.bytes(&[0]);
+    }
+    buf.0
+}
+
+/// 写入 core 文件，超出 RLIMIT_CORE 后停止写入
+struct CoreWriter {
+    file: File,
+    written: u64,
+    limit: u64,
+}
+
+impl CoreWriter {
+    fn write(&mut self, data: &[u8]) -> bool {
+        let len = data.len() as u64;
+        if self.written + len > self.limit {
+            let remain = (self.limit - self.written) as usize;
+            let _ = self.file.write_all(&data[..remain]);
+            self.written = self.limit;
+            return false;
+        }
+        self.written += len;
+        self.file.write_all(data).is_ok()
+    }
+
+    fn zero(&mut self, mut n: usize) -> bool {
+        const ZEROS: [u8; PAGE_SIZE_4K] = [0; PAGE_SIZE_4K];
+        while n > 0 {
+            let len = n.min(PAGE_SIZE_4K);
+            if !self.write(&ZEROS[..len]) {
+                return false;
+            }
+            n -= len;
+        }
+        true
+    }
+}
+
+/// 为当前进程生成 core 文件，返回是否完整写入
+///
+/// 调用者需保证进程即将因 `sig` 终止。`sighold` 为当前线程处理该信号时的阻塞掩码，
+/// `others` 为已被 [`zap_other_threads`](super::exit::zap_other_threads) 杀死的其他线程，寄存器取自其内核栈上保存的陷入帧。
+pub fn dump_core(sig: Signal, sighold: SignalSet, others: &[(Arc<Thread>, AxTaskRef)]) -> bool {
+    let curr = current();
+    let thread = curr.task_ext().thread();
+    let process = thread.process();
+    let proc_data = curr.task_ext().process_data();
+
+    if !proc_data.dumpable.load(Ordering::Relaxed) {
+        debug!("process {} is not dumpable", process.pid());
+        return false;
+    }
+    let limit = proc_data.core_limit.read().rlim_cur as u64;
+    let limit = if limit == RLIM_INFINITY as u64 {
+        u64::MAX
+    } else {
+        limit
+    };
+    // 与 Linux 相同，限制小于一页时不生成 core 文件
+    if limit < PAGE_SIZE_4K as u64 {
+        return false;
+    }
+
+    // 写文件期间不持有地址空间的锁，只在读取每一页时加锁
+    let aspace = proc_data.aspace();
+    let segments = collect_segments(&aspace.lock());
+
+    // 注释段：当前线程在前，gdb 以第一个 NT_PRSTATUS 为出错线程
+    let mut notes = ElfBuf::default();
+    let tf = read_trapframe_from_kstack(curr.get_kernel_stack_top().unwrap());
+    notes.note(NT_PRSTATUS, &prstatus(sig, thread, &tf, sighold));
+    notes.note(NT_PRPSINFO, &prpsinfo(&process, &proc_data.exe_path.read()));
+    let auxv = proc_data.saved_auxv.read();
+    let auxv: Vec<u8> = auxv.iter().flat_map(|v| v.to_le_bytes()).collect();
+    notes.note(NT_AUXV, &auxv);
+    notes.note(NT_FILE, &file_note(&segments));
+    for (other, task) in others {
+        let Some(kstack_top) = task.get_kernel_stack_top() else {
+            continue;
+        };
+        let tf = read_trapframe_from_kstack(kstack_top);
+        let sighold = other
+            .data::<ThreadData>()
+            .map_or(SignalSet::empty(), |data| data.signal().lock().get_blocked());
+        notes.note(NT_PRSTATUS, &prstatus(sig, other, &tf, sighold));
+    }
+
+    // ELF 头与程序头
+    let phnum = 1 + segments.len();
+    let notes_offset = EHDR_SIZE + PHDR_SIZE * phnum;
+    let mut data_offset = (notes_offset + notes.0.len()).align_up_4k();
+
+    let mut header = ElfBuf::default();
+    header.bytes(&[0x7f, b'E', b'L', b'F', ELFCLASS64, ELFDATA2LSB, EV_CURRENT]);
+    header.zero(EI_NIDENT - 7);
+    header.u16(ET_CORE);
+    header.u16(EM_ARCH);
+    header.u32(EV_CURRENT as u32);
+    header.usize(0); // e_entry
+    header.usize(EHDR_SIZE); // e_phoff
+    header.usize(0); // e_shoff
+    header.u32(0); // e_flags
+    header.u16(EHDR_SIZE as u16);
+    header.u16(PHDR_SIZE as u16);
+    header.u16(phnum as u16);
+    header.u16(0); // e_shentsize
+    header.u16(0); // e_shnum
+    header.u16(0); // e_shstrndx
+
+    header.u32(PT_NOTE);
+    header.u32(0);
+    header.usize(notes_offset);
+    header.usize(0);
+    header.usize(0);
+    header.usize(notes.0.len());
+    header.usize(0);
+    header.usize(4);
+    for seg in segments.iter() {
+        let filesz = if seg.dump { seg.size } else { 0 };
+        let mut flags = 0;
+        if seg.flags.contains(MappingFlags::READ) {
+            flags |= PF_R;
+        }
+        if seg.flags.contains(MappingFlags::WRITE) {
+            flags |= PF_W;
+        }
+        if seg.flags.contains(MappingFlags::EXECUTE) {
+            flags |= PF_X;
+        }
+        header.u32(PT_LOAD);
+        header.u32(flags);
+        header.usize(data_offset);
+        header.usize(seg.start.as_usize());
+        header.usize(0);
+        header.usize(filesz);
+        header.usize(seg.size);
+        header.usize(PAGE_SIZE_4K);
+        data_offset += filesz;
+    }
+    header.bytes(&notes.0);
+    header.align(PAGE_SIZE_4K);
+
+    let file = match File::create(CORE_FILE_NAME) {
+        Ok(file) => file,
+        Err(e) => {
+            warn!("failed to create core file: {:?}", e);
+            return false;
+        }
+    };
+    let mut writer = CoreWriter {
+        file,
+        written: 0,
+        limit,
+    };
+    if !writer.write(&header.0) {
+        return false;
+    }
+
+    // 内存内容：未分配物理页的部分写零
+    let mut page = [0u8; PAGE_SIZE_4K];
+    for seg in segments.iter().filter(|seg| seg.dump) {
+        let mut vaddr = seg.start;
+        while vaddr < seg.start + seg.size {
+            let res = aspace.lock().read(vaddr, &mut page);
+            let ok = match res {
+                Ok(()) => writer.write(&page),
+                Err(_) => writer.zero(PAGE_SIZE_4K),
+            };
+            if !ok {
+                return false;
+            }
+            vaddr += PAGE_SIZE_4K;
+        }
+    }
+    info!(
+        "process {} dumped core ({} bytes)",
+        process.pid(),
+        writer.written
+    );
+    true
+}
@@ -2,28 +2,123 @@
 //! 本文件代码参考
 //! [oscomp/starry-next](https://github.com/oscomp/starry-next) 项目。
 //!
+use core::time::Duration;
+
+use alloc::{sync::Arc, vec::Vec};
 use arceos_posix_api::FD_TABLE;
-use axprocess::Pid;
+use axprocess::{Pid, Thread};
 use axsignal::{SigCode, SigCodeSigChld, SigStatus, Signal, SignalSet};
 //use axsignal::{SignalInfo, Signo};
-use crate::task::{process, send_signal_process, ProcessData, SigInfo_};
-use axtask::{TaskExtRef, current};
+use crate::task::{
+    process, send_signal_process, yield_with_time_stat, ProcessData, SigInfo_, ThreadData,
+};
+use axtask::{AxTaskRef, TaskExtRef, current};
 use linux_raw_sys::general::SI_KERNEL;
+use memory_addr::VirtAddr;
 
+use crate::pthread::{FutexFlags, FutexOp, exit_robust_list, futex};
 use crate::ptr::{PtrWrapper, UserPtr};
 
+/// 杀死其他线程时重新打断它们的间隔
+const ZAP_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 线程退出或执行 execve 时释放其用户态状态（同 Linux 的 `mm_release`）：
+/// 释放仍持有的 robust futex，清零 clear_child_tid 并唤醒等待它的线程
+pub(crate) fn release_user_state(thread_data: &ThreadData, tid: Pid) {
+    let robust_list = thread_data.robust_list();
+    if robust_list != 0 {
+        exit_robust_list(robust_list, tid);
+        thread_data.set_robust_list(0);
+    }
+
+    let clear_child_tid = thread_data.clear_child_tid();
+    if let Ok(clear_tid) = UserPtr::<Pid>::from(clear_child_tid).get() {
+        unsafe { clear_tid.write(0) };
+        let _ = futex(VirtAddr::from(clear_child_tid), FutexOp::Wake, 1, FutexFlags::empty(), None);
+    }
+    thread_data.set_clear_child_tid(0);
+}
+
+/// 打断进程中除当前线程外的所有线程并等待它们全部退出，返回被杀死的线程及其任务
+///
+/// 其他线程被打断后，在返回用户态前发现 [`ProcessData::killed`] 并退出。线程可能在
+/// 不可打断的睡眠中清除了打断标记，因此定期重新打断，直到它真正退出。每轮等待前
+/// 检查 `aborted`，其返回 `true` 时放弃等待并返回 `None`
+pub(crate) fn kill_other_threads(
+    aborted: impl Fn() -> bool,
+) -> Option<Vec<(Arc<Thread>, AxTaskRef)>> {
+    let curr = current();
+    let curr_ext = curr.task_ext();
+    let process = curr_ext.thread().process();
+    let tid = curr_ext.thread().tid();
+
+    let mut killed: Vec<(Arc<Thread>, AxTaskRef)> = Vec::new();
+    let mut waiting: Vec<(Arc<Thread>, AxTaskRef)> = Vec::new();
+    loop {
+        if aborted() {
+            return None;
+        }
+        // 等待期间可能有新线程被创建
+        for thr in process.threads() {
+            let known = |(t, _): &(Arc<Thread>, AxTaskRef)| t.tid() == thr.tid();
+            if thr.tid() == tid || killed.iter().chain(waiting.iter()).any(known) {
+                continue;
+            }
+            if let Some(task) = thr.data::<ThreadData>().and_then(|data| data.task()) {
+                waiting.push((thr, task));
+            }
+        }
+        if waiting.is_empty() {
+            if process.threads().len() <= 1 {
+                break;
+            }
+            // 新线程的任务尚未创建
+            yield_with_time_stat();
+            continue;
+        }
+        for (thr, task) in core::mem::take(&mut waiting) {
+            axtask::interrupt_task(&task);
+            if task.join_timeout(ZAP_RETRY_INTERVAL).is_some() {
+                killed.push((thr, task));
+            } else {
+                waiting.push((thr, task));
+            }
+        }
+    }
+    Some(killed)
+}
+
+/// 杀死进程中的其他线程（同 Linux 的 `zap_other_threads`），返回后当前线程是进程中唯一的线程
+///
+/// 已有其他线程在结束进程时，当前线程直接退出。
+///
+/// 返回被杀死的线程及其任务，任务的内核栈上仍保存着线程在用户态的陷入帧
+pub(crate) fn zap_other_threads() -> Vec<(Arc<Thread>, AxTaskRef)> {
+    let curr = current();
+    let curr_ext = curr.task_ext();
+    let process = curr_ext.thread().process();
+    if !curr_ext.process_data().start_group_exit(curr_ext.thread().tid()) {
+        exit_killed();
+    }
+    if !process.is_group_exited() {
+        process.group_exit();
+    }
+    // 结束进程不会被放弃
+    kill_other_threads(|| false).unwrap_or_default()
+}
+
 pub fn do_exit(exit_code: i32, group_exit: bool) -> ! {
+    if group_exit {
+        zap_other_threads();
+    }
+
     let curr = current();
     let curr_ext = curr.task_ext();
 
-    let thread = &curr_ext.thread;
+    let thread = curr_ext.thread();
     info!("{:?} exit with code: {}", thread, exit_code);
 
-    let clear_child_tid = UserPtr::<Pid>::from(curr_ext.thread_data().clear_child_tid());
-    if let Ok(clear_tid) = clear_child_tid.get() {
-        unsafe { clear_tid.write(0) };
-        // TODO: wake up threads, which are blocked by futex, and waiting for the address pointed by clear_child_tid
-    }
+    release_user_state(curr_ext.thread_data(), thread.tid());
 
     let process = thread.process();
     if thread.exit(exit_code) || true {
@@ -39,15 +134,21 @@ pub fn do_exit(exit_code: i32, group_exit: bool) -> ! {
                     .and_then(|it| it.exit_signal)
                     .unwrap_or(Signal::SIGCHLD);
                 debug!("send {:?} to parent {:?}", sig, parent.pid());
+                // wait 状态低 7 位为终止信号，否则高字节为退出码
+                let (code, status) = match exit_code & 0x7f {
+                    0 => (
+                        SigCodeSigChld::CLD_EXITED,
+                        SigStatus::ExitCode((exit_code >> 8) & 0xff),
+                    ),
+                    signo if exit_code & 0x80 != 0 => {
+                        (SigCodeSigChld::CLD_DUMPED, SigStatus::TerminatingSignal(signo))
+                    }
+                    signo => (SigCodeSigChld::CLD_KILLED, SigStatus::TerminatingSignal(signo)),
+                };
                 send_signal_process(
                     &parent.clone(),
                     sig,
-                    SigInfo_::Child(
-                        SigCodeSigChld::CLD_EXITED,
-                        SigStatus::ExitCode(exit_code),
-                        0,
-                        0,
-                    ),
+                    SigInfo_::Child(code, status, 0, 0),
                 );
                 parent_data.child_exit_wq.notify_all(false);
             }
@@ -57,18 +158,21 @@ pub fn do_exit(exit_code: i32, group_exit: bool) -> ! {
         // TODO: clear namespace resources
         FD_TABLE.clear();
     }
-    if group_exit && !process.is_group_exited() {
-        process.group_exit();
-        //let sig = SignalInfo::new(Signo::SIGKILL, SI_KERNEL as _);
-        for thr in process.threads() {
-            //let _ = send_signal_thread(&thr, sig.clone());
-            // TODO: thread local sigctx
-            //thr.data::<ThreadData>().and_then(||)
-        }
-    }
     axtask::exit(exit_code)
 }
 
+/// 被执行 execve 或结束进程的线程杀死：只释放线程自身的状态，不通知父进程
+pub(crate) fn exit_killed() -> ! {
+    let curr = current();
+    let curr_ext = curr.task_ext();
+    let thread = curr_ext.thread();
+    info!("{:?} killed by another thread", thread);
+
+    release_user_state(curr_ext.thread_data(), thread.tid());
+    thread.exit(0);
+    axtask::exit(0)
+}
+
 pub fn sys_exit(exit_code: i32) -> ! {
     do_exit(exit_code << 8, false)
 }
@@ -76,3 +180,9 @@ pub fn sys_exit(exit_code: i32) -> ! {
 pub fn sys_exit_group(exit_code: i32) -> ! {
     do_exit(exit_code << 8, true)
 }
+
+/// 被信号终止，wait 状态的低 7 位为终止信号，生成了 core 文件时置位 0x80
+pub fn exit_with_signal(sig: Signal, core_dumped: bool) -> ! {
+    let core_flag = if core_dumped { 0x80 } else { 0 };
+    do_exit(sig as i32 | core_flag, true)
+}
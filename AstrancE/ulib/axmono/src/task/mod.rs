@@ -17,8 +17,8 @@ use axprocess::{Pid, Process, ProcessGroup, Session, Thread};
 use axsignal::{SignalContext, SignalSet, SignalStackType};
 use core::{
     alloc::Layout,
-    cell::RefCell,
-    sync::atomic::{AtomicU64, AtomicUsize},
+    cell::{RefCell, UnsafeCell},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
 };
 use memory_addr::{VirtAddr, VirtAddrRange};
 use time::TimeStat;
@@ -41,15 +41,24 @@ pub use time::*;
 pub mod process;
 pub use process::*;
 
+pub mod sched;
+
 pub mod wait;
 pub use wait::sys_waitpid;
+pub mod coredump;
 pub mod exit;
 pub use exit::sys_exit;
 
 /// Task extended data for the monolithic kernel.
 pub struct TaskExt {
+    /// 只由任务自身访问
     pub time: RefCell<time::TimeStat>,
-    pub thread: Arc<Thread>,
+    /// 用户态与内核态时间（纳秒）的副本，每次更新 `time` 后写入，
+    /// 供其他任务（可能在其他 CPU 上）读取
+    cpu_time: [AtomicUsize; 2],
+    /// Replaced only by the task itself when `execve` takes over the PID of
+    /// the thread group leader, see [`TaskExt::replace_thread`].
+    thread: UnsafeCell<Arc<Thread>>,
 }
 
 #[allow(unused)]
@@ -67,46 +76,81 @@ impl TaskExt {
              *time: TimeStat::new().into(),
              */
             time: RefCell::new(TimeStat::new()),
-            thread,
+            cpu_time: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            thread: UnsafeCell::new(thread),
         }
     }
 
+    /// Get the [`Thread`] run by this task.
+    pub fn thread(&self) -> &Arc<Thread> {
+        unsafe { &*self.thread.get() }
+    }
+
+    /// Replace the [`Thread`] run by this task, returning the old one.
+    ///
+    /// # Safety
+    ///
+    /// Must be called by the task itself, with no reference returned by
+    /// [`TaskExt::thread`] alive.
+    pub(crate) unsafe fn replace_thread(&self, thread: Arc<Thread>) -> Arc<Thread> {
+        unsafe { core::mem::replace(&mut *self.thread.get(), thread) }
+    }
+
     /// Get the [`ThreadData`] associated with this task.
     pub fn thread_data(&self) -> &ThreadData {
-        self.thread.data().unwrap()
+        self.thread().data().unwrap()
     }
 
     /// Get the [`ProcessData`] associated with this task.
     pub fn process_data(&self) -> &ProcessData {
-        self.thread.process().data().unwrap()
+        self.thread().process().data().unwrap()
+    }
+
+    /// 更新时间统计，并发布新的用户态与内核态时间
+    fn update_time_stat(&self, f: impl FnOnce(&mut TimeStat)) {
+        let mut time = self.time.borrow_mut();
+        f(&mut time);
+        let (utime_ns, stime_ns) = time.output();
+        self.cpu_time[0].store(utime_ns, Ordering::Release);
+        self.cpu_time[1].store(stime_ns, Ordering::Release);
     }
 
     pub(crate) fn time_stat_from_kernel_to_user(&self, current_tick: usize) {
-        self.time.borrow_mut().switch_into_user_mode(current_tick);
+        self.update_time_stat(|time| time.switch_into_user_mode(current_tick));
     }
 
     pub(crate) fn time_stat_from_user_to_kernel(&self, current_tick: usize) {
-        self.time.borrow_mut().switch_into_kernel_mode(current_tick);
+        self.update_time_stat(|time| time.switch_into_kernel_mode(current_tick));
     }
 
     pub(crate) fn time_stat_output(&self) -> (usize, usize) {
         self.time.borrow().output()
     }
 
+    /// 任务已消耗的用户态与内核态时间（纳秒），可由任何任务读取
+    ///
+    /// 两者分别单调不减，但不一定取自同一次更新
+    pub(crate) fn cpu_time(&self) -> (usize, usize) {
+        (
+            self.cpu_time[0].load(Ordering::Acquire),
+            self.cpu_time[1].load(Ordering::Acquire),
+        )
+    }
+
     pub(crate) fn set_heap_top(&self, top: VirtAddr) -> VirtAddr {
-        self.process_data().aspace.lock().set_heap_top(top)
+        self.process_data().aspace().lock().set_heap_top(top)
     }
 
     pub(crate) fn set_heap_size(&self, size: usize) -> VirtAddr {
-        self.process_data().aspace.lock().set_heap_size(size)
+        self.process_data().aspace().lock().set_heap_size(size)
     }
 
     pub(crate) fn heap_size(&self) -> usize {
-        self.process_data().aspace.lock().heap().size()
+        self.process_data().aspace().lock().heap().size()
     }
 
     pub(crate) fn heap_top(&self) -> VirtAddr {
-        self.process_data().aspace.lock().heap().top()
+        self.process_data().aspace().lock().heap().top()
     }
 }
 
@@ -192,16 +236,16 @@ pub fn spawn_user_task(
     let parent = parent.unwrap_or(init_proc());
     let process = parent.fork(tid).data(process_data).build();
 
-    let thread_data = ThreadData {
-        clear_child_tid: AtomicUsize::new(0),
-        signal: spawn_signal_ctx()
-    };
+    let thread_data = ThreadData::new(process.data().unwrap());
     let thread = process.new_thread(tid).data(thread_data).build();
+    add_thread_to_table(&thread);
 
     task.init_task_ext(TaskExt::new(thread));
 
     task.task_ext().process_data().ns_init_new();
-    task.into_arc()
+    let task = task.into_arc();
+    task.task_ext().thread_data().set_task(&task);
+    task
 }
 
 pub fn init_proc() -> Arc<Process> {
@@ -310,18 +354,14 @@ pub fn time_stat_to_new_task() {
     let curr_task = current();
     curr_task
         .task_ext()
-        .time
-        .borrow_mut()
-        .switch_to_new_task(monotonic_time_nanos() as usize);
+        .update_time_stat(|time| time.switch_to_new_task(monotonic_time_nanos() as usize));
 }
 
 pub fn time_stat_from_old_task() {
     let curr_task = current();
     curr_task
         .task_ext()
-        .time
-        .borrow_mut()
-        .switch_from_old_task(monotonic_time_nanos() as usize);
+        .update_time_stat(|time| time.switch_from_old_task(monotonic_time_nanos() as usize));
 }
 
 /// Get the time statistics for the current task.
@@ -356,6 +396,12 @@ fn pre_trap_handler(trap_frame: &mut TrapFrame, from_user: bool) -> bool {
 fn post_trap_handler(trap_frame: &mut TrapFrame, from_user: bool) -> bool {
     if from_user {
         time_stat_from_kernel_to_user();
+        // 其他线程正在执行 execve 或结束进程，当前线程不再返回用户态
+        let curr = current();
+        let curr_ext = curr.task_ext();
+        if curr_ext.process_data().killed(curr_ext.thread().tid()) {
+            exit::exit_killed();
+        }
         handle_pending_signals(trap_frame);
     }
     true
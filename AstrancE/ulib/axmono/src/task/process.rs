@@ -6,14 +6,20 @@
 //!
 use core::{
     ptr,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
 };
 
 use crate::{
     ctypes::TimeStat,
     elf::OwnedElfFile,
-    mm::{load_elf_to_mem, map_trampoline},
-    task::{add_thread_to_table, spawn_signal_ctx},
+    mm::{load_elf_to_mem, map_trampoline, new_user_aspace_empty, read_auxv},
+    task::{
+        add_thread_to_table,
+        exit::{exit_killed, kill_other_threads, release_user_state},
+        flush_signal_handlers,
+        sched::SchedAttr,
+        spawn_signal_ctx,
+    },
 };
 use alloc::{
     boxed::Box,
@@ -21,10 +27,10 @@ use alloc::{
     sync::Arc,
     vec::Vec,
 };
-use arceos_posix_api::{FD_TABLE, ctypes::*};
+use arceos_posix_api::{FD_TABLE, FILE_LIMIT, ctypes::*};
 use axerrno::{AxError, AxResult, LinuxError, LinuxResult};
 use axfs::{
-    CURRENT_DIR, CURRENT_DIR_PATH,
+    CURRENT_DIR, CURRENT_DIR_MOUNT, CURRENT_DIR_PATH,
     api::{current_dir, set_current_dir},
 };
 use axhal::arch::UspaceContext;
@@ -32,12 +38,13 @@ use axio::Read;
 use axmm::{AddrSpace, kernel_aspace};
 use axns::AxNamespace;
 use axprocess::Pid;
-use axsignal::{siginfo::SigInfo, Signal, SignalContext};
+use axsignal::{siginfo::SigInfo, Signal, SignalAltStack, SignalContext, SignalSet};
 use axsync::Mutex;
-use axtask::{AxTaskRef, TaskExtRef, WaitQueue, current};
+use axtask::{AxTaskRef, TaskExtRef, WaitQueue, WeakAxTaskRef, current};
 use core::ffi::c_int;
-use memory_addr::VirtAddrRange;
-use spin::RwLock;
+use linux_raw_sys::general::{RLIM_INFINITY, rlimit};
+use memory_addr::{PhysAddr, VirtAddrRange};
+use spin::{Once, RwLock};
 use xmas_elf::program;
 
 use crate::{
@@ -56,8 +63,8 @@ use super::{read_trapframe_from_kstack, spawn_user_task, spawn_user_task_inner};
 pub struct ProcessData {
     /// The executable path
     pub exe_path: RwLock<String>,
-    /// The virtual memory address space.
-    pub aspace: Arc<Mutex<AddrSpace>>,
+    /// The virtual memory address space, replaced by `execve`.
+    aspace: RwLock<Arc<Mutex<AddrSpace>>>,
     /// The resource namespace
     pub ns: AxNamespace,
 
@@ -69,6 +76,17 @@ pub struct ProcessData {
     /// The process signal manager
     pub signal: Arc<Mutex<SignalContext>>,
     pub signal_stack: Box<[u8; 4096]>,
+
+    /// Whether a core file is written when killed by a signal (`PR_SET_DUMPABLE`)
+    pub dumpable: AtomicBool,
+    /// The core file size limit (`RLIMIT_CORE`)
+    pub core_limit: RwLock<rlimit>,
+    /// The auxiliary vector of the current program, saved for core dumps
+    pub saved_auxv: RwLock<Vec<usize>>,
+    /// The thread running `execve`, which kills all other threads (0 if none)
+    exec_tid: AtomicU32,
+    /// The thread exiting the whole process, which kills all other threads (0 if none)
+    exit_tid: AtomicU32,
 }
 impl ProcessData {
     /// Create a new [`ProcessData`].
@@ -89,24 +107,72 @@ impl ProcessData {
         );
         Self {
             exe_path: RwLock::new(exe_path),
-            aspace,
+            aspace: RwLock::new(aspace),
             ns: AxNamespace::new_thread_local(),
             child_exit_wq: WaitQueue::new(),
             exit_signal,
             signal,
             signal_stack,
+            dumpable: AtomicBool::new(true),
+            core_limit: RwLock::new(rlimit {
+                rlim_cur: 0,
+                rlim_max: RLIM_INFINITY as _,
+            }),
+            saved_auxv: RwLock::new(Vec::new()),
+            exec_tid: AtomicU32::new(0),
+            exit_tid: AtomicU32::new(0),
+        }
+    }
+    /// Get the virtual memory address space.
+    pub fn aspace(&self) -> Arc<Mutex<AddrSpace>> {
+        self.aspace.read().clone()
+    }
+    /// Replace the address space on `execve`, returning the old one.
+    pub(crate) fn replace_aspace(&self, aspace: Arc<Mutex<AddrSpace>>) -> Arc<Mutex<AddrSpace>> {
+        core::mem::replace(&mut *self.aspace.write(), aspace)
+    }
+    /// Whether the thread `tid` is killed by another thread running `execve`
+    /// or exiting the whole process.
+    pub(crate) fn killed(&self, tid: Pid) -> bool {
+        let killed_by = |killer: &AtomicU32| {
+            let killer = killer.load(Ordering::Acquire);
+            killer != 0 && killer != tid
+        };
+        killed_by(&self.exit_tid) || killed_by(&self.exec_tid)
+    }
+    /// Make the thread `tid` the one exiting the whole process.
+    ///
+    /// Returns `false` if another thread is already exiting the process.
+    pub(crate) fn start_group_exit(&self, tid: Pid) -> bool {
+        match self
+            .exit_tid
+            .compare_exchange(0, tid, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => true,
+            Err(killer) => killer == tid,
         }
     }
+    /// Inherit the core dump settings and the program image info from the parent.
+    pub(crate) fn inherit_from(&self, parent: &ProcessData) {
+        self.dumpable
+            .store(parent.dumpable.load(Ordering::Relaxed), Ordering::Relaxed);
+        *self.core_limit.write() = *parent.core_limit.read();
+        *self.saved_auxv.write() = parent.saved_auxv.read().clone();
+    }
     /// Initialize the namespace for the new task.
     pub(crate) fn ns_init_new(&self) {
         let ns = &self.ns;
         FD_TABLE.deref_from(ns).init_new(FD_TABLE.copy_inner());
+        FILE_LIMIT.deref_from(ns).init_new(FILE_LIMIT.copy_inner());
         CURRENT_DIR
             .deref_from(ns)
             .init_new(CURRENT_DIR.copy_inner());
         CURRENT_DIR_PATH
             .deref_from(ns)
             .init_new(CURRENT_DIR_PATH.copy_inner());
+        CURRENT_DIR_MOUNT
+            .deref_from(ns)
+            .init_new(CURRENT_DIR_MOUNT.copy_inner());
     }
     /// Linux manual: A "clone" child is one which delivers no signal, or a
     /// signal other than SIGCHLD to its parent upon termination.
@@ -124,17 +190,24 @@ impl ProcessData {
 }
 impl Drop for ProcessData {
     fn drop(&mut self) {
-        if !cfg!(target_arch = "aarch64") && !cfg!(target_arch = "loongarch64") {
-            // See [`crate::new_user_aspace`]
-
-            debug!("Drop ProcessData");
-
-            let kernel = kernel_aspace().lock();
+        debug!("Drop ProcessData");
+        release_aspace(self.aspace.get_mut());
+    }
+}
 
-            self.aspace
-                .lock()
-                .clear_mappings(VirtAddrRange::from_start_size(kernel.base(), kernel.size()));
+/// 释放用户地址空间前的清理
+///
+/// 内核部分的页表是从内核地址空间复制的（见 [`copy_from_kernel`]），
+/// 最后一个使用者需先清除这些映射，以免释放内核的页表
+fn release_aspace(aspace: &Arc<Mutex<AddrSpace>>) {
+    if !cfg!(target_arch = "aarch64") && !cfg!(target_arch = "loongarch64") {
+        if Arc::strong_count(aspace) != 1 {
+            return;
         }
+        let kernel = kernel_aspace().lock();
+        aspace
+            .lock()
+            .clear_mappings(VirtAddrRange::from_start_size(kernel.base(), kernel.size()));
     }
 }
 /// Extended data for [`Thread`].
@@ -145,9 +218,19 @@ pub struct ThreadData {
     ///
     /// When the thread exits, the kernel clears the word at this address if it is not NULL.
     pub clear_child_tid: AtomicUsize,
-    // The thread-level signal manager
-    //pub signal: ThreadSignalManager<RawMutex, WaitQueueWrapper>,
+    /// The head of the robust futex list set by `set_robust_list`
+    pub robust_list: AtomicUsize,
+    /// The thread-level signal manager: the blocked mask, the thread-directed
+    /// pending signals and the signal frames. Dispositions and the shared
+    /// pending queue live in [`ProcessData::signal`].
     pub signal: Arc<Mutex<SignalContext>>,
+    signal_stack: Box<[u8; 4096]>,
+    /// The alternate signal stack set by `sigaltstack`
+    pub sigaltstack: Mutex<SignalAltStack>,
+    /// The scheduling attributes
+    pub sched: SchedAttr,
+    /// The task running this thread
+    pub(crate) task: Once<WeakAxTaskRef>,
 }
 
 impl ThreadData {
@@ -166,10 +249,25 @@ impl ThreadData {
         drop(signal_);
         Self {
             clear_child_tid: AtomicUsize::new(0),
-            signal: signalctx, // FIXME: thread sig ctx
+            robust_list: AtomicUsize::new(0),
+            signal: signalctx,
+            signal_stack,
+            sigaltstack: Mutex::new(SignalAltStack::default()),
+            sched: SchedAttr::new(),
+            task: Once::new(),
         }
     }
 
+    /// Get the task running this thread, if it is still alive.
+    pub fn task(&self) -> Option<AxTaskRef> {
+        self.task.get().and_then(|task| task.upgrade())
+    }
+
+    /// Set the task running this thread.
+    pub fn set_task(&self, task: &AxTaskRef) {
+        self.task.call_once(|| Arc::downgrade(task));
+    }
+
     /// Get the clear child tid field.
     pub fn clear_child_tid(&self) -> usize {
         self.clear_child_tid.load(Ordering::Relaxed)
@@ -181,6 +279,16 @@ impl ThreadData {
             .store(clear_child_tid, Ordering::Relaxed);
     }
 
+    /// Get the robust futex list head.
+    pub fn robust_list(&self) -> usize {
+        self.robust_list.load(Ordering::Relaxed)
+    }
+
+    /// Set the robust futex list head.
+    pub fn set_robust_list(&self, head: usize) {
+        self.robust_list.store(head, Ordering::Relaxed);
+    }
+
     pub fn signal(&self) -> &Arc<Mutex<SignalContext>> {
         &self.signal
     }
@@ -222,7 +330,8 @@ pub fn clone_task(
     // new task with same ip and sp of current task
     let mut trap_frame = read_trapframe_from_kstack(curr.get_kernel_stack_top().unwrap());
 
-    let mut current_aspace = current_task_ext.process_data().aspace.lock();
+    let current_aspace = current_task_ext.process_data().aspace();
+    let mut current_aspace = current_aspace.lock();
 
     if from_umode {
         trap_frame.set_ret_code(0);
@@ -261,20 +370,20 @@ pub fn clone_task(
             .ctx_mut()
             .set_page_table_root(current_aspace.page_table_root());
 
-        curr.task_ext().thread.process()
+        curr.task_ext().thread().process()
     } else {
         let parent = if flags.contains(CloneFlags::PARENT) {
             curr.task_ext()
-                .thread
+                .thread()
                 .process()
                 .parent()
                 .ok_or(LinuxError::EINVAL)?
         } else {
-            curr.task_ext().thread.process().clone()
+            curr.task_ext().thread().process().clone()
         };
         let builder = parent.fork(tid);
         let aspace = if flags.contains(CloneFlags::VM) {
-            curr.task_ext().process_data().aspace.clone()
+            curr.task_ext().process_data().aspace()
         } else {
             #[cfg(feature = "COW")]
             let mut aspace = current_aspace.clone_on_write()?;
@@ -300,16 +409,21 @@ pub fn clone_task(
             signal,
             exit_signal,
         );
+        process_data.inherit_from(curr.task_ext().process_data());
 
         if flags.contains(CloneFlags::FILES) {
             FD_TABLE
                 .deref_from(&process_data.ns)
-                .init_shared(FD_TABLE.share());
+                .init_new(FD_TABLE.share_inner());
         } else {
             FD_TABLE
                 .deref_from(&process_data.ns)
                 .init_new(FD_TABLE.copy_inner());
         }
+        // 文件数限制属于进程，共享描述符表时也不共享
+        FILE_LIMIT
+            .deref_from(&process_data.ns)
+            .init_new(FILE_LIMIT.copy_inner());
 
         if flags.contains(CloneFlags::FS) {
             CURRENT_DIR
@@ -318,6 +432,9 @@ pub fn clone_task(
             CURRENT_DIR_PATH
                 .deref_from(&process_data.ns)
                 .init_shared(CURRENT_DIR_PATH.share());
+            CURRENT_DIR_MOUNT
+                .deref_from(&process_data.ns)
+                .init_shared(CURRENT_DIR_MOUNT.share());
         } else {
             CURRENT_DIR
                 .deref_from(&process_data.ns)
@@ -325,6 +442,9 @@ pub fn clone_task(
             CURRENT_DIR_PATH
                 .deref_from(&process_data.ns)
                 .init_new(CURRENT_DIR_PATH.copy_inner());
+            CURRENT_DIR_MOUNT
+                .deref_from(&process_data.ns)
+                .init_new(CURRENT_DIR_MOUNT.copy_inner());
         }
         &builder.data(process_data).build()
     };
@@ -332,88 +452,243 @@ pub fn clone_task(
     warn!("child tid: {}", process.pid());
 
     let thread_data = ThreadData::new(process.data().unwrap());
+    // 新线程继承创建者的信号掩码，待处理信号不继承
+    thread_data
+        .signal
+        .lock()
+        .set_mask(current_task_ext.thread_data().signal.lock().get_blocked());
     if flags.contains(CloneFlags::CHILD_CLEARTID) {
         thread_data.set_clear_child_tid(child_tid as usize);
     }
+    thread_data
+        .sched
+        .inherit_from(&current_task_ext.thread_data().sched);
 
     let thread = process.new_thread(tid).data(thread_data).build();
     add_thread_to_table(&thread);
     new_task.init_task_ext(TaskExt::new(thread));
-    Ok(axtask::spawn_task(new_task))
+
+    let new_task = new_task.into_arc();
+    let thread_data = new_task.task_ext().thread_data();
+    thread_data.set_task(&new_task);
+    thread_data.sched.apply(&new_task);
+    axtask::spawn_task_by_ref(new_task.clone());
+    Ok(new_task)
 }
 
+/// 解释器最多嵌套的层数，同 Linux 的 `BINPRM_MAX_RECURSION`
+const MAX_INTERP_DEPTH: usize = 4;
+/// 读取的文件头长度，`#!` 行超出部分被截断，同 Linux 的 `BINPRM_BUF_SIZE`
+const EXEC_HEAD_SIZE: usize = 256;
+
 enum ExecType {
     Elf,
-    Shebang(&'static str),
-    Shell,
+    /// `#!` 脚本：解释器路径及可选的一个参数
+    Script(String, Option<String>),
+}
+
+/// 根据文件头判断可执行文件的类型，既非 ELF 也非脚本时返回 `ENOEXEC`
+fn exec_type(head: &[u8]) -> LinuxResult<ExecType> {
+    if head.starts_with(b"\x7fELF") {
+        return Ok(ExecType::Elf);
+    }
+    let Some(line) = head.strip_prefix(b"#!") else {
+        return Err(LinuxError::ENOEXEC);
+    };
+    let line = line.split(|&c| c == b'\n').next().unwrap_or_default();
+    let line = core::str::from_utf8(line).map_err(|_| LinuxError::ENOEXEC)?;
+    let line = line.trim_matches([' ', '\t', '\r']);
+    // 与 Linux 相同，解释器之后的内容整体作为一个参数
+    let (interp, arg) = match line.split_once([' ', '\t']) {
+        Some((interp, arg)) => (interp, Some(arg.trim_matches([' ', '\t']))),
+        None => (line, None),
+    };
+    if interp.is_empty() {
+        return Err(LinuxError::ENOEXEC);
+    }
+    Ok(ExecType::Script(
+        interp.to_string(),
+        arg.filter(|arg| !arg.is_empty()).map(String::from),
+    ))
+}
+
+/// 切换当前任务的页表，同时修改任务上下文，使之后的任务切换使用新的页表
+///
+/// # Safety
+///
+/// 新页表必须包含当前运行所需的内核映射
+unsafe fn switch_page_table(root: PhysAddr) {
+    let curr = current();
+    unsafe {
+        // 先修改上下文，中途被抢占时切换回来也会使用新的页表
+        (*curr.ctx_mut_ptr()).set_page_table_root(root);
+        // aarch64 与 LoongArch64 的用户地址空间使用单独的页表
+        #[cfg(any(target_arch = "aarch64", target_arch = "loongarch64"))]
+        axhal::arch::write_page_table_root0(root);
+        #[cfg(not(any(target_arch = "aarch64", target_arch = "loongarch64")))]
+        axhal::arch::write_page_table_root(root);
+    }
+}
+
+/// 执行 execve 前杀死进程中的其他线程（同 Linux 的 `de_thread`）
+///
+/// 其他线程退出时完成各自的 clear_child_tid 与 robust futex 清理。等待它们全部退出后，
+/// 若当前线程不是主线程，则接管主线程的 PID。
+///
+/// 另一线程同时在执行 execve 或结束进程时，当前线程反被它杀死，返回 `false`
+fn de_thread() -> bool {
+    let curr = current();
+    let curr_ext = curr.task_ext();
+    let process = curr_ext.thread().process().clone();
+    let proc_data = curr_ext.process_data();
+    let tid = curr_ext.thread().tid();
+
+    if process.threads().len() > 1 {
+        // 同一时间只有一个线程能执行 execve，进程正在退出时也不再执行
+        if process.is_group_exited()
+            || proc_data
+                .exec_tid
+                .compare_exchange(0, tid, Ordering::AcqRel, Ordering::Acquire)
+                .is_err()
+        {
+            return false;
+        }
+        // 等待期间有线程开始结束进程时放弃 execve，由它杀死当前线程
+        let killed = kill_other_threads(|| proc_data.killed(tid));
+        proc_data.exec_tid.store(0, Ordering::Release);
+        if killed.is_none() {
+            return false;
+        }
+    }
+
+    let pid = process.pid();
+    if tid != pid {
+        debug!("exec: thread {} takes over pid {}", tid, pid);
+        let thread_data = ThreadData::new(proc_data);
+        {
+            let mut old_sigctx = curr_ext.thread_data().signal.lock();
+            let mut sigctx = thread_data.signal.lock();
+            sigctx.set_mask(old_sigctx.get_blocked());
+            // 发给当前线程的待处理信号随之转移
+            while let Some((sig, info)) = old_sigctx.dequeue_in(SignalSet::all()) {
+                sigctx.send_signal(sig, info);
+            }
+        }
+        thread_data.sched.inherit_from(&curr_ext.thread_data().sched);
+        thread_data.set_clear_child_tid(curr_ext.thread_data().clear_child_tid());
+        thread_data.set_robust_list(curr_ext.thread_data().robust_list());
+
+        let thread = process.new_thread(pid).data(thread_data).build();
+        add_thread_to_table(&thread);
+        thread.data::<ThreadData>().unwrap().set_task(curr.as_task_ref());
+        // Safety: 由当前任务自己替换，之前取得的引用都已不再使用
+        let old_thread = unsafe { curr_ext.replace_thread(thread) };
+        old_thread.exit(0);
+    }
+    true
 }
 
 /// execve
 /// mainly from starry
 /// **Return**
 /// - `Ok(handler)` if exec successfully, call handler to enter task.
-/// - `Err(AxError)` if exec failed
+/// - `Err(LinuxError)` if exec failed
 ///
-pub fn exec_current(program_name: &str, args: &[String], envs: &[String]) -> AxResult<!> {
+pub fn exec_current(program_name: &str, args: &[String], envs: &[String]) -> LinuxResult<!> {
     warn!(
         "exec: {} with args {:?}, envs {:?}",
         program_name, args, envs
     );
-    let mut args_ = vec![];
     let (oldpwd, pwd) = get_pwd_from_envs(envs);
-    let mut program_path = axfs::path::canonicalize(program_name, pwd.as_ref().map(|s| s.as_str()));
-
-    // 读取文件头部以检测类型
-    let mut buffer: [u8; 64] = [0; 64];
-    let mut file = axfs::api::File::open(program_path.as_str())?;
-    file.read(&mut buffer)?;
-
-    // 确定执行类型（ELF 或 Shell 脚本）
-    let exec_type = if buffer.len() >= 4 && buffer[..4] == *b"\x7fELF" {
-        ExecType::Elf
-    } else if buffer[..2] == [b'#', b'!'] {
-        ExecType::Shell
-    } else {
-        ExecType::Shell
-    };
+    let pwd_ref = pwd.as_ref().map(|s| s.as_str());
+    let program_path = axfs::path::canonicalize(program_name, pwd_ref);
+
+    // 逐层解析 `#!` 解释器，直到遇到 ELF 文件
+    let mut args_ = args.to_vec();
+    let mut file_name = program_name.to_string();
+    let mut elf_path = program_path.clone();
+    let mut depth = 0;
+    let elf_file: OwnedElfFile = loop {
+        // noexec 挂载点上的文件不可执行。没有 set-user-ID 的实现，程序总以
+        // 调用者的身份运行，nosuid 挂载点无需额外处理
+        if axfs::api::mount_flags(&elf_path, true).contains(axfs::api::MountFlags::NOEXEC) {
+            return Err(LinuxError::EACCES);
+        }
 
-    // 加载 ELF 文件
-    let elf_file: OwnedElfFile = match exec_type {
-        ExecType::Elf => load_elf_from_disk(&program_path)
-            .inspect_err(|err| debug!("load_elf_from_disk failed: {:?}", err))?,
-        ExecType::Shell => {
-            program_path = "/ts/musl/busybox".to_string();
-            args_.push(program_path.clone());
-            args_.push("sh".to_string());
-            load_elf_from_disk(program_path.as_str()).unwrap()
+        // 读取文件头部以检测类型
+        let mut head = [0u8; EXEC_HEAD_SIZE];
+        let mut file = axfs::api::File::open(elf_path.as_str())?;
+        let mut len = 0;
+        while len < head.len() {
+            match file.read(&mut head[len..])? {
+                0 => break,
+                n => len += n,
+            }
         }
-        _ => {
-            unimplemented!()
+
+        match exec_type(&head[..len])? {
+            ExecType::Elf => {
+                break load_elf_from_disk(&elf_path)
+                    .inspect_err(|err| debug!("load_elf_from_disk failed: {:?}", err))?;
+            }
+            ExecType::Script(interp, arg) => {
+                depth += 1;
+                if depth > MAX_INTERP_DEPTH {
+                    return Err(LinuxError::ELOOP);
+                }
+                debug!("exec: {} is a script for {} {:?}", file_name, interp, arg);
+                // 同 Linux：argv[0] 被替换为 `解释器 [参数] 脚本路径`
+                let mut new_args = vec![interp.clone()];
+                new_args.extend(arg);
+                new_args.push(file_name);
+                new_args.extend(args_.into_iter().skip(1));
+                args_ = new_args;
+                elf_path = axfs::path::canonicalize(&interp, pwd_ref);
+                file_name = interp;
+            }
         }
     };
-    args_.extend_from_slice(args);
 
     let args_: &[String] = args_.as_slice();
     let current_task = current();
 
-    // 检查地址空间是否被多个任务共享
-    if Arc::strong_count(&current_task.task_ext().process_data().aspace) != 1 {
-        warn!("Address space is shared by multiple tasks, exec is not supported.");
-        return Err(AxError::Unsupported);
-    }
-
-    // 释放旧的用户地址空间映射
-    let mut aspace = current_task.task_ext().process_data().aspace.lock();
-    aspace.unmap_user_areas()?;
-    axhal::arch::flush_tlb(None);
-
-    // 使用之前定义的 load_elf_to_mem 函数加载 ELF 文件到内存
-    let (entry_point, user_stack_base, thread_pointer) =
-        load_elf_to_mem(elf_file, &mut aspace, Some(args_), Some(envs))?;
-
-    axhal::arch::flush_tlb(None);
+    // 在新的地址空间中加载程序，失败时原程序不受影响
+    let mut new_aspace = new_user_aspace_empty()?;
+    let loaded = copy_from_kernel(&mut new_aspace)
+        .and_then(|_| load_elf_to_mem(elf_file, &mut new_aspace, Some(args_), Some(envs)));
+    let aspace = Arc::new(Mutex::new(new_aspace));
+    let (entry_point, user_stack_base, thread_pointer) = match loaded {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            release_aspace(&aspace);
+            return Err(err.into());
+        }
+    };
+    let auxv = read_auxv(&aspace.lock(), user_stack_base).unwrap_or_default();
 
-    unsafe { current_task.task_ext().process_data().aspace.force_unlock() };
+    // 杀死其他线程，之后进程中只剩当前线程
+    if !de_thread() {
+        release_aspace(&aspace);
+        drop(aspace);
+        exit_killed();
+    }
+    let curr_ext = current_task.task_ext();
+    // 旧地址空间中的 clear_child_tid 与 robust futex 链表随之失效
+    release_user_state(curr_ext.thread_data(), curr_ext.thread().tid());
+    // 关闭带有 close-on-exec 标志的文件描述符，并把已捕获的信号恢复为默认处理
+    FD_TABLE.close_on_exec();
+    flush_signal_handlers(&mut curr_ext.process_data().signal.lock());
+
+    // 切换到新的地址空间，旧的地址空间在最后一个使用者（如 vfork 的父进程）释放时回收
+    let root = aspace.lock().page_table_root();
+    let old_aspace = curr_ext.process_data().replace_aspace(aspace);
+    unsafe { switch_page_table(root) };
+    release_aspace(&old_aspace);
+    drop(old_aspace);
+    *curr_ext.process_data().saved_auxv.write() = auxv;
+
+    // 新程序不再使用原来的备用信号栈
+    *current_task.task_ext().thread_data().sigaltstack.lock() = SignalAltStack::default();
 
     // 设置当前任务名称和目录
     current_task.set_name(&program_path);
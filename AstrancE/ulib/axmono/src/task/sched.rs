@@ -0,0 +1,126 @@
+//! 线程的调度属性：调度策略、实时优先级与 nice 值。
+//!
+//! 内核只编译了一种调度器（见 [`axtask::SCHEDULER_KIND`]），这里记录 Linux
+//! 语义下的调度属性，并将其映射到实际的调度器上。
+
+use core::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+
+use axtask::{AxTaskRef, SCHEDULER_KIND, SchedulerKind};
+
+pub const SCHED_OTHER: u32 = 0;
+pub const SCHED_FIFO: u32 = 1;
+pub const SCHED_RR: u32 = 2;
+pub const SCHED_BATCH: u32 = 3;
+pub const SCHED_IDLE: u32 = 5;
+/// 子进程不继承实时策略与负的 nice 值
+pub const SCHED_RESET_ON_FORK: u32 = 0x4000_0000;
+
+pub const MIN_NICE: i32 = -20;
+pub const MAX_NICE: i32 = 19;
+pub const MAX_RT_PRIO: u32 = 99;
+
+/// 策略是否为实时策略
+pub fn is_rt_policy(policy: u32) -> bool {
+    matches!(policy, SCHED_FIFO | SCHED_RR)
+}
+
+/// 策略是否合法
+pub fn is_valid_policy(policy: u32) -> bool {
+    matches!(
+        policy,
+        SCHED_OTHER | SCHED_FIFO | SCHED_RR | SCHED_BATCH | SCHED_IDLE
+    )
+}
+
+/// 线程的调度属性
+pub struct SchedAttr {
+    policy: AtomicU32,
+    rt_priority: AtomicU32,
+    nice: AtomicI32,
+    reset_on_fork: AtomicU32,
+}
+
+impl SchedAttr {
+    pub const fn new() -> Self {
+        Self {
+            policy: AtomicU32::new(SCHED_OTHER),
+            rt_priority: AtomicU32::new(0),
+            nice: AtomicI32::new(0),
+            reset_on_fork: AtomicU32::new(0),
+        }
+    }
+
+    /// 从父线程继承调度属性
+    pub fn inherit_from(&self, parent: &SchedAttr) {
+        if parent.reset_on_fork.load(Ordering::Relaxed) != 0 {
+            if is_rt_policy(parent.policy()) {
+                self.policy.store(SCHED_OTHER, Ordering::Relaxed);
+                self.rt_priority.store(0, Ordering::Relaxed);
+            } else {
+                self.policy.store(parent.policy(), Ordering::Relaxed);
+            }
+            self.nice.store(parent.nice().max(0), Ordering::Relaxed);
+        } else {
+            self.policy.store(parent.policy(), Ordering::Relaxed);
+            self.rt_priority
+                .store(parent.rt_priority(), Ordering::Relaxed);
+            self.nice.store(parent.nice(), Ordering::Relaxed);
+            self.reset_on_fork.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn policy(&self) -> u32 {
+        self.policy.load(Ordering::Relaxed)
+    }
+
+    /// 带有 `SCHED_RESET_ON_FORK` 标志的策略，供 `sched_getscheduler` 使用
+    pub fn policy_with_flags(&self) -> u32 {
+        self.policy() | self.reset_on_fork.load(Ordering::Relaxed)
+    }
+
+    pub fn rt_priority(&self) -> u32 {
+        self.rt_priority.load(Ordering::Relaxed)
+    }
+
+    pub fn nice(&self) -> i32 {
+        self.nice.load(Ordering::Relaxed)
+    }
+
+    /// 设置调度策略与实时优先级，调用者需保证参数合法
+    pub fn set_scheduler(&self, policy: u32, rt_priority: u32, reset_on_fork: bool) {
+        self.policy.store(policy, Ordering::Relaxed);
+        self.rt_priority.store(rt_priority, Ordering::Relaxed);
+        self.reset_on_fork.store(
+            if reset_on_fork { SCHED_RESET_ON_FORK } else { 0 },
+            Ordering::Relaxed,
+        );
+    }
+
+    /// 设置 nice 值，超出范围的值会被截断
+    pub fn set_nice(&self, nice: i32) {
+        self.nice
+            .store(nice.clamp(MIN_NICE, MAX_NICE), Ordering::Relaxed);
+    }
+
+    /// 将调度属性映射到编译进内核的调度器上
+    ///
+    /// 只有 CFS 调度器支持优先级：实时策略映射为最高优先级，`SCHED_IDLE`
+    /// 映射为最低优先级，其余策略使用 nice 值。FIFO 与 RR 调度器忽略优先级。
+    pub fn apply(&self, task: &AxTaskRef) {
+        if SCHEDULER_KIND != SchedulerKind::Cfs {
+            return;
+        }
+        let prio = match self.policy() {
+            SCHED_FIFO | SCHED_RR => MIN_NICE,
+            SCHED_IDLE => MAX_NICE,
+            _ => self.nice(),
+        };
+        axtask::set_task_priority(task, prio as isize);
+    }
+}
+
+impl Default for SchedAttr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
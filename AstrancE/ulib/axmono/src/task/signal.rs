@@ -3,32 +3,64 @@ use core::{ffi::c_int, time::Duration};
 use alloc::sync::Arc;
 //use arceos_posix_api::ctypes::{self, *};
 use axerrno::{LinuxError, LinuxResult, ax_err};
-use axhal::{arch::TrapFrame, time::monotonic_time};
+use axhal::{
+    arch::TrapFrame,
+    time::monotonic_time,
+    trap::{USER_EXCEPTION, UserException, register_trap_handler},
+};
 use axprocess::{Pid, Process, ProcessGroup, Thread};
 use axsignal::{siginfo::SigInfo, *};
 use axsync::Mutex;
-use axtask::{TaskExtRef, current, exit, yield_now};
+use axtask::{SyscallRestart, TaskExtRef, WaitQueue, current, exit, yield_now};
 use linux_raw_sys::general::*;
 use memory_addr::{VirtAddr, VirtAddrRange};
 
 use crate::{
     mm::trampoline_vaddr,
-    task::{PROCESS_GROUP_TABLE, sys_exit},
+    ptr::{PtrWrapper, UserConstPtr, UserPtr},
+    task::{
+        PROCESS_GROUP_TABLE,
+        coredump::dump_core,
+        exit::{exit_with_signal, zap_other_threads},
+    },
 };
 
 use super::{
     PROCESS_TABLE, ProcessData, THREAD_TABLE, ThreadData, find_thread_in_group, get_process,
-    get_process_group, get_thread, processes, time::TimeStat, time_stat_from_old_task,
-    time_stat_to_new_task, write_trapframe_to_kstack, yield_with_time_stat,
+    get_process_group, get_thread, processes, read_trapframe_from_kstack, time::TimeStat,
+    time_stat_from_old_task, time_stat_to_new_task, write_trapframe_to_kstack,
 };
 
-pub fn default_signal_handler(signal: Signal, ctx: &mut SignalContext) {
+/// 默认动作为终止进程并生成 core 文件的信号
+const CORE_SIGNALS: [Signal; 10] = [
+    Signal::SIGSEGV,
+    Signal::SIGBUS,
+    Signal::SIGILL,
+    Signal::SIGFPE,
+    Signal::SIGTRAP,
+    Signal::SIGSYS,
+    Signal::SIGQUIT,
+    Signal::SIGABRT,
+    Signal::SIGXCPU,
+    Signal::SIGXFSZ,
+];
+
+pub fn default_signal_handler(signal: Signal, blocked: SignalSet) {
     match signal {
         Signal::SIGINT | Signal::SIGKILL => {
             // 杀死进程
-            let curr = current();
-            debug!("kill myself");
-            sys_exit(curr.task_ext().thread.process().exit_code());
+            debug!("killed by {:?}", signal);
+            exit_with_signal(signal, false);
+        }
+        sig if CORE_SIGNALS.contains(&sig) => {
+            // 被信号终止，父进程通过 wait 状态得知终止信号以及是否生成了 core 文件
+            debug!("killed by {:?}", signal);
+            // 先杀死其他线程，转储期间内存与寄存器不再变化
+            let others = zap_other_threads();
+            let core_dumped = dump_core(signal, blocked, &others);
+            // exit_with_signal 不再返回，先释放其他线程的任务
+            drop(others);
+            exit_with_signal(signal, core_dumped);
         }
         _ => {
             // 忽略信号
@@ -49,10 +81,32 @@ pub fn spawn_signal_ctx() -> Arc<Mutex<SignalContext>> {
         mask: SignalSet::SIGINT,
         flags: SigFlags::empty(),
     });
+    for sig in CORE_SIGNALS {
+        ctx.set_action(sig, SigAction {
+            handler: SigHandler::Default(default_signal_handler),
+            mask: sig.into(),
+            flags: SigFlags::empty(),
+        });
+    }
 
     Arc::new(Mutex::new(ctx))
 }
 
+/// execve 时重置信号处理：用户注册的处理函数在新程序中已不存在，恢复为默认动作，
+/// 被忽略的信号保持忽略
+pub(crate) fn flush_signal_handlers(ctx: &mut SignalContext) {
+    let defaults = spawn_signal_ctx();
+    let mut defaults = defaults.lock();
+    for sig in (1..=SIGRTMAX).filter_map(Signal::from_u32) {
+        if matches!(
+            ctx.get_action(sig).handler,
+            SigHandler::Handler(_) | SigHandler::Action(_)
+        ) {
+            ctx.set_action(sig, *defaults.get_action(sig));
+        }
+    }
+}
+
 pub(crate) fn sys_sigaction(
     signum: c_int,
     act: *const sigaction,
@@ -62,7 +116,11 @@ pub(crate) fn sys_sigaction(
     let curr = current();
     let mut sigctx = curr.task_ext().process_data().signal.lock();
     if !act.is_null() {
-        let act = SigAction::try_from(unsafe { *act }).inspect_err(|e| {})?;
+        let mut act = SigAction::try_from(unsafe { *act }).inspect_err(|e| {})?;
+        if let SigHandler::Default(_) = act.handler {
+            // SIG_DFL 使用本模块的默认动作
+            act.handler = SigHandler::Default(default_signal_handler);
+        }
         let old = sigctx.set_action(sig, act);
         // 设置旧动作（如果有）
         unsafe { old_act.as_mut().map(|ptr| unsafe { *ptr = old.into() }) };
@@ -112,7 +170,9 @@ pub(crate) fn sys_sigprocmask(
     oldset: *mut sigset_t,
 ) -> LinuxResult<isize> {
     let curr = current();
-    let mut sigctx = curr.task_ext().process_data().signal.lock();
+    // 信号处理动作与共享的待处理队列属于进程，掩码属于线程
+    let proc_sigctx = curr.task_ext().process_data().signal.lock();
+    let mut sigctx = curr.task_ext().thread_data().signal.lock();
 
     // 先保存旧的 mask
     let old_mask = sigctx.get_blocked();
@@ -126,6 +186,11 @@ pub(crate) fn sys_sigprocmask(
             SIG_SETMASK => sigctx.set_mask(set),
             _ => return Err(LinuxError::EINVAL),
         };
+        // 解除阻塞的待处理信号需要打断之后的睡眠
+        let pending = sigctx.get_pending().union(proc_sigctx.get_pending());
+        if has_interrupting(pending, sigctx.get_blocked(), &proc_sigctx) {
+            axtask::interrupt_task(curr.as_task_ref());
+        }
     }
 
     // 如果用户请求 oldset，则写入旧的 mask
@@ -174,45 +239,63 @@ pub(crate) fn sys_sigtimedwait(
         let ts = unsafe { timeout.as_ref().ok_or(LinuxError::EFAULT)? };
         if ts.tv_sec == 0 && ts.tv_nsec == 0 {
             // 立即返回的特殊情况
-            return curr
-                .task_ext()
-                .process_data()
-                .signal
-                .lock()
-                .consume_one_in(sigset)
-                .ok_or(LinuxError::EAGAIN)
-                .map(|sig| sig as isize);
+            return take_waited_signal(sigset, info).ok_or(LinuxError::EAGAIN);
         }
         Some(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
     } else {
         None
     };
 
-    // 主等待循环
-    loop {
+    // 等待期间解除对等待信号的阻塞，使信号到达时打断睡眠，返回前恢复
+    let old_mask = {
+        let mut sigctx = curr.task_ext().thread_data().signal.lock();
+        let old_mask = sigctx.get_blocked();
+        sigctx.set_mask(old_mask.difference(sigset));
+        old_mask
+    };
+    let deadline = timeout_duration.map(|duration| start_time + duration);
+    let wq = WaitQueue::new();
+    let res = loop {
         // 检查是否有待处理的信号
-        if let Some(sig) = curr
-            .task_ext()
-            .process_data()
-            .signal
-            .lock()
-            .consume_one_in(sigset)
-        {
+        if let Some(sig) = take_waited_signal(sigset, info) {
             debug!("Received signal: {:?}", sig);
-            return Ok(sig as isize);
+            break Ok(sig);
         }
 
-        // 检查超时
-        if let Some(duration) = timeout_duration {
-            let elapsed = monotonic_time() - start_time;
-            if elapsed >= duration {
-                return Err(LinuxError::EAGAIN);
+        let waited = match deadline {
+            Some(deadline) => {
+                let now = monotonic_time();
+                if now >= deadline {
+                    break Err(LinuxError::EAGAIN);
+                }
+                wq.wait_timeout_interruptible(deadline - now).map(|_| ())
             }
+            None => wq.wait_interruptible(),
+        };
+        // 被打断时再取一次，以免错过刚到达的等待信号
+        if waited.is_err() {
+            break take_waited_signal(sigset, info).ok_or(LinuxError::EINTR);
         }
+    };
+    curr.task_ext().thread_data().signal.lock().set_mask(old_mask);
+    res
+}
 
-        // 让出CPU
-        yield_with_time_stat();
+/// 取出一个等待的信号，先检查线程再检查进程，并把附加信息写入 `info`
+fn take_waited_signal(sigset: SignalSet, info: *mut siginfo_t) -> Option<isize> {
+    let curr = current();
+    let (sig, siginfo) = {
+        let mut proc_sigctx = curr.task_ext().process_data().signal.lock();
+        let mut sigctx = curr.task_ext().thread_data().signal.lock();
+        sigctx
+            .dequeue_in(sigset)
+            .or_else(|| proc_sigctx.dequeue_in(sigset))?
+    };
+    if let Some(raw) = unsafe { info.as_mut() } {
+        let siginfo = siginfo.unwrap_or_else(|| SigInfo::new_simple(sig, SigCode::UnknownCode(0)));
+        unsafe { siginfo.fill_raw_siginfo(raw) };
     }
+    Some(sig as isize)
 }
 
 pub(crate) fn sys_rt_sigsuspend(
@@ -229,25 +312,34 @@ pub(crate) fn sys_rt_sigsuspend(
         (*mask_ref).into()
     };
 
-    // 3. 获取当前进程和信号上下文
+    // 3. 临时替换当前线程的信号掩码，原掩码在信号处理后恢复：运行处理函数时保存在信号栈帧中，
+    //    由 sigreturn 恢复，否则在返回用户态前恢复
     let curr = current();
-    let mut sigctx = curr.task_ext().process_data().signal.lock();
-    // 4. 保存当前信号掩码
-    // 假设 set_mask 返回旧的掩码（需确认实际实现）
-    let old_mask = sigctx.set_mask(new_mask);
-    // 5. 挂起进程，等待信号
+    curr.task_ext()
+        .thread_data()
+        .signal
+        .lock()
+        .set_temporary_mask(new_mask);
+    // 4. 挂起线程，直到有未被屏蔽的信号到达。发送信号时会打断线程的睡眠
+    let wq = WaitQueue::new();
     loop {
-        // 检查是否有待处理的信号（未被屏蔽的信号）
-        if sigctx.has_pending() {
-            // 如果有待处理信号，恢复原来的信号掩码并返回
-            sigctx.set_mask(old_mask);
-            return Err(LinuxError::EINTR);
+        {
+            let proc_sigctx = curr.task_ext().process_data().signal.lock();
+            let sigctx = curr.task_ext().thread_data().signal.lock();
+            // 检查线程和进程中是否有未被屏蔽的待处理信号
+            let pending = sigctx.get_pending().union(proc_sigctx.get_pending());
+            if has_interrupting(pending, sigctx.get_blocked(), &proc_sigctx) {
+                break;
+            }
+        }
+        // 没有信号也可能被打断（如 execve 杀死其他线程）
+        if wq.wait_interruptible().is_err() {
+            break;
         }
-        // 让出 CPU，进入等待状态
-        drop(sigctx); // 释放锁，避免死锁
-        yield_with_time_stat();
-        sigctx = curr.task_ext().process_data().signal.lock(); // 重新获取锁
     }
+    // 运行处理函数时返回 EINTR，否则重新执行
+    curr.set_syscall_restart(SyscallRestart::NoHand);
+    Err(LinuxError::EINTR)
 }
 
 /*
@@ -272,77 +364,173 @@ pub(crate) fn sys_rt_sigsuspend(
  *}
  */
 
-pub(crate) fn handle_pending_signals(current_tf: &TrapFrame) {
+/// 取出被信号打断的系统调用的重启方式，只有返回 EINTR 的系统调用才需要重启
+fn take_syscall_restart(curr: &axtask::CurrentTask, tf: &TrapFrame) -> SyscallRestart {
+    let restart = curr.take_syscall_restart();
+    if tf.in_syscall() && tf.retval() as isize == -(LinuxError::EINTR.code() as isize) {
+        restart
+    } else {
+        SyscallRestart::None
+    }
+}
+
+pub(crate) fn handle_pending_signals(current_tf: &mut TrapFrame) {
     let curr = current();
+    // 即将返回用户态，待处理的信号会在这里处理，之后的睡眠不应再被打断
+    curr.clear_interrupt();
 
-    // 首先检查进程级别的信号处理
-    let mut proc_sigctx = curr.task_ext().process_data().signal.lock();
-    if proc_sigctx.has_pending() {
-        warn!("a");
-        proc_sigctx.set_current_stack(SignalStackType::Primary);
-        match axsignal::handle_pending_signals(
-            &mut proc_sigctx,
-            current_tf,
-            unsafe { trampoline_vaddr(sigreturn_trampoline as usize).into() },
-            None,
-        ) {
-            Ok(Some((mut uctx, _kstack_top))) => {
-                // 交换tf
-                unsafe { write_trapframe_to_kstack(curr.get_kernel_stack_top().unwrap(), &uctx.0) };
-                return;
-            }
-            Ok(None) => {}
-            Err(_) => {}
-        }
+    let restart = take_syscall_restart(&curr, current_tf);
+    if restart == SyscallRestart::NoIntr {
+        // 无论是否运行处理函数都重新执行
+        current_tf.restart_syscall();
     }
+    let sa_restart = restart == SyscallRestart::Sys;
 
-    // 然后检查线程级别的信号处理
-    let mut thread_sigctx = curr.task_ext().thread_data().signal().lock();
-    if thread_sigctx.has_pending() {
+    loop {
+        // 处理动作与共享的待处理队列在进程上下文中，掩码、线程私有的待处理队列与信号栈帧在线程上下文中
+        let mut proc_sigctx = curr.task_ext().process_data().signal.lock();
+        let mut thread_sigctx = curr.task_ext().thread_data().signal().lock();
+        let mut altstack = curr.task_ext().thread_data().sigaltstack.lock();
         thread_sigctx.set_current_stack(SignalStackType::Primary);
+        // 先处理线程私有的待处理信号
         match axsignal::handle_pending_signals(
             &mut thread_sigctx,
             current_tf,
             unsafe { trampoline_vaddr(sigreturn_trampoline as usize).into() },
             Some(&mut proc_sigctx),
+            Some(&mut altstack),
+            sa_restart,
         )
         .inspect_err(|e| warn!("{e:?}"))
         {
-            Ok(Some((mut uctx, _kstack_top))) => {
-                warn!("123");
+            Ok(Some(SignalDelivery::Handler(mut uctx, _kstack_top))) => {
                 // 交换tf
                 unsafe { write_trapframe_to_kstack(curr.get_kernel_stack_top().unwrap(), &uctx.0) };
+                curr.take_restart_block();
                 return;
             }
+            Ok(Some(SignalDelivery::Default(f, sig, blocked))) => {
+                // 默认动作可能终止进程而不再返回，执行前释放所有锁
+                drop(altstack);
+                drop(thread_sigctx);
+                drop(proc_sigctx);
+                f(sig, blocked);
+                continue;
+            }
             Ok(None) => {}
-            Err(_) => {}
+            Err(_) => break,
         }
+        // 再从进程共享的队列中取出一个当前线程未阻塞的信号，交给当前线程处理
+        let unblocked = thread_sigctx.get_blocked().complement();
+        let Some((sig, info)) = proc_sigctx.dequeue_in(unblocked) else {
+            break;
+        };
+        thread_sigctx.send_signal(sig, info);
+    }
+
+    // 没有运行处理函数，恢复 sigsuspend 等临时替换的掩码，被打断的系统调用重新执行
+    curr.task_ext()
+        .thread_data()
+        .signal()
+        .lock()
+        .restore_saved_mask();
+    match restart {
+        SyscallRestart::Sys | SyscallRestart::NoHand => current_tf.restart_syscall(),
+        SyscallRestart::RestartBlock => {
+            current_tf.restart_syscall();
+            current_tf.set_syscall_num(__NR_restart_syscall as usize);
+        }
+        SyscallRestart::NoIntr | SyscallRestart::None => {}
+    }
+}
+
+/// restart_syscall: 继续被信号打断的系统调用 (ERESTART_RESTARTBLOCK)
+pub(crate) fn sys_restart_syscall() -> LinuxResult<isize> {
+    let Some(block) = current().take_restart_block() else {
+        return Err(LinuxError::EINTR);
+    };
+    let ret = block();
+    if ret < 0 {
+        Err(LinuxError::try_from(-ret as i32).unwrap_or(LinuxError::EINTR))
+    } else {
+        Ok(ret)
     }
 }
 
 pub(crate) fn sys_sigreturn() -> LinuxResult<isize> {
     let curr = current();
     trace!("sigreturn");
-    let (sscratch, mut tf) = {
-        let mut sigctx = curr.task_ext().process_data().signal.lock();
-        let mut t_sigctx = curr.task_ext().thread_data().signal.lock();
-        t_sigctx.unload().or(sigctx.unload()).expect("No sig frame loaded")
+    let (sscratch, mut tf, altstack) = {
+        let mut sigctx = curr.task_ext().thread_data().signal.lock();
+        sigctx.unload().expect("No sig frame loaded")
     };
+    // 恢复因 SS_AUTODISARM 清除的备用栈
+    if let Some(altstack) = altstack {
+        *curr.task_ext().thread_data().sigaltstack.lock() = altstack;
+    }
     // 交换回tf, 返回a0
     unsafe { write_trapframe_to_kstack(curr.get_kernel_stack_top().unwrap(), &tf) };
     unsafe { axhal::arch::exchange_trap_frame(sscratch) };
     Ok(tf.arg0() as isize)
 }
 
+/// 设置/获取当前线程的备用信号栈
+///
+/// 线程正在备用栈上运行时不允许修改（`EPERM`）
+pub(crate) fn sys_sigaltstack(
+    ss: UserConstPtr<stack_t>,
+    old_ss: UserPtr<stack_t>,
+) -> LinuxResult<isize> {
+    let ss = ss.nullable(|ss| Ok(unsafe { ss.get()?.read() }))?;
+    let old_ss = old_ss.nullable(|old_ss| old_ss.get())?;
+
+    let curr = current();
+    // 陷入时保存的用户栈指针
+    let sp = read_trapframe_from_kstack(curr.get_kernel_stack_top().unwrap()).get_sp();
+    let mut altstack = curr.task_ext().thread_data().sigaltstack.lock();
+    let old = *altstack;
+
+    if let Some(ss) = ss {
+        if old.on_stack(sp) {
+            return Err(LinuxError::EPERM);
+        }
+        let flags = ss.ss_flags as u32;
+        let mode = flags & !SS_AUTODISARM;
+        *altstack = match mode {
+            SS_DISABLE => SignalAltStack::default(),
+            // 旧接口允许传入 SS_ONSTACK，与 0 等价
+            0 | SS_ONSTACK => {
+                if ss.ss_size < MINSIGSTKSZ as _ {
+                    return Err(LinuxError::ENOMEM);
+                }
+                SignalAltStack {
+                    sp: ss.ss_sp as usize,
+                    size: ss.ss_size as _,
+                    flags: flags & SS_AUTODISARM,
+                }
+            }
+            _ => return Err(LinuxError::EINVAL),
+        };
+    }
+
+    if let Some(old_ss) = old_ss {
+        let old_ss = unsafe { &mut *old_ss };
+        old_ss.ss_sp = old.sp as _;
+        old_ss.ss_size = old.size as _;
+        old_ss.ss_flags = old.flags_at(sp) as _;
+    }
+    Ok(0)
+}
+
 #[derive(Clone, Copy)]
 pub(crate) enum SigInfo_ {
     Generic(SigCodeCommon),                     // pid, uid
     Child(SigCodeSigChld, SigStatus, u64, u64), // pid, uid, status, utime, stime
-    MemoryAccess(VirtAddr),                     // addr
-    FPEError(VirtAddr),                         // addr
-    IllegalInstruction(VirtAddr),               // addr
-    BusError(VirtAddr),                         // addr
-    Realtime(i32, VirtAddr),                    // value, ptr
+    MemoryAccess(SigCodeSigSegv, VirtAddr),     // code, addr
+    FPEError(SigCodeSigFpe, VirtAddr),          // code, addr
+    IllegalInstruction(SigCodeSigIll, VirtAddr), // code, addr
+    BusError(SigCodeSigBus, VirtAddr),          // code, addr
+    Realtime(SigCodeCommon, usize),             // code, sigval
     PollIO(i32, i64),                           // fd, band
     SyscallError(usize, i32, u32),              // call_addr, syscall_num, arch
     Simple(SigCode),
@@ -350,18 +538,28 @@ pub(crate) enum SigInfo_ {
 
 fn gen_siginfo(signo: Signal, data: SigInfo_) -> SigInfo {
     let curr = current();
-    let current_pid = curr.task_ext().thread.process().pid() as i32;
+    let current_pid = curr.task_ext().thread().process().pid() as i32;
     let current_uid = 0; // 假设 uid 为 0，实际应从进程或用户管理中获取
     match data {
         SigInfo_::Generic(code) => SigInfo::new_generic(signo, code, current_pid, current_uid),
         SigInfo_::Child(code, status, utime, stime) => {
             SigInfo::new_child(signo, code, current_pid, current_uid, status, utime, stime)
         }
-        //SigInfo_::MemoryAccess(addr) => SigInfo::new_memory_access(signo, code, addr),
-        //SigInfo_::FPEError(addr) => SigInfo::new_fpe_error(signo, code, addr),
-        //SigInfo_::IllegalInstruction(addr) => SigInfo::new_illegal_instruction(signo, code, addr),
-        //SigInfo_::BusError(addr) => SigInfo::new_bus_error(signo, code, addr),
-        //SigInfo_::Realtime(value, ptr) => SigInfo::new_realtime(signo, code, value, ptr),
+        SigInfo_::MemoryAccess(code, addr) => {
+            SigInfo::new_memory_access(signo, SigCode::SigSegv(code), addr)
+        }
+        SigInfo_::FPEError(code, addr) => SigInfo::new_fpe_error(signo, SigCode::SigFpe(code), addr),
+        SigInfo_::IllegalInstruction(code, addr) => {
+            SigInfo::new_illegal_instruction(signo, SigCode::SigIll(code), addr)
+        }
+        SigInfo_::BusError(code, addr) => SigInfo::new_bus_error(signo, SigCode::SigBus(code), addr),
+        SigInfo_::Realtime(code, value) => SigInfo::new_realtime(
+            signo,
+            SigCode::Common(code),
+            current_pid,
+            current_uid,
+            value,
+        ),
         //SigInfo_::PollIO(fd, band) => SigInfo::new_poll_io(signo, code, fd, band),
         /*
          *SigInfo_::SyscallError(call_addr, syscall_num, arch) => {
@@ -373,28 +571,168 @@ fn gen_siginfo(signo: Signal, data: SigInfo_) -> SigInfo {
     }
 }
 
+/// 检查待处理信号中是否有能打断睡眠的信号：未被阻塞，且不会被忽略
+///
+/// 信号处理动作保存在进程的信号上下文 `actions` 中
+fn has_interrupting(pending: SignalSet, blocked: SignalSet, actions: &SignalContext) -> bool {
+    let mut set = pending.difference(blocked);
+    while let Some(sig) = set.take_one() {
+        if !actions.is_ignored(sig) {
+            return true;
+        }
+    }
+    false
+}
+
+/// 唤醒处于可中断睡眠的线程，使其阻塞的系统调用返回 `EINTR`
+fn interrupt_thread(thr: &Thread) -> bool {
+    match thr.data::<ThreadData>().and_then(|data| data.task()) {
+        Some(task) => {
+            axtask::interrupt_task(&task);
+            true
+        }
+        None => false,
+    }
+}
+
 /// Send a signal to a thread.
 /// helper function from starryx
 pub fn send_signal_thread(thr: &Thread, sig: Signal, info: SigInfo_) -> LinuxResult<()> {
     info!("Send signal {:?} to thread {}", sig, thr.tid());
-    let Some(thr) = thr.data::<ThreadData>() else {
+    let Some(data) = thr.data::<ThreadData>() else {
+        return Err(LinuxError::EPERM);
+    };
+    data.send_signal(sig, Some(gen_siginfo(sig, info)));
+    notify_thread(thr, sig);
+    Ok(())
+}
+
+/// 向当前线程发送同步异常信号（SIGSEGV、SIGBUS、SIGILL、SIGFPE）
+///
+/// 异常指令返回后会再次触发，因此信号被阻塞或忽略时恢复默认动作并解除阻塞，
+/// 保证进程不会在同一条指令上反复陷入
+pub fn force_signal_current(sig: Signal, info: SigInfo_) {
+    let curr = current();
+    let thread_data = curr.task_ext().thread_data();
+    {
+        let mut proc_sigctx = curr.task_ext().process_data().signal.lock();
+        let mut thread_sigctx = thread_data.signal().lock();
+        let blocked = thread_sigctx.get_blocked().contains(sig.into());
+        if blocked || matches!(proc_sigctx.get_action(sig).handler, SigHandler::Ignore) {
+            proc_sigctx.set_action(sig, SigAction {
+                handler: SigHandler::Default(default_signal_handler),
+                mask: sig.into(),
+                flags: SigFlags::empty(),
+            });
+            thread_sigctx.unblock(sig.into());
+        }
+    }
+    thread_data.send_signal(sig, Some(gen_siginfo(sig, info)));
+}
+
+/// 向当前线程发送 `SIGPIPE`，在向没有读端的管道写入而返回 `EPIPE` 时使用
+pub fn send_sigpipe_current() {
+    let curr = current();
+    let info = SigInfo_::Generic(SigCodeCommon::SI_USER);
+    let _ = send_signal_thread(curr.task_ext().thread(), Signal::SIGPIPE, info);
+}
+
+#[register_trap_handler(USER_EXCEPTION)]
+fn handle_user_exception(_tf: &mut TrapFrame, exception: UserException, vaddr: VirtAddr) -> bool {
+    warn!("User exception {:?} at {:#x?}", exception, vaddr);
+    let (sig, info) = match exception {
+        UserException::IllegalInstruction => (
+            Signal::SIGILL,
+            SigInfo_::IllegalInstruction(SigCodeSigIll::ILL_ILLOPC, vaddr),
+        ),
+        UserException::PrivilegedInstruction => (
+            Signal::SIGILL,
+            SigInfo_::IllegalInstruction(SigCodeSigIll::ILL_PRVOPC, vaddr),
+        ),
+        UserException::Misaligned => (
+            Signal::SIGBUS,
+            SigInfo_::BusError(SigCodeSigBus::BUS_ADRALN, vaddr),
+        ),
+        UserException::AccessFault => (
+            Signal::SIGSEGV,
+            SigInfo_::MemoryAccess(SigCodeSigSegv::SEGV_ACCERR, vaddr),
+        ),
+        UserException::IntegerDivideByZero => (
+            Signal::SIGFPE,
+            SigInfo_::FPEError(SigCodeSigFpe::FPE_INTDIV, vaddr),
+        ),
+        UserException::IntegerOverflow => (
+            Signal::SIGFPE,
+            SigInfo_::FPEError(SigCodeSigFpe::FPE_INTOVF, vaddr),
+        ),
+    };
+    force_signal_current(sig, info);
+    true
+}
+
+/// 向线程排队一个带附加信息的信号，实时信号队列已满时返回 `EAGAIN`
+pub fn queue_signal_thread(thr: &Thread, sig: Signal, info: SigInfo) -> LinuxResult<()> {
+    info!("Queue signal {:?} to thread {}", sig, thr.tid());
+    let Some(data) = thr.data::<ThreadData>() else {
         return Err(LinuxError::EPERM);
     };
-    thr.send_signal(sig, Some(gen_siginfo(sig, info)));
+    data.signal.lock().queue_signal(sig, Some(info))?;
+    notify_thread(thr, sig);
     Ok(())
 }
 
+/// 信号到达后，若未被阻塞则打断线程的睡眠
+fn notify_thread(thr: &Thread, sig: Signal) {
+    let Some(proc_data) = thr.process().data::<ProcessData>() else {
+        return;
+    };
+    if thread_accepts(thr, proc_data, sig) {
+        interrupt_thread(thr);
+    }
+}
+
 /// Send a signal to a process.
 /// helper function from starryx
 pub fn send_signal_process(proc: &Process, sig: Signal, info: SigInfo_) -> LinuxResult<()> {
     info!("Send signal {:?} to process {}", sig, proc.pid());
-    let Some(proc) = proc.data::<ProcessData>() else {
+    let Some(data) = proc.data::<ProcessData>() else {
         return Err(LinuxError::EPERM);
     };
-    proc.send_signal(sig, Some(gen_siginfo(sig, info)));
+    data.send_signal(sig, Some(gen_siginfo(sig, info)));
+    notify_process(proc, data, sig);
     Ok(())
 }
 
+/// 向进程排队一个带附加信息的信号，实时信号队列已满时返回 `EAGAIN`
+pub fn queue_signal_process(proc: &Process, sig: Signal, info: SigInfo) -> LinuxResult<()> {
+    info!("Queue signal {:?} to process {}", sig, proc.pid());
+    let Some(data) = proc.data::<ProcessData>() else {
+        return Err(LinuxError::EPERM);
+    };
+    data.signal.lock().queue_signal(sig, Some(info))?;
+    notify_process(proc, data, sig);
+    Ok(())
+}
+
+/// 线程是否会处理该信号：未被线程阻塞，且不会被忽略
+fn thread_accepts(thr: &Thread, proc_data: &ProcessData, sig: Signal) -> bool {
+    let Some(data) = thr.data::<ThreadData>() else {
+        return false;
+    };
+    let sigctx = proc_data.signal.lock();
+    let blocked = data.signal.lock().get_blocked();
+    has_interrupting(sig.into(), blocked, &sigctx)
+}
+
+/// 信号到达后，打断进程中一个未阻塞该信号的线程的睡眠
+///
+/// 进程信号留在共享的待处理队列中，由第一个未阻塞它的线程在返回用户态时取走
+fn notify_process(proc: &Process, data: &ProcessData, sig: Signal) {
+    proc.threads()
+        .iter()
+        .any(|thr| thread_accepts(thr, data, sig) && interrupt_thread(thr));
+}
+
 /// Send a signal to a process group.
 /// helper function from starryx
 pub fn send_signal_process_group(pg: &ProcessGroup, sig: Signal, info: SigInfo_) -> usize {
@@ -419,7 +757,7 @@ pub fn sys_kill(pid: c_int, signo: u32) -> LinuxResult<isize> {
         }
         0 => {
             // pid = 0: 发送信号给当前进程组中的所有进程
-            let pg = current().task_ext().thread.process().group();
+            let pg = current().task_ext().thread().process().group();
             send_signal_process_group(&pg, sig, info);
             Ok(0)
         }
@@ -453,6 +791,55 @@ pub fn sys_tkill(tid: Pid, signo: u32) -> LinuxResult<isize> {
     send_signal_thread(&thr, sig, info)?;
     Ok(0)
 }
+/// 读取用户传入的 siginfo_t，发送给其他进程时只允许伪造 `si_code < 0` 的用户来源
+fn read_queued_siginfo(
+    sig: Signal,
+    uinfo: UserConstPtr<siginfo_t>,
+    to_self: bool,
+) -> LinuxResult<SigInfo> {
+    let raw = unsafe { uinfo.get()?.read() };
+    let code = unsafe { raw.__bindgen_anon_1.__bindgen_anon_1.si_code };
+    if !to_self && (code >= 0 || code == SI_TKILL) {
+        return Err(LinuxError::EPERM);
+    }
+    Ok(SigInfo::from_raw_queued(sig, &raw))
+}
+
+/// rt_sigqueueinfo: 向进程排队信号并携带 siginfo (sigqueue 使用)
+pub fn sys_rt_sigqueueinfo(
+    tgid: Pid,
+    signo: u32,
+    uinfo: UserConstPtr<siginfo_t>,
+) -> LinuxResult<isize> {
+    let sig = Signal::from_u32(signo).ok_or(LinuxError::EINVAL)?;
+    let proc = get_process(tgid)?;
+    let to_self = tgid == current().task_ext().thread().process().pid();
+    let info = read_queued_siginfo(sig, uinfo, to_self)?;
+    if sig == Signal::NONE {
+        return Ok(0);
+    }
+    queue_signal_process(&proc, sig, info)?;
+    Ok(0)
+}
+
+/// rt_tgsigqueueinfo: 向线程组中的指定线程排队信号并携带 siginfo
+pub fn sys_rt_tgsigqueueinfo(
+    tgid: Pid,
+    tid: Pid,
+    signo: u32,
+    uinfo: UserConstPtr<siginfo_t>,
+) -> LinuxResult<isize> {
+    let sig = Signal::from_u32(signo).ok_or(LinuxError::EINVAL)?;
+    let thr = find_thread_in_group(tgid, tid)?;
+    let to_self = tgid == current().task_ext().thread().process().pid();
+    let info = read_queued_siginfo(sig, uinfo, to_self)?;
+    if sig == Signal::NONE {
+        return Ok(0);
+    }
+    queue_signal_thread(&thr, sig, info)?;
+    Ok(0)
+}
+
 pub fn sys_tgkill(tgid: Pid, tid: Pid, signo: u32) -> LinuxResult<isize> {
     let Some(sig) = Signal::from_u32(signo) else {
         return Ok(0); // 信号无效
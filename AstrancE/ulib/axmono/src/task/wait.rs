@@ -9,7 +9,7 @@ use crate::{
 use alloc::{sync::Arc, vec::Vec};
 use axerrno::{LinuxError, LinuxResult};
 use axprocess::{Pid, Process};
-use axtask::{TaskExtRef, current};
+use axtask::{SyscallRestart, TaskExtRef, current};
 use bitflags::bitflags;
 use linux_raw_sys::general::{
     __WALL, __WCLONE, __WNOTHREAD, WCONTINUED, WEXITED, WNOHANG, WNOWAIT, WUNTRACED,
@@ -67,7 +67,7 @@ pub fn sys_waitpid(pid: i32, exit_code_ptr: UserPtr<i32>, options: u32) -> Linux
 
     let curr = current();
     let proc_data = curr.task_ext().process_data();
-    let process = curr.task_ext().thread.process();
+    let process = curr.task_ext().thread().process();
 
     let pid = if pid == -1 {
         WaitPid::Any
@@ -109,7 +109,14 @@ pub fn sys_waitpid(pid: i32, exit_code_ptr: UserPtr<i32>, options: u32) -> Linux
             return Ok(0);
         } else {
             debug!("keep waiting for children");
-            proc_data.child_exit_wq.wait();
+            // 被信号（或 execve 杀死其他线程）打断时返回，处理后重新执行
+            let woken = proc_data
+                .child_exit_wq
+                .wait_until_interruptible(|| children.iter().any(|child| child.is_zombie()));
+            if woken.is_err() {
+                curr.set_syscall_restart(SyscallRestart::Sys);
+                return Err(LinuxError::EINTR);
+            }
         }
     }
 }